@@ -1,28 +1,62 @@
 //! Configuration management for the arbitrage engine
+//!
+//! [`Config`] mirrors the layout of `config.toml`. Files written after
+//! `config.example.toml`, which groups some settings differently and gives
+//! durations in seconds, slippage and thresholds as fractions and sizes in
+//! bytes, are accepted too: their keys are moved to the fields below and
+//! converted to their units when the file is parsed.
 
 use crate::error::{ArbitrageError, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::env;
 
-/// Main configuration structure
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Main configuration structure, one field per section of `config.toml`
+/// plus the notification settings of `config.example.toml`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     /// Server configuration settings
     pub server: ServerConfig,
     /// Database connection configuration
     pub database: DatabaseConfig,
     /// Redis cache configuration
-    pub redis: RedisConfig,
+    pub cache: CacheConfig,
     /// Solana blockchain configuration
     pub solana: SolanaConfig,
     /// Arbitrage strategy configuration
     pub arbitrage: ArbitrageConfig,
-    /// Logging configuration
-    pub logging: LoggingConfig,
+    /// DEX integration settings
+    pub dex: DexConfig,
+    /// Opportunity detection settings
+    pub opportunities: OpportunitiesConfig,
+    /// Known tokens
+    pub tokens: TokensConfig,
+    /// Trade execution settings
+    pub trading: TradingConfig,
+    /// Dynamic priority fee settings
+    pub priority_fees: PriorityFeeConfig,
+    /// Jito block engine settings
+    pub jito: JitoConfig,
+    /// Risk limits
+    pub risk: RiskConfig,
+    /// Blacklisting of repeatedly failing routes
+    pub route_blacklist: RouteBlacklistConfig,
+    /// Logging, metrics, health checks and alerting
+    pub monitoring: MonitoringConfig,
+    /// Runtime tuning
+    pub performance: PerformanceConfig,
+    /// API access control
+    pub security: SecurityConfig,
+    /// Development and testing switches
+    pub development: DevelopmentConfig,
+    /// Alert channels
+    pub notifications: NotificationsConfig,
 }
 
 /// Server configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ServerConfig {
     /// Server host address
     pub host: String,
@@ -30,10 +64,54 @@ pub struct ServerConfig {
     pub port: u16,
     /// Number of worker threads
     pub workers: usize,
+    /// Maximum number of concurrent HTTP connections
+    pub max_connections: usize,
+    /// Time budget for a single HTTP request
+    pub request_timeout_ms: u64,
+    /// Whether CORS headers are sent
+    pub enable_cors: bool,
+    /// Whether responses are compressed
+    pub enable_compression: bool,
+    /// WebSocket server port number
+    pub ws_port: u16,
+    /// Maximum number of concurrent WebSocket connections
+    pub ws_max_connections: usize,
+    /// Interval between WebSocket heartbeats
+    pub ws_heartbeat_interval_ms: u64,
+    /// Messages buffered per WebSocket client
+    pub ws_message_buffer_size: usize,
+    /// WebSocket server host address
+    pub ws_host: String,
+    /// Time a WebSocket client has to answer a ping
+    pub ws_pong_timeout_ms: u64,
+    /// Largest WebSocket message accepted, in bytes
+    pub ws_max_message_size: usize,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            host: "0.0.0.0".to_string(),
+            port: 8080,
+            workers: num_cpus::get(),
+            max_connections: 1000,
+            request_timeout_ms: 30_000,
+            enable_cors: true,
+            enable_compression: true,
+            ws_port: 8081,
+            ws_max_connections: 500,
+            ws_heartbeat_interval_ms: 30_000,
+            ws_message_buffer_size: 1000,
+            ws_host: "0.0.0.0".to_string(),
+            ws_pong_timeout_ms: 10_000,
+            ws_max_message_size: 1_048_576,
+        }
+    }
 }
 
 /// Database configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct DatabaseConfig {
     /// Database connection URL
     pub url: String,
@@ -41,30 +119,173 @@ pub struct DatabaseConfig {
     pub max_connections: u32,
     /// Minimum number of database connections
     pub min_connections: u32,
+    /// Time budget for opening a connection
+    pub connection_timeout_ms: u64,
+    /// Time budget for a single query
+    pub query_timeout_ms: u64,
+    /// Age after which pooled connections are replaced
+    pub max_lifetime_ms: u64,
+    /// Idle time after which pooled connections are closed
+    pub idle_timeout_ms: u64,
+    /// Whether migrations run on startup
+    pub auto_migrate: bool,
+    /// Directory holding the migrations
+    pub migration_path: String,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: "postgresql://localhost/arbitrage".to_string(),
+            max_connections: 10,
+            min_connections: 1,
+            connection_timeout_ms: 5000,
+            query_timeout_ms: 30_000,
+            max_lifetime_ms: 1_800_000,
+            idle_timeout_ms: 600_000,
+            auto_migrate: true,
+            migration_path: "migrations".to_string(),
+        }
+    }
 }
 
 /// Redis cache configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RedisConfig {
+#[serde(default)]
+pub struct CacheConfig {
     /// Redis connection URL
     pub url: String,
+    /// Redis database index
+    pub db: u32,
     /// Maximum number of Redis connections
     pub max_connections: u32,
+    /// Minimum number of Redis connections
+    pub min_connections: u32,
+    /// Time budget for opening a connection
+    pub connection_timeout_ms: u64,
+    /// Time budget for a single command
+    pub command_timeout_ms: u64,
+    /// Idle time after which pooled connections are closed
+    pub idle_timeout_ms: u64,
+    /// Age after which pooled connections are replaced
+    pub max_lifetime_ms: u64,
+    /// Lifetime of cached entries without a specific lifetime, in seconds
+    pub default_ttl: u64,
+    /// Lifetime of cached prices, in seconds
+    pub price_ttl: u64,
+    /// Lifetime of cached opportunities, in seconds
+    pub opportunity_ttl: u64,
+    /// Lifetime of cached quotes, in seconds
+    pub quote_ttl: u64,
+    /// Lifetime of cached liquidity figures, in seconds
+    pub liquidity_ttl: u64,
+    /// Lifetime of cached DEX statuses, in seconds
+    pub dex_status_ttl: u64,
+    /// Lifetime of cached token metadata, in seconds
+    pub token_info_ttl: u64,
+    /// Prefix of every key
+    pub key_prefix: String,
+    /// Prefix of price keys
+    pub price_prefix: String,
+    /// Prefix of opportunity keys
+    pub opportunity_prefix: String,
+    /// Prefix of quote keys
+    pub quote_prefix: String,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            url: "redis://localhost:6379".to_string(),
+            db: 0,
+            max_connections: 10,
+            min_connections: 1,
+            connection_timeout_ms: 3000,
+            command_timeout_ms: 5000,
+            idle_timeout_ms: 300_000,
+            max_lifetime_ms: 600_000,
+            default_ttl: 300,
+            price_ttl: 5,
+            opportunity_ttl: 10,
+            quote_ttl: 30,
+            liquidity_ttl: 60,
+            dex_status_ttl: 120,
+            token_info_ttl: 3600,
+            key_prefix: "arb:".to_string(),
+            price_prefix: "price:".to_string(),
+            opportunity_prefix: "opp:".to_string(),
+            quote_prefix: "quote:".to_string(),
+        }
+    }
 }
 
 /// Solana blockchain configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SolanaConfig {
     /// Solana RPC endpoint URL
     pub rpc_url: String,
+    /// Fallback RPC endpoints, tried in order when `rpc_url` fails
+    pub rpc_urls: Vec<String>,
     /// Solana WebSocket endpoint URL
     pub ws_url: String,
     /// Transaction commitment level
     pub commitment: String,
+    /// Time budget for a single RPC request
+    pub timeout_ms: u64,
+    /// Retries of a failed RPC request
+    pub max_retries: u32,
+    /// Delay before a failed RPC request is retried
+    pub retry_delay_ms: u64,
+    /// Geyser gRPC endpoint; empty when Geyser is not available
+    pub geyser_endpoint: String,
+    /// Token sent as `x-token` with Geyser requests
+    pub geyser_token: String,
+    /// Geyser connect timeout in milliseconds
+    pub geyser_timeout_ms: u64,
+    /// Largest Geyser message accepted, in bytes
+    pub geyser_max_message_size: usize,
+    /// Delay before a dropped Geyser stream is reconnected
+    pub geyser_reconnect_interval_ms: u64,
+    /// Interval between Geyser keepalive pings
+    pub geyser_ping_interval_ms: u64,
+    /// Compute unit limit requested by arbitrage transactions
+    pub max_compute_units: u32,
+    /// Priority fee paid per arbitrage transaction, in lamports
+    pub priority_fee_lamports: u64,
+    /// Times a transaction is resent before it is given up
+    pub transaction_max_retries: u32,
+    /// Time to wait for a sent transaction to confirm
+    pub confirmation_timeout_ms: u64,
+}
+
+impl Default for SolanaConfig {
+    fn default() -> Self {
+        Self {
+            rpc_url: "https://api.mainnet-beta.solana.com".to_string(),
+            rpc_urls: Vec::new(),
+            ws_url: "wss://api.mainnet-beta.solana.com".to_string(),
+            commitment: "confirmed".to_string(),
+            timeout_ms: 10_000,
+            max_retries: 3,
+            retry_delay_ms: 1000,
+            geyser_endpoint: String::new(),
+            geyser_token: String::new(),
+            geyser_timeout_ms: 5000,
+            geyser_max_message_size: 4_194_304,
+            geyser_reconnect_interval_ms: 5000,
+            geyser_ping_interval_ms: 30_000,
+            max_compute_units: 1_400_000,
+            priority_fee_lamports: 5000,
+            transaction_max_retries: 5,
+            confirmation_timeout_ms: 30_000,
+        }
+    }
 }
 
 /// Arbitrage strategy configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ArbitrageConfig {
     /// Minimum profit threshold to execute trades
     pub min_profit_threshold: f64,
@@ -76,48 +297,1223 @@ pub struct ArbitrageConfig {
     pub execution_timeout: u64,
 }
 
-/// Logging configuration
+impl Default for ArbitrageConfig {
+    fn default() -> Self {
+        Self {
+            min_profit_threshold: 0.01, // 1%
+            max_slippage: 0.005,        // 0.5%
+            max_price_impact: 0.02,     // 2%
+            execution_timeout: 30,      // 30 seconds
+        }
+    }
+}
+
+/// DEX integration settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DexConfig {
+    /// Raydium settings
+    pub raydium: DexProgramConfig,
+    /// Orca settings
+    pub orca: DexProgramConfig,
+    /// Meteora settings
+    pub meteora: DexProgramConfig,
+    /// Jupiter aggregator settings
+    pub jupiter: JupiterConfig,
+}
+
+impl Default for DexConfig {
+    fn default() -> Self {
+        let program = |program_id: &str, api_url: &str| DexProgramConfig {
+            program_id: program_id.to_string(),
+            api_url: api_url.to_string(),
+            ..DexProgramConfig::default()
+        };
+        Self {
+            raydium: program(
+                "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8",
+                "https://api.raydium.io",
+            ),
+            orca: program(
+                "9W959DqEETiGZocYWCQPaJ6sD6MUGLiAZH6Aq9Qk9K6T",
+                "https://api.orca.so",
+            ),
+            meteora: DexProgramConfig {
+                max_slippage_bps: 150,
+                min_liquidity_usd: 5000.0,
+                priority_fee_lamports: 7500,
+                ..program(
+                    "Eo7WjKq67rjJQSZxS6z3YkapzY3eMj6Xy8X5EQVn5UaB",
+                    "https://app.meteora.ag",
+                )
+            },
+            jupiter: JupiterConfig::default(),
+        }
+    }
+}
+
+/// Settings of a DEX traded through its on-chain program
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DexProgramConfig {
+    /// Whether the DEX is traded
+    pub enabled: bool,
+    /// Address of the DEX program
+    pub program_id: String,
+    /// Base URL of the DEX's public API
+    pub api_url: String,
+    /// Slippage tolerance on the DEX, in basis points
+    pub max_slippage_bps: u16,
+    /// Minimum pool liquidity in USD
+    pub min_liquidity_usd: f64,
+    /// Priority fee attached to swaps on the DEX, in lamports
+    pub priority_fee_lamports: u64,
+}
+
+impl Default for DexProgramConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            program_id: String::new(),
+            api_url: String::new(),
+            max_slippage_bps: 100,
+            min_liquidity_usd: 10_000.0,
+            priority_fee_lamports: 5000,
+        }
+    }
+}
+
+/// Jupiter aggregator settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JupiterConfig {
+    /// Whether Jupiter quotes are used
+    pub enabled: bool,
+    /// Base URL of the Jupiter quote API
+    pub api_url: String,
+    /// Slippage tolerance sent with quotes, in basis points
+    pub max_slippage_bps: u16,
+    /// Minimum route liquidity in USD
+    pub min_liquidity_usd: f64,
+    /// Maximum number of accounts a route may use
+    pub max_accounts: u8,
+    /// Priority fee attached to Jupiter swaps, in lamports
+    pub priority_fee_lamports: u64,
+}
+
+impl Default for JupiterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            api_url: "https://quote-api.jup.ag".to_string(),
+            max_slippage_bps: 50,
+            min_liquidity_usd: 1000.0,
+            max_accounts: 64,
+            priority_fee_lamports: 10_000,
+        }
+    }
+}
+
+/// Opportunity detection settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OpportunitiesConfig {
+    /// Minimum expected profit in USD
+    pub min_profit_usd: f64,
+    /// Minimum expected profit as a percentage of the input
+    pub min_profit_percent: f64,
+    /// Age after which pool state and opportunities are considered stale
+    pub max_opportunity_age_ms: u64,
+    /// Cap on opportunities emitted per second
+    pub max_opportunities_per_second: u32,
+    /// Interval between full scans
+    pub scan_interval_ms: u64,
+    /// Maximum number of legs in a route
+    pub max_scan_depth: usize,
+    /// Whether two-leg cross-DEX routes are scanned
+    pub enable_cross_dex_arbitrage: bool,
+    /// Whether triangular and longer cycles are scanned
+    pub enable_triangular_arbitrage: bool,
+    /// Minimum pool liquidity in USD
+    pub min_liquidity_usd: f64,
+    /// Maximum price impact per route, in basis points
+    pub max_price_impact_bps: u32,
+    /// Mints never traded
+    pub blacklisted_tokens: Vec<String>,
+    /// If non-empty, the only mints traded
+    pub whitelisted_tokens: Vec<String>,
+    /// Maximum routes kept per token pair
+    pub max_routes_per_pair: usize,
+    /// Time budget for evaluating a route
+    pub route_timeout_ms: u64,
+    /// Whether discovered routes are cached between scans
+    pub enable_route_caching: bool,
+}
+
+impl Default for OpportunitiesConfig {
+    fn default() -> Self {
+        Self {
+            min_profit_usd: 5.0,
+            min_profit_percent: 0.1,
+            max_opportunity_age_ms: 5000,
+            max_opportunities_per_second: 100,
+            scan_interval_ms: 100,
+            max_scan_depth: 3,
+            enable_cross_dex_arbitrage: true,
+            enable_triangular_arbitrage: true,
+            min_liquidity_usd: 1000.0,
+            max_price_impact_bps: 300,
+            blacklisted_tokens: Vec::new(),
+            whitelisted_tokens: Vec::new(),
+            max_routes_per_pair: 5,
+            route_timeout_ms: 2000,
+            enable_route_caching: true,
+        }
+    }
+}
+
+/// Trade execution settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TradingConfig {
+    /// Detect and simulate trades without sending them
+    pub dry_run: bool,
+    /// Largest input of a single trade, in USD
+    pub max_position_size_usd: f64,
+    /// Minimum net profit for a trade to be executed, in USD
+    pub min_profit_threshold_usd: f64,
+    /// Slippage tolerance applied to each leg, in basis points
+    pub max_slippage_bps: u16,
+    /// Maximum price impact of a trade, in basis points
+    pub max_price_impact_bps: u32,
+    /// Input size used when no better size is known, in USD
+    pub default_position_size_usd: f64,
+    /// Maximum number of open positions
+    pub max_positions: usize,
+    /// Multiplier applied to the default position size for strong signals
+    pub position_size_multiplier: f64,
+    /// Share of the full Kelly bet a position is sized at
+    pub kelly_fraction: f64,
+    /// Time budget for executing a trade
+    pub max_execution_time_ms: u64,
+    /// Maximum number of trades in flight
+    pub max_concurrent_trades: usize,
+    /// Minimum time to wait for a confirmation
+    pub min_confirmation_time_ms: u64,
+    /// Whether trades are protected against MEV
+    pub enable_mev_protection: bool,
+    /// Window during which front-running is guarded against
+    pub max_frontrun_protection_ms: u64,
+    /// Whether sandwich attempts are detected
+    pub sandwich_detection_enabled: bool,
+    /// Factor applied to the estimated transaction fee
+    pub gas_price_multiplier: f64,
+    /// Most paid in fees for a single transaction, in SOL
+    pub max_gas_price_sol: f64,
+    /// Trading wallet
+    pub wallet: WalletConfig,
+}
+
+impl Default for TradingConfig {
+    fn default() -> Self {
+        Self {
+            dry_run: false,
+            max_position_size_usd: 50_000.0,
+            min_profit_threshold_usd: 10.0,
+            max_slippage_bps: 100,
+            max_price_impact_bps: 200,
+            default_position_size_usd: 1_000.0,
+            max_positions: 10,
+            position_size_multiplier: 1.5,
+            kelly_fraction: 0.25,
+            max_execution_time_ms: 5000,
+            max_concurrent_trades: 5,
+            min_confirmation_time_ms: 1000,
+            enable_mev_protection: true,
+            max_frontrun_protection_ms: 2000,
+            sandwich_detection_enabled: true,
+            gas_price_multiplier: 1.0,
+            max_gas_price_sol: 0.01,
+            wallet: WalletConfig::default(),
+        }
+    }
+}
+
+/// Trading wallet settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WalletConfig {
+    /// Keypair file signing trades, overridden by `SOLANA_PRIVATE_KEY_PATH`
+    pub keypair_path: String,
+    /// SOL balance above which the surplus is swept, in SOL
+    pub max_sol_balance: f64,
+    /// SOL balance kept for fees, in SOL
+    pub min_sol_balance: f64,
+    /// Whether balances are rebalanced automatically
+    pub auto_rebalance: bool,
+}
+
+impl Default for WalletConfig {
+    fn default() -> Self {
+        Self {
+            keypair_path: String::new(),
+            max_sol_balance: 100.0,
+            min_sol_balance: 1.0,
+            auto_rebalance: true,
+        }
+    }
+}
+
+/// Dynamic priority fee settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PriorityFeeConfig {
+    /// Percentile of recent fees paid for the same accounts to bid
+    pub percentile: f64,
+    /// Number of recent slots sampled per account set
+    pub window_slots: u64,
+    /// Age after which samples are fetched again
+    pub cache_ttl_ms: u64,
+    /// Lowest compute unit price bid, in micro-lamports
+    pub min_micro_lamports: u64,
+    /// Highest compute unit price bid, in micro-lamports
+    pub max_micro_lamports: u64,
+    /// Largest share of the expected profit spent on priority fees
+    pub max_profit_fraction: f64,
+    /// Factor the bid grows by with every retry
+    pub escalation_multiplier: f64,
+    /// Attempts per opportunity before it is given up
+    pub max_attempts: u32,
+}
+
+impl Default for PriorityFeeConfig {
+    fn default() -> Self {
+        Self {
+            percentile: 75.0,
+            window_slots: 150,
+            cache_ttl_ms: 400,
+            min_micro_lamports: 1_000,
+            max_micro_lamports: 5_000_000,
+            max_profit_fraction: 0.3,
+            escalation_multiplier: 1.5,
+            max_attempts: 3,
+        }
+    }
+}
+
+/// Jito block engine settings, used when `trading.enable_mev_protection` is
+/// set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JitoConfig {
+    /// Block engine base URL
+    pub block_engine_url: String,
+    /// Smallest tip paid per bundle, in lamports
+    pub min_tip_lamports: u64,
+    /// Largest tip paid per bundle, in lamports
+    pub max_tip_lamports: u64,
+    /// Share of the expected profit paid as tip
+    pub tip_profit_fraction: f64,
+    /// Interval between bundle status polls
+    pub status_poll_interval_ms: u64,
+    /// Time after which a bundle that hasn't landed is given up
+    pub bundle_timeout_ms: u64,
+}
+
+impl Default for JitoConfig {
+    fn default() -> Self {
+        Self {
+            block_engine_url: "https://mainnet.block-engine.jito.wtf".to_string(),
+            min_tip_lamports: 10_000,
+            max_tip_lamports: 10_000_000,
+            tip_profit_fraction: 0.5,
+            status_poll_interval_ms: 500,
+            // 150 slots of 400ms
+            bundle_timeout_ms: 60_000,
+        }
+    }
+}
+
+/// Risk limits applied before every trade
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RiskConfig {
+    /// Realized loss within a UTC day after which trading stops, in USD
+    pub max_daily_loss_usd: f64,
+    /// Decline from peak equity after which trading stops, in percent
+    pub max_drawdown_percent: f64,
+    /// Loss of a single trade considered a stop-out, in percent of its size
+    pub stop_loss_percent: f64,
+    /// Gain of a single trade considered a take-profit, in percent of its size
+    pub take_profit_percent: f64,
+    /// Cap on open exposure, and the base of the concentration limits, in USD
+    pub max_portfolio_value_usd: f64,
+    /// Largest open exposure to one token, in percent of the portfolio
+    pub max_token_concentration_percent: f64,
+    /// Largest open exposure to one DEX, in percent of the portfolio
+    pub max_dex_concentration_percent: f64,
+    /// Largest open exposure to one token, in USD, on top of its share of
+    /// the portfolio
+    pub max_token_exposure_usd: Option<f64>,
+    /// Largest open exposure to one DEX, in USD, on top of its share of the
+    /// portfolio
+    pub max_dex_exposure_usd: Option<f64>,
+    /// Interval between risk re-evaluations and between the price samples
+    /// volatility is measured over
+    pub risk_check_interval_ms: u64,
+    /// Price volatility above which markets are considered unstable
+    pub volatility_threshold: f64,
+    /// Number of recent price samples, one every `risk_check_interval_ms`,
+    /// volatility is measured over
+    pub volatility_window: usize,
+    /// Pool liquidity below which pools are considered too thin, in USD
+    pub liquidity_threshold_usd: f64,
+    /// Whether consecutive losses halt trading
+    pub enable_circuit_breaker: bool,
+    /// Consecutive losing trades that trip the circuit breaker
+    pub max_consecutive_losses: u32,
+    /// Time trading stays halted once the circuit breaker trips
+    pub circuit_breaker_cooldown_ms: u64,
+    /// Loss within the breaker's window that trips it, in USD
+    pub circuit_breaker_loss_usd: f64,
+    /// Gain that closes a tripped breaker again, in percent
+    pub circuit_breaker_recovery_percent: f64,
+    /// Share of failed transactions among recent trades that trips the
+    /// circuit breaker
+    pub max_failure_rate: f64,
+    /// Number of recent trades the failure rate is measured over
+    pub failure_rate_window: usize,
+}
+
+impl Default for RiskConfig {
+    fn default() -> Self {
+        Self {
+            max_daily_loss_usd: 5_000.0,
+            max_drawdown_percent: 10.0,
+            stop_loss_percent: 2.0,
+            take_profit_percent: 5.0,
+            max_portfolio_value_usd: 100_000.0,
+            max_token_concentration_percent: 20.0,
+            max_dex_concentration_percent: 30.0,
+            max_token_exposure_usd: None,
+            max_dex_exposure_usd: None,
+            risk_check_interval_ms: 1000,
+            volatility_threshold: 0.05,
+            volatility_window: 30,
+            liquidity_threshold_usd: 10_000.0,
+            enable_circuit_breaker: true,
+            max_consecutive_losses: 5,
+            circuit_breaker_cooldown_ms: 300_000,
+            circuit_breaker_loss_usd: 100.0,
+            circuit_breaker_recovery_percent: 2.0,
+            max_failure_rate: 0.5,
+            failure_rate_window: 20,
+        }
+    }
+}
+
+/// Blacklisting of repeatedly failing routes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RouteBlacklistConfig {
+    /// Whether failing routes are blacklisted
+    pub enabled: bool,
+    /// Failures within the failure window that blacklist a hop or route
+    pub max_failures: u32,
+    /// Time a failure counts towards blacklisting
+    pub failure_window_ms: u64,
+    /// Duration of a first blacklisting, doubling with every repeat
+    pub blacklist_ms: u64,
+    /// Longest blacklisting
+    pub max_blacklist_ms: u64,
+}
+
+impl Default for RouteBlacklistConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_failures: 3,
+            failure_window_ms: 600_000,
+            blacklist_ms: 300_000,
+            max_blacklist_ms: 3_600_000,
+        }
+    }
+}
+
+/// Known tokens, keyed by the name of their `[tokens.<NAME>]` table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TokensConfig {
+    /// Decimals assumed for mints without an entry
+    pub default_decimals: u8,
+    /// Smallest token balance worth tracking, in base units
+    pub min_token_balance: u64,
+    /// Token entries
+    #[serde(flatten)]
+    pub tokens: HashMap<String, TokenInfo>,
+}
+
+impl TokensConfig {
+    /// Find the entry for `mint`
+    pub fn by_mint(&self, mint: &str) -> Option<&TokenInfo> {
+        self.tokens.values().find(|token| token.mint == mint)
+    }
+
+    /// Find the entry whose symbol is `symbol`
+    pub fn by_symbol(&self, symbol: &str) -> Option<&TokenInfo> {
+        self.tokens.values().find(|token| token.symbol == symbol)
+    }
+}
+
+impl Default for TokensConfig {
+    fn default() -> Self {
+        let tokens = [
+            (
+                "SOL",
+                "So11111111111111111111111111111111111111112",
+                9,
+                "solana",
+            ),
+            (
+                "USDC",
+                "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
+                6,
+                "usd-coin",
+            ),
+            (
+                "USDT",
+                "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB",
+                6,
+                "tether",
+            ),
+        ]
+        .into_iter()
+        .map(|(symbol, mint, decimals, coingecko_id)| {
+            (
+                symbol.to_string(),
+                TokenInfo {
+                    symbol: symbol.to_string(),
+                    mint: mint.to_string(),
+                    decimals,
+                    coingecko_id: coingecko_id.to_string(),
+                },
+            )
+        })
+        .collect();
+
+        Self {
+            default_decimals: 9,
+            min_token_balance: 1_000_000,
+            tokens,
+        }
+    }
+}
+
+/// A known token
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenInfo {
+    /// Ticker symbol
+    pub symbol: String,
+    /// Mint address
+    pub mint: String,
+    /// Number of decimals of the mint
+    pub decimals: u8,
+    /// CoinGecko id, for external price feeds
+    #[serde(default)]
+    pub coingecko_id: String,
+}
+
+/// Logging, metrics, health check and alerting settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct LoggingConfig {
+#[serde(default)]
+pub struct MonitoringConfig {
     /// Log level (debug, info, warn, error)
-    pub level: String,
+    pub log_level: String,
     /// Log output format (json, plain)
-    pub format: String,
+    pub log_format: String,
+    /// File logs are written to
+    pub log_file: String,
+    /// Size at which the log file is rotated, in megabytes
+    pub max_log_size_mb: u64,
+    /// Rotated log files kept
+    pub max_log_files: usize,
+    /// Where logs go besides the log file (stdout, stderr)
+    pub log_output: String,
+    /// Whether rotated log files are compressed
+    pub compress_logs: bool,
+    /// File errors are logged to in addition; empty to disable
+    pub error_log_file: String,
+    /// File API requests are logged to; empty to disable
+    pub access_log_file: String,
+    /// Whether Prometheus metrics are exported
+    pub enable_metrics: bool,
+    /// Port metrics are served on
+    pub metrics_port: u16,
+    /// Path metrics are served under
+    pub metrics_path: String,
+    /// Interval between metric collections
+    pub metrics_interval_ms: u64,
+    /// Host address metrics are served on
+    pub metrics_host: String,
+    /// Time collected metrics are kept
+    pub metrics_retention_ms: u64,
+    /// Prefix of every metric name
+    pub metrics_namespace: String,
+    /// Labels attached to every metric
+    pub metrics_labels: HashMap<String, String>,
+    /// Interval between health checks
+    pub health_check_interval_ms: u64,
+    /// Time budget for a health check
+    pub health_check_timeout_ms: u64,
+    /// Whether alerts are sent
+    pub enable_alerts: bool,
+    /// Webhook alerts are posted to; empty to disable
+    pub alert_webhook_url: String,
+    /// Address alerts are mailed to; empty to disable
+    pub alert_email: String,
+    /// Minimum time between two alerts
+    pub max_alert_frequency_ms: u64,
 }
 
-impl Default for Config {
+impl Default for MonitoringConfig {
     fn default() -> Self {
         Self {
-            server: ServerConfig {
-                host: "0.0.0.0".to_string(),
-                port: 8080,
-                workers: num_cpus::get(),
-            },
-            database: DatabaseConfig {
-                url: "postgresql://localhost/arbitrage".to_string(),
-                max_connections: 10,
-                min_connections: 1,
-            },
-            redis: RedisConfig {
-                url: "redis://localhost:6379".to_string(),
-                max_connections: 10,
-            },
-            solana: SolanaConfig {
-                rpc_url: "https://api.mainnet-beta.solana.com".to_string(),
-                ws_url: "wss://api.mainnet-beta.solana.com".to_string(),
-                commitment: "confirmed".to_string(),
-            },
-            arbitrage: ArbitrageConfig {
-                min_profit_threshold: 0.01, // 1%
-                max_slippage: 0.005,        // 0.5%
-                max_price_impact: 0.02,     // 2%
-                execution_timeout: 30,      // 30 seconds
-            },
-            logging: LoggingConfig {
-                level: "info".to_string(),
-                format: "json".to_string(),
-            },
+            log_level: "info".to_string(),
+            log_format: "json".to_string(),
+            log_file: "logs/arbitrage.log".to_string(),
+            max_log_size_mb: 100,
+            max_log_files: 10,
+            log_output: "stdout".to_string(),
+            compress_logs: false,
+            error_log_file: String::new(),
+            access_log_file: String::new(),
+            enable_metrics: true,
+            metrics_port: 9090,
+            metrics_path: "/metrics".to_string(),
+            metrics_interval_ms: 5000,
+            metrics_host: "0.0.0.0".to_string(),
+            metrics_retention_ms: 86_400_000,
+            metrics_namespace: "arbitrage_engine".to_string(),
+            metrics_labels: HashMap::new(),
+            health_check_interval_ms: 10_000,
+            health_check_timeout_ms: 5000,
+            enable_alerts: true,
+            alert_webhook_url: String::new(),
+            alert_email: String::new(),
+            max_alert_frequency_ms: 60_000,
+        }
+    }
+}
+
+/// Runtime tuning
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PerformanceConfig {
+    /// Threads the runtime may run in total
+    pub max_threads: usize,
+    /// Async worker threads
+    pub max_worker_threads: usize,
+    /// Threads for blocking work
+    pub max_blocking_threads: usize,
+    /// Stack size of spawned threads, in kilobytes
+    pub thread_stack_size_kb: usize,
+    /// Memory use above which caches are shed, in megabytes
+    pub max_memory_usage_mb: u64,
+    /// Interval between cache cleanups
+    pub gc_interval_ms: u64,
+    /// Largest in-memory cache, in megabytes
+    pub max_cache_size_mb: u64,
+    /// Outgoing requests in flight at once
+    pub max_concurrent_requests: usize,
+    /// Pooled HTTP clients
+    pub request_pool_size: usize,
+    /// Pooled RPC connections
+    pub connection_pool_size: usize,
+    /// Items processed per batch
+    pub batch_size: usize,
+    /// Time after which a partial batch is processed
+    pub batch_timeout_ms: u64,
+    /// Items queued for batching before producers wait
+    pub max_batch_queue_size: usize,
+    /// Whether file and socket I/O goes through io_uring
+    pub enable_io_uring: bool,
+}
+
+impl Default for PerformanceConfig {
+    fn default() -> Self {
+        Self {
+            max_threads: 12,
+            max_worker_threads: 8,
+            max_blocking_threads: 4,
+            thread_stack_size_kb: 2048,
+            max_memory_usage_mb: 2048,
+            gc_interval_ms: 60_000,
+            max_cache_size_mb: 512,
+            max_concurrent_requests: 100,
+            request_pool_size: 50,
+            connection_pool_size: 20,
+            batch_size: 100,
+            batch_timeout_ms: 1000,
+            max_batch_queue_size: 1000,
+            enable_io_uring: false,
+        }
+    }
+}
+
+/// API access control
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SecurityConfig {
+    /// Whether API requests are rate limited
+    pub enable_rate_limiting: bool,
+    /// Requests allowed per client per minute
+    pub max_requests_per_minute: u32,
+    /// Requests allowed per client per hour
+    pub max_requests_per_hour: u32,
+    /// Window the per-client request counts are kept over
+    pub rate_limit_window_ms: u64,
+    /// Largest request body accepted, in bytes
+    pub max_request_size: usize,
+    /// Whether API requests must carry a valid key
+    pub api_key_required: bool,
+    /// Header carrying the API key
+    pub api_key_header: String,
+    /// Accepted API keys
+    pub valid_api_keys: Vec<String>,
+    /// Whether API requests must carry a valid JWT
+    pub jwt_auth_enabled: bool,
+    /// Secret JWTs are signed with
+    pub jwt_secret: String,
+    /// Lifetime of an issued JWT
+    pub jwt_expiry_ms: u64,
+    /// If non-empty, the only client addresses served
+    pub ip_whitelist: Vec<String>,
+    /// Client addresses never served
+    pub ip_blacklist: Vec<String>,
+    /// Whether local clients are always served
+    pub allow_localhost: bool,
+    /// Origins allowed by CORS
+    pub cors_allowed_origins: Vec<String>,
+    /// Methods allowed by CORS
+    pub cors_allowed_methods: Vec<String>,
+    /// Headers allowed by CORS
+    pub cors_allowed_headers: Vec<String>,
+    /// Whether the API is served over TLS
+    pub enable_tls: bool,
+    /// TLS certificate file
+    pub tls_cert_path: String,
+    /// TLS private key file
+    pub tls_key_path: String,
+}
+
+impl Default for SecurityConfig {
+    fn default() -> Self {
+        let strings = |values: &[&str]| values.iter().map(|value| value.to_string()).collect();
+        Self {
+            enable_rate_limiting: true,
+            max_requests_per_minute: 1000,
+            max_requests_per_hour: 10_000,
+            rate_limit_window_ms: 60_000,
+            max_request_size: 1_048_576,
+            api_key_required: false,
+            api_key_header: "X-API-Key".to_string(),
+            valid_api_keys: Vec::new(),
+            jwt_auth_enabled: false,
+            jwt_secret: String::new(),
+            jwt_expiry_ms: 3_600_000,
+            ip_whitelist: Vec::new(),
+            ip_blacklist: Vec::new(),
+            allow_localhost: true,
+            cors_allowed_origins: strings(&["*"]),
+            cors_allowed_methods: strings(&["GET", "POST", "PUT", "DELETE"]),
+            cors_allowed_headers: strings(&["Content-Type", "Authorization"]),
+            enable_tls: false,
+            tls_cert_path: String::new(),
+            tls_key_path: String::new(),
+        }
+    }
+}
+
+/// Development and testing switches
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DevelopmentConfig {
+    /// Whether debug endpoints and logging are enabled
+    pub enable_debug_mode: bool,
+    /// Whether the profiler runs
+    pub enable_profiling: bool,
+    /// Whether the configuration is reloaded when it changes
+    pub enable_hot_reload: bool,
+    /// Whether the engine runs against the test cluster
+    pub enable_test_mode: bool,
+    /// RPC endpoint used in test mode
+    pub test_rpc_url: String,
+    /// Keypair file used in test mode
+    pub test_private_key: String,
+    /// Whether DEX API responses are mocked
+    pub mock_dex_responses: bool,
+    /// Whether price feeds are mocked
+    pub mock_price_feeds: bool,
+    /// Latency added to every network request
+    pub simulate_network_latency_ms: u64,
+    /// Whether transactions are faked instead of sent
+    pub mock_transactions: bool,
+    /// Whether every detected opportunity is logged, not just traded ones
+    pub log_all_opportunities: bool,
+    /// Whether trades skip the risk limits
+    pub bypass_risk_checks: bool,
+}
+
+impl Default for DevelopmentConfig {
+    fn default() -> Self {
+        Self {
+            enable_debug_mode: false,
+            enable_profiling: false,
+            enable_hot_reload: false,
+            enable_test_mode: false,
+            test_rpc_url: "https://api.devnet.solana.com".to_string(),
+            test_private_key: String::new(),
+            mock_dex_responses: false,
+            mock_price_feeds: false,
+            simulate_network_latency_ms: 0,
+            mock_transactions: false,
+            log_all_opportunities: false,
+            bypass_risk_checks: false,
+        }
+    }
+}
+
+/// Alert channels
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationsConfig {
+    /// Discord webhook alerts
+    pub discord: DiscordConfig,
+    /// Telegram bot alerts
+    pub telegram: TelegramConfig,
+    /// Email alerts
+    pub email: EmailConfig,
+}
+
+/// Discord webhook alerts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiscordConfig {
+    /// Whether alerts are posted
+    pub enabled: bool,
+    /// Webhook alerts are posted to
+    pub webhook_url: String,
+    /// Smallest trade profit alerted on, in USD
+    pub min_profit_alert: f64,
+    /// Whether errors are alerted on
+    pub error_alerts: bool,
+}
+
+impl Default for DiscordConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            webhook_url: String::new(),
+            min_profit_alert: 100.0,
+            error_alerts: true,
+        }
+    }
+}
+
+/// Telegram bot alerts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TelegramConfig {
+    /// Whether alerts are sent
+    pub enabled: bool,
+    /// Token of the bot sending alerts
+    pub bot_token: String,
+    /// Chat alerts are sent to
+    pub chat_id: String,
+    /// Smallest trade profit alerted on, in USD
+    pub min_profit_alert: f64,
+    /// Whether errors are alerted on
+    pub error_alerts: bool,
+}
+
+impl Default for TelegramConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bot_token: String::new(),
+            chat_id: String::new(),
+            min_profit_alert: 100.0,
+            error_alerts: true,
+        }
+    }
+}
+
+/// Email alerts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmailConfig {
+    /// Whether alerts are mailed
+    pub enabled: bool,
+    /// SMTP server host
+    pub smtp_host: String,
+    /// SMTP server port
+    pub smtp_port: u16,
+    /// SMTP user name
+    pub username: String,
+    /// SMTP password
+    pub password: String,
+    /// Addresses alerts are mailed to
+    pub to_addresses: Vec<String>,
+    /// Smallest trade profit alerted on, in USD
+    pub min_profit_alert: f64,
+    /// Whether errors are alerted on
+    pub error_alerts: bool,
+}
+
+impl Default for EmailConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            smtp_host: String::new(),
+            smtp_port: 587,
+            username: String::new(),
+            password: String::new(),
+            to_addresses: Vec::new(),
+            min_profit_alert: 100.0,
+            error_alerts: true,
+        }
+    }
+}
+
+/// Keys of the `config.example.toml` layout, the key of `Config` each one
+/// sets and the factor converting its unit to that key's, applied in order
+const EXAMPLE_KEYS: &[(&str, &str, f64)] = &[
+    (
+        "database.connection_timeout",
+        "database.connection_timeout_ms",
+        1000.0,
+    ),
+    ("database.idle_timeout", "database.idle_timeout_ms", 1000.0),
+    ("database.max_lifetime", "database.max_lifetime_ms", 1000.0),
+    ("redis", "cache", 1.0),
+    (
+        "cache.connection_timeout",
+        "cache.connection_timeout_ms",
+        1000.0,
+    ),
+    ("cache.idle_timeout", "cache.idle_timeout_ms", 1000.0),
+    ("cache.max_lifetime", "cache.max_lifetime_ms", 1000.0),
+    ("solana.timeout", "solana.timeout_ms", 1000.0),
+    ("solana.retry_delay", "solana.retry_delay_ms", 1.0),
+    ("solana.geyser.endpoint", "solana.geyser_endpoint", 1.0),
+    ("solana.geyser.token", "solana.geyser_token", 1.0),
+    (
+        "solana.geyser.max_message_size",
+        "solana.geyser_max_message_size",
+        1.0,
+    ),
+    (
+        "solana.geyser.connection_timeout",
+        "solana.geyser_timeout_ms",
+        1000.0,
+    ),
+    (
+        "solana.geyser.reconnect_interval",
+        "solana.geyser_reconnect_interval_ms",
+        1000.0,
+    ),
+    (
+        "solana.geyser.ping_interval",
+        "solana.geyser_ping_interval_ms",
+        1000.0,
+    ),
+    (
+        "dex.raydium.max_slippage",
+        "dex.raydium.max_slippage_bps",
+        10_000.0,
+    ),
+    (
+        "dex.raydium.min_liquidity",
+        "dex.raydium.min_liquidity_usd",
+        1.0,
+    ),
+    (
+        "dex.orca.max_slippage",
+        "dex.orca.max_slippage_bps",
+        10_000.0,
+    ),
+    ("dex.orca.min_liquidity", "dex.orca.min_liquidity_usd", 1.0),
+    (
+        "dex.meteora.max_slippage",
+        "dex.meteora.max_slippage_bps",
+        10_000.0,
+    ),
+    (
+        "dex.meteora.min_liquidity",
+        "dex.meteora.min_liquidity_usd",
+        1.0,
+    ),
+    (
+        "dex.jupiter.max_slippage",
+        "dex.jupiter.max_slippage_bps",
+        10_000.0,
+    ),
+    (
+        "dex.jupiter.min_liquidity",
+        "dex.jupiter.min_liquidity_usd",
+        1.0,
+    ),
+    (
+        "trading.max_position_size",
+        "trading.max_position_size_usd",
+        1.0,
+    ),
+    (
+        "trading.min_profit_threshold",
+        "opportunities.min_profit_percent",
+        100.0,
+    ),
+    ("trading.max_slippage", "trading.max_slippage_bps", 10_000.0),
+    ("trading.max_gas_price", "trading.max_gas_price_sol", 1.0),
+    (
+        "trading.trade_timeout",
+        "trading.max_execution_time_ms",
+        1000.0,
+    ),
+    ("risk.max_daily_loss", "risk.max_daily_loss_usd", 1.0),
+    (
+        "risk.max_position_size",
+        "trading.max_position_size_usd",
+        1.0,
+    ),
+    (
+        "risk.max_exposure_per_token",
+        "risk.max_token_exposure_usd",
+        1.0,
+    ),
+    (
+        "risk.max_exposure_per_dex",
+        "risk.max_dex_exposure_usd",
+        1.0,
+    ),
+    ("risk.stop_loss_threshold", "risk.stop_loss_percent", 100.0),
+    ("risk.max_drawdown", "risk.max_drawdown_percent", 100.0),
+    (
+        "risk.circuit_breaker.max_consecutive_losses",
+        "risk.max_consecutive_losses",
+        1.0,
+    ),
+    (
+        "risk.circuit_breaker.loss_threshold",
+        "risk.circuit_breaker_loss_usd",
+        1.0,
+    ),
+    (
+        "risk.circuit_breaker.cooldown_period",
+        "risk.circuit_breaker_cooldown_ms",
+        1000.0,
+    ),
+    (
+        "risk.circuit_breaker.recovery_threshold",
+        "risk.circuit_breaker_recovery_percent",
+        100.0,
+    ),
+    ("api.host", "server.host", 1.0),
+    ("api.port", "server.port", 1.0),
+    ("api.max_connections", "server.max_connections", 1.0),
+    ("api.timeout", "server.request_timeout_ms", 1000.0),
+    ("api.cors_origins", "security.cors_allowed_origins", 1.0),
+    ("api.rate_limit", "security.max_requests_per_minute", 1.0),
+    ("api.auth.enabled", "security.jwt_auth_enabled", 1.0),
+    ("api.auth.jwt_secret", "security.jwt_secret", 1.0),
+    ("api.auth.token_expiry", "security.jwt_expiry_ms", 1000.0),
+    ("websocket.host", "server.ws_host", 1.0),
+    ("websocket.port", "server.ws_port", 1.0),
+    (
+        "websocket.max_connections",
+        "server.ws_max_connections",
+        1.0,
+    ),
+    (
+        "websocket.ping_interval",
+        "server.ws_heartbeat_interval_ms",
+        1000.0,
+    ),
+    (
+        "websocket.pong_timeout",
+        "server.ws_pong_timeout_ms",
+        1000.0,
+    ),
+    (
+        "websocket.max_message_size",
+        "server.ws_max_message_size",
+        1.0,
+    ),
+    ("metrics.host", "monitoring.metrics_host", 1.0),
+    ("metrics.port", "monitoring.metrics_port", 1.0),
+    ("metrics.path", "monitoring.metrics_path", 1.0),
+    (
+        "metrics.collection_interval",
+        "monitoring.metrics_interval_ms",
+        1000.0,
+    ),
+    (
+        "metrics.retention_period",
+        "monitoring.metrics_retention_ms",
+        1000.0,
+    ),
+    (
+        "metrics.prometheus.enabled",
+        "monitoring.enable_metrics",
+        1.0,
+    ),
+    (
+        "metrics.prometheus.namespace",
+        "monitoring.metrics_namespace",
+        1.0,
+    ),
+    (
+        "metrics.prometheus.labels",
+        "monitoring.metrics_labels",
+        1.0,
+    ),
+    ("logging.level", "monitoring.log_level", 1.0),
+    ("logging.format", "monitoring.log_format", 1.0),
+    ("logging.output", "monitoring.log_output", 1.0),
+    ("logging.max_file_size", "monitoring.max_log_size_mb", 1.0),
+    ("logging.max_files", "monitoring.max_log_files", 1.0),
+    ("logging.compress", "monitoring.compress_logs", 1.0),
+    ("logging.targets.file", "monitoring.log_file", 1.0),
+    (
+        "logging.targets.error_file",
+        "monitoring.error_log_file",
+        1.0,
+    ),
+    (
+        "logging.targets.access_file",
+        "monitoring.access_log_file",
+        1.0,
+    ),
+    (
+        "performance.worker_threads",
+        "performance.max_worker_threads",
+        1.0,
+    ),
+    (
+        "performance.blocking_threads",
+        "performance.max_blocking_threads",
+        1.0,
+    ),
+    (
+        "performance.thread_stack_size",
+        "performance.thread_stack_size_kb",
+        1.0 / 1024.0,
+    ),
+    (
+        "performance.memory.max_heap_size",
+        "performance.max_memory_usage_mb",
+        1.0 / 1_048_576.0,
+    ),
+    (
+        "performance.memory.garbage_collection_interval",
+        "performance.gc_interval_ms",
+        1000.0,
+    ),
+    (
+        "performance.memory.cache_size",
+        "performance.max_cache_size_mb",
+        1.0 / 1_048_576.0,
+    ),
+    (
+        "security.rate_limit_window",
+        "security.rate_limit_window_ms",
+        1000.0,
+    ),
+    (
+        "security.access_control.whitelist",
+        "security.ip_whitelist",
+        1.0,
+    ),
+    (
+        "security.access_control.blacklist",
+        "security.ip_blacklist",
+        1.0,
+    ),
+    (
+        "security.access_control.allow_localhost",
+        "security.allow_localhost",
+        1.0,
+    ),
+    (
+        "development.debug_mode",
+        "development.enable_debug_mode",
+        1.0,
+    ),
+    ("development.dry_run", "trading.dry_run", 1.0),
+];
+
+/// Move the keys of the `config.example.toml` layout in `file` to the keys of
+/// `Config`, converting their units. Keys set under both names keep the
+/// value given under `Config`'s.
+fn normalize_layout(file: &mut toml::Table) {
+    for (from, to, factor) in EXAMPLE_KEYS {
+        if let Some(value) = take_key(file, from) {
+            put_key(file, to, scale(value, *factor));
+        }
+    }
+}
+
+/// Remove the dotted `path` from `table`, dropping tables it leaves empty
+fn take_key(table: &mut toml::Table, path: &str) -> Option<toml::Value> {
+    let Some((head, rest)) = path.split_once('.') else {
+        return table.remove(path);
+    };
+    let child = table.get_mut(head)?.as_table_mut()?;
+    let value = take_key(child, rest);
+    if child.is_empty() {
+        table.remove(head);
+    }
+    value
+}
+
+/// Set the dotted `path` of `table` to `value` unless it is set already,
+/// merging tables key by key
+fn put_key(table: &mut toml::Table, path: &str, value: toml::Value) {
+    let Some((head, rest)) = path.split_once('.') else {
+        match (table.get_mut(path), value) {
+            (None, value) => {
+                table.insert(path.to_string(), value);
+            }
+            (Some(toml::Value::Table(held)), toml::Value::Table(value)) => {
+                for (key, value) in value {
+                    put_key(held, &key, value);
+                }
+            }
+            _ => {}
         }
+        return;
+    };
+    if let Some(child) = table
+        .entry(head)
+        .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+        .as_table_mut()
+    {
+        put_key(child, rest, value);
+    }
+}
+
+/// `value` multiplied by `factor` if it is a number, kept an integer when the
+/// result is whole
+fn scale(value: toml::Value, factor: f64) -> toml::Value {
+    let scaled = match value {
+        toml::Value::Integer(value) => value as f64 * factor,
+        toml::Value::Float(value) => value * factor,
+        value => return value,
+    };
+    if (scaled - scaled.round()).abs() < 1e-9 {
+        toml::Value::Integer(scaled.round() as i64)
+    } else {
+        toml::Value::Float(scaled)
     }
 }
 
@@ -143,7 +1539,7 @@ impl Config {
 
         // Redis configuration
         if let Ok(url) = env::var("REDIS_URL") {
-            config.redis.url = url;
+            config.cache.url = url;
         }
 
         // Solana configuration
@@ -153,10 +1549,24 @@ impl Config {
         if let Ok(url) = env::var("SOLANA_WS_URL") {
             config.solana.ws_url = url;
         }
+        if let Ok(endpoint) = env::var("GEYSER_ENDPOINT") {
+            config.solana.geyser_endpoint = endpoint;
+        }
+        if let Ok(token) = env::var("GEYSER_TOKEN") {
+            config.solana.geyser_token = token;
+        }
+        if let Ok(path) = env::var("SOLANA_PRIVATE_KEY_PATH") {
+            config.trading.wallet.keypair_path = path;
+        }
+
+        // Jito configuration
+        if let Ok(url) = env::var("JITO_BLOCK_ENGINE_URL") {
+            config.jito.block_engine_url = url;
+        }
 
         // Logging configuration
         if let Ok(level) = env::var("LOG_LEVEL") {
-            config.logging.level = level;
+            config.monitoring.log_level = level;
         }
 
         Ok(config)
@@ -167,7 +1577,129 @@ impl Config {
         let content = std::fs::read_to_string(path)
             .map_err(|e| ArbitrageError::config(format!("Failed to read config file: {}", e)))?;
 
-        toml::from_str(&content)
-            .map_err(|e| ArbitrageError::config(format!("Failed to parse config file: {}", e)))
+        Self::from_toml(&content)
+    }
+
+    /// Parse a TOML configuration laid out like `config.toml` or
+    /// `config.example.toml`
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let parse_error = |e: toml::de::Error| {
+            ArbitrageError::config(format!("Failed to parse config file: {}", e))
+        };
+        let mut file: toml::Table = toml::from_str(content).map_err(parse_error)?;
+        normalize_layout(&mut file);
+        toml::Value::Table(file).try_into().map_err(parse_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = include_str!("../../config.toml");
+    const EXAMPLE: &str = include_str!("../config.example.toml");
+
+    /// Keys of `file` missing from `parsed`, as dotted paths
+    fn unmodeled(file: &toml::Value, parsed: &toml::Value, path: &str, missing: &mut Vec<String>) {
+        let (Some(file), Some(parsed)) = (file.as_table(), parsed.as_table()) else {
+            return;
+        };
+        for (key, value) in file {
+            let path = format!("{}{}", path, key);
+            match parsed.get(key) {
+                Some(modeled) => unmodeled(value, modeled, &format!("{}.", path), missing),
+                None => missing.push(path),
+            }
+        }
+    }
+
+    fn parse(content: &str) -> Config {
+        let config = Config::from_toml(content).unwrap();
+        let mut file: toml::Table = toml::from_str(content).unwrap();
+        normalize_layout(&mut file);
+        let parsed = toml::Value::try_from(&config).unwrap();
+        let mut missing = Vec::new();
+        unmodeled(&toml::Value::Table(file), &parsed, "", &mut missing);
+        assert!(missing.is_empty(), "unmodeled keys: {:?}", missing);
+        config
+    }
+
+    #[test]
+    fn test_parses_shipped_config() {
+        // Already in Config's layout
+        let mut file: toml::Table = toml::from_str(CONFIG).unwrap();
+        normalize_layout(&mut file);
+        assert_eq!(file, toml::from_str::<toml::Table>(CONFIG).unwrap());
+
+        let config = parse(CONFIG);
+        assert_eq!(config.server.ws_port, 8081);
+        assert_eq!(config.database.migration_path, "migrations");
+        assert_eq!(config.cache.key_prefix, "arb:");
+        assert_eq!(config.solana.rpc_urls.len(), 2);
+        assert_eq!(config.solana.transaction_max_retries, 5);
+        assert_eq!(config.dex.meteora.max_slippage_bps, 150);
+        assert_eq!(config.dex.jupiter.max_accounts, 64);
+        assert_eq!(config.trading.max_concurrent_trades, 5);
+        assert!(config.trading.wallet.auto_rebalance);
+        assert_eq!(config.risk.max_consecutive_losses, 5);
+        assert_eq!(config.monitoring.metrics_port, 9090);
+        assert_eq!(config.opportunities.max_scan_depth, 3);
+        assert_eq!(config.tokens.by_symbol("MNGO").unwrap().decimals, 6);
+        assert_eq!(config.performance.batch_size, 100);
+        assert_eq!(config.security.api_key_header, "X-API-Key");
+        assert_eq!(
+            config.development.test_rpc_url,
+            "https://api.devnet.solana.com"
+        );
+    }
+
+    #[test]
+    fn test_parses_example_config() {
+        let config = parse(EXAMPLE);
+        assert_eq!(
+            config.trading.wallet.keypair_path,
+            "/path/to/your/keypair.json"
+        );
+        assert_eq!(config.solana.geyser_token, "your-geyser-token");
+        assert_eq!(config.risk.max_drawdown_percent, 10.0);
+
+        // Units are converted to Config's
+        assert_eq!(config.database.connection_timeout_ms, 30_000);
+        assert_eq!(config.cache.connection_timeout_ms, 5000);
+        assert_eq!(config.cache.default_ttl, 300);
+        assert_eq!(config.solana.geyser_timeout_ms, 10_000);
+        assert_eq!(config.dex.orca.max_slippage_bps, 100);
+        assert_eq!(config.trading.max_slippage_bps, 100);
+        assert_eq!(config.trading.max_execution_time_ms, 30_000);
+        assert_eq!(config.opportunities.min_profit_percent, 0.5);
+        assert_eq!(config.risk.stop_loss_percent, 5.0);
+        assert_eq!(config.risk.max_token_exposure_usd, Some(5000.0));
+        assert_eq!(config.risk.circuit_breaker_cooldown_ms, 300_000);
+        assert_eq!(config.server.ws_heartbeat_interval_ms, 30_000);
+        assert_eq!(
+            config.monitoring.log_file,
+            "/var/log/arbitrage-engine/app.log"
+        );
+        assert_eq!(config.performance.thread_stack_size_kb, 2048);
+        assert_eq!(config.performance.max_memory_usage_mb, 2048);
+        assert_eq!(config.notifications.email.smtp_port, 587);
+
+        // Keys set under both names keep Config's
+        let config =
+            Config::from_toml("[solana]\ntimeout_ms = 2500\ntimeout = 30\n[redis]\ndb = 2\n")
+                .unwrap();
+        assert_eq!(config.solana.timeout_ms, 2500);
+        assert_eq!(config.cache.db, 2);
+
+        // Missing sections and keys fall back to the defaults
+        let config = Config::from_toml("[server]\nport = 9000\n[redis]\ndb = 2\n").unwrap();
+        assert_eq!(config.server.port, 9000);
+        assert_eq!(config.server.host, "0.0.0.0");
+        assert_eq!(config.cache.db, 2);
+        assert_eq!(config.risk.max_daily_loss_usd, 5000.0);
+        assert_eq!(
+            config.dex.raydium.program_id,
+            Config::default().dex.raydium.program_id
+        );
     }
 }
@@ -0,0 +1,236 @@
+//! Streaming data sources
+//!
+//! Geyser and the RPC websocket expose the same subscriptions through
+//! [`DataSource`]. [`connect`] picks Geyser when an endpoint is configured and
+//! falls back to the websocket otherwise. [`subscribe_pools`] keeps the
+//! subscriptions in step with the pool cache, adding the accounts of pools
+//! and dependents it starts tracking after the initial seed.
+
+use crate::config::SolanaConfig;
+use crate::error::Result;
+use crate::geyser::{GeyserClient, SlotUpdate, UpdateStream};
+use crate::pool_cache::{AccountUpdate, PoolCache};
+use crate::websocket::WsClient;
+use futures::stream::SelectAll;
+use futures::StreamExt;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::MissedTickBehavior;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{debug, info};
+
+/// Buffered pool updates before the subscriptions wait for the consumer
+const POOL_UPDATE_BUFFER: usize = 1024;
+
+/// Source of account and slot updates
+pub trait DataSource: Send + Sync + std::fmt::Debug {
+    /// Short name for logs and status
+    fn name(&self) -> &'static str;
+
+    /// Stream updates of specific accounts
+    fn subscribe_accounts(&self, accounts: &[Pubkey]) -> UpdateStream<AccountUpdate>;
+
+    /// Stream updates of every account owned by `programs`
+    fn subscribe_programs(&self, programs: &[Pubkey]) -> UpdateStream<AccountUpdate>;
+
+    /// Stream slot progress
+    fn subscribe_slots(&self) -> UpdateStream<SlotUpdate>;
+}
+
+impl DataSource for GeyserClient {
+    fn name(&self) -> &'static str {
+        "geyser"
+    }
+
+    fn subscribe_accounts(&self, accounts: &[Pubkey]) -> UpdateStream<AccountUpdate> {
+        GeyserClient::subscribe_accounts(self, accounts)
+    }
+
+    fn subscribe_programs(&self, programs: &[Pubkey]) -> UpdateStream<AccountUpdate> {
+        GeyserClient::subscribe_programs(self, programs)
+    }
+
+    fn subscribe_slots(&self) -> UpdateStream<SlotUpdate> {
+        GeyserClient::subscribe_slots(self)
+    }
+}
+
+impl DataSource for WsClient {
+    fn name(&self) -> &'static str {
+        "websocket"
+    }
+
+    fn subscribe_accounts(&self, accounts: &[Pubkey]) -> UpdateStream<AccountUpdate> {
+        WsClient::subscribe_accounts(self, accounts)
+    }
+
+    fn subscribe_programs(&self, programs: &[Pubkey]) -> UpdateStream<AccountUpdate> {
+        WsClient::subscribe_programs(self, programs)
+    }
+
+    fn subscribe_slots(&self) -> UpdateStream<SlotUpdate> {
+        WsClient::subscribe_slots(self)
+    }
+}
+
+/// Create the data source for `config`: Geyser if `geyser_endpoint` is set,
+/// the RPC websocket otherwise
+pub fn connect(config: &SolanaConfig) -> Result<Arc<dyn DataSource>> {
+    match GeyserClient::from_config(config)? {
+        Some(geyser) => {
+            info!("Streaming updates from Geyser at {}", geyser.endpoint());
+            Ok(Arc::new(geyser))
+        }
+        None => {
+            info!(
+                "No Geyser endpoint configured, streaming updates from {}",
+                config.ws_url
+            );
+            Ok(Arc::new(WsClient::from_config(config)))
+        }
+    }
+}
+
+/// Stream updates of every account owned by `programs` and of every account
+/// `pool_cache` tracks, checking every `refresh` for accounts it started
+/// tracking since, such as the vaults of newly discovered pools or the tick
+/// arrays a price moved into, and subscribing to those too
+pub fn subscribe_pools(
+    source: Arc<dyn DataSource>,
+    pool_cache: Arc<PoolCache>,
+    programs: &[Pubkey],
+    refresh: Duration,
+) -> UpdateStream<AccountUpdate> {
+    let (tx, rx) = mpsc::channel(POOL_UPDATE_BUFFER);
+    let programs = source.subscribe_programs(programs);
+    tokio::spawn(forward_pool_updates(
+        source, pool_cache, programs, refresh, tx,
+    ));
+    Box::pin(ReceiverStream::new(rx))
+}
+
+async fn forward_pool_updates(
+    source: Arc<dyn DataSource>,
+    pool_cache: Arc<PoolCache>,
+    programs: UpdateStream<AccountUpdate>,
+    refresh: Duration,
+    tx: mpsc::Sender<AccountUpdate>,
+) {
+    let mut updates = SelectAll::new();
+    updates.push(programs);
+    let mut subscribed = HashSet::new();
+    let mut interval = tokio::time::interval(refresh);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            update = updates.next() => {
+                let Some(update) = update else {
+                    debug!("Pool subscriptions ended");
+                    return;
+                };
+                if tx.send(update).await.is_err() {
+                    return;
+                }
+            }
+            _ = interval.tick() => {
+                let accounts: Vec<Pubkey> = pool_cache
+                    .tracked_accounts()
+                    .into_iter()
+                    .filter(|account| subscribed.insert(*account))
+                    .collect();
+                if !accounts.is_empty() {
+                    debug!("Subscribing to {} newly tracked accounts", accounts.len());
+                    updates.push(source.subscribe_accounts(&accounts));
+                }
+            }
+            _ = tx.closed() => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::dex::raydium::RaydiumAmmAdapter;
+    use crate::dex::DexManager;
+    use crate::test_support::test_pool;
+
+    /// Source recording the account subscriptions made, which never yield
+    #[derive(Debug, Default)]
+    struct RecordingSource {
+        accounts: parking_lot::Mutex<Vec<Vec<Pubkey>>>,
+    }
+
+    impl DataSource for RecordingSource {
+        fn name(&self) -> &'static str {
+            "recording"
+        }
+
+        fn subscribe_accounts(&self, accounts: &[Pubkey]) -> UpdateStream<AccountUpdate> {
+            self.accounts.lock().push(accounts.to_vec());
+            Box::pin(futures::stream::pending())
+        }
+
+        fn subscribe_programs(&self, _programs: &[Pubkey]) -> UpdateStream<AccountUpdate> {
+            Box::pin(futures::stream::pending())
+        }
+
+        fn subscribe_slots(&self) -> UpdateStream<SlotUpdate> {
+            Box::pin(futures::stream::pending())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pools_tracked_later_are_subscribed() {
+        let mut dex = DexManager::new();
+        dex.register(Arc::new(RaydiumAmmAdapter::new()));
+        let cache = Arc::new(PoolCache::new(Arc::new(dex), Duration::from_secs(60)));
+        let source = Arc::new(RecordingSource::default());
+        let tracked = |cache: &PoolCache| -> HashSet<Pubkey> {
+            cache.tracked_accounts().into_iter().collect()
+        };
+        let subscribed = |source: &RecordingSource| -> Vec<HashSet<Pubkey>> {
+            let accounts = source.accounts.lock();
+            accounts
+                .iter()
+                .map(|accounts| accounts.iter().copied().collect())
+                .collect()
+        };
+
+        let seed = test_pool(Pubkey::new_unique(), Pubkey::new_unique(), 1, 1, 25);
+        cache.insert(seed, 1, 0).unwrap();
+        let seeded = tracked(&cache);
+        let _updates = subscribe_pools(
+            source.clone(),
+            cache.clone(),
+            &[],
+            Duration::from_millis(10),
+        );
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        assert_eq!(subscribed(&source), vec![seeded.clone()]);
+
+        // A pool discovered later gets only its own accounts subscribed
+        let discovered = test_pool(Pubkey::new_unique(), Pubkey::new_unique(), 1, 1, 25);
+        let address = discovered.address;
+        cache.insert(discovered, 2, 0).unwrap();
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        let added = &tracked(&cache) - &seeded;
+        assert!(added.contains(&address));
+        assert!(added.len() > 1);
+        assert_eq!(subscribed(&source), vec![seeded, added]);
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_to_websocket() {
+        let mut config = Config::default().solana;
+        assert_eq!(connect(&config).unwrap().name(), "websocket");
+
+        config.geyser_endpoint = "grpc://127.0.0.1:10000".to_string();
+        assert_eq!(connect(&config).unwrap().name(), "geyser");
+    }
+}
@@ -1,15 +1,416 @@
 //! DEX integration module
+//!
+//! Every supported protocol implements [`DexAdapter`], and [`DexManager`]
+//! keeps the registry of adapters keyed by DEX id, so strategies can decode
+//! pools, quote and build swaps without knowing which protocol they talk to.
+
+pub mod dlmm;
+pub mod jupiter;
+pub(crate) mod layout;
+pub mod math;
+pub mod raydium;
+pub mod whirlpool;
+
+pub use dlmm::{DlmmAdapter, DlmmState};
+pub use jupiter::{JupiterClient, JupiterQuote, JupiterSwapInstructions};
+pub use raydium::{RaydiumAmmAdapter, RaydiumAmmState};
+pub use whirlpool::{WhirlpoolAdapter, WhirlpoolState};
+
+use crate::config::DexConfig;
+use crate::error::{ArbitrageError, Result};
+use serde::{Deserialize, Serialize};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::RpcFilterType;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+/// Raydium DEX id
+pub const RAYDIUM: &str = "raydium";
+/// Orca DEX id
+pub const ORCA: &str = "orca";
+/// Meteora DEX id
+pub const METEORA: &str = "meteora";
+/// Jupiter aggregator id
+pub const JUPITER: &str = "jupiter";
+
+/// SPL token program id
+pub const TOKEN_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+/// Wrapped SOL mint
+pub const NATIVE_MINT: Pubkey =
+    Pubkey::from_str_const("So11111111111111111111111111111111111111112");
+
+/// SPL Token-2022 program id
+pub const TOKEN_2022_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+/// Which side of a swap the amount refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SwapMode {
+    /// The amount is the exact input, output is quoted
+    ExactIn,
+    /// The amount is the exact output, input is quoted
+    ExactOut,
+}
+
+/// Quote request against a single pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuoteRequest {
+    /// Mint of the token going into the pool
+    pub input_mint: Pubkey,
+    /// Input amount for `ExactIn`, output amount for `ExactOut`
+    pub amount: u64,
+    /// Swap mode
+    pub mode: SwapMode,
+}
+
+impl QuoteRequest {
+    /// Create an exact-in quote request
+    pub fn exact_in(input_mint: Pubkey, amount: u64) -> Self {
+        Self {
+            input_mint,
+            amount,
+            mode: SwapMode::ExactIn,
+        }
+    }
+
+    /// Create an exact-out quote request
+    pub fn exact_out(input_mint: Pubkey, amount: u64) -> Self {
+        Self {
+            input_mint,
+            amount,
+            mode: SwapMode::ExactOut,
+        }
+    }
+}
+
+/// Result of quoting a swap against a pool
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Quote {
+    /// Mint of the token going into the pool
+    pub input_mint: Pubkey,
+    /// Mint of the token coming out of the pool
+    pub output_mint: Pubkey,
+    /// Amount of input token spent, fees included
+    pub in_amount: u64,
+    /// Amount of output token received
+    pub out_amount: u64,
+    /// Fee charged by the pool, denominated in the input token
+    pub fee_amount: u64,
+    /// Price impact versus the pool's spot price, in basis points
+    pub price_impact_bps: u32,
+}
+
+/// Protocol-specific decoded pool data
+#[derive(Debug, Clone, PartialEq)]
+pub enum PoolData {
+    /// Undecoded account data, for pools without a typed decoder
+    Raw(Vec<u8>),
+    /// Raydium AMM v4 pool
+    Raydium(RaydiumAmmState),
+    /// Orca Whirlpool with its loaded tick arrays
+    Whirlpool(WhirlpoolState),
+    /// Meteora DLMM pair with its loaded bin arrays
+    Dlmm(DlmmState),
+}
+
+/// Decoded state of a liquidity pool
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolState {
+    /// Pool account address
+    pub address: Pubkey,
+    /// Id of the DEX owning the pool
+    pub dex: &'static str,
+    /// First mint of the pair, in the pool's own ordering
+    pub mint_a: Pubkey,
+    /// Second mint of the pair, in the pool's own ordering
+    pub mint_b: Pubkey,
+    /// Protocol-specific state
+    pub data: PoolData,
+}
+
+impl PoolState {
+    /// Check whether the pool trades the given mint
+    pub fn contains_mint(&self, mint: &Pubkey) -> bool {
+        self.mint_a == *mint || self.mint_b == *mint
+    }
+
+    /// Get the counterpart of `mint` in this pool
+    pub fn other_mint(&self, mint: &Pubkey) -> Option<Pubkey> {
+        if self.mint_a == *mint {
+            Some(self.mint_b)
+        } else if self.mint_b == *mint {
+            Some(self.mint_a)
+        } else {
+            None
+        }
+    }
+
+    /// Check whether swapping `input_mint` moves from `mint_a` to `mint_b`
+    pub fn is_a_to_b(&self, input_mint: &Pubkey) -> Result<bool> {
+        if self.mint_a == *input_mint {
+            Ok(true)
+        } else if self.mint_b == *input_mint {
+            Ok(false)
+        } else {
+            Err(ArbitrageError::dex_integration(format!(
+                "Mint {} is not traded by pool {}",
+                input_mint, self.address
+            )))
+        }
+    }
+}
+
+/// User accounts and limits needed to build a swap instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapParams {
+    /// Wallet signing the swap
+    pub user: Pubkey,
+    /// Token account debited with the input token
+    pub source_token_account: Pubkey,
+    /// Token account credited with the output token
+    pub destination_token_account: Pubkey,
+    /// Mint of the token going into the pool
+    pub input_mint: Pubkey,
+    /// Input amount for `ExactIn`, output amount for `ExactOut`
+    pub amount: u64,
+    /// Minimum output for `ExactIn`, maximum input for `ExactOut`
+    pub other_amount_threshold: u64,
+    /// Swap mode
+    pub mode: SwapMode,
+}
+
+/// Protocol-agnostic interface implemented by every DEX integration
+pub trait DexAdapter: Send + Sync + std::fmt::Debug {
+    /// DEX id the adapter is registered under
+    fn id(&self) -> &'static str;
+
+    /// Program owning the pool accounts
+    fn program_id(&self) -> Pubkey;
+
+    /// Data length of pool accounts, used to filter pool discovery
+    fn pool_account_len(&self) -> usize;
+
+    /// Decode a pool account from raw account data
+    fn decode_pool(&self, address: Pubkey, data: &[u8]) -> Result<PoolState>;
+
+    /// Accounts other than the pool itself that quoting depends on
+    fn dependent_accounts(&self, _pool: &PoolState) -> Vec<Pubkey> {
+        Vec::new()
+    }
+
+    /// Apply raw data of one of the pool's dependent accounts
+    fn apply_dependent_account(
+        &self,
+        _pool: &mut PoolState,
+        _address: &Pubkey,
+        _data: &[u8],
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Token amounts backing the pool's current price, as `(mint_a, mint_b)`,
+    /// if the protocol exposes them
+    fn reserves(&self, _pool: &PoolState) -> Option<(u64, u64)> {
+        None
+    }
+
+    /// Token program owning `mint`'s accounts, for a mint of the pool
+    fn token_program(&self, _pool: &PoolState, _mint: &Pubkey) -> Pubkey {
+        TOKEN_PROGRAM_ID
+    }
+
+    /// Quote a swap against the pool
+    fn quote(&self, pool: &PoolState, request: &QuoteRequest) -> Result<Quote>;
+
+    /// Build the swap instruction for the pool
+    fn swap_instruction(&self, pool: &PoolState, params: &SwapParams) -> Result<Instruction>;
+}
 
 /// DEX integration manager
 #[derive(Debug)]
 pub struct DexManager {
-    // TODO: Add DEX integrations
+    adapters: HashMap<String, Arc<dyn DexAdapter>>,
+    jupiter: Option<Arc<JupiterClient>>,
 }
 
 impl DexManager {
     /// Create a new DEX manager
     pub fn new() -> Self {
-        Self {}
+        Self {
+            adapters: HashMap::new(),
+            jupiter: None,
+        }
+    }
+
+    /// Create a DEX manager with every built-in pool adapter registered.
+    ///
+    /// Jupiter is not among them: it routes through an HTTP API rather than
+    /// pool accounts, so it has nothing to decode or quote locally and is
+    /// attached separately with [`DexManager::with_jupiter`].
+    pub fn with_default_adapters() -> Self {
+        let mut manager = Self::new();
+        manager.register(Arc::new(RaydiumAmmAdapter::new()));
+        manager.register(Arc::new(WhirlpoolAdapter::new()));
+        manager.register(Arc::new(DlmmAdapter::new()));
+        manager
+    }
+
+    /// Create a DEX manager with the built-in adapters of every enabled DEX,
+    /// plus a Jupiter client if Jupiter is enabled
+    pub fn from_config(config: &DexConfig) -> Result<Self> {
+        let mut manager = Self::with_default_adapters();
+        for (dex, program) in [
+            (RAYDIUM, &config.raydium),
+            (ORCA, &config.orca),
+            (METEORA, &config.meteora),
+        ] {
+            if !program.enabled {
+                manager.unregister(dex);
+            }
+        }
+        if config.jupiter.enabled {
+            manager = manager.with_jupiter(JupiterClient::new(config.jupiter.clone())?);
+        }
+        Ok(manager)
+    }
+
+    /// Attach a Jupiter client used for routed quotes and price checks
+    pub fn with_jupiter(mut self, client: JupiterClient) -> Self {
+        debug!("Attaching Jupiter client for {}", client.config().api_url);
+        self.jupiter = Some(Arc::new(client));
+        self
+    }
+
+    /// Jupiter client, if one is attached
+    pub fn jupiter(&self) -> Option<Arc<JupiterClient>> {
+        self.jupiter.clone()
+    }
+
+    /// Register an adapter under its DEX id, returning the one it replaces
+    pub fn register(&mut self, adapter: Arc<dyn DexAdapter>) -> Option<Arc<dyn DexAdapter>> {
+        let id = adapter.id().to_string();
+        debug!("Registering DEX adapter {}", id);
+        self.adapters.insert(id, adapter)
+    }
+
+    /// Remove the adapter registered under `dex`
+    pub fn unregister(&mut self, dex: &str) -> Option<Arc<dyn DexAdapter>> {
+        self.adapters.remove(dex)
+    }
+
+    /// Get the adapter registered under `dex`
+    pub fn get(&self, dex: &str) -> Option<Arc<dyn DexAdapter>> {
+        self.adapters.get(dex).cloned()
+    }
+
+    /// Get the adapter registered under `dex`, failing if there is none
+    pub fn adapter(&self, dex: &str) -> Result<Arc<dyn DexAdapter>> {
+        self.get(dex)
+            .ok_or_else(|| ArbitrageError::dex_integration(format!("Unknown DEX: {}", dex)))
+    }
+
+    /// Get the adapter whose program owns accounts of `program_id`
+    pub fn adapter_for_program(&self, program_id: &Pubkey) -> Option<Arc<dyn DexAdapter>> {
+        self.adapters
+            .values()
+            .find(|adapter| adapter.program_id() == *program_id)
+            .cloned()
+    }
+
+    /// Ids of all registered DEXes
+    pub fn dex_ids(&self) -> Vec<&str> {
+        self.adapters.keys().map(String::as_str).collect()
+    }
+
+    /// Iterate over all registered adapters
+    pub fn adapters(&self) -> impl Iterator<Item = &Arc<dyn DexAdapter>> {
+        self.adapters.values()
+    }
+
+    /// Number of registered adapters
+    pub fn len(&self) -> usize {
+        self.adapters.len()
+    }
+
+    /// Check whether no adapter is registered
+    pub fn is_empty(&self) -> bool {
+        self.adapters.is_empty()
+    }
+
+    /// Quote a swap against a pool using the adapter of the pool's DEX
+    pub fn quote(&self, pool: &PoolState, request: &QuoteRequest) -> Result<Quote> {
+        self.adapter(pool.dex)?.quote(pool, request)
+    }
+
+    /// Reserves of a pool as reported by the adapter of the pool's DEX
+    pub fn reserves(&self, pool: &PoolState) -> Option<(u64, u64)> {
+        self.get(pool.dex)?.reserves(pool)
+    }
+
+    /// Token program of one of a pool's mints, as reported by the adapter of
+    /// the pool's DEX
+    pub fn token_program(&self, pool: &PoolState, mint: &Pubkey) -> Result<Pubkey> {
+        Ok(self.adapter(pool.dex)?.token_program(pool, mint))
+    }
+
+    /// Build a swap instruction using the adapter of the pool's DEX
+    pub fn swap_instruction(&self, pool: &PoolState, params: &SwapParams) -> Result<Instruction> {
+        self.adapter(pool.dex)?.swap_instruction(pool, params)
+    }
+
+    /// Decode an account owned by `owner`, if some adapter handles that program
+    pub fn decode_pool(
+        &self,
+        owner: &Pubkey,
+        address: Pubkey,
+        data: &[u8],
+    ) -> Option<Result<PoolState>> {
+        let adapter = self.adapter_for_program(owner)?;
+        if data.len() != adapter.pool_account_len() {
+            return None;
+        }
+        Some(adapter.decode_pool(address, data))
+    }
+
+    /// Fetch and decode every pool account of `dex` from RPC
+    pub async fn discover_pools(&self, rpc: &RpcClient, dex: &str) -> Result<Vec<PoolState>> {
+        let adapter = self.adapter(dex)?;
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::DataSize(
+                adapter.pool_account_len() as u64
+            )]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let accounts = rpc
+            .get_program_ui_accounts_with_config(&adapter.program_id(), config)
+            .await?;
+
+        let mut pools = Vec::with_capacity(accounts.len());
+        for (address, account) in accounts {
+            let Some(data) = account.data.decode() else {
+                warn!("Skipping undecodable {} account {}", dex, address);
+                continue;
+            };
+            match adapter.decode_pool(address, &data) {
+                Ok(pool) => pools.push(pool),
+                Err(e) => warn!("Skipping undecodable {} pool {}: {}", dex, address, e),
+            }
+        }
+
+        debug!("Discovered {} {} pools", pools.len(), dex);
+        Ok(pools)
     }
 }
 
@@ -18,3 +419,117 @@ impl Default for DexManager {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::instruction::AccountMeta;
+
+    #[derive(Debug)]
+    struct FixedRateAdapter;
+
+    impl DexAdapter for FixedRateAdapter {
+        fn id(&self) -> &'static str {
+            RAYDIUM
+        }
+
+        fn program_id(&self) -> Pubkey {
+            Pubkey::new_from_array([7; 32])
+        }
+
+        fn pool_account_len(&self) -> usize {
+            64
+        }
+
+        fn decode_pool(&self, address: Pubkey, data: &[u8]) -> Result<PoolState> {
+            Ok(PoolState {
+                address,
+                dex: RAYDIUM,
+                mint_a: Pubkey::try_from(&data[..32]).unwrap(),
+                mint_b: Pubkey::try_from(&data[32..64]).unwrap(),
+                data: PoolData::Raw(data.to_vec()),
+            })
+        }
+
+        fn quote(&self, pool: &PoolState, request: &QuoteRequest) -> Result<Quote> {
+            let output_mint = pool
+                .other_mint(&request.input_mint)
+                .ok_or(ArbitrageError::InsufficientLiquidity)?;
+            Ok(Quote {
+                input_mint: request.input_mint,
+                output_mint,
+                in_amount: request.amount,
+                out_amount: request.amount / 2,
+                fee_amount: 0,
+                price_impact_bps: 0,
+            })
+        }
+
+        fn swap_instruction(&self, pool: &PoolState, params: &SwapParams) -> Result<Instruction> {
+            Ok(Instruction::new_with_bytes(
+                self.program_id(),
+                &params.amount.to_le_bytes(),
+                vec![AccountMeta::new(pool.address, false)],
+            ))
+        }
+    }
+
+    fn pool_bytes() -> Vec<u8> {
+        let mut data = vec![1u8; 32];
+        data.extend_from_slice(&[2u8; 32]);
+        data
+    }
+
+    #[test]
+    fn test_registry_lookup() {
+        let mut manager = DexManager::new();
+        assert!(manager.is_empty());
+        assert!(manager.register(Arc::new(FixedRateAdapter)).is_none());
+
+        assert_eq!(manager.len(), 1);
+        assert!(manager.get(RAYDIUM).is_some());
+        assert!(manager.adapter(ORCA).is_err());
+        assert!(manager
+            .adapter_for_program(&Pubkey::new_from_array([7; 32]))
+            .is_some());
+    }
+
+    #[test]
+    fn test_decode_and_quote_through_manager() {
+        let mut manager = DexManager::new();
+        manager.register(Arc::new(FixedRateAdapter));
+
+        let owner = Pubkey::new_from_array([7; 32]);
+        let pool = manager
+            .decode_pool(&owner, Pubkey::new_unique(), &pool_bytes())
+            .unwrap()
+            .unwrap();
+        assert!(manager
+            .decode_pool(&owner, pool.address, &[0; 10])
+            .is_none());
+
+        let input_mint = Pubkey::new_from_array([1; 32]);
+        let quote = manager
+            .quote(&pool, &QuoteRequest::exact_in(input_mint, 1_000))
+            .unwrap();
+        assert_eq!(quote.output_mint, Pubkey::new_from_array([2; 32]));
+        assert_eq!(quote.out_amount, 500);
+        assert!(pool.is_a_to_b(&input_mint).unwrap());
+        assert!(pool.is_a_to_b(&Pubkey::new_unique()).is_err());
+    }
+
+    #[test]
+    fn test_from_config() {
+        let mut config = DexConfig::default();
+        let manager = DexManager::from_config(&config).unwrap();
+        assert_eq!(manager.len(), 3);
+        assert!(manager.jupiter().is_some());
+
+        config.orca.enabled = false;
+        config.jupiter.enabled = false;
+        let manager = DexManager::from_config(&config).unwrap();
+        assert!(manager.get(ORCA).is_none());
+        assert!(manager.get(RAYDIUM).is_some());
+        assert!(manager.jupiter().is_none());
+    }
+}
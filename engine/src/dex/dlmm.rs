@@ -0,0 +1,898 @@
+//! Meteora DLMM integration
+//!
+//! Decodes `LbPair` and `BinArray` accounts and quotes swaps bin by bin. Each
+//! bin trades at a fixed price `(1 + bin_step / 10_000)^bin_id`; the fee is the
+//! base fee plus a variable component driven by the volatility accumulator,
+//! which grows with every bin the swap crosses away from the reference bin.
+
+use super::layout::{
+    check_discriminator, read_i32, read_i64, read_pubkey, read_u16, read_u32, read_u64, read_u8,
+};
+use super::math::{mul_shr, shl_div, Q64_ONE, Q64_RESOLUTION};
+use super::{
+    DexAdapter, PoolData, PoolState, Quote, QuoteRequest, SwapMode, SwapParams, METEORA,
+    TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID,
+};
+use crate::error::{ArbitrageError, Result};
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeMap;
+
+/// Meteora DLMM program id
+pub const DLMM_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo");
+
+/// Size of the `LbPair` account
+pub const LB_PAIR_LEN: usize = 904;
+/// Size of the `BinArray` account
+pub const BIN_ARRAY_LEN: usize = 10136;
+/// Number of bins stored in one bin array
+pub const MAX_BIN_PER_ARRAY: i32 = 70;
+
+/// Fee rates are expressed with 9 decimals
+const FEE_PRECISION: u128 = 1_000_000_000;
+/// Cap on the total fee rate (10%)
+const MAX_FEE_RATE: u128 = 100_000_000;
+const BASIS_POINT_MAX: u128 = 10_000;
+/// Bin ids must stay below 2^19 in magnitude for the price to be representable
+const MAX_EXPONENTIAL: u32 = 0x80000;
+
+const LB_PAIR_DISCRIMINATOR: [u8; 8] = [33, 11, 49, 98, 181, 101, 177, 13];
+const BIN_ARRAY_DISCRIMINATOR: [u8; 8] = [92, 142, 92, 220, 5, 148, 70, 181];
+const SWAP_DISCRIMINATOR: [u8; 8] = [248, 198, 158, 145, 225, 117, 135, 200];
+const SWAP_EXACT_OUT_DISCRIMINATOR: [u8; 8] = [250, 73, 101, 33, 38, 207, 75, 184];
+
+const BASE_FACTOR_OFFSET: usize = 8;
+const FILTER_PERIOD_OFFSET: usize = 10;
+const DECAY_PERIOD_OFFSET: usize = 12;
+const REDUCTION_FACTOR_OFFSET: usize = 14;
+const VARIABLE_FEE_CONTROL_OFFSET: usize = 16;
+const MAX_VOLATILITY_ACCUMULATOR_OFFSET: usize = 20;
+const BASE_FEE_POWER_FACTOR_OFFSET: usize = 34;
+const VOLATILITY_ACCUMULATOR_OFFSET: usize = 40;
+const VOLATILITY_REFERENCE_OFFSET: usize = 44;
+const INDEX_REFERENCE_OFFSET: usize = 48;
+const LAST_UPDATE_TIMESTAMP_OFFSET: usize = 56;
+const ACTIVE_ID_OFFSET: usize = 76;
+const BIN_STEP_OFFSET: usize = 80;
+const STATUS_OFFSET: usize = 82;
+const TOKEN_X_MINT_OFFSET: usize = 88;
+const TOKEN_Y_MINT_OFFSET: usize = 120;
+const RESERVE_X_OFFSET: usize = 152;
+const RESERVE_Y_OFFSET: usize = 184;
+const ORACLE_OFFSET: usize = 552;
+const TOKEN_X_PROGRAM_FLAG_OFFSET: usize = 880;
+const TOKEN_Y_PROGRAM_FLAG_OFFSET: usize = 881;
+
+const BIN_ARRAY_INDEX_OFFSET: usize = 8;
+const BIN_ARRAY_LB_PAIR_OFFSET: usize = 24;
+const BIN_ARRAY_BINS_OFFSET: usize = 56;
+const BIN_LEN: usize = 144;
+
+/// `PairStatus::Enabled`
+const STATUS_ENABLED: u8 = 0;
+
+/// Bin arrays kept on each side of the one holding the active bin
+const BIN_ARRAY_WINDOW: i64 = 2;
+
+/// Compute the Q64.64 price of a bin, `(1 + bin_step / 10_000)^bin_id`,
+/// by square-and-multiply exactly as the program does
+pub fn price_from_bin_id(bin_id: i32, bin_step: u16) -> Result<u128> {
+    let overflow = || ArbitrageError::calculation(format!("Bin price overflow at {}", bin_id));
+    let base = Q64_ONE + ((bin_step as u128) << Q64_RESOLUTION) / BASIS_POINT_MAX;
+
+    if bin_id == 0 {
+        return Ok(Q64_ONE);
+    }
+    let exp = bin_id.unsigned_abs();
+    if exp >= MAX_EXPONENTIAL {
+        return Err(overflow());
+    }
+
+    // Work with the reciprocal of the base so every square fits in u128
+    let invert = bin_id > 0;
+    let mut squared_base = u128::MAX / base;
+    let mut result = Q64_ONE;
+    for bit in 0..19 {
+        if exp & (1 << bit) != 0 {
+            result = result.checked_mul(squared_base).ok_or_else(overflow)? >> Q64_RESOLUTION;
+        }
+        squared_base = squared_base
+            .checked_mul(squared_base)
+            .ok_or_else(overflow)?
+            >> Q64_RESOLUTION;
+    }
+    if result == 0 {
+        return Err(overflow());
+    }
+    if invert {
+        result = u128::MAX / result;
+    }
+    Ok(result)
+}
+
+/// Liquidity of a single bin
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Bin {
+    /// Token X held by the bin
+    pub amount_x: u64,
+    /// Token Y held by the bin
+    pub amount_y: u64,
+}
+
+/// Decoded `BinArray` account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BinArray {
+    /// Array index, `floor(bin_id / 70)`
+    pub index: i64,
+    /// Pair the array belongs to
+    pub lb_pair: Pubkey,
+    /// Bins, starting at `index * 70`
+    pub bins: Vec<Bin>,
+}
+
+impl BinArray {
+    /// Decode a `BinArray` account
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        check_discriminator(data, &BIN_ARRAY_DISCRIMINATOR, "BinArray")?;
+        if data.len() != BIN_ARRAY_LEN {
+            return Err(ArbitrageError::dex_integration(format!(
+                "Invalid bin array length: {}",
+                data.len()
+            )));
+        }
+
+        let bins = (0..MAX_BIN_PER_ARRAY as usize)
+            .map(|i| {
+                let offset = BIN_ARRAY_BINS_OFFSET + i * BIN_LEN;
+                Ok(Bin {
+                    amount_x: read_u64(data, offset)?,
+                    amount_y: read_u64(data, offset + 8)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            index: read_i64(data, BIN_ARRAY_INDEX_OFFSET)?,
+            lb_pair: read_pubkey(data, BIN_ARRAY_LB_PAIR_OFFSET)?,
+            bins,
+        })
+    }
+
+    /// Index of the bin array containing `bin_id`
+    pub fn index_for_bin(bin_id: i32) -> i64 {
+        bin_id.div_euclid(MAX_BIN_PER_ARRAY) as i64
+    }
+}
+
+/// Volatility tracking that a swap mutates as it crosses bins
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct VolatilityState {
+    volatility_accumulator: u32,
+    volatility_reference: u32,
+    index_reference: i32,
+}
+
+/// Decoded Meteora DLMM pair
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DlmmState {
+    /// Base fee factor
+    pub base_factor: u16,
+    /// Seconds within which volatility references are kept
+    pub filter_period: u16,
+    /// Seconds after which volatility references reset
+    pub decay_period: u16,
+    /// Decay applied to the volatility reference, in basis points
+    pub reduction_factor: u16,
+    /// Scaling of the variable fee
+    pub variable_fee_control: u32,
+    /// Cap on the volatility accumulator
+    pub max_volatility_accumulator: u32,
+    /// Power-of-ten multiplier applied to the base fee
+    pub base_fee_power_factor: u8,
+    /// Current volatility accumulator
+    pub volatility_accumulator: u32,
+    /// Volatility reference carried over between swaps
+    pub volatility_reference: u32,
+    /// Bin id volatility is measured from
+    pub index_reference: i32,
+    /// Unix timestamp of the last volatility update
+    pub last_update_timestamp: i64,
+    /// Bin holding the current price
+    pub active_id: i32,
+    /// Price increment between bins, in basis points
+    pub bin_step: u16,
+    /// Pair status
+    pub status: u8,
+    /// Token X reserve
+    pub reserve_x: Pubkey,
+    /// Token Y reserve
+    pub reserve_y: Pubkey,
+    /// Pair oracle
+    pub oracle: Pubkey,
+    /// Token program of token X
+    pub token_x_program: Pubkey,
+    /// Token program of token Y
+    pub token_y_program: Pubkey,
+    /// Loaded bin arrays keyed by index
+    pub bin_arrays: BTreeMap<i64, BinArray>,
+}
+
+fn token_program_for_flag(flag: u8) -> Pubkey {
+    if flag == 1 {
+        TOKEN_2022_PROGRAM_ID
+    } else {
+        TOKEN_PROGRAM_ID
+    }
+}
+
+impl DlmmState {
+    /// Decode an `LbPair` account. Bin arrays start empty.
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        check_discriminator(data, &LB_PAIR_DISCRIMINATOR, "LbPair")?;
+        if data.len() != LB_PAIR_LEN {
+            return Err(ArbitrageError::dex_integration(format!(
+                "Invalid LbPair account length: {}",
+                data.len()
+            )));
+        }
+
+        Ok(Self {
+            base_factor: read_u16(data, BASE_FACTOR_OFFSET)?,
+            filter_period: read_u16(data, FILTER_PERIOD_OFFSET)?,
+            decay_period: read_u16(data, DECAY_PERIOD_OFFSET)?,
+            reduction_factor: read_u16(data, REDUCTION_FACTOR_OFFSET)?,
+            variable_fee_control: read_u32(data, VARIABLE_FEE_CONTROL_OFFSET)?,
+            max_volatility_accumulator: read_u32(data, MAX_VOLATILITY_ACCUMULATOR_OFFSET)?,
+            base_fee_power_factor: read_u8(data, BASE_FEE_POWER_FACTOR_OFFSET)?,
+            volatility_accumulator: read_u32(data, VOLATILITY_ACCUMULATOR_OFFSET)?,
+            volatility_reference: read_u32(data, VOLATILITY_REFERENCE_OFFSET)?,
+            index_reference: read_i32(data, INDEX_REFERENCE_OFFSET)?,
+            last_update_timestamp: read_i64(data, LAST_UPDATE_TIMESTAMP_OFFSET)?,
+            active_id: read_i32(data, ACTIVE_ID_OFFSET)?,
+            bin_step: read_u16(data, BIN_STEP_OFFSET)?,
+            status: read_u8(data, STATUS_OFFSET)?,
+            reserve_x: read_pubkey(data, RESERVE_X_OFFSET)?,
+            reserve_y: read_pubkey(data, RESERVE_Y_OFFSET)?,
+            oracle: read_pubkey(data, ORACLE_OFFSET)?,
+            token_x_program: token_program_for_flag(read_u8(data, TOKEN_X_PROGRAM_FLAG_OFFSET)?),
+            token_y_program: token_program_for_flag(read_u8(data, TOKEN_Y_PROGRAM_FLAG_OFFSET)?),
+            bin_arrays: BTreeMap::new(),
+        })
+    }
+
+    /// Base fee rate with 9 decimals
+    pub fn base_fee_rate(&self) -> u128 {
+        self.base_factor as u128
+            * self.bin_step as u128
+            * 10
+            * 10u128.pow(self.base_fee_power_factor as u32)
+    }
+
+    /// Variable fee rate with 9 decimals for a volatility accumulator value
+    pub fn variable_fee_rate(&self, volatility_accumulator: u32) -> u128 {
+        if self.variable_fee_control == 0 {
+            return 0;
+        }
+        let square_vfa_bin = (volatility_accumulator as u128 * self.bin_step as u128).pow(2);
+        (self.variable_fee_control as u128 * square_vfa_bin).div_ceil(100_000_000_000)
+    }
+
+    /// Total fee rate with 9 decimals, capped at 10%
+    pub fn total_fee_rate(&self, volatility_accumulator: u32) -> u128 {
+        (self.base_fee_rate() + self.variable_fee_rate(volatility_accumulator)).min(MAX_FEE_RATE)
+    }
+
+    /// Indexes of the bin arrays around the active bin
+    pub fn bin_array_window(&self) -> Vec<i64> {
+        let current = BinArray::index_for_bin(self.active_id);
+        (-BIN_ARRAY_WINDOW..=BIN_ARRAY_WINDOW)
+            .map(|offset| current + offset)
+            .collect()
+    }
+
+    /// Token amounts held by the bins of the loaded bin arrays
+    pub fn loaded_reserves(&self) -> (u64, u64) {
+        self.bin_arrays
+            .values()
+            .flat_map(|array| &array.bins)
+            .fold((0u64, 0u64), |(x, y), bin| {
+                (
+                    x.saturating_add(bin.amount_x),
+                    y.saturating_add(bin.amount_y),
+                )
+            })
+    }
+
+    fn bin(&self, bin_id: i32) -> Option<&Bin> {
+        let array = self.bin_arrays.get(&BinArray::index_for_bin(bin_id))?;
+        let slot = bin_id.rem_euclid(MAX_BIN_PER_ARRAY) as usize;
+        array.bins.get(slot)
+    }
+
+    /// Volatility references as the program would reset them at `now`
+    fn volatility_at(&self, now: i64) -> VolatilityState {
+        let mut state = VolatilityState {
+            volatility_accumulator: self.volatility_accumulator,
+            volatility_reference: self.volatility_reference,
+            index_reference: self.index_reference,
+        };
+
+        let elapsed = now.saturating_sub(self.last_update_timestamp);
+        if elapsed >= self.filter_period as i64 {
+            state.index_reference = self.active_id;
+            state.volatility_reference = if elapsed < self.decay_period as i64 {
+                (self.volatility_accumulator as u64 * self.reduction_factor as u64
+                    / BASIS_POINT_MAX as u64) as u32
+            } else {
+                0
+            };
+        }
+        state
+    }
+
+    fn update_volatility_accumulator(&self, volatility: &mut VolatilityState, active_id: i32) {
+        let delta_id = volatility.index_reference.abs_diff(active_id) as u64;
+        let accumulator =
+            volatility.volatility_reference as u64 + delta_id * BASIS_POINT_MAX as u64;
+        volatility.volatility_accumulator =
+            accumulator.min(self.max_volatility_accumulator as u64) as u32;
+    }
+
+    /// Run a swap over the loaded bins at unix time `now`, returning
+    /// `(amount_in, amount_out, fee)` where `amount_in` includes the fee
+    pub fn swap(
+        &self,
+        amount: u64,
+        is_input: bool,
+        swap_for_y: bool,
+        now: i64,
+    ) -> Result<(u64, u64, u64)> {
+        if self.status != STATUS_ENABLED {
+            return Err(ArbitrageError::dex_integration("DLMM pair is disabled"));
+        }
+
+        let mut volatility = self.volatility_at(now);
+        let mut active_id = self.active_id;
+        let mut remaining = amount as u128;
+        let mut calculated = 0u128;
+        let mut fee_total = 0u128;
+
+        while remaining > 0 {
+            let Some(bin) = self.bin(active_id) else {
+                return Err(ArbitrageError::InsufficientLiquidity);
+            };
+
+            self.update_volatility_accumulator(&mut volatility, active_id);
+            let fee_rate = self.total_fee_rate(volatility.volatility_accumulator);
+            let price = price_from_bin_id(active_id, self.bin_step)?;
+
+            let max_amount_out = if swap_for_y {
+                bin.amount_y
+            } else {
+                bin.amount_x
+            };
+            if max_amount_out > 0 {
+                let (amount_in, amount_out, fee) = if is_input {
+                    swap_bin_exact_in(
+                        remaining,
+                        max_amount_out as u128,
+                        price,
+                        fee_rate,
+                        swap_for_y,
+                    )?
+                } else {
+                    swap_bin_exact_out(
+                        remaining,
+                        max_amount_out as u128,
+                        price,
+                        fee_rate,
+                        swap_for_y,
+                    )?
+                };
+                if is_input {
+                    remaining -= amount_in;
+                    calculated += amount_out;
+                } else {
+                    remaining -= amount_out;
+                    calculated += amount_in;
+                }
+                fee_total += fee;
+            }
+
+            if remaining > 0 {
+                active_id = if swap_for_y {
+                    active_id - 1
+                } else {
+                    active_id + 1
+                };
+            }
+        }
+
+        let (amount_in, amount_out) = if is_input {
+            (amount as u128, calculated)
+        } else {
+            (calculated, amount as u128)
+        };
+        let to_u64 = |value: u128| {
+            u64::try_from(value).map_err(|_| ArbitrageError::calculation("Swap amount overflow"))
+        };
+        Ok((to_u64(amount_in)?, to_u64(amount_out)?, to_u64(fee_total)?))
+    }
+}
+
+/// Input needed, before fees, to take `amount_out` out of a bin
+fn bin_amount_in(amount_out: u128, price: u128, swap_for_y: bool) -> Result<u128> {
+    if swap_for_y {
+        shl_div(amount_out, Q64_RESOLUTION, price, true)
+    } else {
+        mul_shr(amount_out, price, Q64_RESOLUTION, true)
+    }
+}
+
+/// Output of a bin for `amount_in` after fees
+fn bin_amount_out(amount_in: u128, price: u128, swap_for_y: bool) -> Result<u128> {
+    if swap_for_y {
+        mul_shr(amount_in, price, Q64_RESOLUTION, false)
+    } else {
+        shl_div(amount_in, Q64_RESOLUTION, price, false)
+    }
+}
+
+/// Fee to add on top of an amount that excludes fees
+fn fee_on_top(amount: u128, fee_rate: u128) -> u128 {
+    (amount * fee_rate).div_ceil(FEE_PRECISION - fee_rate)
+}
+
+/// Fee contained in an amount that includes fees
+fn fee_included(amount: u128, fee_rate: u128) -> u128 {
+    (amount * fee_rate).div_ceil(FEE_PRECISION)
+}
+
+/// Swap into one bin by exact input, returning `(amount_in incl. fee, amount_out, fee)`
+fn swap_bin_exact_in(
+    amount_in: u128,
+    max_amount_out: u128,
+    price: u128,
+    fee_rate: u128,
+    swap_for_y: bool,
+) -> Result<(u128, u128, u128)> {
+    let max_amount_in = bin_amount_in(max_amount_out, price, swap_for_y)?;
+    let max_fee = fee_on_top(max_amount_in, fee_rate);
+    let max_amount_in = max_amount_in + max_fee;
+
+    if amount_in > max_amount_in {
+        return Ok((max_amount_in, max_amount_out, max_fee));
+    }
+    let fee = fee_included(amount_in, fee_rate);
+    let amount_out = bin_amount_out(amount_in - fee, price, swap_for_y)?;
+    Ok((amount_in, amount_out.min(max_amount_out), fee))
+}
+
+/// Swap into one bin by exact output, returning `(amount_in incl. fee, amount_out, fee)`
+fn swap_bin_exact_out(
+    amount_out: u128,
+    max_amount_out: u128,
+    price: u128,
+    fee_rate: u128,
+    swap_for_y: bool,
+) -> Result<(u128, u128, u128)> {
+    let amount_out = amount_out.min(max_amount_out);
+    let amount_in = bin_amount_in(amount_out, price, swap_for_y)?;
+    let fee = fee_on_top(amount_in, fee_rate);
+    Ok((amount_in + fee, amount_out, fee))
+}
+
+/// Meteora DLMM adapter
+#[derive(Debug, Default)]
+pub struct DlmmAdapter;
+
+impl DlmmAdapter {
+    /// Create a new DLMM adapter
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Derive the address of a bin array
+    pub fn bin_array_address(lb_pair: &Pubkey, index: i64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"bin_array", lb_pair.as_ref(), &index.to_le_bytes()],
+            &DLMM_PROGRAM_ID,
+        )
+        .0
+    }
+
+    /// Derive the program's event authority
+    pub fn event_authority() -> Pubkey {
+        Pubkey::find_program_address(&[b"__event_authority"], &DLMM_PROGRAM_ID).0
+    }
+
+    fn state(pool: &PoolState) -> Result<&DlmmState> {
+        match &pool.data {
+            PoolData::Dlmm(state) => Ok(state),
+            _ => Err(ArbitrageError::dex_integration(format!(
+                "Pool {} is not a Meteora DLMM pair",
+                pool.address
+            ))),
+        }
+    }
+
+    /// Quote a swap at unix time `now`
+    pub fn quote_at(&self, pool: &PoolState, request: &QuoteRequest, now: i64) -> Result<Quote> {
+        let state = Self::state(pool)?;
+        let swap_for_y = pool.is_a_to_b(&request.input_mint)?;
+        let is_input = request.mode == SwapMode::ExactIn;
+        let (in_amount, out_amount, fee_amount) =
+            state.swap(request.amount, is_input, swap_for_y, now)?;
+
+        let price = price_from_bin_id(state.active_id, state.bin_step)?;
+        let spot_out = bin_amount_out((in_amount - fee_amount) as u128, price, swap_for_y)?;
+        let price_impact_bps = (spot_out.saturating_sub(out_amount as u128) * 10_000)
+            .checked_div(spot_out)
+            .unwrap_or(0) as u32;
+
+        Ok(Quote {
+            input_mint: request.input_mint,
+            output_mint: if swap_for_y { pool.mint_b } else { pool.mint_a },
+            in_amount,
+            out_amount,
+            fee_amount,
+            price_impact_bps,
+        })
+    }
+}
+
+impl DexAdapter for DlmmAdapter {
+    fn id(&self) -> &'static str {
+        METEORA
+    }
+
+    fn program_id(&self) -> Pubkey {
+        DLMM_PROGRAM_ID
+    }
+
+    fn pool_account_len(&self) -> usize {
+        LB_PAIR_LEN
+    }
+
+    fn decode_pool(&self, address: Pubkey, data: &[u8]) -> Result<PoolState> {
+        let state = DlmmState::decode(data)?;
+        Ok(PoolState {
+            address,
+            dex: METEORA,
+            mint_a: read_pubkey(data, TOKEN_X_MINT_OFFSET)?,
+            mint_b: read_pubkey(data, TOKEN_Y_MINT_OFFSET)?,
+            data: PoolData::Dlmm(state),
+        })
+    }
+
+    fn dependent_accounts(&self, pool: &PoolState) -> Vec<Pubkey> {
+        let Ok(state) = Self::state(pool) else {
+            return Vec::new();
+        };
+        state
+            .bin_array_window()
+            .into_iter()
+            .map(|index| Self::bin_array_address(&pool.address, index))
+            .collect()
+    }
+
+    fn apply_dependent_account(
+        &self,
+        pool: &mut PoolState,
+        _address: &Pubkey,
+        data: &[u8],
+    ) -> Result<()> {
+        let PoolData::Dlmm(state) = &mut pool.data else {
+            return Err(ArbitrageError::dex_integration(format!(
+                "Pool {} is not a Meteora DLMM pair",
+                pool.address
+            )));
+        };
+
+        let array = BinArray::decode(data)?;
+        if array.lb_pair != pool.address {
+            return Err(ArbitrageError::dex_integration(format!(
+                "Bin array belongs to {}, not {}",
+                array.lb_pair, pool.address
+            )));
+        }
+
+        state.bin_arrays.insert(array.index, array);
+        let window = state.bin_array_window();
+        state.bin_arrays.retain(|index, _| window.contains(index));
+        Ok(())
+    }
+
+    fn reserves(&self, pool: &PoolState) -> Option<(u64, u64)> {
+        Some(Self::state(pool).ok()?.loaded_reserves())
+    }
+
+    fn token_program(&self, pool: &PoolState, mint: &Pubkey) -> Pubkey {
+        match Self::state(pool) {
+            Ok(state) if *mint == pool.mint_b => state.token_y_program,
+            Ok(state) => state.token_x_program,
+            Err(_) => TOKEN_PROGRAM_ID,
+        }
+    }
+
+    fn quote(&self, pool: &PoolState, request: &QuoteRequest) -> Result<Quote> {
+        self.quote_at(pool, request, chrono::Utc::now().timestamp())
+    }
+
+    fn swap_instruction(&self, pool: &PoolState, params: &SwapParams) -> Result<Instruction> {
+        let state = Self::state(pool)?;
+        let swap_for_y = pool.is_a_to_b(&params.input_mint)?;
+
+        let mut data = Vec::with_capacity(24);
+        match params.mode {
+            SwapMode::ExactIn => {
+                data.extend_from_slice(&SWAP_DISCRIMINATOR);
+                data.extend_from_slice(&params.amount.to_le_bytes());
+                data.extend_from_slice(&params.other_amount_threshold.to_le_bytes());
+            }
+            SwapMode::ExactOut => {
+                data.extend_from_slice(&SWAP_EXACT_OUT_DISCRIMINATOR);
+                data.extend_from_slice(&params.other_amount_threshold.to_le_bytes());
+                data.extend_from_slice(&params.amount.to_le_bytes());
+            }
+        }
+
+        // Optional accounts (bitmap extension, host fee) are passed as the
+        // program id, which Anchor reads as `None`
+        let mut accounts = vec![
+            AccountMeta::new(pool.address, false),
+            AccountMeta::new_readonly(DLMM_PROGRAM_ID, false),
+            AccountMeta::new(state.reserve_x, false),
+            AccountMeta::new(state.reserve_y, false),
+            AccountMeta::new(params.source_token_account, false),
+            AccountMeta::new(params.destination_token_account, false),
+            AccountMeta::new_readonly(pool.mint_a, false),
+            AccountMeta::new_readonly(pool.mint_b, false),
+            AccountMeta::new(state.oracle, false),
+            AccountMeta::new_readonly(DLMM_PROGRAM_ID, false),
+            AccountMeta::new_readonly(params.user, true),
+            AccountMeta::new_readonly(state.token_x_program, false),
+            AccountMeta::new_readonly(state.token_y_program, false),
+            AccountMeta::new_readonly(Self::event_authority(), false),
+            AccountMeta::new_readonly(DLMM_PROGRAM_ID, false),
+        ];
+
+        // The active bin array, then every loaded array in swap direction
+        let step = if swap_for_y { -1 } else { 1 };
+        let mut index = BinArray::index_for_bin(state.active_id);
+        loop {
+            accounts.push(AccountMeta::new(
+                Self::bin_array_address(&pool.address, index),
+                false,
+            ));
+            index += step;
+            if !state.bin_arrays.contains_key(&index) {
+                break;
+            }
+        }
+
+        Ok(Instruction {
+            program_id: DLMM_PROGRAM_ID,
+            accounts,
+            data,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINT_X: Pubkey = Pubkey::new_from_array([1; 32]);
+    const MINT_Y: Pubkey = Pubkey::new_from_array([2; 32]);
+    const NOW: i64 = 1_700_000_000;
+
+    /// Pair with bin step 10, base factor 10_000 (0.1% base fee) and the
+    /// variable fee parameters of a typical volatile pair
+    fn lb_pair_fixture(active_id: i32, last_update_timestamp: i64) -> Vec<u8> {
+        let mut data = vec![0u8; LB_PAIR_LEN];
+        data[..8].copy_from_slice(&LB_PAIR_DISCRIMINATOR);
+        let mut put =
+            |offset: usize, bytes: &[u8]| data[offset..offset + bytes.len()].copy_from_slice(bytes);
+        put(BASE_FACTOR_OFFSET, &10_000u16.to_le_bytes());
+        put(FILTER_PERIOD_OFFSET, &30u16.to_le_bytes());
+        put(DECAY_PERIOD_OFFSET, &600u16.to_le_bytes());
+        put(REDUCTION_FACTOR_OFFSET, &5_000u16.to_le_bytes());
+        put(VARIABLE_FEE_CONTROL_OFFSET, &40_000u32.to_le_bytes());
+        put(MAX_VOLATILITY_ACCUMULATOR_OFFSET, &350_000u32.to_le_bytes());
+        put(VOLATILITY_ACCUMULATOR_OFFSET, &20_000u32.to_le_bytes());
+        put(VOLATILITY_REFERENCE_OFFSET, &10_000u32.to_le_bytes());
+        put(INDEX_REFERENCE_OFFSET, &active_id.to_le_bytes());
+        put(
+            LAST_UPDATE_TIMESTAMP_OFFSET,
+            &last_update_timestamp.to_le_bytes(),
+        );
+        put(ACTIVE_ID_OFFSET, &active_id.to_le_bytes());
+        put(BIN_STEP_OFFSET, &10u16.to_le_bytes());
+        put(TOKEN_X_MINT_OFFSET, MINT_X.as_ref());
+        put(TOKEN_Y_MINT_OFFSET, MINT_Y.as_ref());
+        put(TOKEN_Y_PROGRAM_FLAG_OFFSET, &[1]);
+        data
+    }
+
+    /// Bin array with `(bin_id, amount_x, amount_y)` liquidity
+    fn bin_array_fixture(lb_pair: &Pubkey, index: i64, bins: &[(i32, u64, u64)]) -> Vec<u8> {
+        let mut data = vec![0u8; BIN_ARRAY_LEN];
+        data[..8].copy_from_slice(&BIN_ARRAY_DISCRIMINATOR);
+        data[BIN_ARRAY_INDEX_OFFSET..BIN_ARRAY_INDEX_OFFSET + 8]
+            .copy_from_slice(&index.to_le_bytes());
+        data[BIN_ARRAY_LB_PAIR_OFFSET..BIN_ARRAY_LB_PAIR_OFFSET + 32]
+            .copy_from_slice(lb_pair.as_ref());
+        for (bin_id, amount_x, amount_y) in bins {
+            let slot = bin_id.rem_euclid(MAX_BIN_PER_ARRAY) as usize;
+            let offset = BIN_ARRAY_BINS_OFFSET + slot * BIN_LEN;
+            data[offset..offset + 8].copy_from_slice(&amount_x.to_le_bytes());
+            data[offset + 8..offset + 16].copy_from_slice(&amount_y.to_le_bytes());
+        }
+        data
+    }
+
+    /// Pair at bin 0: Y liquidity in bins -2..=0, X liquidity in bins 1..=3
+    fn loaded_pool(last_update_timestamp: i64) -> PoolState {
+        let adapter = DlmmAdapter::new();
+        let address = Pubkey::new_unique();
+        let mut pool = adapter
+            .decode_pool(address, &lb_pair_fixture(0, last_update_timestamp))
+            .unwrap();
+        let arrays = [
+            (-1, vec![(-2, 0, 1_000_000_000), (-1, 0, 1_000_000_000)]),
+            (
+                0,
+                vec![
+                    (0, 0, 1_000_000_000),
+                    (1, 1_000_000_000, 0),
+                    (2, 1_000_000_000, 0),
+                    (3, 1_000_000_000, 0),
+                ],
+            ),
+        ];
+        for (index, bins) in arrays {
+            adapter
+                .apply_dependent_account(
+                    &mut pool,
+                    &Pubkey::new_unique(),
+                    &bin_array_fixture(&address, index, &bins),
+                )
+                .unwrap();
+        }
+        pool
+    }
+
+    #[test]
+    fn test_price_from_bin_id() {
+        assert_eq!(price_from_bin_id(0, 10).unwrap(), Q64_ONE);
+        assert_eq!(
+            price_from_bin_id(1, 10).unwrap(),
+            18_465_190_817_783_261_167
+        );
+        assert_eq!(
+            price_from_bin_id(-1, 10).unwrap(),
+            18_428_315_757_951_600_016
+        );
+        assert_eq!(
+            price_from_bin_id(100, 25).unwrap(),
+            23_678_699_809_202_413_098
+        );
+        assert!(price_from_bin_id(1 << 19, 10).is_err());
+    }
+
+    #[test]
+    fn test_decode_lb_pair() {
+        let pool = loaded_pool(NOW);
+        let state = DlmmAdapter::state(&pool).unwrap();
+        assert_eq!(pool.mint_a, MINT_X);
+        assert_eq!(pool.mint_b, MINT_Y);
+        assert_eq!(state.bin_step, 10);
+        assert_eq!(state.base_fee_rate(), 1_000_000);
+        assert_eq!(state.token_x_program, TOKEN_PROGRAM_ID);
+        assert_eq!(state.token_y_program, TOKEN_2022_PROGRAM_ID);
+        assert_eq!(
+            DlmmAdapter::new().token_program(&pool, &MINT_Y),
+            TOKEN_2022_PROGRAM_ID
+        );
+        assert_eq!(state.bin_arrays.len(), 2);
+        assert_eq!(DlmmAdapter::new().dependent_accounts(&pool).len(), 5);
+        assert_eq!(BinArray::index_for_bin(-1), -1);
+        assert_eq!(BinArray::index_for_bin(70), 1);
+    }
+
+    #[test]
+    fn test_variable_fee() {
+        let pool = loaded_pool(NOW);
+        let state = DlmmAdapter::state(&pool).unwrap();
+        assert_eq!(state.variable_fee_rate(0), 0);
+        assert_eq!(state.variable_fee_rate(10_000), 4_000);
+        assert_eq!(state.variable_fee_rate(350_000), 4_900_000);
+        assert_eq!(state.total_fee_rate(10_000), 1_004_000);
+    }
+
+    #[test]
+    fn test_quote_exact_in_within_active_bin() {
+        let adapter = DlmmAdapter::new();
+        let pool = loaded_pool(NOW);
+
+        // Fresh references: the accumulator restarts from the stored reference
+        let quote = adapter
+            .quote_at(&pool, &QuoteRequest::exact_in(MINT_X, 1_000_000), NOW)
+            .unwrap();
+        assert_eq!(quote.output_mint, MINT_Y);
+        assert_eq!(quote.fee_amount, 1_004);
+        assert_eq!(quote.out_amount, 998_996);
+        assert_eq!(quote.price_impact_bps, 0);
+    }
+
+    #[test]
+    fn test_quote_crosses_bins_with_rising_fee() {
+        let adapter = DlmmAdapter::new();
+        let pool = loaded_pool(NOW - 1_000);
+
+        // Past the decay period the reference resets, so only the bins
+        // crossed by this swap contribute to the variable fee
+        let quote = adapter
+            .quote_at(&pool, &QuoteRequest::exact_in(MINT_Y, 2_500_000_000), NOW)
+            .unwrap();
+        assert_eq!(quote.output_mint, MINT_X);
+        assert_eq!(quote.out_amount, 2_492_980_700);
+        assert_eq!(quote.fee_amount, 2_537_877);
+        assert!(quote.price_impact_bps > 0);
+
+        assert!(matches!(
+            adapter.quote_at(&pool, &QuoteRequest::exact_in(MINT_Y, 5_000_000_000), NOW),
+            Err(ArbitrageError::InsufficientLiquidity)
+        ));
+    }
+
+    #[test]
+    fn test_quote_exact_out_round_trip() {
+        let adapter = DlmmAdapter::new();
+        let pool = loaded_pool(NOW - 1_000);
+
+        let quote = adapter
+            .quote_at(&pool, &QuoteRequest::exact_out(MINT_X, 1_500_000_000), NOW)
+            .unwrap();
+        assert_eq!(quote.out_amount, 1_500_000_000);
+        assert_eq!(quote.in_amount, 1_502_004_010);
+        assert_eq!(quote.fee_amount, 1_504_010);
+
+        let forward = adapter
+            .quote_at(&pool, &QuoteRequest::exact_in(MINT_X, quote.in_amount), NOW)
+            .unwrap();
+        assert!(forward.out_amount >= 1_500_000_000);
+    }
+
+    #[test]
+    fn test_swap_instruction_layout() {
+        let adapter = DlmmAdapter::new();
+        let pool = loaded_pool(NOW);
+        let params = SwapParams {
+            user: Pubkey::new_unique(),
+            source_token_account: Pubkey::new_unique(),
+            destination_token_account: Pubkey::new_unique(),
+            input_mint: MINT_X,
+            amount: 1_000,
+            other_amount_threshold: 990,
+            mode: SwapMode::ExactIn,
+        };
+
+        let ix = adapter.swap_instruction(&pool, &params).unwrap();
+        assert_eq!(ix.program_id, DLMM_PROGRAM_ID);
+        assert_eq!(&ix.data[..8], &SWAP_DISCRIMINATOR);
+        assert_eq!(&ix.data[8..16], &1_000u64.to_le_bytes());
+        assert_eq!(ix.accounts.len(), 17);
+        assert!(ix.accounts[10].is_signer);
+        assert_eq!(
+            ix.accounts[15].pubkey,
+            DlmmAdapter::bin_array_address(&pool.address, 0)
+        );
+        assert_eq!(
+            ix.accounts[16].pubkey,
+            DlmmAdapter::bin_array_address(&pool.address, -1)
+        );
+    }
+}
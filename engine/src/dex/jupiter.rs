@@ -0,0 +1,488 @@
+//! Jupiter aggregator client
+//!
+//! Jupiter is not quoted from on-chain state like the other adapters; its
+//! HTTP API returns routed quotes and the instructions to execute them. We use
+//! it as a price sanity-check for our own quotes and as a fallback route.
+
+pub mod mock;
+
+use super::{Quote, QuoteRequest, SwapMode};
+use crate::config::JupiterConfig;
+use crate::error::{ArbitrageError, Result};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use serde::{Deserialize, Serialize};
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::debug;
+
+pub use mock::{JupiterMockHandle, JupiterMockServer};
+
+/// Timeout for a single Jupiter API request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Amounts are sent as decimal strings to avoid precision loss in JSON
+mod u64_string {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+fn parse_pubkey(value: &str) -> Result<Pubkey> {
+    Pubkey::from_str(value).map_err(|e| {
+        ArbitrageError::dex_integration(format!("Invalid pubkey from Jupiter {}: {}", value, e))
+    })
+}
+
+/// One AMM hop of a Jupiter route
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwapInfo {
+    /// Pool the hop trades through
+    pub amm_key: String,
+    /// Human-readable DEX label
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Mint sold in this hop
+    pub input_mint: String,
+    /// Mint bought in this hop
+    pub output_mint: String,
+    /// Amount sold
+    #[serde(with = "u64_string")]
+    pub in_amount: u64,
+    /// Amount bought
+    #[serde(with = "u64_string")]
+    pub out_amount: u64,
+    /// Fee charged by the pool
+    #[serde(with = "u64_string")]
+    pub fee_amount: u64,
+    /// Mint the fee is charged in
+    pub fee_mint: String,
+}
+
+/// A step of a Jupiter route plan
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoutePlanStep {
+    /// The hop itself
+    pub swap_info: SwapInfo,
+    /// Share of the input routed through this hop
+    pub percent: u8,
+}
+
+/// Quote response from the Jupiter API
+///
+/// Fields we don't use are kept in `extra` so the quote can be sent back
+/// verbatim when requesting swap instructions.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JupiterQuote {
+    /// Mint sold
+    pub input_mint: String,
+    /// Amount sold
+    #[serde(with = "u64_string")]
+    pub in_amount: u64,
+    /// Mint bought
+    pub output_mint: String,
+    /// Amount bought
+    #[serde(with = "u64_string")]
+    pub out_amount: u64,
+    /// Worst acceptable amount after slippage
+    #[serde(with = "u64_string")]
+    pub other_amount_threshold: u64,
+    /// Whether the quote is exact-in or exact-out
+    pub swap_mode: SwapMode,
+    /// Slippage tolerance the quote was made with
+    pub slippage_bps: u16,
+    /// Price impact as a fraction, e.g. `"0.0002"` for 2 bps
+    pub price_impact_pct: String,
+    /// Hops making up the route
+    pub route_plan: Vec<RoutePlanStep>,
+    /// Slot the quote was computed at
+    #[serde(default)]
+    pub context_slot: Option<u64>,
+    /// Remaining response fields
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl JupiterQuote {
+    /// Price impact in basis points
+    pub fn price_impact_bps(&self) -> u32 {
+        self.price_impact_pct
+            .parse::<f64>()
+            .map(|pct| (pct.abs() * 10_000.0).round() as u32)
+            .unwrap_or(0)
+    }
+
+    /// Convert to the engine's quote type. Only fees charged in the input
+    /// mint are counted, since `Quote::fee_amount` is in input tokens.
+    pub fn to_quote(&self) -> Result<Quote> {
+        let fee_amount = self
+            .route_plan
+            .iter()
+            .filter(|step| step.swap_info.fee_mint == self.input_mint)
+            .map(|step| step.swap_info.fee_amount)
+            .sum();
+
+        Ok(Quote {
+            input_mint: parse_pubkey(&self.input_mint)?,
+            output_mint: parse_pubkey(&self.output_mint)?,
+            in_amount: self.in_amount,
+            out_amount: self.out_amount,
+            fee_amount,
+            price_impact_bps: self.price_impact_bps(),
+        })
+    }
+
+    /// Pools the route trades through
+    pub fn amm_keys(&self) -> Vec<&str> {
+        self.route_plan
+            .iter()
+            .map(|step| step.swap_info.amm_key.as_str())
+            .collect()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiAccountMeta {
+    pubkey: String,
+    is_signer: bool,
+    is_writable: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiInstruction {
+    program_id: String,
+    accounts: Vec<ApiAccountMeta>,
+    data: String,
+}
+
+impl ApiInstruction {
+    fn decode(&self) -> Result<Instruction> {
+        let accounts = self
+            .accounts
+            .iter()
+            .map(|meta| {
+                let pubkey = parse_pubkey(&meta.pubkey)?;
+                Ok(if meta.is_writable {
+                    AccountMeta::new(pubkey, meta.is_signer)
+                } else {
+                    AccountMeta::new_readonly(pubkey, meta.is_signer)
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let data = STANDARD.decode(&self.data).map_err(|e| {
+            ArbitrageError::dex_integration(format!("Invalid instruction data from Jupiter: {}", e))
+        })?;
+
+        Ok(Instruction {
+            program_id: parse_pubkey(&self.program_id)?,
+            accounts,
+            data,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiSwapInstructions {
+    #[serde(default)]
+    compute_budget_instructions: Vec<ApiInstruction>,
+    #[serde(default)]
+    setup_instructions: Vec<ApiInstruction>,
+    swap_instruction: ApiInstruction,
+    #[serde(default)]
+    cleanup_instruction: Option<ApiInstruction>,
+    #[serde(default)]
+    address_lookup_table_addresses: Vec<String>,
+}
+
+/// Decoded instructions to execute a Jupiter quote
+#[derive(Debug, Clone, PartialEq)]
+pub struct JupiterSwapInstructions {
+    /// Compute unit limit and price
+    pub compute_budget_instructions: Vec<Instruction>,
+    /// Token account creation and SOL wrapping
+    pub setup_instructions: Vec<Instruction>,
+    /// The routed swap
+    pub swap_instruction: Instruction,
+    /// SOL unwrapping, if any
+    pub cleanup_instruction: Option<Instruction>,
+    /// Lookup tables the route's accounts live in
+    pub address_lookup_table_addresses: Vec<Pubkey>,
+}
+
+impl TryFrom<ApiSwapInstructions> for JupiterSwapInstructions {
+    type Error = ArbitrageError;
+
+    fn try_from(response: ApiSwapInstructions) -> Result<Self> {
+        let decode_all = |instructions: &[ApiInstruction]| {
+            instructions
+                .iter()
+                .map(ApiInstruction::decode)
+                .collect::<Result<Vec<_>>>()
+        };
+
+        Ok(Self {
+            compute_budget_instructions: decode_all(&response.compute_budget_instructions)?,
+            setup_instructions: decode_all(&response.setup_instructions)?,
+            swap_instruction: response.swap_instruction.decode()?,
+            cleanup_instruction: response
+                .cleanup_instruction
+                .as_ref()
+                .map(ApiInstruction::decode)
+                .transpose()?,
+            address_lookup_table_addresses: response
+                .address_lookup_table_addresses
+                .iter()
+                .map(|address| parse_pubkey(address))
+                .collect::<Result<Vec<_>>>()?,
+        })
+    }
+}
+
+/// HTTP client for the Jupiter quote and swap API
+#[derive(Debug, Clone)]
+pub struct JupiterClient {
+    http: reqwest::Client,
+    config: JupiterConfig,
+}
+
+impl JupiterClient {
+    /// Create a client for the configured API
+    pub fn new(config: JupiterConfig) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()?;
+        Ok(Self { http, config })
+    }
+
+    /// Client settings
+    pub fn config(&self) -> &JupiterConfig {
+        &self.config
+    }
+
+    fn endpoint(&self, path: &str) -> String {
+        format!("{}/v6/{}", self.config.api_url.trim_end_matches('/'), path)
+    }
+
+    async fn check_status(response: reqwest::Response, what: &str) -> Result<reqwest::Response> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let body = response.text().await.unwrap_or_default();
+        Err(ArbitrageError::dex_integration(format!(
+            "Jupiter {} request failed with {}: {}",
+            what, status, body
+        )))
+    }
+
+    /// Fetch a routed quote from `request.input_mint` to `output_mint`
+    pub async fn quote(
+        &self,
+        request: &QuoteRequest,
+        output_mint: &Pubkey,
+    ) -> Result<JupiterQuote> {
+        let swap_mode = match request.mode {
+            SwapMode::ExactIn => "ExactIn",
+            SwapMode::ExactOut => "ExactOut",
+        };
+        let query = [
+            ("inputMint", request.input_mint.to_string()),
+            ("outputMint", output_mint.to_string()),
+            ("amount", request.amount.to_string()),
+            ("swapMode", swap_mode.to_string()),
+            ("slippageBps", self.config.max_slippage_bps.to_string()),
+            ("maxAccounts", self.config.max_accounts.to_string()),
+        ];
+
+        debug!(
+            "Requesting Jupiter quote {} -> {} for {}",
+            request.input_mint, output_mint, request.amount
+        );
+        let response = self
+            .http
+            .get(self.endpoint("quote"))
+            .query(&query)
+            .send()
+            .await?;
+        let quote = Self::check_status(response, "quote")
+            .await?
+            .json::<JupiterQuote>()
+            .await?;
+
+        Ok(quote)
+    }
+
+    /// Fetch the instructions executing `quote` for `user`
+    pub async fn swap_instructions(
+        &self,
+        quote: &JupiterQuote,
+        user: &Pubkey,
+    ) -> Result<JupiterSwapInstructions> {
+        let body = serde_json::json!({
+            "userPublicKey": user.to_string(),
+            "quoteResponse": quote,
+            "wrapAndUnwrapSol": true,
+            "dynamicComputeUnitLimit": true,
+            "prioritizationFeeLamports": self.config.priority_fee_lamports,
+        });
+
+        let response = self
+            .http
+            .post(self.endpoint("swap-instructions"))
+            .json(&body)
+            .send()
+            .await?;
+        let instructions = Self::check_status(response, "swap-instructions")
+            .await?
+            .json::<ApiSwapInstructions>()
+            .await?;
+
+        instructions.try_into()
+    }
+
+    /// Deviation of `quote` from Jupiter's best route for the same input, in
+    /// basis points of Jupiter's output. Positive means our quote is better.
+    pub async fn price_deviation_bps(&self, quote: &Quote) -> Result<i64> {
+        let reference = self
+            .quote(
+                &QuoteRequest::exact_in(quote.input_mint, quote.in_amount),
+                &quote.output_mint,
+            )
+            .await?;
+        if reference.out_amount == 0 {
+            return Err(ArbitrageError::InsufficientLiquidity);
+        }
+
+        let diff = quote.out_amount as i128 - reference.out_amount as i128;
+        Ok((diff * 10_000 / reference.out_amount as i128) as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOL: Pubkey = Pubkey::from_str_const("So11111111111111111111111111111111111111112");
+    const USDC: Pubkey = Pubkey::from_str_const("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+
+    fn client_for(mock: &JupiterMockHandle) -> JupiterClient {
+        JupiterClient::new(JupiterConfig {
+            api_url: mock.url(),
+            ..JupiterConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_quote_conversion() {
+        let quote: JupiterQuote =
+            serde_json::from_str(include_str!("../../fixtures/jupiter/quote-sol-usdc.json"))
+                .unwrap();
+        let converted = quote.to_quote().unwrap();
+        assert_eq!(converted.input_mint, SOL);
+        assert_eq!(converted.output_mint, USDC);
+        assert_eq!(converted.out_amount, 151_234_567);
+        assert_eq!(converted.fee_amount, 1_620_000);
+        assert_eq!(converted.price_impact_bps, 2);
+        assert_eq!(quote.amm_keys().len(), 2);
+
+        // Unknown fields survive a round trip
+        let value = serde_json::to_value(&quote).unwrap();
+        assert!(value.get("platformFee").is_some());
+        assert_eq!(value["inAmount"], "1000000000");
+    }
+
+    #[tokio::test]
+    async fn test_quote_against_mock() {
+        let mock = JupiterMockServer::load_dir(mock::FIXTURE_DIR)
+            .unwrap()
+            .spawn()
+            .await
+            .unwrap();
+        let client = client_for(&mock);
+
+        let quote = client
+            .quote(&QuoteRequest::exact_in(SOL, 1_000_000_000), &USDC)
+            .await
+            .unwrap();
+        assert_eq!(quote.out_amount, 151_234_567);
+        assert_eq!(quote.context_slot, Some(287_654_321));
+
+        // Nothing recorded for the reverse direction
+        let err = client
+            .quote(&QuoteRequest::exact_in(USDC, 1_000_000), &SOL)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("COULD_NOT_FIND_ANY_ROUTE"));
+    }
+
+    #[tokio::test]
+    async fn test_swap_instructions_against_mock() {
+        let mock = JupiterMockServer::load_dir(mock::FIXTURE_DIR)
+            .unwrap()
+            .spawn()
+            .await
+            .unwrap();
+        let client = client_for(&mock);
+
+        let quote = client
+            .quote(&QuoteRequest::exact_in(SOL, 1_000_000_000), &USDC)
+            .await
+            .unwrap();
+        let instructions = client
+            .swap_instructions(&quote, &Pubkey::new_unique())
+            .await
+            .unwrap();
+
+        assert_eq!(instructions.compute_budget_instructions.len(), 2);
+        assert_eq!(instructions.setup_instructions.len(), 1);
+        assert!(instructions.cleanup_instruction.is_none());
+        assert_eq!(instructions.address_lookup_table_addresses.len(), 2);
+        let swap = &instructions.swap_instruction;
+        assert_eq!(
+            swap.program_id,
+            Pubkey::from_str_const("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4")
+        );
+        assert!(swap.accounts[1].is_signer);
+        assert!(!swap.accounts[1].is_writable);
+        assert_eq!(
+            &swap.data[..8],
+            &[0xe5, 0x17, 0xcb, 0x97, 0x7a, 0xe3, 0xad, 0x2a]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_price_deviation() {
+        let mock = JupiterMockServer::load_dir(mock::FIXTURE_DIR)
+            .unwrap()
+            .spawn()
+            .await
+            .unwrap();
+        let client = client_for(&mock);
+
+        let ours = Quote {
+            input_mint: SOL,
+            output_mint: USDC,
+            in_amount: 1_000_000_000,
+            out_amount: 150_478_394,
+            fee_amount: 0,
+            price_impact_bps: 0,
+        };
+        assert_eq!(client.price_deviation_bps(&ours).await.unwrap(), -50);
+    }
+}
@@ -0,0 +1,207 @@
+//! Local stand-in for the Jupiter API
+//!
+//! Replays recorded quote and swap-instruction responses over HTTP so the
+//! client and anything built on it can run offline. Quotes are matched on
+//! input mint, output mint, swap mode and amount; anything else gets the same
+//! "no route" error the real API returns.
+
+use crate::error::{ArbitrageError, Result};
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+/// Recorded responses shipped with the crate
+pub const FIXTURE_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/jupiter");
+
+/// File name prefix of recorded quote responses
+const QUOTE_PREFIX: &str = "quote";
+/// File name prefix of recorded swap-instruction responses
+const SWAP_INSTRUCTIONS_PREFIX: &str = "swap-instructions";
+
+#[derive(Debug, Default)]
+struct Recordings {
+    quotes: Vec<Value>,
+    swap_instructions: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QuoteParams {
+    input_mint: String,
+    output_mint: String,
+    amount: String,
+    #[serde(default)]
+    swap_mode: Option<String>,
+}
+
+/// Builder for the mock Jupiter server
+#[derive(Debug, Default)]
+pub struct JupiterMockServer {
+    recordings: Recordings,
+}
+
+impl JupiterMockServer {
+    /// Create a server with no recordings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load every `quote*.json` and `swap-instructions*.json` file in `dir`
+    pub fn load_dir(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let entries = std::fs::read_dir(dir).map_err(|e| {
+            ArbitrageError::config(format!("Failed to read {}: {}", dir.display(), e))
+        })?;
+
+        let mut paths = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        let mut server = Self::new();
+        for path in paths {
+            let name = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or_default();
+            let content = std::fs::read_to_string(&path).map_err(|e| {
+                ArbitrageError::config(format!("Failed to read {}: {}", path.display(), e))
+            })?;
+            let value: Value = serde_json::from_str(&content)?;
+
+            if name.starts_with(SWAP_INSTRUCTIONS_PREFIX) {
+                server = server.with_swap_instructions(value);
+            } else if name.starts_with(QUOTE_PREFIX) {
+                server = server.with_quote(value);
+            }
+        }
+        Ok(server)
+    }
+
+    /// Add a recorded quote response
+    pub fn with_quote(mut self, response: Value) -> Self {
+        self.recordings.quotes.push(response);
+        self
+    }
+
+    /// Set the recorded swap-instructions response returned for any quote
+    pub fn with_swap_instructions(mut self, response: Value) -> Self {
+        self.recordings.swap_instructions = Some(response);
+        self
+    }
+
+    /// Serve the recordings on an ephemeral local port
+    pub async fn spawn(self) -> Result<JupiterMockHandle> {
+        let app = Router::new()
+            .route("/v6/quote", get(quote))
+            .route("/v6/swap-instructions", post(swap_instructions))
+            .with_state(Arc::new(self.recordings));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .map_err(|e| ArbitrageError::internal(format!("Failed to bind mock server: {}", e)))?;
+        let addr = listener
+            .local_addr()
+            .map_err(|e| ArbitrageError::internal(format!("Failed to bind mock server: {}", e)))?;
+
+        let task = tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                debug!("Jupiter mock server stopped: {}", e);
+            }
+        });
+        Ok(JupiterMockHandle { addr, task })
+    }
+}
+
+/// Running mock server; stops when dropped
+#[derive(Debug)]
+pub struct JupiterMockHandle {
+    addr: SocketAddr,
+    task: JoinHandle<()>,
+}
+
+impl JupiterMockHandle {
+    /// Address the server listens on
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Base URL to use as `api_url`
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+}
+
+impl Drop for JupiterMockHandle {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+fn error_response(status: StatusCode, code: &str, message: &str) -> Response {
+    (status, Json(json!({ "error": message, "errorCode": code }))).into_response()
+}
+
+fn matches_quote(recorded: &Value, params: &QuoteParams) -> bool {
+    let swap_mode = params.swap_mode.as_deref().unwrap_or("ExactIn");
+    let amount_field = if swap_mode == "ExactOut" {
+        "outAmount"
+    } else {
+        "inAmount"
+    };
+
+    recorded["inputMint"] == params.input_mint.as_str()
+        && recorded["outputMint"] == params.output_mint.as_str()
+        && recorded["swapMode"] == swap_mode
+        && recorded[amount_field] == params.amount.as_str()
+}
+
+async fn quote(
+    State(recordings): State<Arc<Recordings>>,
+    Query(params): Query<QuoteParams>,
+) -> Response {
+    match recordings
+        .quotes
+        .iter()
+        .find(|recorded| matches_quote(recorded, &params))
+    {
+        Some(recorded) => Json(recorded.clone()).into_response(),
+        None => error_response(
+            StatusCode::BAD_REQUEST,
+            "COULD_NOT_FIND_ANY_ROUTE",
+            "Could not find any route",
+        ),
+    }
+}
+
+async fn swap_instructions(
+    State(recordings): State<Arc<Recordings>>,
+    Json(body): Json<Value>,
+) -> Response {
+    if body.get("quoteResponse").is_none() || body.get("userPublicKey").is_none() {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            "INVALID_REQUEST",
+            "quoteResponse and userPublicKey are required",
+        );
+    }
+
+    match &recordings.swap_instructions {
+        Some(recorded) => Json(recorded.clone()).into_response(),
+        None => error_response(
+            StatusCode::NOT_FOUND,
+            "NOT_RECORDED",
+            "No swap-instructions response recorded",
+        ),
+    }
+}
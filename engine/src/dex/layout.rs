@@ -0,0 +1,84 @@
+//! Little-endian readers for fixed-layout on-chain accounts
+
+use crate::error::{ArbitrageError, Result};
+use solana_sdk::pubkey::Pubkey;
+
+/// Offset of the `owner` field in an SPL token account
+pub(crate) const TOKEN_ACCOUNT_OWNER_OFFSET: usize = 32;
+
+/// Offset of the `amount` field in an SPL token account
+const TOKEN_ACCOUNT_AMOUNT_OFFSET: usize = 64;
+
+fn slice<const N: usize>(data: &[u8], offset: usize) -> Result<[u8; N]> {
+    data.get(offset..offset + N)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| {
+            ArbitrageError::dex_integration(format!(
+                "Account data too short: need {} bytes at offset {}, have {}",
+                N,
+                offset,
+                data.len()
+            ))
+        })
+}
+
+pub(crate) fn read_u8(data: &[u8], offset: usize) -> Result<u8> {
+    Ok(slice::<1>(data, offset)?[0])
+}
+
+pub(crate) fn read_bool(data: &[u8], offset: usize) -> Result<bool> {
+    Ok(read_u8(data, offset)? != 0)
+}
+
+pub(crate) fn read_u16(data: &[u8], offset: usize) -> Result<u16> {
+    Ok(u16::from_le_bytes(slice(data, offset)?))
+}
+
+pub(crate) fn read_i32(data: &[u8], offset: usize) -> Result<i32> {
+    Ok(i32::from_le_bytes(slice(data, offset)?))
+}
+
+pub(crate) fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+    Ok(u32::from_le_bytes(slice(data, offset)?))
+}
+
+pub(crate) fn read_u64(data: &[u8], offset: usize) -> Result<u64> {
+    Ok(u64::from_le_bytes(slice(data, offset)?))
+}
+
+pub(crate) fn read_i64(data: &[u8], offset: usize) -> Result<i64> {
+    Ok(i64::from_le_bytes(slice(data, offset)?))
+}
+
+pub(crate) fn read_u128(data: &[u8], offset: usize) -> Result<u128> {
+    Ok(u128::from_le_bytes(slice(data, offset)?))
+}
+
+pub(crate) fn read_i128(data: &[u8], offset: usize) -> Result<i128> {
+    Ok(i128::from_le_bytes(slice(data, offset)?))
+}
+
+pub(crate) fn read_pubkey(data: &[u8], offset: usize) -> Result<Pubkey> {
+    Ok(Pubkey::new_from_array(slice(data, offset)?))
+}
+
+/// Read the mint of an SPL token account
+pub(crate) fn token_account_mint(data: &[u8]) -> Result<Pubkey> {
+    read_pubkey(data, 0)
+}
+
+/// Read the token balance of an SPL token account
+pub(crate) fn token_account_amount(data: &[u8]) -> Result<u64> {
+    read_u64(data, TOKEN_ACCOUNT_AMOUNT_OFFSET)
+}
+
+/// Check the 8-byte Anchor account discriminator
+pub(crate) fn check_discriminator(data: &[u8], expected: &[u8; 8], name: &str) -> Result<()> {
+    if data.get(..8) != Some(expected.as_slice()) {
+        return Err(ArbitrageError::dex_integration(format!(
+            "Account is not a {} account",
+            name
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,93 @@
+//! Fixed-point helpers shared by the concentrated-liquidity adapters
+//!
+//! Prices are Q64.64 fixed-point numbers. Intermediate products can exceed
+//! 128 bits, so the full-precision operations go through `BigUint`.
+
+use crate::error::{ArbitrageError, Result};
+use num_bigint::BigUint;
+use num_traits::{ToPrimitive, Zero};
+
+/// Number of fractional bits in a Q64.64 number
+pub const Q64_RESOLUTION: u32 = 64;
+
+/// 1.0 in Q64.64
+pub const Q64_ONE: u128 = 1 << Q64_RESOLUTION;
+
+fn overflow(what: &str) -> ArbitrageError {
+    ArbitrageError::calculation(format!("{} overflow", what))
+}
+
+/// Convert a big integer back to `u128`
+pub(crate) fn big_to_u128(value: &BigUint, what: &str) -> Result<u128> {
+    value.to_u128().ok_or_else(|| overflow(what))
+}
+
+/// Divide two big integers, rounding up when requested
+pub(crate) fn big_div(
+    numerator: &BigUint,
+    denominator: &BigUint,
+    round_up: bool,
+) -> Result<BigUint> {
+    if denominator.is_zero() {
+        return Err(ArbitrageError::calculation("Division by zero"));
+    }
+    let quotient = numerator / denominator;
+    if round_up && !(numerator % denominator).is_zero() {
+        Ok(quotient + 1u32)
+    } else {
+        Ok(quotient)
+    }
+}
+
+/// Compute `a * b / denominator` without intermediate overflow
+pub fn mul_div(a: u128, b: u128, denominator: u128, round_up: bool) -> Result<u128> {
+    let product = BigUint::from(a) * b;
+    big_to_u128(
+        &big_div(&product, &BigUint::from(denominator), round_up)?,
+        "mul_div",
+    )
+}
+
+/// Compute `(a * b) >> shift` without intermediate overflow
+pub fn mul_shr(a: u128, b: u128, shift: u32, round_up: bool) -> Result<u128> {
+    let product = BigUint::from(a) * b;
+    let shifted = &product >> shift;
+    let rounded = if round_up && (&shifted << shift) != product {
+        shifted + 1u32
+    } else {
+        shifted
+    };
+    big_to_u128(&rounded, "mul_shr")
+}
+
+/// Compute `(a << shift) / denominator` without intermediate overflow
+pub fn shl_div(a: u128, shift: u32, denominator: u128, round_up: bool) -> Result<u128> {
+    let numerator = BigUint::from(a) << shift;
+    big_to_u128(
+        &big_div(&numerator, &BigUint::from(denominator), round_up)?,
+        "shl_div",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mul_div_rounding() {
+        assert_eq!(mul_div(10, 10, 3, false).unwrap(), 33);
+        assert_eq!(mul_div(10, 10, 3, true).unwrap(), 34);
+        assert_eq!(mul_div(u128::MAX, 4, 8, false).unwrap(), u128::MAX / 2);
+        assert!(mul_div(u128::MAX, 4, 2, false).is_err());
+        assert!(mul_div(1, 1, 0, false).is_err());
+    }
+
+    #[test]
+    fn test_shifts() {
+        assert_eq!(mul_shr(Q64_ONE, Q64_ONE, 64, false).unwrap(), Q64_ONE);
+        assert_eq!(mul_shr(3, 1, 1, false).unwrap(), 1);
+        assert_eq!(mul_shr(3, 1, 1, true).unwrap(), 2);
+        assert_eq!(shl_div(1, 64, 2, false).unwrap(), Q64_ONE / 2);
+        assert_eq!(shl_div(1, 64, 3, true).unwrap(), Q64_ONE / 3 + 1);
+    }
+}
@@ -0,0 +1,652 @@
+//! Raydium AMM v4 integration
+//!
+//! Decodes the `AmmInfo` account and quotes swaps with the same integer
+//! constant-product math the program runs on-chain. Reserves come from the
+//! coin/pc vault balances plus the OpenBook open-orders totals, minus the PnL
+//! the pool still owes to the protocol.
+
+use super::layout::{read_pubkey, read_u64, token_account_amount};
+use super::{
+    DexAdapter, PoolData, PoolState, Quote, QuoteRequest, SwapMode, SwapParams, RAYDIUM,
+    TOKEN_PROGRAM_ID,
+};
+use crate::error::{ArbitrageError, Result};
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+
+/// Raydium AMM v4 program id
+pub const RAYDIUM_AMM_V4_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8");
+
+/// PDA signing for all AMM v4 vaults (`[b"amm authority"]`)
+pub const RAYDIUM_AMM_AUTHORITY: Pubkey =
+    Pubkey::from_str_const("5Q544fKrFoe6tsEbD7S8EmxGTJYAKtTVhAW5Q5pge4j1");
+
+/// Size of the `AmmInfo` account
+pub const AMM_INFO_LEN: usize = 752;
+
+const STATUS_OFFSET: usize = 0;
+const COIN_DECIMALS_OFFSET: usize = 32;
+const PC_DECIMALS_OFFSET: usize = 40;
+const SWAP_FEE_NUMERATOR_OFFSET: usize = 176;
+const SWAP_FEE_DENOMINATOR_OFFSET: usize = 184;
+const NEED_TAKE_PNL_COIN_OFFSET: usize = 192;
+const NEED_TAKE_PNL_PC_OFFSET: usize = 200;
+const COIN_VAULT_OFFSET: usize = 336;
+const PC_VAULT_OFFSET: usize = 368;
+const COIN_MINT_OFFSET: usize = 400;
+const PC_MINT_OFFSET: usize = 432;
+const OPEN_ORDERS_OFFSET: usize = 496;
+
+/// Offsets of `native_coin_total`/`native_pc_total` in an OpenBook open-orders account
+const OPEN_ORDERS_COIN_TOTAL_OFFSET: usize = 85;
+const OPEN_ORDERS_PC_TOTAL_OFFSET: usize = 101;
+
+/// `AmmStatus::Initialized`
+const STATUS_INITIALIZED: u64 = 1;
+/// `AmmStatus::SwapOnly`
+const STATUS_SWAP_ONLY: u64 = 6;
+
+/// `SwapBaseInV2` instruction tag, which no longer needs OpenBook accounts
+const SWAP_BASE_IN_V2_TAG: u8 = 16;
+/// `SwapBaseOutV2` instruction tag
+const SWAP_BASE_OUT_V2_TAG: u8 = 17;
+
+/// Decoded Raydium AMM v4 pool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RaydiumAmmState {
+    /// Pool status (`AmmStatus`)
+    pub status: u64,
+    /// Decimals of the coin (base) mint
+    pub coin_decimals: u64,
+    /// Decimals of the pc (quote) mint
+    pub pc_decimals: u64,
+    /// Swap fee numerator
+    pub swap_fee_numerator: u64,
+    /// Swap fee denominator
+    pub swap_fee_denominator: u64,
+    /// Coin PnL owed to the protocol, excluded from reserves
+    pub need_take_pnl_coin: u64,
+    /// Pc PnL owed to the protocol, excluded from reserves
+    pub need_take_pnl_pc: u64,
+    /// Coin token vault
+    pub coin_vault: Pubkey,
+    /// Pc token vault
+    pub pc_vault: Pubkey,
+    /// OpenBook open-orders account
+    pub open_orders: Pubkey,
+    /// Coin vault balance
+    pub coin_vault_amount: u64,
+    /// Pc vault balance
+    pub pc_vault_amount: u64,
+    /// Coin held in open orders
+    pub open_orders_coin_total: u64,
+    /// Pc held in open orders
+    pub open_orders_pc_total: u64,
+}
+
+impl RaydiumAmmState {
+    /// Decode an `AmmInfo` account. Balances start at zero until the vaults
+    /// and open orders are applied.
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        if data.len() != AMM_INFO_LEN {
+            return Err(ArbitrageError::dex_integration(format!(
+                "Invalid Raydium AMM account length: {}",
+                data.len()
+            )));
+        }
+
+        Ok(Self {
+            status: read_u64(data, STATUS_OFFSET)?,
+            coin_decimals: read_u64(data, COIN_DECIMALS_OFFSET)?,
+            pc_decimals: read_u64(data, PC_DECIMALS_OFFSET)?,
+            swap_fee_numerator: read_u64(data, SWAP_FEE_NUMERATOR_OFFSET)?,
+            swap_fee_denominator: read_u64(data, SWAP_FEE_DENOMINATOR_OFFSET)?,
+            need_take_pnl_coin: read_u64(data, NEED_TAKE_PNL_COIN_OFFSET)?,
+            need_take_pnl_pc: read_u64(data, NEED_TAKE_PNL_PC_OFFSET)?,
+            coin_vault: read_pubkey(data, COIN_VAULT_OFFSET)?,
+            pc_vault: read_pubkey(data, PC_VAULT_OFFSET)?,
+            open_orders: read_pubkey(data, OPEN_ORDERS_OFFSET)?,
+            coin_vault_amount: 0,
+            pc_vault_amount: 0,
+            open_orders_coin_total: 0,
+            open_orders_pc_total: 0,
+        })
+    }
+
+    /// Coin reserve used for pricing
+    pub fn coin_reserve(&self) -> u64 {
+        self.coin_vault_amount
+            .saturating_add(self.open_orders_coin_total)
+            .saturating_sub(self.need_take_pnl_coin)
+    }
+
+    /// Pc reserve used for pricing
+    pub fn pc_reserve(&self) -> u64 {
+        self.pc_vault_amount
+            .saturating_add(self.open_orders_pc_total)
+            .saturating_sub(self.need_take_pnl_pc)
+    }
+
+    /// Check whether the pool currently accepts swaps
+    pub fn is_swappable(&self) -> bool {
+        matches!(self.status, STATUS_INITIALIZED | STATUS_SWAP_ONLY)
+    }
+
+    /// Check whether the pool has a live open-orders account
+    fn has_open_orders(&self) -> bool {
+        self.open_orders != Pubkey::default()
+    }
+
+    /// Quote an exact-in swap, returning `(amount_out, fee)`
+    pub fn quote_exact_in(&self, amount_in: u64, coin_to_pc: bool) -> Result<(u64, u64)> {
+        let (reserve_in, reserve_out) = self.reserves(coin_to_pc)?;
+
+        let fee = ceil_div(
+            amount_in as u128 * self.swap_fee_numerator as u128,
+            self.swap_fee_denominator as u128,
+        )?;
+        let amount_in_less_fee = amount_in as u128 - fee;
+        let amount_out =
+            reserve_out as u128 * amount_in_less_fee / (reserve_in as u128 + amount_in_less_fee);
+
+        Ok((amount_out as u64, fee as u64))
+    }
+
+    /// Quote an exact-out swap, returning `(amount_in, fee)`
+    pub fn quote_exact_out(&self, amount_out: u64, coin_to_pc: bool) -> Result<(u64, u64)> {
+        let (reserve_in, reserve_out) = self.reserves(coin_to_pc)?;
+        if amount_out >= reserve_out {
+            return Err(ArbitrageError::InsufficientLiquidity);
+        }
+
+        let amount_in_before_fee = ceil_div(
+            reserve_in as u128 * amount_out as u128,
+            (reserve_out - amount_out) as u128,
+        )?;
+        let amount_in = ceil_div(
+            amount_in_before_fee * self.swap_fee_denominator as u128,
+            (self.swap_fee_denominator - self.swap_fee_numerator) as u128,
+        )?;
+        let amount_in = u64::try_from(amount_in)
+            .map_err(|_| ArbitrageError::calculation("Raydium input amount overflow"))?;
+
+        Ok((amount_in, amount_in - amount_in_before_fee as u64))
+    }
+
+    fn reserves(&self, coin_to_pc: bool) -> Result<(u64, u64)> {
+        if !self.is_swappable() {
+            return Err(ArbitrageError::dex_integration(format!(
+                "Raydium pool is not swappable (status {})",
+                self.status
+            )));
+        }
+        if self.swap_fee_denominator == 0 || self.swap_fee_numerator >= self.swap_fee_denominator {
+            return Err(ArbitrageError::dex_integration("Invalid Raydium swap fee"));
+        }
+
+        let (reserve_in, reserve_out) = if coin_to_pc {
+            (self.coin_reserve(), self.pc_reserve())
+        } else {
+            (self.pc_reserve(), self.coin_reserve())
+        };
+        if reserve_in == 0 || reserve_out == 0 {
+            return Err(ArbitrageError::InsufficientLiquidity);
+        }
+        Ok((reserve_in, reserve_out))
+    }
+}
+
+fn ceil_div(numerator: u128, denominator: u128) -> Result<u128> {
+    if denominator == 0 {
+        return Err(ArbitrageError::calculation("Division by zero"));
+    }
+    Ok(numerator.div_ceil(denominator))
+}
+
+/// Price impact in basis points of receiving `amount_out` for `amount_in`
+/// against the spot price `reserve_out / reserve_in`
+fn price_impact_bps(amount_in: u64, amount_out: u64, reserve_in: u64, reserve_out: u64) -> u32 {
+    let spot_out = amount_in as u128 * reserve_out as u128 / reserve_in as u128;
+    if spot_out == 0 {
+        return 0;
+    }
+    (spot_out.saturating_sub(amount_out as u128) * 10_000 / spot_out) as u32
+}
+
+/// Raydium AMM v4 adapter
+#[derive(Debug, Default)]
+pub struct RaydiumAmmAdapter;
+
+impl RaydiumAmmAdapter {
+    /// Create a new Raydium AMM v4 adapter
+    pub fn new() -> Self {
+        Self
+    }
+
+    fn state(pool: &PoolState) -> Result<&RaydiumAmmState> {
+        match &pool.data {
+            PoolData::Raydium(state) => Ok(state),
+            _ => Err(ArbitrageError::dex_integration(format!(
+                "Pool {} is not a Raydium AMM v4 pool",
+                pool.address
+            ))),
+        }
+    }
+}
+
+impl DexAdapter for RaydiumAmmAdapter {
+    fn id(&self) -> &'static str {
+        RAYDIUM
+    }
+
+    fn program_id(&self) -> Pubkey {
+        RAYDIUM_AMM_V4_PROGRAM_ID
+    }
+
+    fn pool_account_len(&self) -> usize {
+        AMM_INFO_LEN
+    }
+
+    fn decode_pool(&self, address: Pubkey, data: &[u8]) -> Result<PoolState> {
+        let state = RaydiumAmmState::decode(data)?;
+        Ok(PoolState {
+            address,
+            dex: RAYDIUM,
+            mint_a: read_pubkey(data, COIN_MINT_OFFSET)?,
+            mint_b: read_pubkey(data, PC_MINT_OFFSET)?,
+            data: PoolData::Raydium(state),
+        })
+    }
+
+    fn dependent_accounts(&self, pool: &PoolState) -> Vec<Pubkey> {
+        let Ok(state) = Self::state(pool) else {
+            return Vec::new();
+        };
+        let mut accounts = vec![state.coin_vault, state.pc_vault];
+        if state.has_open_orders() {
+            accounts.push(state.open_orders);
+        }
+        accounts
+    }
+
+    fn apply_dependent_account(
+        &self,
+        pool: &mut PoolState,
+        address: &Pubkey,
+        data: &[u8],
+    ) -> Result<()> {
+        let PoolData::Raydium(state) = &mut pool.data else {
+            return Err(ArbitrageError::dex_integration(format!(
+                "Pool {} is not a Raydium AMM v4 pool",
+                pool.address
+            )));
+        };
+
+        if *address == state.coin_vault {
+            state.coin_vault_amount = token_account_amount(data)?;
+        } else if *address == state.pc_vault {
+            state.pc_vault_amount = token_account_amount(data)?;
+        } else if *address == state.open_orders && state.has_open_orders() {
+            state.open_orders_coin_total = read_u64(data, OPEN_ORDERS_COIN_TOTAL_OFFSET)?;
+            state.open_orders_pc_total = read_u64(data, OPEN_ORDERS_PC_TOTAL_OFFSET)?;
+        }
+        Ok(())
+    }
+
+    fn reserves(&self, pool: &PoolState) -> Option<(u64, u64)> {
+        let state = Self::state(pool).ok()?;
+        Some((state.coin_reserve(), state.pc_reserve()))
+    }
+
+    fn quote(&self, pool: &PoolState, request: &QuoteRequest) -> Result<Quote> {
+        let state = Self::state(pool)?;
+        let coin_to_pc = pool.is_a_to_b(&request.input_mint)?;
+        let (in_amount, out_amount, fee_amount) = match request.mode {
+            SwapMode::ExactIn => {
+                let (out, fee) = state.quote_exact_in(request.amount, coin_to_pc)?;
+                (request.amount, out, fee)
+            }
+            SwapMode::ExactOut => {
+                let (amount_in, fee) = state.quote_exact_out(request.amount, coin_to_pc)?;
+                (amount_in, request.amount, fee)
+            }
+        };
+
+        let (reserve_in, reserve_out) = state.reserves(coin_to_pc)?;
+        Ok(Quote {
+            input_mint: request.input_mint,
+            output_mint: if coin_to_pc { pool.mint_b } else { pool.mint_a },
+            in_amount,
+            out_amount,
+            fee_amount,
+            price_impact_bps: price_impact_bps(
+                in_amount - fee_amount,
+                out_amount,
+                reserve_in,
+                reserve_out,
+            ),
+        })
+    }
+
+    fn swap_instruction(&self, pool: &PoolState, params: &SwapParams) -> Result<Instruction> {
+        let state = Self::state(pool)?;
+        pool.is_a_to_b(&params.input_mint)?;
+
+        // Both v2 swaps take (amount, threshold): amount_in/min_out for base-in,
+        // max_in/amount_out for base-out.
+        let mut data = Vec::with_capacity(17);
+        match params.mode {
+            SwapMode::ExactIn => {
+                data.push(SWAP_BASE_IN_V2_TAG);
+                data.extend_from_slice(&params.amount.to_le_bytes());
+                data.extend_from_slice(&params.other_amount_threshold.to_le_bytes());
+            }
+            SwapMode::ExactOut => {
+                data.push(SWAP_BASE_OUT_V2_TAG);
+                data.extend_from_slice(&params.other_amount_threshold.to_le_bytes());
+                data.extend_from_slice(&params.amount.to_le_bytes());
+            }
+        }
+
+        Ok(Instruction {
+            program_id: RAYDIUM_AMM_V4_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                AccountMeta::new(pool.address, false),
+                AccountMeta::new_readonly(RAYDIUM_AMM_AUTHORITY, false),
+                AccountMeta::new(state.coin_vault, false),
+                AccountMeta::new(state.pc_vault, false),
+                AccountMeta::new(params.source_token_account, false),
+                AccountMeta::new(params.destination_token_account, false),
+                AccountMeta::new_readonly(params.user, true),
+            ],
+            data,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COIN_MINT: Pubkey = Pubkey::new_from_array([1; 32]);
+    const PC_MINT: Pubkey = Pubkey::new_from_array([2; 32]);
+    const COIN_VAULT: Pubkey = Pubkey::new_from_array([3; 32]);
+    const PC_VAULT: Pubkey = Pubkey::new_from_array([4; 32]);
+    const OPEN_ORDERS: Pubkey = Pubkey::new_from_array([5; 32]);
+
+    /// Little-endian writer serializing account structs field by field
+    #[derive(Default)]
+    struct AccountWriter(Vec<u8>);
+
+    impl AccountWriter {
+        fn u64(&mut self, value: u64) -> &mut Self {
+            self.0.extend_from_slice(&value.to_le_bytes());
+            self
+        }
+
+        fn u128(&mut self, value: u128) -> &mut Self {
+            self.0.extend_from_slice(&value.to_le_bytes());
+            self
+        }
+
+        fn pubkey(&mut self, key: Pubkey) -> &mut Self {
+            self.0.extend_from_slice(key.as_ref());
+            self
+        }
+
+        fn bytes(&mut self, bytes: &[u8]) -> &mut Self {
+            self.0.extend_from_slice(bytes);
+            self
+        }
+
+        fn zeros(&mut self, len: usize) -> &mut Self {
+            self.0.resize(self.0.len() + len, 0);
+            self
+        }
+    }
+
+    /// `AmmInfo` of a SOL/USDC-like pool, serialized in the declaration order
+    /// of the program's struct rather than through the decoder's offsets
+    fn amm_info_fixture() -> Vec<u8> {
+        let mut w = AccountWriter::default();
+        w.u64(STATUS_SWAP_ONLY) // status
+            .u64(254) // nonce
+            .u64(7) // order_num
+            .u64(3) // depth
+            .u64(9) // coin_decimals
+            .u64(6) // pc_decimals
+            .u64(1) // state
+            .u64(0) // reset_flag
+            .u64(1) // min_size
+            .u64(500) // vol_max_cut_ratio
+            .u64(5_000_000) // amount_wave
+            .u64(1_000_000) // coin_lot_size
+            .u64(1) // pc_lot_size
+            .u64(1) // min_price_multiplier
+            .u64(1_000_000_000) // max_price_multiplier
+            .u64(1_000_000_000); // sys_decimal_value
+
+        // Fees
+        w.u64(5) // min_separate_numerator
+            .u64(10_000) // min_separate_denominator
+            .u64(25) // trade_fee_numerator
+            .u64(10_000) // trade_fee_denominator
+            .u64(12) // pnl_numerator
+            .u64(100) // pnl_denominator
+            .u64(25) // swap_fee_numerator
+            .u64(10_000); // swap_fee_denominator
+
+        // StateData
+        w.u64(0) // need_take_pnl_coin
+            .u64(2_000_000) // need_take_pnl_pc
+            .u64(1_000_000_000) // total_pnl_pc
+            .u64(7_000_000_000) // total_pnl_coin
+            .u64(0) // pool_open_time
+            .zeros(16) // padding
+            .u64(0) // orderbook_to_init_time
+            .u128(1 << 70) // swap_coin_in_amount
+            .u128(1 << 60) // swap_pc_out_amount
+            .u64(1 << 40) // swap_acc_pc_fee
+            .u128(1 << 61) // swap_pc_in_amount
+            .u128(1 << 71) // swap_coin_out_amount
+            .u64(1 << 41); // swap_acc_coin_fee
+        w.pubkey(COIN_VAULT)
+            .pubkey(PC_VAULT)
+            .pubkey(COIN_MINT)
+            .pubkey(PC_MINT)
+            .pubkey(Pubkey::new_from_array([6; 32])) // lp_mint
+            .pubkey(OPEN_ORDERS)
+            .pubkey(Pubkey::new_from_array([7; 32])) // market
+            .pubkey(Pubkey::new_from_array([8; 32])) // market_program
+            .pubkey(Pubkey::new_from_array([9; 32])) // target_orders
+            .zeros(64) // padding1
+            .pubkey(Pubkey::new_from_array([10; 32])) // amm_owner
+            .u64(1_000_000_000_000) // lp_amount
+            .u64(42) // client_order_id
+            .u64(600) // recent_epoch
+            .u64(0); // padding2
+        assert_eq!(w.0.len(), AMM_INFO_LEN);
+        w.0
+    }
+
+    /// SPL token account holding `amount`
+    fn token_account_fixture(amount: u64) -> Vec<u8> {
+        let mut w = AccountWriter::default();
+        w.pubkey(COIN_MINT) // mint
+            .pubkey(Pubkey::new_from_array([11; 32])) // owner
+            .u64(amount)
+            .zeros(4 + 32) // delegate
+            .bytes(&[1]) // state
+            .zeros(4 + 8) // is_native
+            .u64(0) // delegated_amount
+            .zeros(4 + 32); // close_authority
+        assert_eq!(w.0.len(), 165);
+        w.0
+    }
+
+    /// OpenBook `OpenOrders` account with the given coin and pc totals
+    fn open_orders_fixture(coin_total: u64, pc_total: u64) -> Vec<u8> {
+        let mut w = AccountWriter::default();
+        w.bytes(b"serum")
+            .u64(0b101) // account_flags: initialized | open_orders
+            .pubkey(Pubkey::new_from_array([7; 32])) // market
+            .pubkey(RAYDIUM_AMM_AUTHORITY) // owner
+            .u64(1_000) // native_coin_free
+            .u64(coin_total)
+            .u64(2_000) // native_pc_free
+            .u64(pc_total)
+            .u128(0) // free_slot_bits
+            .u128(0) // is_bid_bits
+            .zeros(128 * 16) // orders
+            .zeros(128 * 8) // client_order_ids
+            .u64(0) // referrer_rebates_accrued
+            .bytes(b"padding");
+        assert_eq!(w.0.len(), 3228);
+        w.0
+    }
+
+    fn loaded_pool() -> PoolState {
+        let adapter = RaydiumAmmAdapter::new();
+        let mut pool = adapter
+            .decode_pool(Pubkey::new_unique(), &amm_info_fixture())
+            .unwrap();
+        adapter
+            .apply_dependent_account(
+                &mut pool,
+                &COIN_VAULT,
+                &token_account_fixture(1_000_000_000_000),
+            )
+            .unwrap();
+        adapter
+            .apply_dependent_account(
+                &mut pool,
+                &PC_VAULT,
+                &token_account_fixture(150_000_000_000),
+            )
+            .unwrap();
+        adapter
+            .apply_dependent_account(
+                &mut pool,
+                &OPEN_ORDERS,
+                &open_orders_fixture(5_000_000, 1_000_000),
+            )
+            .unwrap();
+        pool
+    }
+
+    #[test]
+    fn test_decode_amm_info() {
+        let adapter = RaydiumAmmAdapter::new();
+        let pool = adapter
+            .decode_pool(Pubkey::new_unique(), &amm_info_fixture())
+            .unwrap();
+
+        assert_eq!(pool.mint_a, COIN_MINT);
+        assert_eq!(pool.mint_b, PC_MINT);
+        let state = RaydiumAmmAdapter::state(&pool).unwrap();
+        assert_eq!(state.coin_decimals, 9);
+        assert_eq!(state.pc_decimals, 6);
+        assert_eq!(state.swap_fee_numerator, 25);
+        assert_eq!(state.swap_fee_denominator, 10_000);
+        assert_eq!(
+            adapter.dependent_accounts(&pool),
+            vec![COIN_VAULT, PC_VAULT, OPEN_ORDERS]
+        );
+
+        assert!(adapter
+            .decode_pool(Pubkey::new_unique(), &[0u8; 100])
+            .is_err());
+    }
+
+    #[test]
+    fn test_reserves_include_open_orders_and_pnl() {
+        let pool = loaded_pool();
+        let state = RaydiumAmmAdapter::state(&pool).unwrap();
+        assert_eq!(state.coin_reserve(), 1_000_005_000_000);
+        assert_eq!(state.pc_reserve(), 149_999_000_000);
+    }
+
+    #[test]
+    fn test_quote_exact_in_both_directions() {
+        let adapter = RaydiumAmmAdapter::new();
+        let pool = loaded_pool();
+
+        let quote = adapter
+            .quote(&pool, &QuoteRequest::exact_in(COIN_MINT, 1_000_000_000))
+            .unwrap();
+        assert_eq!(quote.output_mint, PC_MINT);
+        assert_eq!(quote.fee_amount, 2_500_000);
+        assert_eq!(quote.out_amount, 149_474_154);
+        assert_eq!(quote.price_impact_bps, 9);
+
+        let quote = adapter
+            .quote(&pool, &QuoteRequest::exact_in(PC_MINT, 150_000_000))
+            .unwrap();
+        assert_eq!(quote.output_mint, COIN_MINT);
+        assert_eq!(quote.fee_amount, 375_000);
+        assert_eq!(quote.out_amount, 996_517_604);
+    }
+
+    #[test]
+    fn test_quote_exact_out() {
+        let adapter = RaydiumAmmAdapter::new();
+        let pool = loaded_pool();
+
+        let quote = adapter
+            .quote(&pool, &QuoteRequest::exact_out(COIN_MINT, 100_000_000))
+            .unwrap();
+        assert_eq!(quote.in_amount, 668_791_172);
+        assert_eq!(quote.fee_amount, 1_671_978);
+
+        // Spending the quoted input must yield at least the requested output
+        let forward = adapter
+            .quote(&pool, &QuoteRequest::exact_in(COIN_MINT, quote.in_amount))
+            .unwrap();
+        assert!(forward.out_amount >= 100_000_000);
+
+        assert!(matches!(
+            adapter.quote(&pool, &QuoteRequest::exact_out(COIN_MINT, 149_999_000_000)),
+            Err(ArbitrageError::InsufficientLiquidity)
+        ));
+    }
+
+    #[test]
+    fn test_swap_instruction_layout() {
+        let adapter = RaydiumAmmAdapter::new();
+        let pool = loaded_pool();
+        let user = Pubkey::new_unique();
+        let params = SwapParams {
+            user,
+            source_token_account: Pubkey::new_unique(),
+            destination_token_account: Pubkey::new_unique(),
+            input_mint: COIN_MINT,
+            amount: 1_000,
+            other_amount_threshold: 900,
+            mode: SwapMode::ExactIn,
+        };
+
+        let ix = adapter.swap_instruction(&pool, &params).unwrap();
+        assert_eq!(ix.program_id, RAYDIUM_AMM_V4_PROGRAM_ID);
+        assert_eq!(ix.data[0], SWAP_BASE_IN_V2_TAG);
+        assert_eq!(&ix.data[1..9], &1_000u64.to_le_bytes());
+        assert_eq!(&ix.data[9..17], &900u64.to_le_bytes());
+        assert_eq!(ix.accounts.len(), 8);
+        assert_eq!(ix.accounts[1].pubkey, pool.address);
+        assert!(ix.accounts[7].is_signer);
+        assert_eq!(ix.accounts[7].pubkey, user);
+    }
+
+    #[test]
+    fn test_rejects_disabled_pool() {
+        let adapter = RaydiumAmmAdapter::new();
+        let mut pool = loaded_pool();
+        if let PoolData::Raydium(state) = &mut pool.data {
+            state.status = 4; // OrderBookOnly
+        }
+        assert!(adapter
+            .quote(&pool, &QuoteRequest::exact_in(COIN_MINT, 1_000))
+            .is_err());
+    }
+}
@@ -0,0 +1,1031 @@
+//! Orca Whirlpool integration
+//!
+//! Decodes `Whirlpool` and `TickArray` accounts and quotes swaps the way the
+//! program executes them: step from the current sqrt price to the next
+//! initialized tick, cross it (updating active liquidity by the tick's
+//! `liquidity_net`) and continue until the amount is exhausted. All price math
+//! is Q64.64 fixed point with the program's rounding directions.
+
+use super::layout::{
+    check_discriminator, read_bool, read_i128, read_i32, read_pubkey, read_u128, read_u16,
+};
+use super::math::{big_div, big_to_u128, mul_shr, shl_div, Q64_ONE, Q64_RESOLUTION};
+use super::{
+    DexAdapter, PoolData, PoolState, Quote, QuoteRequest, SwapMode, SwapParams, ORCA,
+    TOKEN_PROGRAM_ID,
+};
+use crate::error::{ArbitrageError, Result};
+use num_bigint::BigUint;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeMap;
+
+/// Orca Whirlpool program id
+pub const WHIRLPOOL_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc");
+
+/// Size of the `Whirlpool` account
+pub const WHIRLPOOL_LEN: usize = 653;
+/// Size of the `TickArray` account
+pub const TICK_ARRAY_LEN: usize = 9988;
+/// Number of ticks stored in one tick array
+pub const TICK_ARRAY_SIZE: i32 = 88;
+
+/// Lowest tick index supported by Whirlpools
+pub const MIN_TICK_INDEX: i32 = -443_636;
+/// Highest tick index supported by Whirlpools
+pub const MAX_TICK_INDEX: i32 = 443_636;
+/// Sqrt price at `MIN_TICK_INDEX`
+pub const MIN_SQRT_PRICE: u128 = 4_295_048_016;
+/// Sqrt price at `MAX_TICK_INDEX`
+pub const MAX_SQRT_PRICE: u128 = 79_226_673_515_401_279_992_447_579_055;
+
+/// Fee rates are expressed in hundredths of a basis point
+const FEE_RATE_DENOMINATOR: u128 = 1_000_000;
+
+const WHIRLPOOL_DISCRIMINATOR: [u8; 8] = [63, 149, 209, 12, 225, 128, 99, 9];
+const TICK_ARRAY_DISCRIMINATOR: [u8; 8] = [69, 97, 189, 190, 110, 7, 66, 187];
+const SWAP_DISCRIMINATOR: [u8; 8] = [248, 198, 158, 145, 225, 117, 135, 200];
+
+const TICK_SPACING_OFFSET: usize = 41;
+const FEE_RATE_OFFSET: usize = 45;
+const LIQUIDITY_OFFSET: usize = 49;
+const SQRT_PRICE_OFFSET: usize = 65;
+const TICK_CURRENT_INDEX_OFFSET: usize = 81;
+const TOKEN_MINT_A_OFFSET: usize = 101;
+const TOKEN_VAULT_A_OFFSET: usize = 133;
+const TOKEN_MINT_B_OFFSET: usize = 181;
+const TOKEN_VAULT_B_OFFSET: usize = 213;
+
+const TICK_ARRAY_START_OFFSET: usize = 8;
+const TICK_ARRAY_TICKS_OFFSET: usize = 12;
+const TICK_ARRAY_WHIRLPOOL_OFFSET: usize = 9956;
+const TICK_LEN: usize = 113;
+
+/// Tick arrays kept on each side of the one holding the current tick
+const TICK_ARRAY_WINDOW: i32 = 2;
+
+/// `sqrt(1.0001)^(-2^i)` in Q64 for every bit of a negative tick index, as in
+/// the Whirlpool program's `get_sqrt_price_negative_tick`
+const NEGATIVE_TICK_RATIOS: [u128; 19] = [
+    18445821805675392311,
+    18444899583751176498,
+    18443055278223354162,
+    18439367220385604838,
+    18431993317065449817,
+    18417254355718160513,
+    18387811781193591352,
+    18329067761203520168,
+    18212142134806087854,
+    17980523815641551639,
+    17526086738831147013,
+    16651378430235024244,
+    15030750278693429944,
+    12247334978882834399,
+    8131365268884726200,
+    3584323654723342297,
+    696457651847595233,
+    26294789957452057,
+    37481735321082,
+];
+
+/// `sqrt(1.0001)^(2^i)` in Q96 for every bit of a positive tick index, as in
+/// the Whirlpool program's `get_sqrt_price_positive_tick`
+const POSITIVE_TICK_RATIOS: [u128; 19] = [
+    79232123823359799118286999567,
+    79236085330515764027303304731,
+    79244008939048815603706035061,
+    79259858533276714757314932305,
+    79291567232598584799939703904,
+    79355022692464371645785046466,
+    79482085999252804386437311141,
+    79736823300114093921829183326,
+    80248749790819932309965073892,
+    81282483887344747381513967011,
+    83390072131320151908154831281,
+    87770609709833776024991924138,
+    97234110755111693312479820773,
+    119332217159966728226237229890,
+    179736315981702064433883588727,
+    407748233172238350107850275304,
+    2098478828474011932436660412517,
+    55581415166113811149459800483533,
+    38992368544603139932233054999993551,
+];
+
+/// Q96 resolution of `POSITIVE_TICK_RATIOS`
+const Q96_RESOLUTION: u32 = 96;
+
+/// Compute the Q64.64 sqrt price of a tick index, `sqrt(1.0001^tick)`,
+/// rounding exactly like the on-chain program
+pub fn sqrt_price_from_tick_index(tick: i32) -> Result<u128> {
+    if !(MIN_TICK_INDEX..=MAX_TICK_INDEX).contains(&tick) {
+        return Err(ArbitrageError::calculation(format!(
+            "Tick index out of range: {}",
+            tick
+        )));
+    }
+
+    if tick >= 0 {
+        positive_tick_sqrt_price(tick.unsigned_abs())
+    } else {
+        Ok(negative_tick_sqrt_price(tick.unsigned_abs()))
+    }
+}
+
+/// Q96 product of the positive ratios, narrowed to Q64.64 at the end
+fn positive_tick_sqrt_price(tick: u32) -> Result<u128> {
+    let mut ratio = BigUint::from(if tick & 1 != 0 {
+        POSITIVE_TICK_RATIOS[0]
+    } else {
+        1u128 << Q96_RESOLUTION
+    });
+    for (bit, factor) in POSITIVE_TICK_RATIOS.iter().enumerate().skip(1) {
+        if tick & (1 << bit) != 0 {
+            ratio = (ratio * *factor) >> Q96_RESOLUTION;
+        }
+    }
+    big_to_u128(&(ratio >> (Q96_RESOLUTION - Q64_RESOLUTION)), "sqrt price")
+}
+
+/// Q64 product of the negative ratios, truncated after every step
+fn negative_tick_sqrt_price(tick: u32) -> u128 {
+    let mut ratio = if tick & 1 != 0 {
+        NEGATIVE_TICK_RATIOS[0]
+    } else {
+        Q64_ONE
+    };
+    for (bit, factor) in NEGATIVE_TICK_RATIOS.iter().enumerate().skip(1) {
+        if tick & (1 << bit) != 0 {
+            // Both factors are below 2^64, so the product fits
+            ratio = (ratio * factor) >> Q64_RESOLUTION;
+        }
+    }
+    ratio
+}
+
+/// Amount of token A between two sqrt prices: `L * (upper - lower) / (upper * lower)`
+fn amount_delta_a(price_0: u128, price_1: u128, liquidity: u128, round_up: bool) -> Result<u128> {
+    let (lower, upper) = if price_0 < price_1 {
+        (price_0, price_1)
+    } else {
+        (price_1, price_0)
+    };
+    let numerator = (BigUint::from(liquidity) * (upper - lower)) << Q64_RESOLUTION;
+    let denominator = BigUint::from(upper) * lower;
+    big_to_u128(
+        &big_div(&numerator, &denominator, round_up)?,
+        "amount delta A",
+    )
+}
+
+/// Amount of token B between two sqrt prices: `L * (upper - lower)`
+fn amount_delta_b(price_0: u128, price_1: u128, liquidity: u128, round_up: bool) -> Result<u128> {
+    let diff = price_0.abs_diff(price_1);
+    mul_shr(liquidity, diff, Q64_RESOLUTION, round_up)
+}
+
+/// Sqrt price after adding (or removing) `amount` of token A
+fn next_sqrt_price_from_a(
+    sqrt_price: u128,
+    liquidity: u128,
+    amount: u128,
+    add: bool,
+) -> Result<u128> {
+    if amount == 0 {
+        return Ok(sqrt_price);
+    }
+    let product = BigUint::from(sqrt_price) * amount;
+    let liquidity_x64 = BigUint::from(liquidity) << Q64_RESOLUTION;
+    let numerator = &liquidity_x64 * sqrt_price;
+    let denominator = if add {
+        liquidity_x64 + product
+    } else if product < liquidity_x64 {
+        liquidity_x64 - product
+    } else {
+        return Err(ArbitrageError::InsufficientLiquidity);
+    };
+    big_to_u128(&big_div(&numerator, &denominator, true)?, "sqrt price")
+}
+
+/// Sqrt price after adding (or removing) `amount` of token B
+fn next_sqrt_price_from_b(
+    sqrt_price: u128,
+    liquidity: u128,
+    amount: u128,
+    add: bool,
+) -> Result<u128> {
+    let delta = shl_div(amount, Q64_RESOLUTION, liquidity, !add)?;
+    if add {
+        sqrt_price
+            .checked_add(delta)
+            .ok_or_else(|| ArbitrageError::calculation("Sqrt price overflow"))
+    } else {
+        sqrt_price
+            .checked_sub(delta)
+            .ok_or(ArbitrageError::InsufficientLiquidity)
+    }
+}
+
+/// Amount of the token whose amount is specified, between two sqrt prices
+fn amount_fixed_delta(
+    current: u128,
+    target: u128,
+    liquidity: u128,
+    is_input: bool,
+    a_to_b: bool,
+) -> Result<u128> {
+    if a_to_b == is_input {
+        amount_delta_a(current, target, liquidity, is_input)
+    } else {
+        amount_delta_b(current, target, liquidity, is_input)
+    }
+}
+
+/// Amount of the token being computed, between two sqrt prices
+fn amount_unfixed_delta(
+    current: u128,
+    target: u128,
+    liquidity: u128,
+    is_input: bool,
+    a_to_b: bool,
+) -> Result<u128> {
+    if a_to_b == is_input {
+        amount_delta_b(current, target, liquidity, !is_input)
+    } else {
+        amount_delta_a(current, target, liquidity, !is_input)
+    }
+}
+
+/// Result of swapping within a single tick range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SwapStep {
+    amount_in: u128,
+    amount_out: u128,
+    fee_amount: u128,
+    next_sqrt_price: u128,
+}
+
+/// Swap `amount_remaining` from `sqrt_price` towards `target_sqrt_price`
+fn compute_swap_step(
+    amount_remaining: u128,
+    fee_rate: u16,
+    liquidity: u128,
+    sqrt_price: u128,
+    target_sqrt_price: u128,
+    is_input: bool,
+    a_to_b: bool,
+) -> Result<SwapStep> {
+    let fee_rate = fee_rate as u128;
+    let max_fixed_delta =
+        amount_fixed_delta(sqrt_price, target_sqrt_price, liquidity, is_input, a_to_b)?;
+
+    let amount_calc = if is_input {
+        amount_remaining * (FEE_RATE_DENOMINATOR - fee_rate) / FEE_RATE_DENOMINATOR
+    } else {
+        amount_remaining
+    };
+
+    let next_sqrt_price = if max_fixed_delta <= amount_calc {
+        target_sqrt_price
+    } else if is_input == a_to_b {
+        next_sqrt_price_from_a(sqrt_price, liquidity, amount_calc, is_input)?
+    } else {
+        next_sqrt_price_from_b(sqrt_price, liquidity, amount_calc, is_input)?
+    };
+    let is_max_swap = next_sqrt_price == target_sqrt_price;
+
+    let unfixed_delta =
+        amount_unfixed_delta(sqrt_price, next_sqrt_price, liquidity, is_input, a_to_b)?;
+    let fixed_delta = if is_max_swap {
+        max_fixed_delta
+    } else {
+        amount_fixed_delta(sqrt_price, next_sqrt_price, liquidity, is_input, a_to_b)?
+    };
+
+    let (amount_in, mut amount_out) = if is_input {
+        (fixed_delta, unfixed_delta)
+    } else {
+        (unfixed_delta, fixed_delta)
+    };
+    if !is_input {
+        amount_out = amount_out.min(amount_remaining);
+    }
+
+    let fee_amount = if is_input && !is_max_swap {
+        amount_remaining - amount_in
+    } else {
+        (amount_in * fee_rate).div_ceil(FEE_RATE_DENOMINATOR - fee_rate)
+    };
+
+    Ok(SwapStep {
+        amount_in,
+        amount_out,
+        fee_amount,
+        next_sqrt_price,
+    })
+}
+
+/// Single tick of a tick array
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Tick {
+    /// Whether any position references the tick
+    pub initialized: bool,
+    /// Liquidity added when crossing the tick left to right
+    pub liquidity_net: i128,
+    /// Total liquidity referencing the tick
+    pub liquidity_gross: u128,
+}
+
+/// Decoded `TickArray` account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TickArray {
+    /// First tick index covered by the array
+    pub start_tick_index: i32,
+    /// Whirlpool the array belongs to
+    pub whirlpool: Pubkey,
+    /// Ticks, spaced by the pool's tick spacing
+    pub ticks: Vec<Tick>,
+}
+
+impl TickArray {
+    /// Decode a `TickArray` account
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        check_discriminator(data, &TICK_ARRAY_DISCRIMINATOR, "TickArray")?;
+        if data.len() != TICK_ARRAY_LEN {
+            return Err(ArbitrageError::dex_integration(format!(
+                "Invalid tick array length: {}",
+                data.len()
+            )));
+        }
+
+        let ticks = (0..TICK_ARRAY_SIZE as usize)
+            .map(|i| {
+                let offset = TICK_ARRAY_TICKS_OFFSET + i * TICK_LEN;
+                Ok(Tick {
+                    initialized: read_bool(data, offset)?,
+                    liquidity_net: read_i128(data, offset + 1)?,
+                    liquidity_gross: read_u128(data, offset + 17)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            start_tick_index: read_i32(data, TICK_ARRAY_START_OFFSET)?,
+            whirlpool: read_pubkey(data, TICK_ARRAY_WHIRLPOOL_OFFSET)?,
+            ticks,
+        })
+    }
+}
+
+/// Decoded Orca Whirlpool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WhirlpoolState {
+    /// Distance between usable ticks
+    pub tick_spacing: u16,
+    /// Swap fee in hundredths of a basis point
+    pub fee_rate: u16,
+    /// Liquidity active at the current price
+    pub liquidity: u128,
+    /// Current Q64.64 sqrt price
+    pub sqrt_price: u128,
+    /// Tick containing the current price
+    pub tick_current_index: i32,
+    /// Token A vault
+    pub token_vault_a: Pubkey,
+    /// Token B vault
+    pub token_vault_b: Pubkey,
+    /// Loaded tick arrays keyed by start tick index
+    pub tick_arrays: BTreeMap<i32, TickArray>,
+}
+
+impl WhirlpoolState {
+    /// Decode a `Whirlpool` account. Tick arrays start empty.
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        check_discriminator(data, &WHIRLPOOL_DISCRIMINATOR, "Whirlpool")?;
+        if data.len() != WHIRLPOOL_LEN {
+            return Err(ArbitrageError::dex_integration(format!(
+                "Invalid Whirlpool account length: {}",
+                data.len()
+            )));
+        }
+
+        let tick_spacing = read_u16(data, TICK_SPACING_OFFSET)?;
+        if tick_spacing == 0 {
+            return Err(ArbitrageError::dex_integration(
+                "Whirlpool tick spacing is zero",
+            ));
+        }
+
+        Ok(Self {
+            tick_spacing,
+            fee_rate: read_u16(data, FEE_RATE_OFFSET)?,
+            liquidity: read_u128(data, LIQUIDITY_OFFSET)?,
+            sqrt_price: read_u128(data, SQRT_PRICE_OFFSET)?,
+            tick_current_index: read_i32(data, TICK_CURRENT_INDEX_OFFSET)?,
+            token_vault_a: read_pubkey(data, TOKEN_VAULT_A_OFFSET)?,
+            token_vault_b: read_pubkey(data, TOKEN_VAULT_B_OFFSET)?,
+            tick_arrays: BTreeMap::new(),
+        })
+    }
+
+    /// Number of ticks covered by one tick array
+    pub fn tick_array_span(&self) -> i32 {
+        self.tick_spacing as i32 * TICK_ARRAY_SIZE
+    }
+
+    /// Start index of the tick array containing `tick`
+    pub fn tick_array_start_index(&self, tick: i32) -> i32 {
+        let span = self.tick_array_span();
+        tick.div_euclid(span) * span
+    }
+
+    /// Start indexes of the tick arrays around the current tick
+    pub fn tick_array_window(&self) -> Vec<i32> {
+        let span = self.tick_array_span();
+        let current = self.tick_array_start_index(self.tick_current_index);
+        (-TICK_ARRAY_WINDOW..=TICK_ARRAY_WINDOW)
+            .map(|offset| current + offset * span)
+            .filter(|start| *start + span > MIN_TICK_INDEX && *start <= MAX_TICK_INDEX)
+            .collect()
+    }
+
+    /// Token amounts equivalent to the active liquidity at the current price,
+    /// `(L / sqrt_price, L * sqrt_price)`
+    pub fn virtual_reserves(&self) -> Option<(u64, u64)> {
+        if self.sqrt_price == 0 {
+            return None;
+        }
+        let a = shl_div(self.liquidity, Q64_RESOLUTION, self.sqrt_price, false).ok()?;
+        let b = mul_shr(self.liquidity, self.sqrt_price, Q64_RESOLUTION, false).ok()?;
+        Some((
+            u64::try_from(a).unwrap_or(u64::MAX),
+            u64::try_from(b).unwrap_or(u64::MAX),
+        ))
+    }
+
+    /// Tick range `[low, high)` covered by loaded tick arrays contiguous with
+    /// the current tick
+    fn loaded_range(&self) -> Result<(i32, i32)> {
+        let span = self.tick_array_span();
+        let current = self.tick_array_start_index(self.tick_current_index);
+        if !self.tick_arrays.contains_key(&current) {
+            return Err(ArbitrageError::dex_integration(
+                "Tick array for the current tick is not loaded",
+            ));
+        }
+
+        let mut low = current;
+        while self.tick_arrays.contains_key(&(low - span)) {
+            low -= span;
+        }
+        let mut high = current + span;
+        while self.tick_arrays.contains_key(&high) {
+            high += span;
+        }
+        Ok((low, high))
+    }
+
+    /// Initialized ticks of all loaded arrays, with their `liquidity_net`
+    fn initialized_ticks(&self) -> BTreeMap<i32, i128> {
+        let spacing = self.tick_spacing as i32;
+        self.tick_arrays
+            .values()
+            .flat_map(|array| {
+                array
+                    .ticks
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, tick)| tick.initialized)
+                    .map(move |(i, tick)| {
+                        (
+                            array.start_tick_index + i as i32 * spacing,
+                            tick.liquidity_net,
+                        )
+                    })
+            })
+            .collect()
+    }
+
+    /// Run a swap over the loaded ticks, returning `(amount_in, amount_out, fee)`
+    /// where `amount_in` includes the fee
+    pub fn swap(&self, amount: u64, is_input: bool, a_to_b: bool) -> Result<(u64, u64, u64)> {
+        let (low, high) = self.loaded_range()?;
+        let ticks = self.initialized_ticks();
+        let sqrt_price_limit = if a_to_b {
+            MIN_SQRT_PRICE
+        } else {
+            MAX_SQRT_PRICE
+        };
+
+        let mut remaining = amount as u128;
+        let mut calculated = 0u128;
+        let mut fee_total = 0u128;
+        let mut sqrt_price = self.sqrt_price;
+        let mut tick = self.tick_current_index;
+        let mut liquidity = self.liquidity;
+
+        while remaining > 0 && sqrt_price != sqrt_price_limit && (low..high).contains(&tick) {
+            let (next_tick, liquidity_net) = if a_to_b {
+                ticks
+                    .range(low..=tick)
+                    .next_back()
+                    .map_or((low, None), |(index, net)| (*index, Some(*net)))
+            } else {
+                ticks
+                    .range(tick + 1..high)
+                    .next()
+                    .map_or((high, None), |(index, net)| (*index, Some(*net)))
+            };
+            let next_tick = next_tick.clamp(MIN_TICK_INDEX, MAX_TICK_INDEX);
+            let next_sqrt_price = sqrt_price_from_tick_index(next_tick)?;
+            let target = if a_to_b {
+                next_sqrt_price.max(sqrt_price_limit)
+            } else {
+                next_sqrt_price.min(sqrt_price_limit)
+            };
+
+            let step = compute_swap_step(
+                remaining,
+                self.fee_rate,
+                liquidity,
+                sqrt_price,
+                target,
+                is_input,
+                a_to_b,
+            )?;
+
+            if is_input {
+                remaining -= step.amount_in + step.fee_amount;
+                calculated += step.amount_out;
+            } else {
+                remaining -= step.amount_out;
+                calculated += step.amount_in + step.fee_amount;
+            }
+            fee_total += step.fee_amount;
+            sqrt_price = step.next_sqrt_price;
+
+            if step.next_sqrt_price != next_sqrt_price {
+                break;
+            }
+            if let Some(net) = liquidity_net {
+                let delta = if a_to_b { -net } else { net };
+                liquidity = liquidity
+                    .checked_add_signed(delta)
+                    .ok_or_else(|| ArbitrageError::calculation("Liquidity underflow"))?;
+            }
+            tick = if a_to_b { next_tick - 1 } else { next_tick };
+        }
+
+        if remaining > 0 {
+            return Err(ArbitrageError::InsufficientLiquidity);
+        }
+
+        let (amount_in, amount_out) = if is_input {
+            (amount as u128, calculated)
+        } else {
+            (calculated, amount as u128)
+        };
+        let to_u64 = |value: u128| {
+            u64::try_from(value).map_err(|_| ArbitrageError::calculation("Swap amount overflow"))
+        };
+        Ok((to_u64(amount_in)?, to_u64(amount_out)?, to_u64(fee_total)?))
+    }
+
+    /// Output at the current spot price for `amount_in` (after fees)
+    fn spot_output(&self, amount_in: u64, a_to_b: bool) -> Result<u128> {
+        if a_to_b {
+            let scaled = mul_shr(amount_in as u128, self.sqrt_price, Q64_RESOLUTION, false)?;
+            mul_shr(scaled, self.sqrt_price, Q64_RESOLUTION, false)
+        } else {
+            let scaled = shl_div(amount_in as u128, Q64_RESOLUTION, self.sqrt_price, false)?;
+            shl_div(scaled, Q64_RESOLUTION, self.sqrt_price, false)
+        }
+    }
+}
+
+/// Orca Whirlpool adapter
+#[derive(Debug, Default)]
+pub struct WhirlpoolAdapter;
+
+impl WhirlpoolAdapter {
+    /// Create a new Whirlpool adapter
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Derive the address of a tick array
+    pub fn tick_array_address(whirlpool: &Pubkey, start_tick_index: i32) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                b"tick_array",
+                whirlpool.as_ref(),
+                start_tick_index.to_string().as_bytes(),
+            ],
+            &WHIRLPOOL_PROGRAM_ID,
+        )
+        .0
+    }
+
+    /// Derive the address of a Whirlpool's oracle
+    pub fn oracle_address(whirlpool: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"oracle", whirlpool.as_ref()], &WHIRLPOOL_PROGRAM_ID).0
+    }
+
+    fn state(pool: &PoolState) -> Result<&WhirlpoolState> {
+        match &pool.data {
+            PoolData::Whirlpool(state) => Ok(state),
+            _ => Err(ArbitrageError::dex_integration(format!(
+                "Pool {} is not an Orca Whirlpool",
+                pool.address
+            ))),
+        }
+    }
+}
+
+impl DexAdapter for WhirlpoolAdapter {
+    fn id(&self) -> &'static str {
+        ORCA
+    }
+
+    fn program_id(&self) -> Pubkey {
+        WHIRLPOOL_PROGRAM_ID
+    }
+
+    fn pool_account_len(&self) -> usize {
+        WHIRLPOOL_LEN
+    }
+
+    fn decode_pool(&self, address: Pubkey, data: &[u8]) -> Result<PoolState> {
+        let state = WhirlpoolState::decode(data)?;
+        Ok(PoolState {
+            address,
+            dex: ORCA,
+            mint_a: read_pubkey(data, TOKEN_MINT_A_OFFSET)?,
+            mint_b: read_pubkey(data, TOKEN_MINT_B_OFFSET)?,
+            data: PoolData::Whirlpool(state),
+        })
+    }
+
+    fn dependent_accounts(&self, pool: &PoolState) -> Vec<Pubkey> {
+        let Ok(state) = Self::state(pool) else {
+            return Vec::new();
+        };
+        state
+            .tick_array_window()
+            .into_iter()
+            .map(|start| Self::tick_array_address(&pool.address, start))
+            .collect()
+    }
+
+    fn apply_dependent_account(
+        &self,
+        pool: &mut PoolState,
+        _address: &Pubkey,
+        data: &[u8],
+    ) -> Result<()> {
+        let PoolData::Whirlpool(state) = &mut pool.data else {
+            return Err(ArbitrageError::dex_integration(format!(
+                "Pool {} is not an Orca Whirlpool",
+                pool.address
+            )));
+        };
+
+        let array = TickArray::decode(data)?;
+        if array.whirlpool != pool.address {
+            return Err(ArbitrageError::dex_integration(format!(
+                "Tick array belongs to {}, not {}",
+                array.whirlpool, pool.address
+            )));
+        }
+
+        state.tick_arrays.insert(array.start_tick_index, array);
+        let window = state.tick_array_window();
+        state.tick_arrays.retain(|start, _| window.contains(start));
+        Ok(())
+    }
+
+    fn reserves(&self, pool: &PoolState) -> Option<(u64, u64)> {
+        Self::state(pool).ok()?.virtual_reserves()
+    }
+
+    fn quote(&self, pool: &PoolState, request: &QuoteRequest) -> Result<Quote> {
+        let state = Self::state(pool)?;
+        let a_to_b = pool.is_a_to_b(&request.input_mint)?;
+        let is_input = request.mode == SwapMode::ExactIn;
+        let (in_amount, out_amount, fee_amount) = state.swap(request.amount, is_input, a_to_b)?;
+
+        let spot_out = state.spot_output(in_amount - fee_amount, a_to_b)?;
+        let price_impact_bps = (spot_out.saturating_sub(out_amount as u128) * 10_000)
+            .checked_div(spot_out)
+            .unwrap_or(0) as u32;
+
+        Ok(Quote {
+            input_mint: request.input_mint,
+            output_mint: if a_to_b { pool.mint_b } else { pool.mint_a },
+            in_amount,
+            out_amount,
+            fee_amount,
+            price_impact_bps,
+        })
+    }
+
+    fn swap_instruction(&self, pool: &PoolState, params: &SwapParams) -> Result<Instruction> {
+        let state = Self::state(pool)?;
+        let a_to_b = pool.is_a_to_b(&params.input_mint)?;
+
+        // Three arrays in the swap direction; missing ones repeat the last
+        // loaded array, as the program accepts
+        let span = state.tick_array_span();
+        let step = if a_to_b { -span } else { span };
+        let mut start = state.tick_array_start_index(state.tick_current_index);
+        let mut tick_arrays = Vec::with_capacity(3);
+        for _ in 0..3 {
+            if state.tick_arrays.contains_key(&start) || tick_arrays.is_empty() {
+                tick_arrays.push(Self::tick_array_address(&pool.address, start));
+            } else {
+                tick_arrays.push(tick_arrays[tick_arrays.len() - 1]);
+            }
+            start += step;
+        }
+
+        let (owner_account_a, owner_account_b) = if a_to_b {
+            (
+                params.source_token_account,
+                params.destination_token_account,
+            )
+        } else {
+            (
+                params.destination_token_account,
+                params.source_token_account,
+            )
+        };
+        let sqrt_price_limit = if a_to_b {
+            MIN_SQRT_PRICE
+        } else {
+            MAX_SQRT_PRICE
+        };
+
+        let mut data = Vec::with_capacity(42);
+        data.extend_from_slice(&SWAP_DISCRIMINATOR);
+        data.extend_from_slice(&params.amount.to_le_bytes());
+        data.extend_from_slice(&params.other_amount_threshold.to_le_bytes());
+        data.extend_from_slice(&sqrt_price_limit.to_le_bytes());
+        data.push((params.mode == SwapMode::ExactIn) as u8);
+        data.push(a_to_b as u8);
+
+        Ok(Instruction {
+            program_id: WHIRLPOOL_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                AccountMeta::new_readonly(params.user, true),
+                AccountMeta::new(pool.address, false),
+                AccountMeta::new(owner_account_a, false),
+                AccountMeta::new(state.token_vault_a, false),
+                AccountMeta::new(owner_account_b, false),
+                AccountMeta::new(state.token_vault_b, false),
+                AccountMeta::new(tick_arrays[0], false),
+                AccountMeta::new(tick_arrays[1], false),
+                AccountMeta::new(tick_arrays[2], false),
+                AccountMeta::new(Self::oracle_address(&pool.address), false),
+            ],
+            data,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINT_A: Pubkey = Pubkey::new_from_array([1; 32]);
+    const MINT_B: Pubkey = Pubkey::new_from_array([2; 32]);
+    const TICK_SPACING: u16 = 64;
+    const LIQUIDITY: u128 = 1_000_000_000_000;
+
+    fn whirlpool_fixture(sqrt_price: u128, tick_current_index: i32) -> Vec<u8> {
+        let mut data = vec![0u8; WHIRLPOOL_LEN];
+        data[..8].copy_from_slice(&WHIRLPOOL_DISCRIMINATOR);
+        data[TICK_SPACING_OFFSET..TICK_SPACING_OFFSET + 2]
+            .copy_from_slice(&TICK_SPACING.to_le_bytes());
+        data[FEE_RATE_OFFSET..FEE_RATE_OFFSET + 2].copy_from_slice(&3000u16.to_le_bytes());
+        data[LIQUIDITY_OFFSET..LIQUIDITY_OFFSET + 16].copy_from_slice(&LIQUIDITY.to_le_bytes());
+        data[SQRT_PRICE_OFFSET..SQRT_PRICE_OFFSET + 16].copy_from_slice(&sqrt_price.to_le_bytes());
+        data[TICK_CURRENT_INDEX_OFFSET..TICK_CURRENT_INDEX_OFFSET + 4]
+            .copy_from_slice(&tick_current_index.to_le_bytes());
+        data[TOKEN_MINT_A_OFFSET..TOKEN_MINT_A_OFFSET + 32].copy_from_slice(MINT_A.as_ref());
+        data[TOKEN_MINT_B_OFFSET..TOKEN_MINT_B_OFFSET + 32].copy_from_slice(MINT_B.as_ref());
+        data
+    }
+
+    /// Tick array with the given `(tick index, liquidity_net)` pairs initialized
+    fn tick_array_fixture(whirlpool: &Pubkey, start: i32, initialized: &[(i32, i128)]) -> Vec<u8> {
+        let mut data = vec![0u8; TICK_ARRAY_LEN];
+        data[..8].copy_from_slice(&TICK_ARRAY_DISCRIMINATOR);
+        data[TICK_ARRAY_START_OFFSET..TICK_ARRAY_START_OFFSET + 4]
+            .copy_from_slice(&start.to_le_bytes());
+        for (tick, net) in initialized {
+            let slot = ((tick - start) / TICK_SPACING as i32) as usize;
+            let offset = TICK_ARRAY_TICKS_OFFSET + slot * TICK_LEN;
+            data[offset] = 1;
+            data[offset + 1..offset + 17].copy_from_slice(&net.to_le_bytes());
+            data[offset + 17..offset + 33].copy_from_slice(&net.unsigned_abs().to_le_bytes());
+        }
+        data[TICK_ARRAY_WHIRLPOOL_OFFSET..TICK_ARRAY_WHIRLPOOL_OFFSET + 32]
+            .copy_from_slice(whirlpool.as_ref());
+        data
+    }
+
+    /// Pool at tick 0 with liquidity L in [-5632, 5632) and L/2 beyond
+    /// ticks -640 and 640
+    fn loaded_pool() -> PoolState {
+        let adapter = WhirlpoolAdapter::new();
+        let address = Pubkey::new_unique();
+        let mut pool = adapter
+            .decode_pool(address, &whirlpool_fixture(Q64_ONE, 0))
+            .unwrap();
+        let half = (LIQUIDITY / 2) as i128;
+        for (start, ticks) in [
+            (-5632, vec![(-640, half)]),
+            (0, vec![(640, -half)]),
+            (5632, vec![]),
+            (-11264, vec![]),
+        ] {
+            adapter
+                .apply_dependent_account(
+                    &mut pool,
+                    &Pubkey::new_unique(),
+                    &tick_array_fixture(&address, start, &ticks),
+                )
+                .unwrap();
+        }
+        pool
+    }
+
+    #[test]
+    fn test_sqrt_price_from_tick_index() {
+        assert_eq!(sqrt_price_from_tick_index(0).unwrap(), Q64_ONE);
+        assert_eq!(
+            sqrt_price_from_tick_index(1).unwrap(),
+            18_447_666_387_855_959_850
+        );
+        assert_eq!(
+            sqrt_price_from_tick_index(-1).unwrap(),
+            18_445_821_805_675_392_311
+        );
+        // Odd negative ticks, where truncating every step rounds differently
+        // from a Q128 product
+        for (tick, sqrt_price) in [
+            (-3, 18_443_977_407_934_598_850),
+            (-5, 18_442_133_194_615_137_336),
+            (-7, 18_440_289_165_698_567_478),
+            (-9, 18_438_445_321_166_450_835),
+            (-1001, 17_546_252_323_307_152_250),
+            (-12345, 9_950_957_148_631_419_635),
+            (-65537, 696_422_831_576_501_423),
+        ] {
+            assert_eq!(sqrt_price_from_tick_index(tick).unwrap(), sqrt_price);
+        }
+        assert_eq!(
+            sqrt_price_from_tick_index(MIN_TICK_INDEX).unwrap(),
+            MIN_SQRT_PRICE
+        );
+        assert_eq!(
+            sqrt_price_from_tick_index(MAX_TICK_INDEX).unwrap(),
+            MAX_SQRT_PRICE
+        );
+        assert!(sqrt_price_from_tick_index(MAX_TICK_INDEX + 1).is_err());
+
+        let mut previous = 0;
+        for tick in (-2000..2000).step_by(37) {
+            let price = sqrt_price_from_tick_index(tick).unwrap();
+            assert!(price > previous);
+            previous = price;
+        }
+    }
+
+    #[test]
+    fn test_decode_whirlpool() {
+        let pool = loaded_pool();
+        let state = WhirlpoolAdapter::state(&pool).unwrap();
+        assert_eq!(pool.mint_a, MINT_A);
+        assert_eq!(pool.mint_b, MINT_B);
+        assert_eq!(state.tick_spacing, TICK_SPACING);
+        assert_eq!(state.fee_rate, 3000);
+        assert_eq!(state.liquidity, LIQUIDITY);
+        assert_eq!(state.tick_array_span(), 5632);
+        assert_eq!(state.tick_array_start_index(-1), -5632);
+        assert_eq!(state.tick_arrays.len(), 4);
+        assert_eq!(WhirlpoolAdapter::new().dependent_accounts(&pool).len(), 5);
+        assert_eq!(
+            WhirlpoolAdapter::new().reserves(&pool),
+            Some((LIQUIDITY as u64, LIQUIDITY as u64))
+        );
+
+        let mut bad = whirlpool_fixture(Q64_ONE, 0);
+        bad[0] ^= 1;
+        assert!(WhirlpoolState::decode(&bad).is_err());
+    }
+
+    #[test]
+    fn test_quote_within_single_range() {
+        let adapter = WhirlpoolAdapter::new();
+        let pool = loaded_pool();
+
+        let quote = adapter
+            .quote(&pool, &QuoteRequest::exact_in(MINT_B, 1_000_000))
+            .unwrap();
+        assert_eq!(quote.output_mint, MINT_A);
+        assert_eq!(quote.fee_amount, 3_000);
+        assert_eq!(quote.out_amount, 996_999);
+
+        let quote = adapter
+            .quote(&pool, &QuoteRequest::exact_in(MINT_A, 1_000_000))
+            .unwrap();
+        assert_eq!(quote.output_mint, MINT_B);
+        assert_eq!(quote.fee_amount, 3_000);
+        assert_eq!(quote.out_amount, 996_999);
+    }
+
+    #[test]
+    fn test_quote_crosses_initialized_ticks() {
+        let adapter = WhirlpoolAdapter::new();
+        let pool = loaded_pool();
+
+        // Large enough to push the price past tick 640, where half the
+        // liquidity drops out
+        let quote = adapter
+            .quote(&pool, &QuoteRequest::exact_in(MINT_B, 50_000_000_000))
+            .unwrap();
+        assert_eq!(quote.fee_amount, 150_000_001);
+        assert_eq!(quote.out_amount, 47_223_232_112);
+        assert!(quote.price_impact_bps > 400);
+
+        let quote = adapter
+            .quote(&pool, &QuoteRequest::exact_in(MINT_A, 50_000_000_000))
+            .unwrap();
+        assert_eq!(quote.out_amount, 47_223_232_112);
+    }
+
+    #[test]
+    fn test_quote_exact_out_round_trip() {
+        let adapter = WhirlpoolAdapter::new();
+        let pool = loaded_pool();
+
+        let quote = adapter
+            .quote(&pool, &QuoteRequest::exact_out(MINT_B, 40_000_000_000))
+            .unwrap();
+        assert_eq!(quote.out_amount, 40_000_000_000);
+        assert_eq!(quote.in_amount, 41_874_114_540);
+        assert_eq!(quote.fee_amount, 125_622_345);
+
+        let forward = adapter
+            .quote(&pool, &QuoteRequest::exact_in(MINT_B, quote.in_amount))
+            .unwrap();
+        assert!(forward.out_amount >= 40_000_000_000);
+        assert!(forward.out_amount - 40_000_000_000 <= 2);
+    }
+
+    #[test]
+    fn test_quote_beyond_loaded_ticks_fails() {
+        let adapter = WhirlpoolAdapter::new();
+        let pool = loaded_pool();
+        assert!(matches!(
+            adapter.quote(&pool, &QuoteRequest::exact_in(MINT_B, u64::MAX / 2)),
+            Err(ArbitrageError::InsufficientLiquidity)
+        ));
+    }
+
+    #[test]
+    fn test_swap_instruction_layout() {
+        let adapter = WhirlpoolAdapter::new();
+        let pool = loaded_pool();
+        let params = SwapParams {
+            user: Pubkey::new_unique(),
+            source_token_account: Pubkey::new_unique(),
+            destination_token_account: Pubkey::new_unique(),
+            input_mint: MINT_A,
+            amount: 1_000,
+            other_amount_threshold: 990,
+            mode: SwapMode::ExactIn,
+        };
+
+        let ix = adapter.swap_instruction(&pool, &params).unwrap();
+        assert_eq!(ix.program_id, WHIRLPOOL_PROGRAM_ID);
+        assert_eq!(ix.data.len(), 42);
+        assert_eq!(&ix.data[..8], &SWAP_DISCRIMINATOR);
+        assert_eq!(&ix.data[24..40], &MIN_SQRT_PRICE.to_le_bytes());
+        assert_eq!(ix.data[40], 1);
+        assert_eq!(ix.data[41], 1);
+        assert_eq!(ix.accounts.len(), 11);
+        assert_eq!(ix.accounts[3].pubkey, params.source_token_account);
+        assert_eq!(
+            ix.accounts[7].pubkey,
+            WhirlpoolAdapter::tick_array_address(&pool.address, 0)
+        );
+        assert_eq!(
+            ix.accounts[8].pubkey,
+            WhirlpoolAdapter::tick_array_address(&pool.address, -5632)
+        );
+    }
+}
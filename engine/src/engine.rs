@@ -1,25 +1,154 @@
 //! Main arbitrage engine implementation
+//!
+//! Once started, the engine seeds the pool cache over RPC, streams pool
+//! account updates into the [`Scheduler`] and hands every opportunity it
+//! reports to the [`Executor`], at most `trading.max_concurrent_trades` at a
+//! time and never two on the same route. Every trade must get past the
+//! circuit breakers and be admitted by the [`RiskManager`] first, and stays
+//! in flight, counting against the risk limits, until the confirmation
+//! tracker resolves it; its outcome then feeds the breakers and the route's
+//! reputation. What a landed trade made is measured from the balances in its
+//! transaction's metadata. Nothing is traded until the SOL and tokens in the
+//! wallet have been valued to seed the risk equity, retrying every few
+//! seconds. Without a wallet keypair the engine only scans.
 
 use crate::config::Config;
-use crate::error::Result;
+use crate::datasource::{self, DataSource};
+use crate::dex::layout::{token_account_amount, token_account_mint, TOKEN_ACCOUNT_OWNER_OFFSET};
+use crate::dex::{DexManager, NATIVE_MINT, TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID};
+use crate::error::{ArbitrageError, Result};
+use crate::execution::{Executor, TransactionBuilder};
+use crate::geyser::UpdateStream;
+use crate::models::{ArbitrageOpportunity, TradeFailure, TradeResult, TradeStatus};
+use crate::pool_cache::PoolCache;
+use crate::pricing::PriceSnapshot;
+use crate::risk::{
+    BreakerEvent, BreakerScope, BreakerState, BreakerStatus, CircuitBreaker, PositionSizer,
+    RiskManager, RouteReputation, TradeOutcome,
+};
+use crate::scheduler::Scheduler;
+use crate::strategy::StrategyManager;
+use futures::StreamExt;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use tracing::{info, warn};
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, OwnedSemaphorePermit, RwLock, Semaphore};
+use tokio::task::{JoinHandle, JoinSet};
+use tracing::{debug, info, warn};
 
-/// Main arbitrage engine
+/// Interval between attempts to value the wallet while its equity is unknown
+const EQUITY_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Interval between checks for pool accounts to subscribe to
+const SUBSCRIPTION_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A trade between being picked for execution and being resolved
 #[derive(Debug)]
+struct InFlightTrade {
+    opportunity: ArbitrageOpportunity,
+    _slot: OwnedSemaphorePermit,
+}
+
+/// Main arbitrage engine
+#[derive(Clone)]
 pub struct ArbitrageEngine {
     config: Arc<Config>,
+    rpc: Arc<RpcClient>,
+    dex: Arc<DexManager>,
+    pool_cache: Arc<PoolCache>,
+    data_source: Arc<dyn DataSource>,
+    strategy: Arc<StrategyManager>,
+    builder: Arc<TransactionBuilder>,
+    executor: Option<Arc<Executor>>,
+    risk: Arc<RiskManager>,
+    breaker: Arc<CircuitBreaker>,
+    trade_slots: Arc<Semaphore>,
+    in_flight: Arc<parking_lot::Mutex<HashMap<String, InFlightTrade>>>,
+    tasks: Arc<parking_lot::Mutex<Vec<JoinHandle<()>>>>,
+    executions: Arc<parking_lot::Mutex<JoinSet<()>>>,
     running: Arc<RwLock<bool>>,
 }
 
+impl fmt::Debug for ArbitrageEngine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArbitrageEngine")
+            .field("rpc", &self.rpc.url())
+            .field("dex", &self.dex)
+            .field("pool_cache", &self.pool_cache)
+            .field("data_source", &self.data_source)
+            .field("strategy", &self.strategy)
+            .field("executor", &self.executor)
+            .field("risk", &self.risk)
+            .field("breaker", &self.breaker)
+            .field("in_flight", &self.in_flight.lock().len())
+            .finish_non_exhaustive()
+    }
+}
+
 impl ArbitrageEngine {
     /// Create a new arbitrage engine instance
     pub async fn new(config: Config) -> Result<Self> {
         info!("Initializing arbitrage engine");
 
+        let commitment = CommitmentConfig::from_str(&config.solana.commitment).map_err(|_| {
+            ArbitrageError::config(format!(
+                "Invalid commitment level: {}",
+                config.solana.commitment
+            ))
+        })?;
+        let rpc = Arc::new(RpcClient::new_with_timeout_and_commitment(
+            config.solana.rpc_url.clone(),
+            Duration::from_millis(config.solana.timeout_ms),
+            commitment,
+        ));
+        let dex = Arc::new(DexManager::from_config(&config.dex)?);
+        let pool_cache = Arc::new(PoolCache::from_config(dex.clone(), &config.opportunities));
+        let data_source = datasource::connect(&config.solana)?;
+        let strategy = Arc::new(StrategyManager::new(
+            &config,
+            dex.clone(),
+            pool_cache.clone(),
+        ));
+        let builder = Arc::new(TransactionBuilder::from_config(
+            dex.clone(),
+            pool_cache.clone(),
+            &config,
+        ));
+        let executor = Executor::from_config(rpc.clone(), builder.clone(), &config)?.map(Arc::new);
+        match &executor {
+            Some(executor) => info!("Trading from wallet {}", executor.payer()),
+            None => warn!("No wallet keypair configured, opportunities will not be executed"),
+        }
+        // Equity is read from the wallet once the engine starts
+        let risk = Arc::new(RiskManager::from_config(&config, 0.0));
+        let breaker = Arc::new(CircuitBreaker::from_config(&config));
+
         let engine = Self {
+            trade_slots: Arc::new(Semaphore::new(config.trading.max_concurrent_trades.max(1))),
             config: Arc::new(config),
+            rpc,
+            dex,
+            pool_cache,
+            data_source,
+            strategy,
+            builder,
+            executor,
+            risk,
+            breaker,
+            in_flight: Arc::new(parking_lot::Mutex::new(HashMap::new())),
+            tasks: Arc::new(parking_lot::Mutex::new(Vec::new())),
+            executions: Arc::new(parking_lot::Mutex::new(JoinSet::new())),
             running: Arc::new(RwLock::new(false)),
         };
 
@@ -38,13 +167,12 @@ impl ArbitrageEngine {
         info!("Starting arbitrage engine");
         *running = true;
 
-        // TODO: Initialize components
-        // - Database connection
-        // - Redis connection
-        // - Solana client
-        // - DEX integrations
-        // - WebSocket connections
-        // - Strategy execution loops
+        let mut tasks = self.tasks.lock();
+        tasks.push(tokio::spawn(self.clone().run()));
+        if let Some(executor) = &self.executor {
+            let resolved = executor.tracker().clone().spawn();
+            tasks.push(tokio::spawn(self.clone().resolve_trades(resolved)));
+        }
 
         info!("Arbitrage engine started successfully");
         Ok(())
@@ -61,11 +189,25 @@ impl ArbitrageEngine {
         info!("Stopping arbitrage engine");
         *running = false;
 
-        // TODO: Cleanup components
-        // - Close database connections
-        // - Close Redis connections
-        // - Close WebSocket connections
-        // - Cancel running tasks
+        // Dropping the tasks' streams also stops the subscriptions, the
+        // scheduler and the confirmation tracker feeding them
+        for task in self.tasks.lock().drain(..) {
+            task.abort();
+        }
+        // Trades being submitted may already be on their way to the cluster,
+        // so they finish before their exposure is given up
+        let mut executions = std::mem::take(&mut *self.executions.lock());
+        while executions.join_next().await.is_some() {}
+        let abandoned = std::mem::take(&mut *self.in_flight.lock());
+        for opportunity_id in abandoned.keys() {
+            self.risk.close(opportunity_id, 0.0);
+        }
+        if !abandoned.is_empty() {
+            warn!(
+                "Stopped tracking {} trades still in flight",
+                abandoned.len()
+            );
+        }
 
         info!("Arbitrage engine stopped successfully");
         Ok(())
@@ -81,6 +223,341 @@ impl ArbitrageEngine {
         &self.config
     }
 
+    /// Get the DEX adapter registry
+    pub fn dex(&self) -> &Arc<DexManager> {
+        &self.dex
+    }
+
+    /// Get the pool state cache
+    pub fn pool_cache(&self) -> &Arc<PoolCache> {
+        &self.pool_cache
+    }
+
+    /// Get the account and slot update source
+    pub fn data_source(&self) -> &Arc<dyn DataSource> {
+        &self.data_source
+    }
+
+    /// Get the opportunity detector
+    pub fn strategy(&self) -> &Arc<StrategyManager> {
+        &self.strategy
+    }
+
+    /// Get the arbitrage transaction builder
+    pub fn transaction_builder(&self) -> &Arc<TransactionBuilder> {
+        &self.builder
+    }
+
+    /// Get the trade executor, if a wallet keypair is configured
+    pub fn executor(&self) -> Option<&Arc<Executor>> {
+        self.executor.as_ref()
+    }
+
+    /// Number of trades picked for execution and not resolved yet
+    pub fn trades_in_flight(&self) -> usize {
+        self.in_flight.lock().len()
+    }
+
+    /// Get the pre-trade risk manager
+    pub fn risk_manager(&self) -> &Arc<RiskManager> {
+        &self.risk
+    }
+
+    /// Get the circuit breakers
+    pub fn circuit_breaker(&self) -> &Arc<CircuitBreaker> {
+        &self.breaker
+    }
+
+    /// Get the reputation of scanned routes
+    pub fn route_reputation(&self) -> &Arc<RouteReputation> {
+        self.strategy.reputation()
+    }
+
+    /// Get the position sizer
+    pub fn position_sizer(&self) -> &Arc<PositionSizer> {
+        self.strategy.sizer()
+    }
+
+    /// Subscribe to circuit breaker state changes
+    pub fn breaker_events(&self) -> broadcast::Receiver<BreakerEvent> {
+        self.breaker.subscribe()
+    }
+
+    /// Check whether `opportunity` may be submitted, returning the tripped
+    /// breaker refusing it otherwise. Scanning goes on regardless; only
+    /// submission stops while a breaker is open.
+    pub fn may_submit(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+    ) -> std::result::Result<(), BreakerScope> {
+        self.breaker.allow(opportunity)
+    }
+
+    /// Claim the circuit breakers' go-ahead for submitting `opportunity`,
+    /// including the probe of any half-open breaker it falls under
+    fn claim_submission(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+    ) -> std::result::Result<(), BreakerScope> {
+        self.breaker.claim_probe(opportunity).inspect_err(|scope| {
+            warn!(
+                "Not submitting opportunity {}: circuit breaker {} is open",
+                opportunity.id, scope
+            )
+        })
+    }
+
+    /// Record how a submitted trade of `opportunity` ended
+    pub fn record_trade(&self, opportunity: &ArbitrageOpportunity, trade: &TradeResult) {
+        self.breaker.record(opportunity, TradeOutcome::of(trade));
+        match trade.status {
+            TradeStatus::Failed => self.route_reputation().record_failure(opportunity),
+            TradeStatus::Success => self.route_reputation().record_success(opportunity),
+            _ => {}
+        }
+    }
+
+    /// Create a scheduler driving the strategies from pool updates
+    pub fn scheduler(&self) -> Scheduler {
+        Scheduler::from_config(
+            self.strategy.clone(),
+            self.pool_cache.clone(),
+            &self.config.opportunities,
+        )
+    }
+
+    /// Seed the pool cache, stream updates into the scheduler and execute
+    /// the opportunities it reports
+    async fn run(self) {
+        for dex in self.dex.dex_ids() {
+            match self.pool_cache.seed_from_rpc(&self.rpc, dex).await {
+                Ok(count) => info!("Seeded {} {} pools", count, dex),
+                Err(e) => warn!("Failed to seed {} pools: {}", dex, e),
+            }
+        }
+        let mut equity_loaded = match &self.executor {
+            Some(executor) => self.load_equity(&executor.payer()).await,
+            None => false,
+        };
+        let mut next_equity_attempt = Instant::now() + EQUITY_RETRY_INTERVAL;
+
+        let programs: Vec<Pubkey> = self
+            .dex
+            .adapters()
+            .map(|adapter| adapter.program_id())
+            .collect();
+        let updates = datasource::subscribe_pools(
+            self.data_source.clone(),
+            self.pool_cache.clone(),
+            &programs,
+            SUBSCRIPTION_REFRESH_INTERVAL,
+        );
+        let mut reports = self.scheduler().spawn(updates);
+
+        while let Some(report) = reports.next().await {
+            debug!(
+                "{:?} scan of {} changed pools found {} opportunities in {:?}",
+                report.kind,
+                report.changed_pools,
+                report.opportunities.len(),
+                report.elapsed
+            );
+            let Some(executor) = &self.executor else {
+                continue;
+            };
+            if report.opportunities.is_empty() {
+                continue;
+            }
+            if !equity_loaded {
+                if Instant::now() < next_equity_attempt {
+                    continue;
+                }
+                equity_loaded = self.load_equity(&executor.payer()).await;
+                next_equity_attempt = Instant::now() + EQUITY_RETRY_INTERVAL;
+                if !equity_loaded {
+                    continue;
+                }
+            }
+
+            let prices = Arc::new(self.strategy.prices());
+            let now = chrono::Utc::now();
+            for opportunity in report.opportunities {
+                if opportunity.is_expired(now) || !self.reserve(&opportunity) {
+                    continue;
+                }
+                self.spawn_execution(executor.clone(), opportunity, prices.clone());
+            }
+        }
+    }
+
+    /// Execute a reserved opportunity in the background, where [`stop`]
+    /// waits for it
+    ///
+    /// [`stop`]: Self::stop
+    fn spawn_execution(
+        &self,
+        executor: Arc<Executor>,
+        opportunity: ArbitrageOpportunity,
+        prices: Arc<PriceSnapshot>,
+    ) {
+        let engine = self.clone();
+        let mut executions = self.executions.lock();
+        while executions.try_join_next().is_some() {}
+        executions.spawn(async move { engine.execute(&executor, opportunity, &prices).await });
+    }
+
+    /// Rebase the risk manager's equity and the bankroll on the value of the
+    /// SOL and tokens held by `wallet`, returning whether it could be valued
+    async fn load_equity(&self, wallet: &Pubkey) -> bool {
+        match self.wallet_value_usd(wallet).await {
+            Ok(Some(equity_usd)) => {
+                self.risk.set_equity(equity_usd);
+                self.strategy.set_bankroll(equity_usd);
+                true
+            }
+            Ok(None) => {
+                warn!("No SOL price to value wallet {} with yet", wallet);
+                false
+            }
+            Err(e) => {
+                warn!("Failed to read the holdings of wallet {}: {}", wallet, e);
+                false
+            }
+        }
+    }
+
+    /// Value of the SOL and tokens held by `wallet`, in USD, or `None` while
+    /// SOL has no price
+    async fn wallet_value_usd(&self, wallet: &Pubkey) -> Result<Option<f64>> {
+        let mut holdings = vec![(NATIVE_MINT, self.rpc.get_balance(wallet).await?)];
+        for program in [TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID] {
+            holdings.extend(token_holdings(&self.rpc, wallet, &program).await?);
+        }
+        Ok(holdings_usd(&self.strategy.prices(), &holdings))
+    }
+
+    /// Take a trade slot for `opportunity`, unless every slot is taken or a
+    /// trade on the same route is already in flight
+    fn reserve(&self, opportunity: &ArbitrageOpportunity) -> bool {
+        let mut in_flight = self.in_flight.lock();
+        let pools = |opportunity: &ArbitrageOpportunity| {
+            opportunity
+                .legs
+                .iter()
+                .map(|leg| leg.pool.clone())
+                .collect::<Vec<_>>()
+        };
+        let route = pools(opportunity);
+        if in_flight
+            .values()
+            .any(|trade| pools(&trade.opportunity) == route)
+        {
+            return false;
+        }
+        let Ok(slot) = self.trade_slots.clone().try_acquire_owned() else {
+            return false;
+        };
+        in_flight.insert(
+            opportunity.id.clone(),
+            InFlightTrade {
+                opportunity: opportunity.clone(),
+                _slot: slot,
+            },
+        );
+        true
+    }
+
+    /// Execute a reserved opportunity if the circuit breakers and the risk
+    /// limits allow it, releasing its slot, exposure and any breaker probe it
+    /// claimed unless it was sent
+    async fn execute(
+        &self,
+        executor: &Executor,
+        opportunity: ArbitrageOpportunity,
+        prices: &PriceSnapshot,
+    ) {
+        if self.claim_submission(&opportunity).is_err() {
+            self.in_flight.lock().remove(&opportunity.id);
+            return;
+        }
+        if self.risk.admit(&opportunity, prices).is_err() {
+            self.breaker.release_probe(&opportunity);
+            self.in_flight.lock().remove(&opportunity.id);
+            return;
+        }
+        let sent = match executor.execute(&opportunity, prices).await {
+            Ok(trade) => trade.status == TradeStatus::Executing,
+            Err(e) => {
+                warn!("Failed to execute opportunity {}: {}", opportunity.id, e);
+                false
+            }
+        };
+        if !sent {
+            self.breaker.release_probe(&opportunity);
+            self.risk.close(&opportunity.id, 0.0);
+            self.in_flight.lock().remove(&opportunity.id);
+        }
+    }
+
+    /// Release the slots and exposure of trades as the confirmation tracker
+    /// resolves them, recording what they realized and how they ended
+    async fn resolve_trades(self, mut resolved: UpdateStream<TradeResult>) {
+        while let Some(mut trade) = resolved.next().await {
+            let Some(in_flight) = self.in_flight.lock().remove(&trade.opportunity_id) else {
+                continue;
+            };
+            trade.actual_profit = self.measure_profit(&in_flight.opportunity, &trade).await;
+            let pnl = realized_pnl(&trade);
+            trade.net_profit = Some(pnl);
+            self.risk
+                .close(&trade.opportunity_id, pnl.to_f64().unwrap_or_default());
+            self.record_trade(&in_flight.opportunity, &trade);
+            if self.strategy.bankroll_usd().is_some() {
+                self.strategy.set_bankroll(self.risk.equity_usd());
+            }
+            info!(
+                "Trade for opportunity {} resolved as {:?}, netting ${}",
+                in_flight.opportunity.id, trade.status, pnl
+            );
+        }
+    }
+
+    /// What a landed `trade` of `opportunity` actually made, in USD, from
+    /// the payer's and the profit account's balances in its transaction's
+    /// metadata. `None` if it never landed or they can't be read or valued,
+    /// in which case its estimated result is booked instead.
+    async fn measure_profit(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        trade: &TradeResult,
+    ) -> Option<Decimal> {
+        let executor = self.executor.as_ref()?;
+        let signature = trade.signature.as_deref()?;
+        trade.slot?;
+
+        let measured = match Signature::from_str(signature) {
+            Ok(signature) => match executor.landed_balances(opportunity, &signature).await {
+                Ok(change) => Pubkey::from_str(&opportunity.token_mint)
+                    .ok()
+                    .and_then(|mint| change.net_usd(&mint, &self.strategy.prices())),
+                Err(e) => {
+                    warn!("Failed to read the balances of trade {}: {}", signature, e);
+                    None
+                }
+            },
+            Err(_) => None,
+        }
+        .and_then(Decimal::from_f64_retain)
+        .map(|usd| usd.round_dp(6));
+        if measured.is_none() {
+            warn!(
+                "Could not measure what trade {} made, booking its estimated result",
+                signature
+            );
+        }
+        measured
+    }
+
     /// Get engine status
     pub async fn status(&self) -> EngineStatus {
         EngineStatus {
@@ -89,6 +566,8 @@ impl ArbitrageEngine {
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap_or_default()
                 .as_secs(),
+            circuit_breaker: self.breaker.global_state(),
+            circuit_breakers: self.breaker.statuses(),
             // TODO: Add more status fields
             // - Active strategies
             // - Executed trades
@@ -98,6 +577,72 @@ impl ArbitrageEngine {
     }
 }
 
+/// Mint and amount of every token account of `owner` under the token
+/// `program`
+async fn token_holdings(
+    rpc: &RpcClient,
+    owner: &Pubkey,
+    program: &Pubkey,
+) -> Result<Vec<(Pubkey, u64)>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+            TOKEN_ACCOUNT_OWNER_OFFSET,
+            owner.as_ref(),
+        ))]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let accounts = rpc
+        .get_program_ui_accounts_with_config(program, config)
+        .await?;
+    Ok(accounts
+        .into_iter()
+        .filter_map(|(_, account)| {
+            let data = account.data.decode()?;
+            Some((
+                token_account_mint(&data).ok()?,
+                token_account_amount(&data).ok()?,
+            ))
+        })
+        .collect())
+}
+
+/// USD value of `holdings` of mints and base units, or `None` if SOL has no
+/// price. Tokens without a price are left out.
+fn holdings_usd(prices: &PriceSnapshot, holdings: &[(Pubkey, u64)]) -> Option<f64> {
+    prices.price(&NATIVE_MINT)?;
+    let mut value = 0.0;
+    for (mint, amount) in holdings {
+        match prices.usd_value(mint, *amount) {
+            Some(usd) => value += usd,
+            None if *amount > 0 => debug!("Leaving unpriced {} out of the wallet's value", mint),
+            None => {}
+        }
+    }
+    Some(value)
+}
+
+/// Profit or loss of a resolved trade, in USD: what it was measured to make
+/// if it landed, falling back to the simulated net profit if it succeeded or
+/// the fees paid if it reverted, and nothing if it never landed
+fn realized_pnl(trade: &TradeResult) -> Decimal {
+    if let Some(actual) = trade.actual_profit {
+        return actual;
+    }
+    match (&trade.status, &trade.failure) {
+        (TradeStatus::Success, _) => trade
+            .simulation
+            .as_ref()
+            .and_then(|simulation| simulation.net_profit_usd)
+            .unwrap_or_default(),
+        (_, Some(TradeFailure::Expired { .. })) => Decimal::ZERO,
+        _ => -trade.gas_cost,
+    }
+}
+
 /// Engine status information
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct EngineStatus {
@@ -105,12 +650,20 @@ pub struct EngineStatus {
     pub running: bool,
     /// Engine uptime in seconds
     pub uptime: u64,
+    /// State of the global circuit breaker
+    pub circuit_breaker: BreakerState,
+    /// Every circuit breaker that has seen a trade
+    pub circuit_breakers: Vec<BreakerStatus>,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::config::Config;
+    use crate::dex::RaydiumAmmAdapter;
+    use crate::models::{OpportunityLeg, OpportunityStatus, SimulationReport};
+    use crate::pricing::PriceOracle;
+    use crate::test_support::test_pool;
 
     #[tokio::test]
     async fn test_engine_lifecycle() {
@@ -126,6 +679,188 @@ mod tests {
         assert!(!engine.is_running().await);
     }
 
+    /// Point `config` at a new wallet keypair, kept until the returned file
+    /// is dropped
+    fn with_wallet(config: &mut Config) -> (Pubkey, tempfile::NamedTempFile) {
+        let payer = solana_sdk::signature::Keypair::new();
+        let file = tempfile::NamedTempFile::new().unwrap();
+        solana_sdk::signature::write_keypair_file(&payer, file.path()).unwrap();
+        config.trading.wallet.keypair_path = file.path().display().to_string();
+        (solana_sdk::signature::Signer::pubkey(&payer), file)
+    }
+
+    #[tokio::test]
+    async fn test_executor_needs_a_keypair() {
+        let engine = ArbitrageEngine::new(Config::default()).await.unwrap();
+        assert!(engine.executor().is_none());
+
+        let mut config = Config::default();
+        let (payer, _file) = with_wallet(&mut config);
+        let engine = ArbitrageEngine::new(config.clone()).await.unwrap();
+        assert_eq!(engine.executor().unwrap().payer(), payer);
+
+        config.trading.wallet.keypair_path = "/nonexistent/keypair.json".to_string();
+        assert!(ArbitrageEngine::new(config).await.is_err());
+    }
+
+    fn opportunity(id: &str, pools: &[&str]) -> ArbitrageOpportunity {
+        let now = chrono::Utc::now();
+        let leg = |pool: &&str| OpportunityLeg {
+            pool: pool.to_string(),
+            dex: "raydium".to_string(),
+            input_mint: String::new(),
+            output_mint: String::new(),
+            amount_in: 1,
+            expected_out: 1,
+            fee_amount: 0,
+            price_impact_bps: 0,
+        };
+        ArbitrageOpportunity {
+            id: id.to_string(),
+            token_mint: String::new(),
+            legs: pools.iter().map(leg).collect(),
+            input_amount: 1,
+            max_trade_size: Decimal::ONE,
+            buy_price: Decimal::ONE,
+            sell_price: Decimal::ONE,
+            price_difference: Decimal::ZERO,
+            profit_percentage: Decimal::ZERO,
+            estimated_profit_usd: Decimal::ZERO,
+            gas_cost_estimate: Decimal::ZERO,
+            net_profit_usd: Decimal::ZERO,
+            confidence_score: Decimal::ONE,
+            risk_score: Decimal::ZERO,
+            status: OpportunityStatus::Detected,
+            slot: 1,
+            detected_at: now,
+            expires_at: now,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_trades_in_flight_are_limited() {
+        let mut config = Config::default();
+        config.trading.max_concurrent_trades = 2;
+        let engine = ArbitrageEngine::new(config).await.unwrap();
+
+        assert!(engine.reserve(&opportunity("a", &["p1", "p2"])));
+        // The same route is not traded twice at once
+        assert!(!engine.reserve(&opportunity("b", &["p1", "p2"])));
+        assert!(engine.reserve(&opportunity("c", &["p2", "p1"])));
+        // Every slot is taken
+        assert!(!engine.reserve(&opportunity("d", &["p3", "p4"])));
+        assert_eq!(engine.trades_in_flight(), 2);
+
+        engine.in_flight.lock().remove("a");
+        assert!(engine.reserve(&opportunity("d", &["p3", "p4"])));
+    }
+
+    #[tokio::test]
+    async fn test_stop_waits_for_executions() {
+        let mut config = Config::default();
+        let _wallet = with_wallet(&mut config);
+        let engine = ArbitrageEngine::new(config).await.unwrap();
+        let executor = engine.executor().unwrap().clone();
+        let prices = Arc::new(engine.strategy().prices());
+        engine.start().await.unwrap();
+
+        let trade = opportunity("a", &["p1", "p2"]);
+        assert!(engine.reserve(&trade));
+        engine.spawn_execution(executor, trade, prices);
+        engine.stop().await.unwrap();
+        assert!(engine.executions.lock().is_empty());
+        assert_eq!(engine.trades_in_flight(), 0);
+        assert_eq!(engine.risk_manager().exposure_usd(), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_unsent_probes_are_released() {
+        let mut config = Config::default();
+        config.risk.max_consecutive_losses = 1;
+        config.risk.circuit_breaker_cooldown_ms = 10;
+        let _wallet = with_wallet(&mut config);
+        let engine = ArbitrageEngine::new(config).await.unwrap();
+        let executor = engine.executor().unwrap().clone();
+        let prices = engine.strategy().prices();
+
+        engine
+            .circuit_breaker()
+            .record(&opportunity("lost", &["p1"]), TradeOutcome::Failed);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(
+            engine.circuit_breaker().global_state(),
+            BreakerState::HalfOpen
+        );
+
+        // The risk manager refuses the probe, so the next opportunity gets to
+        // probe instead
+        let probe = opportunity("probe", &["p2"]);
+        assert!(engine.reserve(&probe));
+        engine.execute(&executor, probe, &prices).await;
+        assert_eq!(engine.trades_in_flight(), 0);
+        assert!(engine
+            .claim_submission(&opportunity("next", &["p3"]))
+            .is_ok());
+        // Which holds the probe until it resolves
+        assert!(engine.may_submit(&opportunity("after", &["p4"])).is_err());
+    }
+
+    #[test]
+    fn test_realized_pnl() {
+        let mut trade = TradeResult::pending(&opportunity("a", &["p1"]), Decimal::new(2, 2));
+        trade.simulation = Some(SimulationReport {
+            net_profit_usd: Some(Decimal::from(12)),
+            ..SimulationReport::default()
+        });
+
+        trade.status = TradeStatus::Success;
+        assert_eq!(realized_pnl(&trade), Decimal::from(12));
+
+        // A reverted trade still paid its fees
+        trade.status = TradeStatus::Failed;
+        trade.failure = Some(TradeFailure::Reverted {
+            instruction: Some(4),
+            custom_code: Some(0x1771),
+            message: "slippage".to_string(),
+        });
+        assert_eq!(realized_pnl(&trade), Decimal::new(-2, 2));
+
+        // An expired one never landed
+        trade.failure = Some(TradeFailure::Expired {
+            last_valid_block_height: 10,
+        });
+        assert_eq!(realized_pnl(&trade), Decimal::ZERO);
+
+        // What a landed trade was measured to make takes precedence
+        trade.status = TradeStatus::Success;
+        trade.failure = None;
+        trade.actual_profit = Some(Decimal::new(75, 1));
+        assert_eq!(realized_pnl(&trade), Decimal::new(75, 1));
+    }
+
+    #[test]
+    fn test_wallet_holdings_are_valued() {
+        let usdc = Pubkey::from_str("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v").unwrap();
+        let mut dex = DexManager::new();
+        dex.register(Arc::new(RaydiumAmmAdapter::new()));
+        let oracle = PriceOracle::new(&Config::default().tokens);
+        let holdings = [
+            (NATIVE_MINT, 2_000_000_000),
+            (usdc, 500_000_000),
+            (Pubkey::new_unique(), 1_000),
+        ];
+
+        // Stablecoins alone don't make for a complete valuation
+        let stablecoins = oracle.snapshot(&dex, []);
+        assert_eq!(holdings_usd(&stablecoins, &holdings), None);
+
+        let pool = test_pool(NATIVE_MINT, usdc, 1_000_000_000_000, 150_000_000_000, 25);
+        let prices = oracle.snapshot(&dex, [&pool]);
+        let sol_usd = prices.usd_value(&NATIVE_MINT, 2_000_000_000).unwrap();
+        assert!(sol_usd > 290.0);
+        assert_eq!(holdings_usd(&prices, &holdings), Some(sol_usd + 500.0));
+    }
+
     #[tokio::test]
     async fn test_engine_status() {
         let config = Config::default();
@@ -133,5 +868,7 @@ mod tests {
 
         let status = engine.status().await;
         assert!(!status.running);
+        assert_eq!(status.circuit_breaker, BreakerState::Closed);
+        assert!(status.circuit_breakers.is_empty());
     }
 }
@@ -0,0 +1,40 @@
+//! Trade execution
+//!
+//! Turns detected opportunities into signed transactions. [`TransactionBuilder`]
+//! assembles one v0 transaction per opportunity: compute budget, creation of
+//! any missing token accounts, one swap per leg through the leg's DEX adapter,
+//! and a profit check that makes the whole transaction fail unless the route
+//! returns more than it was given. Routes too large for a legacy transaction
+//! are compiled against our address lookup tables, kept by
+//! [`LookupTableManager`]. Before anything is sent, [`Simulator`] runs the
+//! transaction against the current bank and drops it unless the simulated
+//! balances clear the profit threshold. Priority fees are bid by
+//! [`PriorityFeeEstimator`] from recent fees paid for the same accounts. With
+//! MEV protection on, transactions go to the Jito block engine as tipped
+//! bundles through [`JitoClient`] instead of the public mempool. Sent
+//! transactions are followed by [`ConfirmationTracker`] until they land, fail
+//! or expire with their blockhash. [`Executor`] runs an opportunity through
+//! all of the above.
+
+pub mod builder;
+pub mod confirmation;
+pub mod executor;
+pub mod jito;
+pub mod lookup_tables;
+pub mod priority_fees;
+pub mod simulation;
+
+pub use builder::{
+    associated_token_address, close_account, create_associated_token_account_idempotent,
+    set_compute_unit_limit, set_compute_unit_price, sync_native, FeeBid, TransactionBuilder,
+    ASSOCIATED_TOKEN_PROGRAM_ID, COMPUTE_BUDGET_PROGRAM_ID, SYSTEM_PROGRAM_ID,
+};
+pub use confirmation::{ConfirmationTracker, SignatureStatus};
+pub use executor::Executor;
+pub use jito::{BundleStatus, BundleSubmission, JitoClient, TIP_ACCOUNTS};
+pub use lookup_tables::{LookupTable, LookupTableManager, ADDRESS_LOOKUP_TABLE_PROGRAM_ID};
+pub use priority_fees::PriorityFeeEstimator;
+pub use simulation::{BalanceChange, SimulationOutcome, Simulator};
+
+/// Signature fee of a single-signer transaction, in lamports
+pub const BASE_FEE_LAMPORTS: u64 = 5_000;
@@ -0,0 +1,732 @@
+//! Arbitrage transaction assembly
+//!
+//! Legs are chained through the payer's associated token accounts: every leg
+//! spends the previous leg's minimum output, so slippage on one leg can never
+//! make the next one overdraw. The last leg's minimum output is the profit
+//! check: it is set to the route's input plus the transaction's fees, any
+//! bundle tip and `trading.min_profit_threshold_usd`, converted into the
+//! route's token, and the swap program rejects anything less, reverting every
+//! leg before it.
+//!
+//! Routes through SOL swap wrapped SOL: a route starting with SOL wraps its
+//! input into the payer's wSOL account first, and any route touching SOL
+//! closes that account at the end, unwrapping whatever it holds back to the
+//! payer.
+
+use super::jito::transfer;
+use super::lookup_tables::LookupTableManager;
+use super::BASE_FEE_LAMPORTS;
+use crate::config::Config;
+use crate::dex::{DexManager, PoolState, SwapMode, SwapParams, NATIVE_MINT, TOKEN_PROGRAM_ID};
+use crate::error::{ArbitrageError, Result};
+use crate::models::ArbitrageOpportunity;
+use crate::pool_cache::PoolCache;
+use crate::pricing::PriceSnapshot;
+use parking_lot::RwLock;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::message::{v0, AddressLookupTableAccount, VersionedMessage};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::VersionedTransaction;
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::debug;
+
+/// Compute budget program id
+pub const COMPUTE_BUDGET_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("ComputeBudget111111111111111111111111111111");
+
+/// Associated token account program id
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+/// System program id
+pub const SYSTEM_PROGRAM_ID: Pubkey = Pubkey::from_str_const("11111111111111111111111111111111");
+
+/// `ComputeBudgetInstruction::SetComputeUnitLimit`
+const SET_COMPUTE_UNIT_LIMIT_TAG: u8 = 2;
+
+/// `ComputeBudgetInstruction::SetComputeUnitPrice`
+const SET_COMPUTE_UNIT_PRICE_TAG: u8 = 3;
+
+/// `AssociatedTokenAccountInstruction::CreateIdempotent`
+const CREATE_IDEMPOTENT_TAG: u8 = 1;
+
+/// `TokenInstruction::CloseAccount`
+const CLOSE_ACCOUNT_TAG: u8 = 9;
+
+/// `TokenInstruction::SyncNative`
+const SYNC_NATIVE_TAG: u8 = 17;
+
+/// Accounts fetched per `getMultipleAccounts` request
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// Request a compute unit limit for the transaction
+pub fn set_compute_unit_limit(units: u32) -> Instruction {
+    let mut data = vec![SET_COMPUTE_UNIT_LIMIT_TAG];
+    data.extend_from_slice(&units.to_le_bytes());
+    Instruction::new_with_bytes(COMPUTE_BUDGET_PROGRAM_ID, &data, Vec::new())
+}
+
+/// Pay `micro_lamports` per compute unit as priority fee
+pub fn set_compute_unit_price(micro_lamports: u64) -> Instruction {
+    let mut data = vec![SET_COMPUTE_UNIT_PRICE_TAG];
+    data.extend_from_slice(&micro_lamports.to_le_bytes());
+    Instruction::new_with_bytes(COMPUTE_BUDGET_PROGRAM_ID, &data, Vec::new())
+}
+
+/// Associated token account of `owner` for `mint`
+pub fn associated_token_address(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[owner.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Create `owner`'s associated token account for `mint`, doing nothing if it
+/// already exists
+pub fn create_associated_token_account_idempotent(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        ASSOCIATED_TOKEN_PROGRAM_ID,
+        &[CREATE_IDEMPOTENT_TAG],
+        vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(associated_token_address(owner, mint, token_program), false),
+            AccountMeta::new_readonly(*owner, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+    )
+}
+
+/// Update the token balance of a wrapped SOL account to its lamports
+pub fn sync_native(account: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        TOKEN_PROGRAM_ID,
+        &[SYNC_NATIVE_TAG],
+        vec![AccountMeta::new(*account, false)],
+    )
+}
+
+/// Close `owner`'s token account, sending its lamports to `destination`
+pub fn close_account(account: &Pubkey, destination: &Pubkey, owner: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        TOKEN_PROGRAM_ID,
+        &[CLOSE_ACCOUNT_TAG],
+        vec![
+            AccountMeta::new(*account, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+    )
+}
+
+/// Fees a transaction bids on top of its swaps, with the prices converting
+/// them into the route's token
+#[derive(Debug, Clone, Copy)]
+pub struct FeeBid<'a> {
+    /// Priority fee per compute unit in micro-lamports, or `None` for the
+    /// configured priority fee
+    pub compute_unit_price: Option<u64>,
+    /// Tip paid to the block engine by a separate transaction in the same
+    /// bundle, in lamports
+    pub tip_lamports: u64,
+    /// Prices of SOL and the route's token
+    pub prices: &'a PriceSnapshot,
+}
+
+impl<'a> FeeBid<'a> {
+    /// Bid the configured priority fee
+    pub fn new(prices: &'a PriceSnapshot) -> Self {
+        Self {
+            compute_unit_price: None,
+            tip_lamports: 0,
+            prices,
+        }
+    }
+
+    /// Bid `micro_lamports` per compute unit
+    pub fn with_compute_unit_price(mut self, micro_lamports: u64) -> Self {
+        self.compute_unit_price = Some(micro_lamports);
+        self
+    }
+
+    /// Tip `lamports` from a separate transaction in the same bundle
+    pub fn with_tip(mut self, lamports: u64) -> Self {
+        self.tip_lamports = lamports;
+        self
+    }
+}
+
+/// Builds arbitrage transactions from opportunities
+#[derive(Debug)]
+pub struct TransactionBuilder {
+    dex: Arc<DexManager>,
+    pool_cache: Arc<PoolCache>,
+    compute_unit_limit: u32,
+    priority_fee_lamports: u64,
+    slippage_bps: u16,
+    min_profit_usd: f64,
+    token_accounts: RwLock<HashSet<Pubkey>>,
+}
+
+impl TransactionBuilder {
+    /// Create a builder quoting pools from `pool_cache` and requiring routes
+    /// to clear `min_profit_usd` after fees
+    pub fn new(
+        dex: Arc<DexManager>,
+        pool_cache: Arc<PoolCache>,
+        compute_unit_limit: u32,
+        priority_fee_lamports: u64,
+        slippage_bps: u16,
+        min_profit_usd: f64,
+    ) -> Self {
+        Self {
+            dex,
+            pool_cache,
+            compute_unit_limit,
+            priority_fee_lamports,
+            slippage_bps,
+            min_profit_usd: min_profit_usd.max(0.0),
+            token_accounts: RwLock::new(HashSet::new()),
+        }
+    }
+
+    /// Create a builder with the configured compute budget, priority fee,
+    /// slippage tolerance and profit threshold
+    pub fn from_config(dex: Arc<DexManager>, pool_cache: Arc<PoolCache>, config: &Config) -> Self {
+        Self::new(
+            dex,
+            pool_cache,
+            config.solana.max_compute_units,
+            config.solana.priority_fee_lamports,
+            config.trading.max_slippage_bps,
+            config.trading.min_profit_threshold_usd,
+        )
+    }
+
+    /// Compute unit limit requested by every transaction
+    pub fn compute_unit_limit(&self) -> u32 {
+        self.compute_unit_limit
+    }
+
+    /// Priority fee paid per transaction, in lamports
+    pub fn priority_fee_lamports(&self) -> u64 {
+        self.priority_fee_lamports
+    }
+
+    /// Record token accounts known to exist, so transactions stop creating
+    /// them
+    pub fn mark_token_accounts(&self, accounts: impl IntoIterator<Item = Pubkey>) {
+        self.token_accounts.write().extend(accounts);
+    }
+
+    /// Check whether a token account is known to exist
+    pub fn has_token_account(&self, account: &Pubkey) -> bool {
+        self.token_accounts.read().contains(account)
+    }
+
+    /// Look up which of `accounts` exist on chain and record them, returning
+    /// the number found
+    pub async fn refresh_token_accounts(
+        &self,
+        rpc: &RpcClient,
+        accounts: &[Pubkey],
+    ) -> Result<usize> {
+        let mut found = Vec::new();
+        for chunk in accounts.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let fetched = rpc.get_multiple_accounts(chunk).await?;
+            found.extend(
+                chunk
+                    .iter()
+                    .zip(fetched)
+                    .filter(|(_, account)| account.is_some())
+                    .map(|(address, _)| *address),
+            );
+        }
+        let count = found.len();
+        self.mark_token_accounts(found);
+        Ok(count)
+    }
+
+    /// Token account of `payer` the route of `opportunity` starts and ends
+    /// in, where its profit lands
+    pub fn profit_account(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        payer: &Pubkey,
+    ) -> Result<Pubkey> {
+        let leg = opportunity.legs.first().ok_or_else(|| {
+            ArbitrageError::transaction(format!("Opportunity {} has no legs", opportunity.id))
+        })?;
+        let mint = parse_pubkey(&opportunity.token_mint)?;
+        let token_program = self.dex.token_program(&*self.pool(&leg.pool)?, &mint)?;
+        Ok(associated_token_address(payer, &mint, &token_program))
+    }
+
+    /// Compute unit price paying the configured priority fee over the
+    /// compute unit limit, in micro-lamports
+    pub fn default_compute_unit_price(&self) -> u64 {
+        (self.priority_fee_lamports as u128 * 1_000_000 / self.compute_unit_limit.max(1) as u128)
+            as u64
+    }
+
+    /// Fees of one transaction at `compute_unit_price`, in lamports, rounding
+    /// the priority fee up like the runtime does
+    pub fn fee_lamports(&self, compute_unit_price: u64) -> u64 {
+        let priority =
+            (compute_unit_price as u128 * self.compute_unit_limit as u128).div_ceil(1_000_000);
+        BASE_FEE_LAMPORTS.saturating_add(priority as u64)
+    }
+
+    /// Compute unit price bid by `bid`, in micro-lamports
+    pub fn compute_unit_price(&self, bid: &FeeBid<'_>) -> u64 {
+        bid.compute_unit_price
+            .unwrap_or_else(|| self.default_compute_unit_price())
+    }
+
+    /// Lamports `bid` costs: the transaction's fees, plus the tip and the
+    /// fee of the transaction paying it if there is one
+    pub fn bid_lamports(&self, bid: &FeeBid<'_>) -> u64 {
+        let tip = if bid.tip_lamports > 0 {
+            bid.tip_lamports.saturating_add(BASE_FEE_LAMPORTS)
+        } else {
+            0
+        };
+        self.fee_lamports(self.compute_unit_price(bid))
+            .saturating_add(tip)
+    }
+
+    /// Smallest output the route may return: its input plus the fees and tip
+    /// of `bid` and the minimum profit, in the route's token. Fails if the fees
+    /// can't be converted because SOL or the token has no price.
+    pub fn min_output(&self, opportunity: &ArbitrageOpportunity, bid: &FeeBid<'_>) -> Result<u64> {
+        let mint = parse_pubkey(&opportunity.token_mint)?;
+        let fees = self.bid_lamports(bid);
+        let margin = if mint == NATIVE_MINT {
+            fees.saturating_add(token_amount(bid.prices, &mint, self.min_profit_usd)?)
+        } else {
+            let fees_usd = bid
+                .prices
+                .usd_value(&NATIVE_MINT, fees)
+                .ok_or_else(|| unpriced(&NATIVE_MINT))?;
+            token_amount(bid.prices, &mint, fees_usd + self.min_profit_usd)?
+        };
+        Ok(opportunity.input_amount.saturating_add(margin.max(1)))
+    }
+
+    /// Instructions of the arbitrage transaction for `opportunity`, paid for
+    /// and signed by `payer`, with the fees of `bid`
+    pub fn instructions(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        payer: &Pubkey,
+        bid: &FeeBid<'_>,
+    ) -> Result<Vec<Instruction>> {
+        if opportunity.legs.is_empty() {
+            return Err(ArbitrageError::transaction(format!(
+                "Opportunity {} has no legs",
+                opportunity.id
+            )));
+        }
+
+        let compute_unit_price = self.compute_unit_price(bid);
+        let mut instructions = vec![set_compute_unit_limit(self.compute_unit_limit)];
+        if compute_unit_price > 0 {
+            instructions.push(set_compute_unit_price(compute_unit_price));
+        }
+
+        let wsol_account = associated_token_address(payer, &NATIVE_MINT, &TOKEN_PROGRAM_ID);
+        let mut swaps = Vec::with_capacity(opportunity.legs.len());
+        let mut created = HashSet::new();
+        let mut touches_sol = false;
+        let mut amount = opportunity.input_amount;
+        for (i, leg) in opportunity.legs.iter().enumerate() {
+            let pool = self.pool(&leg.pool)?;
+            let input_mint = parse_pubkey(&leg.input_mint)?;
+            let output_mint = parse_pubkey(&leg.output_mint)?;
+
+            let mut accounts = [Pubkey::default(); 2];
+            for (account, mint) in accounts.iter_mut().zip([input_mint, output_mint]) {
+                let token_program = self.dex.token_program(&pool, &mint)?;
+                *account = associated_token_address(payer, &mint, &token_program);
+                // The wSOL account is closed after every route, so it never
+                // outlives a transaction
+                let exists = mint != NATIVE_MINT && self.has_token_account(account);
+                touches_sol |= mint == NATIVE_MINT;
+                if !exists && created.insert(*account) {
+                    instructions.push(create_associated_token_account_idempotent(
+                        payer,
+                        payer,
+                        &mint,
+                        &token_program,
+                    ));
+                }
+            }
+
+            let min_out = if i + 1 == opportunity.legs.len() {
+                self.min_output(opportunity, bid)?
+            } else {
+                self.min_leg_output(leg.expected_out, leg.amount_in, amount)
+            };
+            swaps.push(self.dex.swap_instruction(
+                &pool,
+                &SwapParams {
+                    user: *payer,
+                    source_token_account: accounts[0],
+                    destination_token_account: accounts[1],
+                    input_mint,
+                    amount,
+                    other_amount_threshold: min_out,
+                    mode: SwapMode::ExactIn,
+                },
+            )?);
+            amount = min_out;
+        }
+
+        if opportunity.legs[0].input_mint == NATIVE_MINT.to_string() {
+            instructions.push(transfer(payer, &wsol_account, opportunity.input_amount));
+            instructions.push(sync_native(&wsol_account));
+        }
+        instructions.extend(swaps);
+        if touches_sol {
+            instructions.push(close_account(&wsol_account, payer, payer));
+        }
+        Ok(instructions)
+    }
+
+    /// Sign the arbitrage transaction for `opportunity`, compiling its
+    /// message against `lookup_tables`
+    pub fn build(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        payer: &Keypair,
+        recent_blockhash: Hash,
+        bid: &FeeBid<'_>,
+        lookup_tables: &[AddressLookupTableAccount],
+    ) -> Result<VersionedTransaction> {
+        let instructions = self.instructions(opportunity, &payer.pubkey(), bid)?;
+        self.sign(
+            opportunity,
+            &instructions,
+            payer,
+            recent_blockhash,
+            lookup_tables,
+        )
+    }
+
+    /// Like [`Self::build`], with the tables picked from `lookup_tables` for
+    /// use at `slot`
+    pub fn build_with_lookup_tables(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        payer: &Keypair,
+        recent_blockhash: Hash,
+        bid: &FeeBid<'_>,
+        lookup_tables: &LookupTableManager,
+        slot: u64,
+    ) -> Result<VersionedTransaction> {
+        let instructions = self.instructions(opportunity, &payer.pubkey(), bid)?;
+        let tables = lookup_tables.select(&instructions, slot);
+        self.sign(opportunity, &instructions, payer, recent_blockhash, &tables)
+    }
+
+    fn sign(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        instructions: &[Instruction],
+        payer: &Keypair,
+        recent_blockhash: Hash,
+        lookup_tables: &[AddressLookupTableAccount],
+    ) -> Result<VersionedTransaction> {
+        let message = v0::Message::try_compile(
+            &payer.pubkey(),
+            instructions,
+            lookup_tables,
+            recent_blockhash,
+        )
+        .map_err(|e| {
+            ArbitrageError::transaction(format!(
+                "Failed to compile message for opportunity {}: {}",
+                opportunity.id, e
+            ))
+        })?;
+        let transaction = VersionedTransaction::try_new(VersionedMessage::V0(message), &[payer])
+            .map_err(|e| {
+                ArbitrageError::transaction(format!(
+                    "Failed to sign transaction for opportunity {}: {}",
+                    opportunity.id, e
+                ))
+            })?;
+
+        debug!(
+            "Built transaction for opportunity {} with {} instructions and {} lookup tables",
+            opportunity.id,
+            instructions.len(),
+            lookup_tables.len()
+        );
+        Ok(transaction)
+    }
+
+    /// Minimum output of an intermediate leg quoted at `quoted_in` but
+    /// spending `amount`, after slippage
+    fn min_leg_output(&self, expected_out: u64, quoted_in: u64, amount: u64) -> u64 {
+        let scaled = if quoted_in == 0 {
+            expected_out as u128
+        } else {
+            expected_out as u128 * amount.min(quoted_in) as u128 / quoted_in as u128
+        };
+        (scaled * (10_000 - self.slippage_bps.min(10_000)) as u128 / 10_000) as u64
+    }
+
+    fn pool(&self, address: &str) -> Result<Arc<PoolState>> {
+        self.pool_cache
+            .get(&parse_pubkey(address)?)
+            .map(|cached| cached.state)
+            .ok_or_else(|| ArbitrageError::transaction(format!("Pool {} is not cached", address)))
+    }
+}
+
+fn unpriced(mint: &Pubkey) -> ArbitrageError {
+    ArbitrageError::transaction(format!("No price for {}", mint))
+}
+
+/// Base units of `mint` worth at least `usd` dollars
+fn token_amount(prices: &PriceSnapshot, mint: &Pubkey, usd: f64) -> Result<u64> {
+    if usd <= 0.0 {
+        return Ok(0);
+    }
+    let price = prices
+        .price(mint)
+        .filter(|price| *price > 0.0)
+        .ok_or_else(|| unpriced(mint))?;
+    let amount = (usd / price * 10f64.powi(prices.decimals(mint) as i32)).ceil();
+    Ok(amount.min(u64::MAX as f64) as u64)
+}
+
+fn parse_pubkey(address: &str) -> Result<Pubkey> {
+    Pubkey::from_str(address)
+        .map_err(|e| ArbitrageError::transaction(format!("Invalid address {}: {}", address, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dex::{ORCA, RAYDIUM};
+    use crate::strategy::StrategyManager;
+    use crate::test_support::{cross_dex_cache, usdc_pool, USDC};
+
+    async fn setup() -> (TransactionBuilder, Vec<ArbitrageOpportunity>, PriceSnapshot) {
+        let (dex, cache) = cross_dex_cache();
+        for (name, usdc_per_sol) in [(RAYDIUM, 150), (ORCA, 155)] {
+            cache
+                .insert(usdc_pool(name, NATIVE_MINT, usdc_per_sol), 1, 0)
+                .unwrap();
+        }
+
+        let config = Config::default();
+        let strategy = StrategyManager::new(&config, dex.clone(), cache.clone());
+        let opportunities = strategy.find_opportunities().await.unwrap();
+        (
+            TransactionBuilder::from_config(dex, cache, &config),
+            opportunities,
+            strategy.prices(),
+        )
+    }
+
+    fn message_len(transaction: &VersionedTransaction) -> usize {
+        transaction.message.serialize().len()
+    }
+
+    fn threshold(instruction: &Instruction) -> u64 {
+        u64::from_le_bytes(instruction.data[9..17].try_into().unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_builds_signed_v0_transaction() {
+        let (builder, opportunities, prices) = setup().await;
+        let bid = FeeBid::new(&prices);
+        let opportunity = opportunities
+            .iter()
+            .find(|opportunity| opportunity.token_mint == USDC.to_string())
+            .unwrap();
+        let payer = Keypair::new();
+
+        let instructions = builder
+            .instructions(opportunity, &payer.pubkey(), &bid)
+            .unwrap();
+        // Compute limit and price, two token accounts, two swaps, and the
+        // wSOL account closed again
+        assert_eq!(instructions.len(), 7);
+        assert_eq!(instructions[0].program_id, COMPUTE_BUDGET_PROGRAM_ID);
+        assert_eq!(instructions[1].program_id, COMPUTE_BUDGET_PROGRAM_ID);
+        assert_eq!(instructions[2].program_id, ASSOCIATED_TOKEN_PROGRAM_ID);
+        assert_eq!(instructions[3].program_id, ASSOCIATED_TOKEN_PROGRAM_ID);
+
+        // The second swap spends the first one's minimum output and must
+        // return the input plus the fees and minimum profit in USDC
+        let (buy, sell) = (&instructions[4], &instructions[5]);
+        let usdc_account =
+            associated_token_address(&payer.pubkey(), &USDC, &crate::dex::TOKEN_PROGRAM_ID);
+        assert_eq!(buy.accounts[5].pubkey, usdc_account);
+        assert_eq!(sell.accounts[6].pubkey, usdc_account);
+        let bought = threshold(buy);
+        assert!(bought < opportunity.legs[0].expected_out);
+        assert_eq!(
+            u64::from_le_bytes(sell.data[1..9].try_into().unwrap()),
+            bought
+        );
+        let min_output = builder.min_output(opportunity, &bid).unwrap();
+        assert_eq!(threshold(sell), min_output);
+        let fees_usd = prices
+            .usd_value(
+                &NATIVE_MINT,
+                builder.fee_lamports(builder.compute_unit_price(&bid)),
+            )
+            .unwrap();
+        let margin = prices
+            .usd_value(&USDC, min_output - opportunity.input_amount)
+            .unwrap();
+        assert!(margin >= fees_usd + 10.0 && margin < fees_usd + 10.0 + 1e-6);
+
+        let transaction = builder
+            .build(opportunity, &payer, Hash::default(), &bid, &[])
+            .unwrap();
+        assert!(matches!(transaction.message, VersionedMessage::V0(_)));
+        assert_eq!(transaction.signatures.len(), 1);
+        assert!(transaction.verify_with_results().iter().all(|ok| *ok));
+
+        // Pool and vault accounts stored in a lookup table shrink the message
+        let tables = LookupTableManager::new(payer.pubkey());
+        let accounts: Vec<Pubkey> = instructions[4..6]
+            .iter()
+            .flat_map(|ix| &ix.accounts)
+            .filter(|meta| !meta.is_signer)
+            .map(|meta| meta.pubkey)
+            .collect();
+        tables.ensure(&payer.pubkey(), &accounts, 10, 11).unwrap();
+        let compact = builder
+            .build_with_lookup_tables(opportunity, &payer, Hash::default(), &bid, &tables, 12)
+            .unwrap();
+        let VersionedMessage::V0(message) = &compact.message else {
+            unreachable!()
+        };
+        assert_eq!(message.address_table_lookups.len(), 1);
+        assert!(
+            message_len(&compact) < message_len(&transaction),
+            "lookup tables should shrink the transaction"
+        );
+
+        // Known token accounts are not created again, except the wSOL
+        // account every route closes
+        let wsol_account =
+            associated_token_address(&payer.pubkey(), &NATIVE_MINT, &TOKEN_PROGRAM_ID);
+        builder.mark_token_accounts([usdc_account, wsol_account]);
+        assert_eq!(
+            builder
+                .instructions(opportunity, &payer.pubkey(), &bid)
+                .unwrap()
+                .len(),
+            6
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wraps_and_unwraps_sol() {
+        let (builder, opportunities, prices) = setup().await;
+        let opportunity = opportunities
+            .iter()
+            .find(|opportunity| opportunity.token_mint == NATIVE_MINT.to_string())
+            .unwrap();
+        let payer = Pubkey::new_unique();
+        let wsol_account = associated_token_address(&payer, &NATIVE_MINT, &TOKEN_PROGRAM_ID);
+
+        let instructions = builder
+            .instructions(opportunity, &payer, &FeeBid::new(&prices))
+            .unwrap();
+        // Compute limit and price, two token accounts, wrap, two swaps, unwrap
+        assert_eq!(instructions.len(), 9);
+        assert_eq!(
+            instructions[4],
+            transfer(&payer, &wsol_account, opportunity.input_amount)
+        );
+        assert_eq!(instructions[5], sync_native(&wsol_account));
+        assert_eq!(instructions[6].accounts[5].pubkey, wsol_account);
+        assert_eq!(instructions[7].accounts[6].pubkey, wsol_account);
+        assert_eq!(
+            instructions[8],
+            close_account(&wsol_account, &payer, &payer)
+        );
+        assert_eq!(
+            builder.profit_account(opportunity, &payer).unwrap(),
+            wsol_account
+        );
+    }
+
+    #[tokio::test]
+    async fn test_routes_must_cover_fees_and_min_profit() {
+        let (builder, opportunities, prices) = setup().await;
+        let bid = FeeBid::new(&prices);
+        let opportunity = opportunities
+            .iter()
+            .find(|opportunity| opportunity.token_mint == NATIVE_MINT.to_string())
+            .unwrap();
+        let min_profit = (10.0 / prices.price(&NATIVE_MINT).unwrap() * 1e9).ceil() as u64;
+        assert_eq!(
+            builder.min_output(opportunity, &bid).unwrap(),
+            opportunity.input_amount
+                + BASE_FEE_LAMPORTS
+                + builder.priority_fee_lamports()
+                + min_profit
+        );
+
+        // A higher bid raises the bar by the extra fee
+        let price = 10 * builder.default_compute_unit_price();
+        let bid = bid.with_compute_unit_price(price);
+        let instructions = builder
+            .instructions(opportunity, &Pubkey::new_unique(), &bid)
+            .unwrap();
+        assert_eq!(instructions[1], set_compute_unit_price(price));
+        assert_eq!(
+            builder.min_output(opportunity, &bid).unwrap(),
+            opportunity.input_amount + builder.fee_lamports(price) + min_profit
+        );
+        assert!(
+            builder.fee_lamports(price) > BASE_FEE_LAMPORTS + 9 * builder.priority_fee_lamports()
+        );
+
+        // So does a tip, and the fee of the transaction paying it
+        assert_eq!(
+            builder
+                .min_output(opportunity, &bid.with_tip(50_000))
+                .unwrap(),
+            opportunity.input_amount
+                + builder.fee_lamports(price)
+                + 50_000
+                + BASE_FEE_LAMPORTS
+                + min_profit
+        );
+
+        let mut missing = opportunity.clone();
+        missing.legs[1].pool = Pubkey::new_unique().to_string();
+        assert!(builder
+            .instructions(&missing, &Pubkey::new_unique(), &bid)
+            .is_err());
+
+        // Fees can't be converted without prices
+        let unpriced = PriceSnapshot::default();
+        assert!(builder
+            .min_output(opportunity, &FeeBid::new(&unpriced))
+            .is_err());
+    }
+}
@@ -0,0 +1,363 @@
+//! Confirmation tracking
+//!
+//! A sent transaction either lands, lands and fails, or is dropped, and the
+//! RPC node only ever answers "not found" for the last one. The tracker polls
+//! `getSignatureStatuses` for every trade in flight and resolves it once the
+//! status reaches the configured commitment: `Success`, or `Failed` with the
+//! on-chain error parsed into a [`TradeFailure`]. A transaction still unseen
+//! once the block height passes its blockhash's last valid block height can
+//! no longer land, so it is resolved as `Failed` with
+//! [`TradeFailure::Expired`] instead of being waited on forever.
+
+use crate::config::Config;
+use crate::error::{ArbitrageError, Result};
+use crate::geyser::UpdateStream;
+use crate::models::{TradeFailure, TradeResult, TradeStatus};
+use parking_lot::RwLock;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_commitment_config::CommitmentConfig;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::TransactionError;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{debug, info, warn};
+
+/// Interval between status polls, about one slot
+const POLL_INTERVAL: Duration = Duration::from_millis(400);
+
+/// Signatures per `getSignatureStatuses` request
+const MAX_SIGNATURE_STATUSES: usize = 256;
+
+/// Resolved trades buffered before the tracker waits for the consumer
+const RESOLVED_BUFFER: usize = 64;
+
+/// Status of a sent transaction the node has seen
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureStatus {
+    /// Slot the transaction landed in
+    pub slot: u64,
+    /// Error the transaction failed with, if it did
+    pub err: Option<TransactionError>,
+    /// Whether the status has reached the tracker's commitment
+    pub confirmed: bool,
+}
+
+#[derive(Debug)]
+struct InFlight {
+    trade: TradeResult,
+    last_valid_block_height: u64,
+}
+
+/// Follows sent transactions until they land or expire
+pub struct ConfirmationTracker {
+    rpc: Arc<RpcClient>,
+    commitment: CommitmentConfig,
+    poll_interval: Duration,
+    in_flight: RwLock<HashMap<Signature, InFlight>>,
+}
+
+impl fmt::Debug for ConfirmationTracker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConfirmationTracker")
+            .field("rpc", &self.rpc.url())
+            .field("commitment", &self.commitment)
+            .field("poll_interval", &self.poll_interval)
+            .field("in_flight", &self.in_flight.read().len())
+            .finish()
+    }
+}
+
+impl ConfirmationTracker {
+    /// Create a tracker resolving trades at `commitment`, polling every
+    /// `poll_interval`
+    pub fn new(rpc: Arc<RpcClient>, commitment: CommitmentConfig, poll_interval: Duration) -> Self {
+        Self {
+            rpc,
+            commitment,
+            poll_interval,
+            in_flight: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Create a tracker resolving trades at the configured commitment
+    pub fn from_config(rpc: Arc<RpcClient>, config: &Config) -> Result<Self> {
+        let commitment = CommitmentConfig::from_str(&config.solana.commitment).map_err(|_| {
+            ArbitrageError::config(format!(
+                "Invalid commitment level: {}",
+                config.solana.commitment
+            ))
+        })?;
+        Ok(Self::new(rpc, commitment, POLL_INTERVAL))
+    }
+
+    /// Follow `trade`, sent as `signature` with a blockhash valid up to
+    /// `last_valid_block_height`
+    pub fn track(
+        &self,
+        mut trade: TradeResult,
+        signature: Signature,
+        last_valid_block_height: u64,
+    ) {
+        trade.status = TradeStatus::Executing;
+        trade.signature = Some(signature.to_string());
+        debug!(
+            "Tracking {} for opportunity {} until block height {}",
+            signature, trade.opportunity_id, last_valid_block_height
+        );
+        self.in_flight.write().insert(
+            signature,
+            InFlight {
+                trade,
+                last_valid_block_height,
+            },
+        );
+    }
+
+    /// Whether `signature` is still in flight
+    pub fn is_tracking(&self, signature: &Signature) -> bool {
+        self.in_flight.read().contains_key(signature)
+    }
+
+    /// Number of trades in flight
+    pub fn len(&self) -> usize {
+        self.in_flight.read().len()
+    }
+
+    /// Whether no trades are in flight
+    pub fn is_empty(&self) -> bool {
+        self.in_flight.read().is_empty()
+    }
+
+    /// Resolve the trades whose `statuses` are final, given the block height
+    /// read before the statuses were fetched. Resolved trades stop being
+    /// tracked.
+    pub fn resolve(
+        &self,
+        statuses: &[(Signature, Option<SignatureStatus>)],
+        block_height: u64,
+    ) -> Vec<TradeResult> {
+        let mut in_flight = self.in_flight.write();
+        let mut resolved = Vec::new();
+
+        for (signature, status) in statuses {
+            let Some(pending) = in_flight.get(signature) else {
+                continue;
+            };
+            let (status, slot, failure) = match status {
+                Some(status) if !status.confirmed => continue,
+                Some(status) => match &status.err {
+                    None => (TradeStatus::Success, Some(status.slot), None),
+                    Some(err) => (
+                        TradeStatus::Failed,
+                        Some(status.slot),
+                        Some(TradeFailure::from(err)),
+                    ),
+                },
+                None if block_height > pending.last_valid_block_height => (
+                    TradeStatus::Failed,
+                    None,
+                    Some(TradeFailure::Expired {
+                        last_valid_block_height: pending.last_valid_block_height,
+                    }),
+                ),
+                None => continue,
+            };
+
+            let Some(InFlight { mut trade, .. }) = in_flight.remove(signature) else {
+                continue;
+            };
+            trade.status = status;
+            trade.slot = slot;
+            trade.failure = failure;
+            match &trade.failure {
+                None => info!("Trade {} confirmed in slot {:?}", signature, slot),
+                Some(failure) => info!("Trade {} failed: {:?}", signature, failure),
+            }
+            resolved.push(trade);
+        }
+        resolved
+    }
+
+    /// Fetch the statuses of every trade in flight and resolve the final ones
+    pub async fn poll(&self) -> Result<Vec<TradeResult>> {
+        let signatures: Vec<Signature> = self.in_flight.read().keys().copied().collect();
+        if signatures.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Read first: a transaction missing from statuses fetched afterwards
+        // can't land anymore if its blockhash had expired at this height
+        let block_height = self.rpc.get_block_height().await?;
+        let mut statuses = Vec::with_capacity(signatures.len());
+        for chunk in signatures.chunks(MAX_SIGNATURE_STATUSES) {
+            let response = self.rpc.get_signature_statuses(chunk).await?;
+            statuses.extend(
+                chunk
+                    .iter()
+                    .copied()
+                    .zip(response.value.into_iter().map(|status| {
+                        status.map(|status| SignatureStatus {
+                            slot: status.slot,
+                            confirmed: status.satisfies_commitment(self.commitment),
+                            err: status.err,
+                        })
+                    })),
+            );
+        }
+        Ok(self.resolve(&statuses, block_height))
+    }
+
+    /// Poll in a background task and stream every resolved trade. The task
+    /// stops when the stream is dropped.
+    pub fn spawn(self: Arc<Self>) -> UpdateStream<TradeResult> {
+        let (tx, rx) = mpsc::channel(RESOLVED_BUFFER);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.poll_interval);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = tx.closed() => return,
+                }
+                let resolved = self.poll().await.unwrap_or_else(|e| {
+                    warn!("Failed to poll signature statuses: {}", e);
+                    Vec::new()
+                });
+                for trade in resolved {
+                    if tx.send(trade).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        Box::pin(ReceiverStream::new(rx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ArbitrageOpportunity, OpportunityStatus};
+    use rust_decimal::Decimal;
+    use solana_sdk::instruction::InstructionError;
+
+    fn tracker() -> ConfirmationTracker {
+        ConfirmationTracker::new(
+            Arc::new(RpcClient::new(String::new())),
+            CommitmentConfig::confirmed(),
+            POLL_INTERVAL,
+        )
+    }
+
+    fn trade() -> TradeResult {
+        let now = chrono::Utc::now();
+        let opportunity = ArbitrageOpportunity {
+            id: "test".to_string(),
+            token_mint: "So11111111111111111111111111111111111111112".to_string(),
+            legs: Vec::new(),
+            input_amount: 1_000_000_000,
+            max_trade_size: Decimal::from(150),
+            buy_price: Decimal::from(150),
+            sell_price: Decimal::from(155),
+            price_difference: Decimal::new(3, 2),
+            profit_percentage: Decimal::from(3),
+            estimated_profit_usd: Decimal::new(45, 1),
+            gas_cost_estimate: Decimal::new(1, 2),
+            net_profit_usd: Decimal::new(449, 2),
+            confidence_score: Decimal::new(9, 1),
+            risk_score: Decimal::new(1, 1),
+            status: OpportunityStatus::Executing,
+            slot: 1,
+            detected_at: now,
+            expires_at: now,
+        };
+        TradeResult::pending(&opportunity, Decimal::new(1, 2))
+    }
+
+    fn landed(slot: u64, err: Option<TransactionError>) -> Option<SignatureStatus> {
+        Some(SignatureStatus {
+            slot,
+            err,
+            confirmed: true,
+        })
+    }
+
+    #[test]
+    fn test_resolves_landed_trades() {
+        let tracker = tracker();
+        let (ok, failed, processed) = (
+            Signature::new_unique(),
+            Signature::new_unique(),
+            Signature::new_unique(),
+        );
+        for signature in [ok, failed, processed] {
+            tracker.track(trade(), signature, 100);
+        }
+        assert_eq!(tracker.len(), 3);
+
+        let error = TransactionError::InstructionError(3, InstructionError::Custom(6001));
+        let resolved = tracker.resolve(
+            &[
+                (ok, landed(10, None)),
+                (failed, landed(11, Some(error))),
+                (
+                    processed,
+                    Some(SignatureStatus {
+                        slot: 12,
+                        err: None,
+                        confirmed: false,
+                    }),
+                ),
+            ],
+            90,
+        );
+        assert_eq!(resolved.len(), 2);
+        assert_eq!(resolved[0].status, TradeStatus::Success);
+        assert_eq!(resolved[0].slot, Some(10));
+        assert_eq!(resolved[0].signature, Some(ok.to_string()));
+        assert_eq!(resolved[1].status, TradeStatus::Failed);
+        assert_eq!(
+            resolved[1].failure,
+            Some(TradeFailure::Reverted {
+                instruction: Some(3),
+                custom_code: Some(6001),
+                message: "instruction 3 failed with custom error 0x1771".to_string(),
+            })
+        );
+
+        // Processed but not yet confirmed keeps waiting, even past expiry
+        let unconfirmed = Some(SignatureStatus {
+            slot: 12,
+            err: None,
+            confirmed: false,
+        });
+        assert!(tracker.resolve(&[(processed, unconfirmed)], 101).is_empty());
+        assert!(tracker.is_tracking(&processed));
+        assert!(!tracker.is_tracking(&ok));
+    }
+
+    #[test]
+    fn test_unseen_trades_expire_with_their_blockhash() {
+        let tracker = tracker();
+        let signature = Signature::new_unique();
+        tracker.track(trade(), signature, 100);
+
+        // Still valid at its last valid block height
+        assert!(tracker.resolve(&[(signature, None)], 100).is_empty());
+
+        let resolved = tracker.resolve(&[(signature, None)], 101);
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].status, TradeStatus::Failed);
+        assert_eq!(
+            resolved[0].failure,
+            Some(TradeFailure::Expired {
+                last_valid_block_height: 100
+            })
+        );
+        assert!(tracker.is_empty());
+    }
+}
@@ -0,0 +1,272 @@
+//! Trade execution
+//!
+//! [`Executor`] takes an opportunity from detection to the wire: it bids a
+//! priority fee from recent fees on the route's accounts, builds and signs
+//! the transaction against a fresh blockhash, simulates it and, if the
+//! simulation clears the profit threshold, sends it and hands it to the
+//! [`ConfirmationTracker`]. With `trading.enable_mev_protection` set the
+//! transaction goes to the Jito block engine as a tipped bundle instead of
+//! through RPC, and the tip is part of what the route must return. With
+//! `trading.dry_run` set it stops after the simulation.
+
+use super::{
+    BalanceChange, ConfirmationTracker, FeeBid, JitoClient, LookupTableManager,
+    PriorityFeeEstimator, Simulator, TransactionBuilder,
+};
+use crate::config::Config;
+use crate::dex::NATIVE_MINT;
+use crate::error::{ArbitrageError, Result};
+use crate::models::{ArbitrageOpportunity, TradeResult, TradeStatus};
+use crate::pricing::PriceSnapshot;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcSendTransactionConfig, RpcTransactionConfig};
+use solana_commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signature, Signer};
+use solana_transaction_status_client_types::UiTransactionEncoding;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::info;
+
+/// Sends arbitrage transactions for opportunities and tracks them
+pub struct Executor {
+    rpc: Arc<RpcClient>,
+    payer: Arc<Keypair>,
+    builder: Arc<TransactionBuilder>,
+    simulator: Simulator,
+    fees: PriorityFeeEstimator,
+    tracker: Arc<ConfirmationTracker>,
+    lookup_tables: Arc<LookupTableManager>,
+    jito: Option<JitoClient>,
+    max_retries: usize,
+    dry_run: bool,
+}
+
+impl fmt::Debug for Executor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Executor")
+            .field("rpc", &self.rpc.url())
+            .field("payer", &self.payer.pubkey())
+            .field("simulator", &self.simulator)
+            .field("fees", &self.fees)
+            .field("tracker", &self.tracker)
+            .field(
+                "jito",
+                &self
+                    .jito
+                    .as_ref()
+                    .map(|jito| &jito.config().block_engine_url),
+            )
+            .field("max_retries", &self.max_retries)
+            .field("dry_run", &self.dry_run)
+            .finish()
+    }
+}
+
+impl Executor {
+    /// Create an executor signing with `payer`, configured from `config`
+    pub fn new(
+        rpc: Arc<RpcClient>,
+        payer: Arc<Keypair>,
+        builder: Arc<TransactionBuilder>,
+        config: &Config,
+    ) -> Result<Self> {
+        Ok(Self {
+            simulator: Simulator::from_config(rpc.clone(), config),
+            fees: PriorityFeeEstimator::from_config(rpc.clone(), config),
+            tracker: Arc::new(ConfirmationTracker::from_config(rpc.clone(), config)?),
+            lookup_tables: Arc::new(LookupTableManager::new(payer.pubkey())),
+            jito: config
+                .trading
+                .enable_mev_protection
+                .then(|| JitoClient::from_config(config))
+                .transpose()?,
+            max_retries: config.solana.transaction_max_retries as usize,
+            dry_run: config.trading.dry_run,
+            rpc,
+            payer,
+            builder,
+        })
+    }
+
+    /// Create an executor signing with the configured wallet keypair, or
+    /// `None` if no keypair is configured
+    pub fn from_config(
+        rpc: Arc<RpcClient>,
+        builder: Arc<TransactionBuilder>,
+        config: &Config,
+    ) -> Result<Option<Self>> {
+        let path = &config.trading.wallet.keypair_path;
+        if path.is_empty() {
+            return Ok(None);
+        }
+        let payer = read_keypair_file(path).map_err(|e| {
+            ArbitrageError::config(format!("Failed to read keypair {}: {}", path, e))
+        })?;
+        Self::new(rpc, Arc::new(payer), builder, config).map(Some)
+    }
+
+    /// Wallet paying for and signing trades
+    pub fn payer(&self) -> Pubkey {
+        self.payer.pubkey()
+    }
+
+    /// Tracker following sent trades
+    pub fn tracker(&self) -> &Arc<ConfirmationTracker> {
+        &self.tracker
+    }
+
+    /// Lookup tables transactions are compiled against
+    pub fn lookup_tables(&self) -> &Arc<LookupTableManager> {
+        &self.lookup_tables
+    }
+
+    /// Whether trades stop after simulation
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Build, simulate and send the transaction for `opportunity`, valuing
+    /// it with `prices`. Returns the trade as sent and tracked, or cancelled
+    /// if the simulation rejected it or this is a dry run.
+    pub async fn execute(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        prices: &PriceSnapshot,
+    ) -> Result<TradeResult> {
+        let payer = self.payer.pubkey();
+        let expected_profit_lamports = opportunity
+            .net_profit_usd
+            .to_f64()
+            .and_then(|usd| prices.amount_for_usd(&NATIVE_MINT, usd))
+            .unwrap_or(0);
+        let instructions = self
+            .builder
+            .instructions(opportunity, &payer, &FeeBid::new(prices))?;
+        let compute_unit_price = self
+            .fees
+            .estimate(
+                &instructions,
+                self.builder.compute_unit_limit(),
+                expected_profit_lamports,
+                0,
+            )
+            .await?;
+        let mut bid = FeeBid::new(prices).with_compute_unit_price(compute_unit_price);
+        if let Some(jito) = &self.jito {
+            let Some(tip) = jito.tip_lamports(expected_profit_lamports) else {
+                info!(
+                    "Not bundling opportunity {}: the minimum tip would take its profit",
+                    opportunity.id
+                );
+                let mut trade = TradeResult::pending(opportunity, Decimal::ZERO);
+                trade.status = TradeStatus::Cancelled;
+                return Ok(trade);
+            };
+            bid = bid.with_tip(tip);
+        }
+
+        let (latest, slot) = tokio::join!(
+            self.rpc
+                .get_latest_blockhash_with_commitment(self.rpc.commitment()),
+            self.rpc.get_slot()
+        );
+        let ((blockhash, last_valid_block_height), slot) = (latest?, slot?);
+        let transaction = self.builder.build_with_lookup_tables(
+            opportunity,
+            &self.payer,
+            blockhash,
+            &bid,
+            &self.lookup_tables,
+            slot,
+        )?;
+
+        let profit_account = self.builder.profit_account(opportunity, &payer)?;
+        let simulation = self
+            .simulator
+            .simulate(&transaction, opportunity, &payer, &profit_account, prices)
+            .await?;
+        if !simulation.passed() {
+            return Ok(TradeResult::rejected(opportunity, simulation));
+        }
+
+        let gas_cost = prices
+            .usd_value(&NATIVE_MINT, self.builder.bid_lamports(&bid))
+            .and_then(Decimal::from_f64_retain)
+            .unwrap_or_default()
+            .round_dp(6);
+        let mut trade = TradeResult::pending(opportunity, gas_cost);
+        trade.simulation = Some(simulation);
+        if self.dry_run {
+            info!(
+                "Dry run: not sending opportunity {} ({:?} USD simulated)",
+                opportunity.id,
+                trade.simulation.as_ref().and_then(|s| s.net_profit_usd)
+            );
+            trade.status = TradeStatus::Cancelled;
+            return Ok(trade);
+        }
+
+        let signature = match &self.jito {
+            Some(jito) => {
+                jito.submit(transaction, &self.payer, bid.tip_lamports, blockhash)
+                    .await?
+                    .signature
+            }
+            None => {
+                self.rpc
+                    .send_transaction_with_config(
+                        &transaction,
+                        RpcSendTransactionConfig {
+                            skip_preflight: true,
+                            max_retries: Some(self.max_retries),
+                            ..Default::default()
+                        },
+                    )
+                    .await?
+            }
+        };
+        info!("Sent opportunity {} as {}", opportunity.id, signature);
+        self.tracker
+            .track(trade.clone(), signature, last_valid_block_height);
+        trade.status = TradeStatus::Executing;
+        trade.signature = Some(signature.to_string());
+        Ok(trade)
+    }
+
+    /// Balances of the payer and of its account the profit of `opportunity`
+    /// lands in around `signature`, read from the landed transaction's
+    /// metadata
+    pub async fn landed_balances(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        signature: &Signature,
+    ) -> Result<BalanceChange> {
+        // Transactions can't be fetched at processed
+        let commitment = match self.rpc.commitment() {
+            commitment if commitment.is_at_least_confirmed() => commitment,
+            _ => CommitmentConfig::confirmed(),
+        };
+        let transaction = self
+            .rpc
+            .get_transaction_with_config(
+                signature,
+                RpcTransactionConfig {
+                    encoding: Some(UiTransactionEncoding::Base64),
+                    commitment: Some(commitment),
+                    max_supported_transaction_version: Some(0),
+                },
+            )
+            .await?;
+        let meta = transaction.transaction.meta.ok_or_else(|| {
+            ArbitrageError::transaction(format!("Transaction {} has no metadata", signature))
+        })?;
+        let mint = Pubkey::from_str(&opportunity.token_mint).map_err(|_| {
+            ArbitrageError::transaction(format!("Invalid mint {}", opportunity.token_mint))
+        })?;
+        BalanceChange::from_meta(&meta, &self.payer.pubkey(), &mint)
+    }
+}
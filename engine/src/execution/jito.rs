@@ -0,0 +1,433 @@
+//! Jito bundle submission
+//!
+//! With `trading.enable_mev_protection` set, arbitrage transactions skip the
+//! public mempool and go to the Jito block engine as bundles: the arbitrage
+//! transaction followed by a transfer tipping one of the Jito tip accounts.
+//! Bundles execute atomically, so the tip is only paid if the arbitrage lands.
+//! The tip is a share of the expected profit, kept between the configured
+//! minimum and maximum; a trade expected to make no more than the minimum tip
+//! isn't worth a bundle at all. The tip account rotates between bundles to
+//! spread write locks. Submission goes through the block engine's JSON-RPC
+//! `sendBundle`, and landing is tracked by polling `getBundleStatuses`.
+
+pub mod mock;
+
+use crate::config::{Config, JitoConfig};
+use crate::error::{ArbitrageError, Result};
+use crate::execution::SYSTEM_PROGRAM_ID;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::message::{v0, VersionedMessage};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::transaction::VersionedTransaction;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+pub use mock::{JitoMockHandle, JitoMockServer};
+
+/// Timeout for a single block engine request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// `SystemInstruction::Transfer`
+const TRANSFER_TAG: u32 = 2;
+
+/// Mainnet tip accounts of the Jito tip payment program
+pub const TIP_ACCOUNTS: [Pubkey; 8] = [
+    Pubkey::from_str_const("96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5"),
+    Pubkey::from_str_const("HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe"),
+    Pubkey::from_str_const("Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY"),
+    Pubkey::from_str_const("ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49"),
+    Pubkey::from_str_const("DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh"),
+    Pubkey::from_str_const("ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt"),
+    Pubkey::from_str_const("DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL"),
+    Pubkey::from_str_const("3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT"),
+];
+
+/// System program transfer of `lamports` from `from` to `to`
+pub fn transfer(from: &Pubkey, to: &Pubkey, lamports: u64) -> Instruction {
+    let mut data = TRANSFER_TAG.to_le_bytes().to_vec();
+    data.extend_from_slice(&lamports.to_le_bytes());
+    Instruction::new_with_bytes(
+        SYSTEM_PROGRAM_ID,
+        &data,
+        vec![AccountMeta::new(*from, true), AccountMeta::new(*to, false)],
+    )
+}
+
+/// Commitment a landed bundle has reached
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BundleConfirmation {
+    /// Included in a block
+    Processed,
+    /// Voted on by a supermajority
+    Confirmed,
+    /// Rooted
+    Finalized,
+}
+
+/// Status of a landed bundle, as returned by `getBundleStatuses`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleStatus {
+    /// Bundle id returned by `sendBundle`
+    pub bundle_id: String,
+    /// Signatures of the bundle's transactions
+    pub transactions: Vec<String>,
+    /// Slot the bundle landed in
+    pub slot: u64,
+    /// Commitment reached
+    pub confirmation_status: BundleConfirmation,
+    /// `{"Ok": null}` on success, the transaction error otherwise
+    pub err: Value,
+}
+
+impl BundleStatus {
+    /// Whether the bundle executed without error
+    pub fn succeeded(&self) -> bool {
+        self.err.get("Ok").is_some()
+    }
+}
+
+/// A bundle sent to the block engine
+#[derive(Debug, Clone, PartialEq)]
+pub struct BundleSubmission {
+    /// Bundle id returned by `sendBundle`
+    pub bundle_id: String,
+    /// Signature of the arbitrage transaction
+    pub signature: Signature,
+    /// Tip account paid
+    pub tip_account: Pubkey,
+    /// Tip paid, in lamports
+    pub tip_lamports: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<RpcError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StatusesResult {
+    value: Vec<Option<BundleStatus>>,
+}
+
+/// JSON-RPC client for the Jito block engine
+#[derive(Debug)]
+pub struct JitoClient {
+    http: reqwest::Client,
+    config: JitoConfig,
+    next_tip_account: AtomicUsize,
+}
+
+impl JitoClient {
+    /// Create a client for the configured block engine
+    pub fn new(config: JitoConfig) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()?;
+        Ok(Self {
+            http,
+            config,
+            next_tip_account: AtomicUsize::new(0),
+        })
+    }
+
+    /// Create a client with the configured block engine and tips
+    pub fn from_config(config: &Config) -> Result<Self> {
+        Self::new(config.jito.clone())
+    }
+
+    /// Client settings
+    pub fn config(&self) -> &JitoConfig {
+        &self.config
+    }
+
+    /// Tip account for the next bundle
+    pub fn tip_account(&self) -> Pubkey {
+        let index = self.next_tip_account.fetch_add(1, Ordering::Relaxed);
+        TIP_ACCOUNTS[index % TIP_ACCOUNTS.len()]
+    }
+
+    /// Tip for a bundle expected to make `expected_profit_lamports`, or
+    /// `None` if even the minimum tip would take all of it
+    pub fn tip_lamports(&self, expected_profit_lamports: u64) -> Option<u64> {
+        if expected_profit_lamports <= self.config.min_tip_lamports {
+            return None;
+        }
+        let share = self.config.tip_profit_fraction.clamp(0.0, 1.0);
+        let tip = (expected_profit_lamports as f64 * share) as u64;
+        Some(
+            tip.clamp(
+                self.config.min_tip_lamports,
+                self.config
+                    .max_tip_lamports
+                    .max(self.config.min_tip_lamports),
+            ),
+        )
+    }
+
+    /// Transaction paying `lamports` from `payer` to `tip_account`
+    pub fn tip_transaction(
+        payer: &Keypair,
+        tip_account: &Pubkey,
+        lamports: u64,
+        recent_blockhash: Hash,
+    ) -> Result<VersionedTransaction> {
+        let message = v0::Message::try_compile(
+            &payer.pubkey(),
+            &[transfer(&payer.pubkey(), tip_account, lamports)],
+            &[],
+            recent_blockhash,
+        )
+        .map_err(|e| ArbitrageError::transaction(format!("Failed to compile tip: {}", e)))?;
+        VersionedTransaction::try_new(VersionedMessage::V0(message), &[payer])
+            .map_err(|e| ArbitrageError::transaction(format!("Failed to sign tip: {}", e)))
+    }
+
+    async fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+        let response = self
+            .http
+            .post(format!(
+                "{}/api/v1/bundles",
+                self.config.block_engine_url.trim_end_matches('/')
+            ))
+            .json(&body)
+            .send()
+            .await?;
+        let status = response.status();
+        let text = response.text().await?;
+        let response: RpcResponse<T> = serde_json::from_str(&text).map_err(|_| {
+            ArbitrageError::transaction(format!(
+                "Jito {} request failed with {}: {}",
+                method, status, text
+            ))
+        })?;
+
+        match (response.result, response.error) {
+            (_, Some(error)) => Err(ArbitrageError::transaction(format!(
+                "Jito {} failed ({}): {}",
+                method, error.code, error.message
+            ))),
+            (Some(result), None) => Ok(result),
+            (None, None) => Err(ArbitrageError::transaction(format!(
+                "Jito {} returned no result",
+                method
+            ))),
+        }
+    }
+
+    /// Send `transactions` as one bundle, returning its id
+    pub async fn send_bundle(&self, transactions: &[VersionedTransaction]) -> Result<String> {
+        let encoded = transactions
+            .iter()
+            .map(|transaction| {
+                bincode::serialize(transaction)
+                    .map(|bytes| STANDARD.encode(bytes))
+                    .map_err(|e| {
+                        ArbitrageError::transaction(format!(
+                            "Failed to serialize transaction: {}",
+                            e
+                        ))
+                    })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        self.call("sendBundle", json!([encoded, { "encoding": "base64" }]))
+            .await
+    }
+
+    /// Statuses of `bundle_ids`; `None` for bundles that haven't landed
+    pub async fn bundle_statuses(
+        &self,
+        bundle_ids: &[String],
+    ) -> Result<Vec<Option<BundleStatus>>> {
+        let result: StatusesResult = self.call("getBundleStatuses", json!([bundle_ids])).await?;
+        Ok(result.value)
+    }
+
+    /// Bundle `transaction` with a tip of `tip_lamports` and send it. The
+    /// tip is picked with [`Self::tip_lamports`] before `transaction` is
+    /// built, so its profit check can cover it.
+    pub async fn submit(
+        &self,
+        transaction: VersionedTransaction,
+        payer: &Keypair,
+        tip_lamports: u64,
+        recent_blockhash: Hash,
+    ) -> Result<BundleSubmission> {
+        let tip_account = self.tip_account();
+        let tip = Self::tip_transaction(payer, &tip_account, tip_lamports, recent_blockhash)?;
+        let signature = transaction.signatures[0];
+
+        let bundle_id = self.send_bundle(&[transaction, tip]).await?;
+        info!(
+            "Sent bundle {} for {} tipping {} lamports",
+            bundle_id, signature, tip_lamports
+        );
+        Ok(BundleSubmission {
+            bundle_id,
+            signature,
+            tip_account,
+            tip_lamports,
+        })
+    }
+
+    /// Poll the status of `bundle_id` until it lands or the bundle timeout
+    /// passes, returning `None` if it never landed
+    pub async fn wait_for_bundle(&self, bundle_id: &str) -> Result<Option<BundleStatus>> {
+        let deadline = Instant::now() + Duration::from_millis(self.config.bundle_timeout_ms);
+        let poll_interval = Duration::from_millis(self.config.status_poll_interval_ms.max(1));
+        let ids = [bundle_id.to_string()];
+
+        loop {
+            if let Some(Some(status)) = self.bundle_statuses(&ids).await?.pop() {
+                debug!(
+                    "Bundle {} landed in slot {} ({:?})",
+                    bundle_id, status.slot, status.confirmation_status
+                );
+                return Ok(Some(status));
+            }
+            if Instant::now() + poll_interval > deadline {
+                debug!("Bundle {} did not land in time", bundle_id);
+                return Ok(None);
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client_for(mock: &JitoMockHandle) -> JitoClient {
+        JitoClient::new(JitoConfig {
+            block_engine_url: mock.url(),
+            status_poll_interval_ms: 10,
+            bundle_timeout_ms: 100,
+            ..JitoConfig::default()
+        })
+        .unwrap()
+    }
+
+    /// Stand-in for an arbitrage transaction
+    fn arbitrage_transaction(payer: &Keypair) -> VersionedTransaction {
+        let message = v0::Message::try_compile(
+            &payer.pubkey(),
+            &[transfer(&payer.pubkey(), &Pubkey::new_unique(), 1)],
+            &[],
+            Hash::new_unique(),
+        )
+        .unwrap();
+        VersionedTransaction::try_new(VersionedMessage::V0(message), &[payer]).unwrap()
+    }
+
+    #[test]
+    fn test_tips() {
+        let client = JitoClient::new(JitoConfig::default()).unwrap();
+        // The minimum tip would take the whole profit
+        assert_eq!(client.tip_lamports(0), None);
+        assert_eq!(client.tip_lamports(10_000), None);
+        assert_eq!(client.tip_lamports(15_000), Some(10_000));
+        assert_eq!(client.tip_lamports(1_000_000), Some(500_000));
+        assert_eq!(client.tip_lamports(1_000_000_000), Some(10_000_000));
+
+        let accounts: Vec<Pubkey> = (0..TIP_ACCOUNTS.len() + 1)
+            .map(|_| client.tip_account())
+            .collect();
+        assert_eq!(accounts[..TIP_ACCOUNTS.len()], TIP_ACCOUNTS);
+        assert_eq!(accounts[TIP_ACCOUNTS.len()], TIP_ACCOUNTS[0]);
+    }
+
+    #[tokio::test]
+    async fn test_bundle_lands_against_mock() {
+        let mock = JitoMockServer::new().with_slot(300).spawn().await.unwrap();
+        let client = client_for(&mock);
+        let payer = Keypair::new();
+        let transaction = arbitrage_transaction(&payer);
+
+        let tip = client.tip_lamports(1_000_000).unwrap();
+        let submission = client
+            .submit(transaction.clone(), &payer, tip, Hash::new_unique())
+            .await
+            .unwrap();
+        assert_eq!(submission.signature, transaction.signatures[0]);
+        assert_eq!(submission.tip_lamports, 500_000);
+
+        // The arbitrage goes first, the tip last
+        let bundles = mock.bundles();
+        assert_eq!(bundles.len(), 1);
+        assert_eq!(bundles[0][0], transaction);
+        let tip = &bundles[0][1];
+        assert!(tip
+            .message
+            .static_account_keys()
+            .contains(&submission.tip_account));
+
+        let status = client
+            .wait_for_bundle(&submission.bundle_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(status.succeeded());
+        assert_eq!(status.slot, 300);
+        assert_eq!(status.confirmation_status, BundleConfirmation::Confirmed);
+        assert_eq!(
+            status.transactions[0],
+            transaction.signatures[0].to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rejected_and_outbid_bundles() {
+        let mock = JitoMockServer::new()
+            .with_landing_tip(1_000_000)
+            .spawn()
+            .await
+            .unwrap();
+        let client = client_for(&mock);
+        let payer = Keypair::new();
+
+        // Bundles without a tip are refused outright
+        let err = client
+            .send_bundle(&[arbitrage_transaction(&payer)])
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("tip account"));
+
+        // Bundles tipping too little are accepted but never land
+        let submission = client
+            .submit(
+                arbitrage_transaction(&payer),
+                &payer,
+                client.tip_lamports(100_000).unwrap(),
+                Hash::new_unique(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(mock.bundles().len(), 1);
+        assert!(client
+            .wait_for_bundle(&submission.bundle_id)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,276 @@
+//! Local stand-in for the Jito block engine
+//!
+//! Implements `sendBundle` and `getBundleStatuses` over JSON-RPC so bundle
+//! submission can be exercised offline. Bundles are decoded and their
+//! signatures verified like the block engine does, and bundles that don't
+//! write-lock a tip account are rejected. Accepted bundles tipping at least
+//! the landing tip land immediately, one slot apart; cheaper ones are
+//! accepted but never land, like bundles that lose the auction.
+
+use super::{TIP_ACCOUNTS, TRANSFER_TAG};
+use crate::error::{ArbitrageError, Result};
+use crate::execution::SYSTEM_PROGRAM_ID;
+use axum::extract::State;
+use axum::routing::post;
+use axum::{Json, Router};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use parking_lot::Mutex;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use solana_sdk::transaction::VersionedTransaction;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+/// Most transactions the block engine accepts in one bundle
+const MAX_BUNDLE_TRANSACTIONS: usize = 5;
+
+/// JSON-RPC invalid params error code
+const INVALID_PARAMS: i64 = -32602;
+/// JSON-RPC method not found error code
+const METHOD_NOT_FOUND: i64 = -32601;
+
+#[derive(Debug)]
+struct LandedBundle {
+    signatures: Vec<String>,
+    slot: u64,
+}
+
+#[derive(Debug, Default)]
+struct BlockEngine {
+    landing_tip: u64,
+    slot: u64,
+    received: Vec<Vec<VersionedTransaction>>,
+    landed: HashMap<String, LandedBundle>,
+}
+
+/// Builder for the mock block engine
+#[derive(Debug, Default)]
+pub struct JitoMockServer {
+    landing_tip: u64,
+    slot: u64,
+}
+
+impl JitoMockServer {
+    /// Create a block engine landing every tipping bundle from slot zero
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only land bundles tipping at least `lamports`
+    pub fn with_landing_tip(mut self, lamports: u64) -> Self {
+        self.landing_tip = lamports;
+        self
+    }
+
+    /// Land the next bundle at `slot`
+    pub fn with_slot(mut self, slot: u64) -> Self {
+        self.slot = slot;
+        self
+    }
+
+    /// Serve the block engine on an ephemeral local port
+    pub async fn spawn(self) -> Result<JitoMockHandle> {
+        let engine = Arc::new(Mutex::new(BlockEngine {
+            landing_tip: self.landing_tip,
+            slot: self.slot,
+            ..BlockEngine::default()
+        }));
+        let app = Router::new()
+            .route("/api/v1/bundles", post(rpc))
+            .with_state(engine.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .map_err(|e| ArbitrageError::internal(format!("Failed to bind mock server: {}", e)))?;
+        let addr = listener
+            .local_addr()
+            .map_err(|e| ArbitrageError::internal(format!("Failed to bind mock server: {}", e)))?;
+
+        let task = tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                debug!("Jito mock server stopped: {}", e);
+            }
+        });
+        Ok(JitoMockHandle { addr, task, engine })
+    }
+}
+
+/// Running mock block engine; stops when dropped
+#[derive(Debug)]
+pub struct JitoMockHandle {
+    addr: SocketAddr,
+    task: JoinHandle<()>,
+    engine: Arc<Mutex<BlockEngine>>,
+}
+
+impl JitoMockHandle {
+    /// Address the server listens on
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Base URL to use as `block_engine_url`
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Every bundle accepted so far, in order
+    pub fn bundles(&self) -> Vec<Vec<VersionedTransaction>> {
+        self.engine.lock().received.clone()
+    }
+}
+
+impl Drop for JitoMockHandle {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+fn rpc_error(id: &Value, code: i64, message: impl Into<String>) -> Json<Value> {
+    Json(json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message.into() },
+    }))
+}
+
+fn rpc_result(id: &Value, result: Value) -> Json<Value> {
+    Json(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+}
+
+fn decode_transaction(encoded: &Value) -> std::result::Result<VersionedTransaction, String> {
+    let bytes = STANDARD
+        .decode(encoded.as_str().ok_or("transactions must be strings")?)
+        .map_err(|e| format!("invalid base64: {}", e))?;
+    let transaction: VersionedTransaction =
+        bincode::deserialize(&bytes).map_err(|e| format!("invalid transaction: {}", e))?;
+    if transaction.signatures.is_empty() || !transaction.verify_with_results().iter().all(|ok| *ok)
+    {
+        return Err("transaction signature verification failed".to_string());
+    }
+    Ok(transaction)
+}
+
+/// Lamports `transaction` transfers to tip accounts, if it write-locks any
+fn tip_paid(transaction: &VersionedTransaction) -> Option<u64> {
+    let keys = transaction.message.static_account_keys();
+    let mut tip = None;
+    for ix in transaction.message.instructions() {
+        if keys.get(ix.program_id_index as usize) != Some(&SYSTEM_PROGRAM_ID)
+            || ix.data.len() != 12
+            || ix.data[..4] != TRANSFER_TAG.to_le_bytes()
+        {
+            continue;
+        }
+        let Some(to) = ix.accounts.get(1).and_then(|i| keys.get(*i as usize)) else {
+            continue;
+        };
+        if TIP_ACCOUNTS.contains(to) {
+            let lamports = u64::from_le_bytes(ix.data[4..12].try_into().unwrap_or_default());
+            tip = Some(tip.unwrap_or(0) + lamports);
+        }
+    }
+    tip
+}
+
+fn send_bundle(engine: &Mutex<BlockEngine>, id: &Value, params: &Value) -> Json<Value> {
+    let Some(encoded) = params.get(0).and_then(Value::as_array) else {
+        return rpc_error(id, INVALID_PARAMS, "expected an array of transactions");
+    };
+    if encoded.is_empty() || encoded.len() > MAX_BUNDLE_TRANSACTIONS {
+        return rpc_error(
+            id,
+            INVALID_PARAMS,
+            format!(
+                "bundles must contain 1 to {} transactions",
+                MAX_BUNDLE_TRANSACTIONS
+            ),
+        );
+    }
+    let transactions = match encoded
+        .iter()
+        .map(decode_transaction)
+        .collect::<std::result::Result<Vec<_>, _>>()
+    {
+        Ok(transactions) => transactions,
+        Err(message) => return rpc_error(id, INVALID_PARAMS, message),
+    };
+    let Some(tip) = transactions
+        .iter()
+        .filter_map(tip_paid)
+        .reduce(|a, b| a + b)
+    else {
+        return rpc_error(
+            id,
+            INVALID_PARAMS,
+            "bundle must write lock at least one tip account",
+        );
+    };
+
+    let mut hasher = Sha256::new();
+    let signatures: Vec<String> = transactions
+        .iter()
+        .map(|transaction| {
+            hasher.update(transaction.signatures[0].as_ref());
+            transaction.signatures[0].to_string()
+        })
+        .collect();
+    let bundle_id = hex::encode(hasher.finalize());
+
+    let mut engine = engine.lock();
+    engine.received.push(transactions);
+    if tip >= engine.landing_tip {
+        let slot = engine.slot;
+        engine.slot += 1;
+        engine
+            .landed
+            .insert(bundle_id.clone(), LandedBundle { signatures, slot });
+    }
+    rpc_result(id, json!(bundle_id))
+}
+
+fn bundle_statuses(engine: &Mutex<BlockEngine>, id: &Value, params: &Value) -> Json<Value> {
+    let Some(ids) = params.get(0).and_then(Value::as_array) else {
+        return rpc_error(id, INVALID_PARAMS, "expected an array of bundle ids");
+    };
+    let engine = engine.lock();
+    let value: Vec<Value> = ids
+        .iter()
+        .map(|bundle_id| {
+            let bundle_id = bundle_id.as_str()?;
+            let landed = engine.landed.get(bundle_id)?;
+            Some(json!({
+                "bundle_id": bundle_id,
+                "transactions": landed.signatures,
+                "slot": landed.slot,
+                "confirmation_status": "confirmed",
+                "err": { "Ok": null },
+            }))
+        })
+        .map(|status| status.unwrap_or(Value::Null))
+        .collect();
+    rpc_result(
+        id,
+        json!({ "context": { "slot": engine.slot }, "value": value }),
+    )
+}
+
+async fn rpc(
+    State(engine): State<Arc<Mutex<BlockEngine>>>,
+    Json(body): Json<Value>,
+) -> Json<Value> {
+    let id = body.get("id").cloned().unwrap_or(Value::Null);
+    let params = body.get("params").cloned().unwrap_or(Value::Null);
+    match body.get("method").and_then(Value::as_str) {
+        Some("sendBundle") => send_bundle(&engine, &id, &params),
+        Some("getBundleStatuses") => bundle_statuses(&engine, &id, &params),
+        method => rpc_error(
+            &id,
+            METHOD_NOT_FOUND,
+            format!("Method not found: {}", method.unwrap_or_default()),
+        ),
+    }
+}
@@ -0,0 +1,581 @@
+//! Address lookup table management
+//!
+//! Three- and four-leg routes reference more accounts than fit in a legacy
+//! transaction. [`LookupTableManager`] keeps the tables owned by our authority:
+//! it builds the instructions that create, extend, deactivate and close them,
+//! mirrors their contents locally so it knows which pool and vault accounts
+//! are already covered, and picks the tables worth referencing when a v0
+//! message is compiled.
+//!
+//! Addresses appended to a table only become usable in the slot after the
+//! extension, and a deactivated table can only be closed once it has left the
+//! slot hashes sysvar; both rules are tracked here so callers never reference
+//! an address the runtime would reject.
+
+use crate::error::{ArbitrageError, Result};
+use parking_lot::RwLock;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::message::AddressLookupTableAccount;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, HashSet};
+use tracing::{debug, info};
+
+use super::SYSTEM_PROGRAM_ID;
+
+/// Address lookup table program id
+pub const ADDRESS_LOOKUP_TABLE_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("AddressLookupTab1e1111111111111111111111111");
+
+/// Most addresses a table can hold
+pub const MAX_TABLE_ADDRESSES: usize = 256;
+
+/// Most addresses appended by one extend instruction, keeping the transaction
+/// carrying it under the packet size limit
+pub const MAX_EXTEND_ADDRESSES: usize = 30;
+
+/// Slots a deactivated table stays in the slot hashes sysvar before it can be
+/// closed
+pub const DEACTIVATION_COOLDOWN_SLOTS: u64 = 513;
+
+/// Size of the table account header preceding the addresses
+const LOOKUP_TABLE_META_SIZE: usize = 56;
+
+/// `ProgramState::LookupTable`
+const LOOKUP_TABLE_STATE_TAG: u32 = 1;
+
+const CREATE_TAG: u32 = 0;
+const EXTEND_TAG: u32 = 2;
+const DEACTIVATE_TAG: u32 = 3;
+const CLOSE_TAG: u32 = 4;
+
+/// Local mirror of a lookup table account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LookupTable {
+    /// Table account address
+    pub address: Pubkey,
+    /// Addresses stored in the table, in index order
+    pub addresses: Vec<Pubkey>,
+    /// Slot the table was last extended in
+    pub last_extended_slot: u64,
+    /// Number of addresses the table held before its last extension
+    pub last_extended_slot_start_index: usize,
+    /// Slot the table was deactivated in, if it was
+    pub deactivation_slot: Option<u64>,
+}
+
+impl LookupTable {
+    /// Decode a lookup table account
+    pub fn decode(address: Pubkey, data: &[u8]) -> Result<Self> {
+        let invalid =
+            || ArbitrageError::transaction(format!("Account {} is not a lookup table", address));
+        if data.len() < LOOKUP_TABLE_META_SIZE
+            || !(data.len() - LOOKUP_TABLE_META_SIZE).is_multiple_of(32)
+            || u32::from_le_bytes(data[0..4].try_into().map_err(|_| invalid())?)
+                != LOOKUP_TABLE_STATE_TAG
+        {
+            return Err(invalid());
+        }
+
+        let read_u64 = |offset: usize| {
+            data[offset..offset + 8]
+                .try_into()
+                .map(u64::from_le_bytes)
+                .map_err(|_| invalid())
+        };
+        let deactivation_slot = read_u64(4)?;
+        Ok(Self {
+            address,
+            addresses: data[LOOKUP_TABLE_META_SIZE..]
+                .chunks_exact(32)
+                .map(|chunk| Pubkey::new_from_array(chunk.try_into().unwrap_or_default()))
+                .collect(),
+            last_extended_slot: read_u64(12)?,
+            last_extended_slot_start_index: data[20] as usize,
+            deactivation_slot: (deactivation_slot != u64::MAX).then_some(deactivation_slot),
+        })
+    }
+
+    /// Check whether the table can still be referenced and extended
+    pub fn is_active(&self) -> bool {
+        self.deactivation_slot.is_none()
+    }
+
+    /// Number of addresses that can be looked up at `slot`; addresses from
+    /// the last extension are only usable in later slots
+    pub fn usable_len(&self, slot: u64) -> usize {
+        if slot > self.last_extended_slot {
+            self.addresses.len()
+        } else {
+            self.last_extended_slot_start_index
+                .min(self.addresses.len())
+        }
+    }
+
+    /// Room left for new addresses
+    pub fn remaining_capacity(&self) -> usize {
+        MAX_TABLE_ADDRESSES.saturating_sub(self.addresses.len())
+    }
+
+    /// The table as passed to message compilation, restricted to addresses
+    /// usable at `slot`
+    pub fn account(&self, slot: u64) -> AddressLookupTableAccount {
+        AddressLookupTableAccount {
+            key: self.address,
+            addresses: self.addresses[..self.usable_len(slot)].to_vec(),
+        }
+    }
+}
+
+/// Maintains the lookup tables owned by one authority
+#[derive(Debug)]
+pub struct LookupTableManager {
+    authority: Pubkey,
+    tables: RwLock<HashMap<Pubkey, LookupTable>>,
+}
+
+impl LookupTableManager {
+    /// Create a manager for tables owned by `authority`
+    pub fn new(authority: Pubkey) -> Self {
+        Self {
+            authority,
+            tables: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Authority of the managed tables
+    pub fn authority(&self) -> &Pubkey {
+        &self.authority
+    }
+
+    /// Snapshot of a managed table
+    pub fn get(&self, table: &Pubkey) -> Option<LookupTable> {
+        self.tables.read().get(table).cloned()
+    }
+
+    /// Snapshot of every managed table
+    pub fn tables(&self) -> Vec<LookupTable> {
+        self.tables.read().values().cloned().collect()
+    }
+
+    /// Number of managed tables
+    pub fn len(&self) -> usize {
+        self.tables.read().len()
+    }
+
+    /// Check whether no table is managed
+    pub fn is_empty(&self) -> bool {
+        self.tables.read().is_empty()
+    }
+
+    /// Check whether an active table holds `address`
+    pub fn contains(&self, address: &Pubkey) -> bool {
+        self.tables
+            .read()
+            .values()
+            .any(|table| table.is_active() && table.addresses.contains(address))
+    }
+
+    /// Track a table decoded from chain, replacing any local copy
+    pub fn insert(&self, table: LookupTable) {
+        self.tables.write().insert(table.address, table);
+    }
+
+    /// Fetch and track the given tables, returning the number found
+    pub async fn load(&self, rpc: &RpcClient, tables: &[Pubkey]) -> Result<usize> {
+        let accounts = rpc.get_multiple_accounts(tables).await?;
+        let mut loaded = 0;
+        for (address, account) in tables.iter().zip(accounts) {
+            let Some(account) = account else {
+                continue;
+            };
+            self.insert(LookupTable::decode(*address, &account.data)?);
+            loaded += 1;
+        }
+        info!("Loaded {} address lookup tables", loaded);
+        Ok(loaded)
+    }
+
+    /// Instruction creating a new table derived from `recent_slot`, which
+    /// must be a recent finalized slot. The table is tracked as empty right
+    /// away.
+    pub fn create(&self, payer: &Pubkey, recent_slot: u64) -> (Pubkey, Instruction) {
+        let (table, bump) = Pubkey::find_program_address(
+            &[self.authority.as_ref(), &recent_slot.to_le_bytes()],
+            &ADDRESS_LOOKUP_TABLE_PROGRAM_ID,
+        );
+        let mut data = CREATE_TAG.to_le_bytes().to_vec();
+        data.extend_from_slice(&recent_slot.to_le_bytes());
+        data.push(bump);
+
+        self.insert(LookupTable {
+            address: table,
+            addresses: Vec::new(),
+            last_extended_slot: 0,
+            last_extended_slot_start_index: 0,
+            deactivation_slot: None,
+        });
+        debug!("Creating address lookup table {}", table);
+        (
+            table,
+            Instruction::new_with_bytes(
+                ADDRESS_LOOKUP_TABLE_PROGRAM_ID,
+                &data,
+                vec![
+                    AccountMeta::new(table, false),
+                    AccountMeta::new_readonly(self.authority, true),
+                    AccountMeta::new(*payer, true),
+                    AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+                ],
+            ),
+        )
+    }
+
+    /// Instructions appending the `addresses` not yet in `table`, at most
+    /// [`MAX_EXTEND_ADDRESSES`] per instruction. The local copy is updated as
+    /// if they land in `slot`.
+    pub fn extend(
+        &self,
+        table: &Pubkey,
+        payer: &Pubkey,
+        addresses: &[Pubkey],
+        slot: u64,
+    ) -> Result<Vec<Instruction>> {
+        let mut tables = self.tables.write();
+        let state = active_table(&mut tables, table)?;
+        let mut seen: HashSet<Pubkey> = state.addresses.iter().copied().collect();
+        let new: Vec<Pubkey> = addresses
+            .iter()
+            .filter(|address| seen.insert(**address))
+            .copied()
+            .collect();
+        if new.len() > state.remaining_capacity() {
+            return Err(ArbitrageError::transaction(format!(
+                "Lookup table {} has room for {} more addresses, {} requested",
+                table,
+                state.remaining_capacity(),
+                new.len()
+            )));
+        }
+        if new.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        if slot > state.last_extended_slot {
+            state.last_extended_slot = slot;
+            state.last_extended_slot_start_index = state.addresses.len();
+        }
+        state.addresses.extend(&new);
+        debug!("Extending address lookup table {} by {}", table, new.len());
+
+        Ok(new
+            .chunks(MAX_EXTEND_ADDRESSES)
+            .map(|chunk| {
+                let mut data = EXTEND_TAG.to_le_bytes().to_vec();
+                data.extend_from_slice(&(chunk.len() as u64).to_le_bytes());
+                for address in chunk {
+                    data.extend_from_slice(address.as_ref());
+                }
+                Instruction::new_with_bytes(
+                    ADDRESS_LOOKUP_TABLE_PROGRAM_ID,
+                    &data,
+                    vec![
+                        AccountMeta::new(*table, false),
+                        AccountMeta::new_readonly(self.authority, true),
+                        AccountMeta::new(*payer, true),
+                        AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+                    ],
+                )
+            })
+            .collect())
+    }
+
+    /// Instructions storing the `addresses` not already in an active table,
+    /// filling tables with room first. When they run out, one new table is
+    /// derived from `recent_slot`; anything that still doesn't fit is left
+    /// for a later call with another slot. New addresses are recorded as
+    /// added in `slot`.
+    pub fn ensure(
+        &self,
+        payer: &Pubkey,
+        addresses: &[Pubkey],
+        recent_slot: u64,
+        slot: u64,
+    ) -> Result<Vec<Instruction>> {
+        let mut missing: Vec<Pubkey> = Vec::new();
+        for address in addresses {
+            if !self.contains(address) && !missing.contains(address) {
+                missing.push(*address);
+            }
+        }
+
+        let mut with_room: Vec<(Pubkey, usize)> = self
+            .tables
+            .read()
+            .values()
+            .filter(|table| table.is_active() && table.remaining_capacity() > 0)
+            .map(|table| (table.address, table.remaining_capacity()))
+            .collect();
+        with_room.sort_by_key(|(_, room)| *room);
+
+        let mut instructions = Vec::new();
+        let mut created = false;
+        while !missing.is_empty() {
+            let (table, room) = match with_room.pop() {
+                Some(table) => table,
+                None if !created => {
+                    let (table, create) = self.create(payer, recent_slot);
+                    instructions.push(create);
+                    created = true;
+                    (table, MAX_TABLE_ADDRESSES)
+                }
+                None => {
+                    debug!("{} addresses left for another lookup table", missing.len());
+                    break;
+                }
+            };
+            let batch: Vec<Pubkey> = missing.drain(..room.min(missing.len())).collect();
+            instructions.extend(self.extend(&table, payer, &batch, slot)?);
+        }
+        Ok(instructions)
+    }
+
+    /// Instruction deactivating `table` in `slot`. It can no longer be
+    /// referenced or extended and can be closed once the cooldown passes.
+    pub fn deactivate(&self, table: &Pubkey, slot: u64) -> Result<Instruction> {
+        let mut tables = self.tables.write();
+        active_table(&mut tables, table)?.deactivation_slot = Some(slot);
+        info!("Deactivating address lookup table {}", table);
+        Ok(Instruction::new_with_bytes(
+            ADDRESS_LOOKUP_TABLE_PROGRAM_ID,
+            &DEACTIVATE_TAG.to_le_bytes(),
+            vec![
+                AccountMeta::new(*table, false),
+                AccountMeta::new_readonly(self.authority, true),
+            ],
+        ))
+    }
+
+    /// Instruction closing a deactivated `table` and sending its rent to
+    /// `recipient`. Fails until the deactivation cooldown has passed at
+    /// `slot`.
+    pub fn close(&self, table: &Pubkey, recipient: &Pubkey, slot: u64) -> Result<Instruction> {
+        let mut tables = self.tables.write();
+        let state = tables.get(table).ok_or_else(|| unknown_table(table))?;
+        match state.deactivation_slot {
+            Some(deactivated) if slot > deactivated + DEACTIVATION_COOLDOWN_SLOTS => {}
+            Some(deactivated) => {
+                return Err(ArbitrageError::transaction(format!(
+                    "Lookup table {} is cooling down until slot {}",
+                    table,
+                    deactivated + DEACTIVATION_COOLDOWN_SLOTS + 1
+                )))
+            }
+            None => {
+                return Err(ArbitrageError::transaction(format!(
+                    "Lookup table {} must be deactivated before closing",
+                    table
+                )))
+            }
+        }
+        tables.remove(table);
+        info!("Closing address lookup table {}", table);
+        Ok(Instruction::new_with_bytes(
+            ADDRESS_LOOKUP_TABLE_PROGRAM_ID,
+            &CLOSE_TAG.to_le_bytes(),
+            vec![
+                AccountMeta::new(*table, false),
+                AccountMeta::new_readonly(self.authority, true),
+                AccountMeta::new(*recipient, false),
+            ],
+        ))
+    }
+
+    /// Tables to compile `instructions` against at `slot`
+    ///
+    /// Only accounts that are neither signers nor invoked programs can be
+    /// looked up. Tables are picked greedily by how many still-uncovered
+    /// accounts they hold, and only while they save space: a reference costs
+    /// 34 bytes, and each address looked up instead of inlined saves 31.
+    pub fn select(
+        &self,
+        instructions: &[Instruction],
+        slot: u64,
+    ) -> Vec<AddressLookupTableAccount> {
+        let programs: HashSet<Pubkey> = instructions.iter().map(|ix| ix.program_id).collect();
+        let mut uncovered: HashSet<Pubkey> = instructions
+            .iter()
+            .flat_map(|ix| &ix.accounts)
+            .filter(|meta| !meta.is_signer && !programs.contains(&meta.pubkey))
+            .map(|meta| meta.pubkey)
+            .collect();
+
+        let candidates: Vec<AddressLookupTableAccount> = self
+            .tables
+            .read()
+            .values()
+            .filter(|table| table.is_active())
+            .map(|table| table.account(slot))
+            .collect();
+
+        let mut selected = Vec::new();
+        let mut remaining: Vec<&AddressLookupTableAccount> = candidates.iter().collect();
+        loop {
+            let best = remaining
+                .iter()
+                .enumerate()
+                .map(|(i, table)| {
+                    let covered = table
+                        .addresses
+                        .iter()
+                        .filter(|address| uncovered.contains(address))
+                        .collect::<HashSet<_>>()
+                        .len();
+                    (i, covered)
+                })
+                .max_by_key(|(_, covered)| *covered);
+            let Some((i, covered)) = best else {
+                break;
+            };
+            if covered * 31 <= 34 {
+                break;
+            }
+            let table = remaining.swap_remove(i);
+            for address in &table.addresses {
+                uncovered.remove(address);
+            }
+            selected.push(table.clone());
+        }
+        selected
+    }
+}
+
+fn active_table<'a>(
+    tables: &'a mut HashMap<Pubkey, LookupTable>,
+    table: &Pubkey,
+) -> Result<&'a mut LookupTable> {
+    let state = tables.get_mut(table).ok_or_else(|| unknown_table(table))?;
+    if !state.is_active() {
+        return Err(ArbitrageError::transaction(format!(
+            "Lookup table {} is deactivated",
+            table
+        )));
+    }
+    Ok(state)
+}
+
+fn unknown_table(table: &Pubkey) -> ArbitrageError {
+    ArbitrageError::transaction(format!("Lookup table {} is not managed", table))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(table: &LookupTable) -> Vec<u8> {
+        let mut data = vec![0u8; LOOKUP_TABLE_META_SIZE];
+        data[0..4].copy_from_slice(&LOOKUP_TABLE_STATE_TAG.to_le_bytes());
+        data[4..12].copy_from_slice(&table.deactivation_slot.unwrap_or(u64::MAX).to_le_bytes());
+        data[12..20].copy_from_slice(&table.last_extended_slot.to_le_bytes());
+        data[20] = table.last_extended_slot_start_index as u8;
+        for address in &table.addresses {
+            data.extend_from_slice(address.as_ref());
+        }
+        data
+    }
+
+    fn swap(accounts: &[Pubkey], signer: &Pubkey) -> Instruction {
+        let mut metas: Vec<AccountMeta> = accounts
+            .iter()
+            .map(|account| AccountMeta::new(*account, false))
+            .collect();
+        metas.push(AccountMeta::new_readonly(*signer, true));
+        Instruction::new_with_bytes(Pubkey::new_unique(), &[], metas)
+    }
+
+    #[test]
+    fn test_decode_round_trip() {
+        let table = LookupTable {
+            address: Pubkey::new_unique(),
+            addresses: (0..3).map(|_| Pubkey::new_unique()).collect(),
+            last_extended_slot: 42,
+            last_extended_slot_start_index: 1,
+            deactivation_slot: None,
+        };
+        let decoded = LookupTable::decode(table.address, &encode(&table)).unwrap();
+        assert_eq!(decoded, table);
+        assert_eq!(decoded.usable_len(42), 1);
+        assert_eq!(decoded.usable_len(43), 3);
+        assert!(LookupTable::decode(table.address, &[0; 10]).is_err());
+    }
+
+    #[test]
+    fn test_table_lifecycle() {
+        let authority = Pubkey::new_unique();
+        let manager = LookupTableManager::new(authority);
+        let (table, create) = manager.create(&authority, 100);
+        assert_eq!(create.program_id, ADDRESS_LOOKUP_TABLE_PROGRAM_ID);
+        assert_eq!(create.accounts[0].pubkey, table);
+
+        let addresses: Vec<Pubkey> = (0..45).map(|_| Pubkey::new_unique()).collect();
+        let extend = manager.extend(&table, &authority, &addresses, 101).unwrap();
+        assert_eq!(extend.len(), 2);
+        assert_eq!(extend[0].data.len(), 4 + 8 + 30 * 32);
+        assert!(manager.contains(&addresses[44]));
+        // Re-adding known addresses is a no-op
+        assert!(manager
+            .extend(&table, &authority, &addresses[..5], 102)
+            .unwrap()
+            .is_empty());
+
+        assert!(manager.close(&table, &authority, 200).is_err());
+        manager.deactivate(&table, 200).unwrap();
+        assert!(!manager.contains(&addresses[0]));
+        assert!(manager
+            .extend(&table, &authority, &[authority], 201)
+            .is_err());
+        assert!(manager.close(&table, &authority, 300).is_err());
+        let close = manager
+            .close(&table, &authority, 200 + DEACTIVATION_COOLDOWN_SLOTS + 1)
+            .unwrap();
+        assert_eq!(close.data, CLOSE_TAG.to_le_bytes());
+        assert!(manager.is_empty());
+    }
+
+    #[test]
+    fn test_ensure_and_select() {
+        let authority = Pubkey::new_unique();
+        let manager = LookupTableManager::new(authority);
+        let accounts: Vec<Pubkey> = (0..300).map(|_| Pubkey::new_unique()).collect();
+
+        // Fills one new table per call and spills into a second one
+        let instructions = manager.ensure(&authority, &accounts, 100, 101).unwrap();
+        assert_eq!(instructions[0].data[..4], CREATE_TAG.to_le_bytes());
+        assert_eq!(
+            instructions.len(),
+            1 + 256usize.div_ceil(MAX_EXTEND_ADDRESSES)
+        );
+        assert!(!manager.contains(&accounts[299]));
+        manager.ensure(&authority, &accounts, 99, 101).unwrap();
+        assert_eq!(manager.len(), 2);
+        assert!(accounts.iter().all(|account| manager.contains(account)));
+        assert!(manager
+            .ensure(&authority, &accounts, 98, 101)
+            .unwrap()
+            .is_empty());
+
+        // Fresh addresses are unusable in the slot they were added
+        let route = [swap(&accounts[..8], &authority)];
+        assert!(manager.select(&route, 101).is_empty());
+        let selected = manager.select(&route, 102);
+        assert_eq!(selected.len(), 1);
+        assert!(accounts[..8]
+            .iter()
+            .all(|account| selected[0].addresses.contains(account)));
+
+        // A table covering a single account isn't worth referencing
+        let spill = accounts[299];
+        let route = [swap(&[spill, accounts[0], accounts[1]], &authority)];
+        assert_eq!(manager.select(&route, 102).len(), 1);
+    }
+}
@@ -0,0 +1,299 @@
+//! Dynamic priority fees
+//!
+//! A flat priority fee loses every contested opportunity and overpays on
+//! quiet ones. [`PriorityFeeEstimator`] instead samples
+//! `getRecentPrioritizationFees` for the accounts a transaction writes, keeps
+//! a rolling window of per-slot fees for every account set and bids a
+//! percentile of that window. The bid grows with every retry of the same
+//! opportunity and never spends more than `max_profit_fraction` of the
+//! expected profit, so a thin edge is never bid away.
+
+use crate::config::{Config, PriorityFeeConfig};
+use crate::error::Result;
+use parking_lot::RwLock;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// Most accounts `getRecentPrioritizationFees` accepts
+const MAX_FEE_ACCOUNTS: usize = 128;
+
+/// Recent fees paid for one account set
+#[derive(Debug, Clone)]
+struct FeeWindow {
+    /// Fee per slot, in micro-lamports per compute unit
+    fees: BTreeMap<u64, u64>,
+    fetched_at: Instant,
+}
+
+/// Prices compute units from recent fees paid for the same accounts
+pub struct PriorityFeeEstimator {
+    rpc: Arc<RpcClient>,
+    config: PriorityFeeConfig,
+    windows: RwLock<HashMap<Vec<Pubkey>, FeeWindow>>,
+}
+
+impl fmt::Debug for PriorityFeeEstimator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PriorityFeeEstimator")
+            .field("rpc", &self.rpc.url())
+            .field("config", &self.config)
+            .field("windows", &self.windows.read().len())
+            .finish()
+    }
+}
+
+impl PriorityFeeEstimator {
+    /// Create an estimator with the given settings
+    pub fn new(rpc: Arc<RpcClient>, config: PriorityFeeConfig) -> Self {
+        Self {
+            rpc,
+            config,
+            windows: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Create an estimator with the configured settings
+    pub fn from_config(rpc: Arc<RpcClient>, config: &Config) -> Self {
+        Self::new(rpc, config.priority_fees.clone())
+    }
+
+    /// Attempts per opportunity before it is given up
+    pub fn max_attempts(&self) -> u32 {
+        self.config.max_attempts
+    }
+
+    /// Whether an opportunity that failed `attempt` (zero-based) may be
+    /// retried
+    pub fn should_retry(&self, attempt: u32) -> bool {
+        attempt + 1 < self.config.max_attempts
+    }
+
+    /// Accounts written by `instructions` other than their signers, sorted
+    /// and deduplicated so the same route always maps to the same set
+    pub fn writable_accounts(instructions: &[Instruction]) -> Vec<Pubkey> {
+        instructions
+            .iter()
+            .flat_map(|ix| &ix.accounts)
+            .filter(|meta| meta.is_writable && !meta.is_signer)
+            .map(|meta| meta.pubkey)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .take(MAX_FEE_ACCOUNTS)
+            .collect()
+    }
+
+    /// Add per-slot fee samples for `accounts`, dropping samples older than
+    /// the window
+    pub fn record(&self, accounts: &[Pubkey], samples: impl IntoIterator<Item = (u64, u64)>) {
+        let mut windows = self.windows.write();
+        let window = windows
+            .entry(accounts.to_vec())
+            .or_insert_with(|| FeeWindow {
+                fees: BTreeMap::new(),
+                fetched_at: Instant::now(),
+            });
+        window.fees.extend(samples);
+        window.fetched_at = Instant::now();
+        if let Some(&newest) = window.fees.keys().next_back() {
+            let oldest = newest.saturating_sub(self.config.window_slots.saturating_sub(1));
+            window.fees = window.fees.split_off(&oldest);
+        }
+    }
+
+    /// Configured percentile of the recent fees paid for `accounts`, if any
+    /// were sampled
+    pub fn percentile(&self, accounts: &[Pubkey]) -> Option<u64> {
+        let windows = self.windows.read();
+        let window = windows.get(accounts)?;
+        let mut fees: Vec<u64> = window.fees.values().copied().collect();
+        if fees.is_empty() {
+            return None;
+        }
+        fees.sort_unstable();
+        let rank = (self.config.percentile.clamp(0.0, 100.0) / 100.0 * fees.len() as f64).ceil();
+        Some(fees[(rank as usize).clamp(1, fees.len()) - 1])
+    }
+
+    /// Compute unit price for attempt `attempt` (zero-based) of a
+    /// transaction writing `accounts`, using `compute_units` and expected to
+    /// make `expected_profit_lamports`, in micro-lamports
+    pub fn price(
+        &self,
+        accounts: &[Pubkey],
+        compute_units: u32,
+        expected_profit_lamports: u64,
+        attempt: u32,
+    ) -> u64 {
+        let base = self
+            .percentile(accounts)
+            .unwrap_or(0)
+            .max(self.config.min_micro_lamports);
+        let escalated = base as f64
+            * self
+                .config
+                .escalation_multiplier
+                .max(1.0)
+                .powi(attempt as i32);
+        let budget = self.config.max_profit_fraction.clamp(0.0, 1.0)
+            * expected_profit_lamports as f64
+            * 1_000_000.0
+            / compute_units.max(1) as f64;
+        escalated
+            .min(self.config.max_micro_lamports as f64)
+            .min(budget)
+            .max(0.0) as u64
+    }
+
+    /// Fetch recent fees for `accounts` unless they were fetched within the
+    /// cache TTL
+    pub async fn refresh(&self, accounts: &[Pubkey]) -> Result<()> {
+        let ttl = Duration::from_millis(self.config.cache_ttl_ms);
+        let fresh = self
+            .windows
+            .read()
+            .get(accounts)
+            .is_some_and(|window| window.fetched_at.elapsed() < ttl);
+        if fresh {
+            return Ok(());
+        }
+
+        let fees = self.rpc.get_recent_prioritization_fees(accounts).await?;
+        debug!(
+            "Sampled {} slots of prioritization fees for {} accounts",
+            fees.len(),
+            accounts.len()
+        );
+        self.record(
+            accounts,
+            fees.into_iter()
+                .map(|fee| (fee.slot, fee.prioritization_fee)),
+        );
+        Ok(())
+    }
+
+    /// Compute unit price for attempt `attempt` (zero-based) of the
+    /// transaction made of `instructions`, refreshing its fee samples first
+    pub async fn estimate(
+        &self,
+        instructions: &[Instruction],
+        compute_units: u32,
+        expected_profit_lamports: u64,
+        attempt: u32,
+    ) -> Result<u64> {
+        let accounts = Self::writable_accounts(instructions);
+        self.refresh(&accounts).await?;
+        Ok(self.price(&accounts, compute_units, expected_profit_lamports, attempt))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::instruction::AccountMeta;
+
+    fn estimator() -> PriorityFeeEstimator {
+        PriorityFeeEstimator::new(
+            Arc::new(RpcClient::new(String::new())),
+            PriorityFeeConfig {
+                window_slots: 10,
+                min_micro_lamports: 100,
+                max_micro_lamports: 1_000_000,
+                max_profit_fraction: 0.25,
+                ..PriorityFeeConfig::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_writable_accounts() {
+        let payer = Pubkey::new_unique();
+        let (a, b, c) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let ix = |accounts| Instruction::new_with_bytes(Pubkey::new_unique(), &[], accounts);
+        let instructions = [
+            ix(vec![
+                AccountMeta::new(payer, true),
+                AccountMeta::new(b, false),
+            ]),
+            ix(vec![
+                AccountMeta::new_readonly(c, false),
+                AccountMeta::new(a, false),
+                AccountMeta::new(b, false),
+            ]),
+        ];
+        let mut expected = vec![a, b];
+        expected.sort();
+        assert_eq!(
+            PriorityFeeEstimator::writable_accounts(&instructions),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_rolling_percentile() {
+        let estimator = estimator();
+        let accounts = [Pubkey::new_unique()];
+        assert_eq!(estimator.percentile(&accounts), None);
+
+        // 75th percentile of 1..=8 thousand
+        estimator.record(&accounts, (1..=8).map(|slot| (slot, slot * 1_000)));
+        assert_eq!(estimator.percentile(&accounts), Some(6_000));
+
+        // Slots 1..=5 fall out of the ten-slot window
+        estimator.record(&accounts, (9..=14).map(|slot| (slot, 0)));
+        assert_eq!(estimator.percentile(&accounts), Some(6_000));
+        estimator.record(&accounts, [(20, 0)]);
+        assert_eq!(estimator.percentile(&accounts), Some(0));
+
+        // Account sets are tracked separately
+        assert_eq!(estimator.percentile(&[Pubkey::new_unique()]), None);
+    }
+
+    #[test]
+    fn test_price_escalates_within_profit_budget() {
+        let estimator = estimator();
+        let accounts = [Pubkey::new_unique()];
+
+        // Quiet accounts bid the floor
+        assert_eq!(estimator.price(&accounts, 200_000, 1_000_000_000, 0), 100);
+
+        estimator.record(&accounts, (1..=4).map(|slot| (slot, 10_000)));
+        assert_eq!(
+            estimator.price(&accounts, 200_000, 1_000_000_000, 0),
+            10_000
+        );
+        assert_eq!(
+            estimator.price(&accounts, 200_000, 1_000_000_000, 1),
+            15_000
+        );
+        assert_eq!(
+            estimator.price(&accounts, 200_000, 1_000_000_000, 2),
+            22_500
+        );
+        assert!(estimator.should_retry(1));
+        assert!(!estimator.should_retry(2));
+
+        // A quarter of a 5_000-lamport edge over 200k units is 6_250
+        // micro-lamports
+        let price = estimator.price(&accounts, 200_000, 5_000, 2);
+        assert_eq!(price, 6_250);
+        assert!(price as u128 * 200_000 / 1_000_000 <= 1_250);
+
+        // And the configured maximum always applies
+        estimator.record(&accounts, [(5, 50_000_000)]);
+        estimator.record(&accounts, [(6, 50_000_000)]);
+        assert_eq!(
+            estimator.price(&accounts, 200_000, 1_000_000_000_000, 0),
+            1_000_000
+        );
+    }
+}
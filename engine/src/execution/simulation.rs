@@ -0,0 +1,494 @@
+//! Pre-flight simulation
+//!
+//! Every transaction is simulated with `replaceRecentBlockhash` before it is
+//! sent, returning the payer and the account the route's profit lands in.
+//! Their balances before are read at `processed` first, and the simulation,
+//! also at `processed`, is pinned to that slot or a later one so it never
+//! runs against an older bank. The balances before and after give the profit
+//! the transaction would actually make, fees included; a profit account that
+//! doesn't exist, like one the transaction creates or the wSOL account it
+//! closes, holds nothing.
+//! Trades that fail, overrun the compute budget or clear less than
+//! `trading.min_profit_threshold_usd` are dropped, and the
+//! [`SimulationReport`] is kept with the trade record either way.
+
+use crate::config::Config;
+use crate::dex::layout::token_account_amount;
+use crate::dex::NATIVE_MINT;
+use crate::error::{ArbitrageError, Result};
+use crate::models::{ArbitrageOpportunity, SimulationReport};
+use crate::pricing::PriceSnapshot;
+use rust_decimal::Decimal;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{
+    RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig,
+};
+use solana_commitment_config::CommitmentConfig;
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::VersionedTransaction;
+use solana_transaction_status_client_types::option_serializer::OptionSerializer;
+use solana_transaction_status_client_types::{UiTransactionStatusMeta, UiTransactionTokenBalance};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::{debug, info};
+
+/// Balances of the payer and the profit account around a simulation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BalanceChange {
+    /// Payer lamports before
+    pub lamports_before: u64,
+    /// Payer lamports after
+    pub lamports_after: u64,
+    /// Profit account token amount before
+    pub tokens_before: u64,
+    /// Profit account token amount after
+    pub tokens_after: u64,
+}
+
+impl BalanceChange {
+    /// Read the balances from the payer and profit token accounts as they
+    /// were before and after, a missing token account holding nothing
+    pub fn from_accounts(
+        before: (&Account, Option<&Account>),
+        after: (&Account, Option<&Account>),
+    ) -> Result<Self> {
+        let amount = |account: Option<&Account>| {
+            account.map_or(Ok(0), |account| token_account_amount(&account.data))
+        };
+        Ok(Self {
+            lamports_before: before.0.lamports,
+            lamports_after: after.0.lamports,
+            tokens_before: amount(before.1)?,
+            tokens_after: amount(after.1)?,
+        })
+    }
+
+    /// Read the balances from the metadata of a landed transaction: the fee
+    /// payer's lamports and the `mint` tokens held by `owner` in the account
+    /// the transaction touched, which holds nothing before it is created or
+    /// after it is closed
+    pub fn from_meta(
+        meta: &UiTransactionStatusMeta,
+        owner: &Pubkey,
+        mint: &Pubkey,
+    ) -> Result<Self> {
+        let (owner, mint) = (owner.to_string(), mint.to_string());
+        let lamports = |balances: &[u64]| {
+            balances.first().copied().ok_or_else(|| {
+                ArbitrageError::transaction("Transaction metadata has no fee payer balance")
+            })
+        };
+        let amount = |balances: &OptionSerializer<Vec<UiTransactionTokenBalance>>| {
+            let balances: Option<&Vec<UiTransactionTokenBalance>> = balances.as_ref().into();
+            let Some(balance) = balances.into_iter().flatten().find(|balance| {
+                balance.mint == mint && Option::from(balance.owner.as_ref()) == Some(&owner)
+            }) else {
+                return Ok(0);
+            };
+            balance.ui_token_amount.amount.parse::<u64>().map_err(|_| {
+                ArbitrageError::transaction(format!(
+                    "Invalid token amount {} in transaction metadata",
+                    balance.ui_token_amount.amount
+                ))
+            })
+        };
+        Ok(Self {
+            lamports_before: lamports(&meta.pre_balances)?,
+            lamports_after: lamports(&meta.post_balances)?,
+            tokens_before: amount(&meta.pre_token_balances)?,
+            tokens_after: amount(&meta.post_token_balances)?,
+        })
+    }
+
+    /// Value of the change in USD, with the profit account holding `mint`
+    pub fn net_usd(&self, mint: &Pubkey, prices: &PriceSnapshot) -> Option<f64> {
+        let value = |mint: &Pubkey, before: u64, after: u64| {
+            if after >= before {
+                prices.usd_value(mint, after - before)
+            } else {
+                prices.usd_value(mint, before - after).map(|usd| -usd)
+            }
+        };
+        let lamports = if self.lamports_before == self.lamports_after {
+            0.0
+        } else {
+            value(&NATIVE_MINT, self.lamports_before, self.lamports_after)?
+        };
+        Some(value(mint, self.tokens_before, self.tokens_after)? + lamports)
+    }
+}
+
+/// What a simulation returned
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SimulationOutcome {
+    /// Slot the simulation ran against
+    pub slot: u64,
+    /// Transaction error, if it failed
+    pub error: Option<String>,
+    /// Program logs
+    pub logs: Vec<String>,
+    /// Compute units consumed
+    pub units_consumed: Option<u64>,
+    /// Payer and profit account balances, if the payer was returned
+    pub balances: Option<BalanceChange>,
+}
+
+/// Simulates arbitrage transactions and decides whether they may be sent
+#[derive(Clone)]
+pub struct Simulator {
+    rpc: Arc<RpcClient>,
+    min_profit_usd: f64,
+    max_compute_units: u32,
+}
+
+impl fmt::Debug for Simulator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Simulator")
+            .field("rpc", &self.rpc.url())
+            .field("min_profit_usd", &self.min_profit_usd)
+            .field("max_compute_units", &self.max_compute_units)
+            .finish()
+    }
+}
+
+impl Simulator {
+    /// Create a simulator dropping trades that net less than
+    /// `min_profit_usd` or consume more than `max_compute_units`
+    pub fn new(rpc: Arc<RpcClient>, min_profit_usd: f64, max_compute_units: u32) -> Self {
+        Self {
+            rpc,
+            min_profit_usd,
+            max_compute_units,
+        }
+    }
+
+    /// Create a simulator with the configured profit threshold and compute
+    /// budget
+    pub fn from_config(rpc: Arc<RpcClient>, config: &Config) -> Self {
+        Self::new(
+            rpc,
+            config.trading.min_profit_threshold_usd,
+            config.solana.max_compute_units,
+        )
+    }
+
+    /// Simulate `transaction` for `opportunity`, paid by `payer` with the
+    /// profit landing in `profit_account`
+    pub async fn simulate(
+        &self,
+        transaction: &VersionedTransaction,
+        opportunity: &ArbitrageOpportunity,
+        payer: &Pubkey,
+        profit_account: &Pubkey,
+        prices: &PriceSnapshot,
+    ) -> Result<SimulationReport> {
+        let watched = [*payer, *profit_account];
+        let before = self
+            .rpc
+            .get_multiple_accounts_with_commitment(&watched, CommitmentConfig::processed())
+            .await?;
+        let config = RpcSimulateTransactionConfig {
+            sig_verify: false,
+            replace_recent_blockhash: true,
+            commitment: Some(CommitmentConfig::processed()),
+            min_context_slot: Some(before.context.slot),
+            accounts: Some(RpcSimulateTransactionAccountsConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                addresses: watched.iter().map(ToString::to_string).collect(),
+            }),
+            ..Default::default()
+        };
+        let simulated = self
+            .rpc
+            .simulate_transaction_with_config(transaction, config)
+            .await?;
+        let (before, result) = (before.value, simulated.value);
+
+        let after: Vec<Option<Account>> = result
+            .accounts
+            .unwrap_or_default()
+            .iter()
+            .map(|account| account.as_ref().and_then(|account| account.decode()))
+            .collect();
+        let balances = match (before.as_slice(), after.as_slice()) {
+            ([Some(payer_before), tokens_before], [Some(payer_after), tokens_after]) => {
+                BalanceChange::from_accounts(
+                    (payer_before, tokens_before.as_ref()),
+                    (payer_after, tokens_after.as_ref()),
+                )
+                .ok()
+            }
+            _ => None,
+        };
+
+        let report = self.assess(
+            opportunity,
+            prices,
+            SimulationOutcome {
+                slot: simulated.context.slot,
+                error: result.err.map(|err| format!("{:?}", err)),
+                logs: result.logs.unwrap_or_default(),
+                units_consumed: result.units_consumed,
+                balances,
+            },
+        );
+        match &report.rejection {
+            Some(reason) => info!("Dropping opportunity {}: {}", opportunity.id, reason),
+            None => debug!(
+                "Simulated opportunity {}: {:?} USD net",
+                opportunity.id, report.net_profit_usd
+            ),
+        }
+        Ok(report)
+    }
+
+    /// Judge a simulation outcome
+    pub fn assess(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        prices: &PriceSnapshot,
+        outcome: SimulationOutcome,
+    ) -> SimulationReport {
+        let SimulationOutcome {
+            slot,
+            error,
+            logs,
+            units_consumed,
+            balances,
+        } = outcome;
+        let net_profit_usd = Pubkey::from_str(&opportunity.token_mint)
+            .ok()
+            .zip(balances)
+            .and_then(|(mint, balances)| balances.net_usd(&mint, prices));
+
+        let rejection = if let Some(error) = &error {
+            Some(format!("Simulation failed: {}", error))
+        } else if units_consumed.is_some_and(|units| units > self.max_compute_units as u64) {
+            Some(format!(
+                "Consumed {} compute units, budget is {}",
+                units_consumed.unwrap_or_default(),
+                self.max_compute_units
+            ))
+        } else {
+            match net_profit_usd {
+                None => Some("Simulated balances could not be valued".to_string()),
+                Some(net) if net < self.min_profit_usd => Some(format!(
+                    "Simulated net profit ${:.2} is below ${:.2}",
+                    net, self.min_profit_usd
+                )),
+                Some(_) => None,
+            }
+        };
+
+        SimulationReport {
+            slot,
+            error,
+            logs,
+            units_consumed,
+            net_profit_usd: net_profit_usd
+                .and_then(Decimal::from_f64_retain)
+                .map(|net| net.round_dp(6)),
+            rejection,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TokensConfig;
+    use crate::dex::{DexManager, RaydiumAmmAdapter};
+    use crate::models::{OpportunityStatus, TradeResult};
+    use crate::pricing::PriceOracle;
+    use crate::test_support::test_pool;
+
+    const USDC: Pubkey = Pubkey::from_str_const("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+
+    fn prices() -> PriceSnapshot {
+        let mut dex = DexManager::new();
+        dex.register(Arc::new(RaydiumAmmAdapter::new()));
+        // SOL at $150
+        let pool = test_pool(NATIVE_MINT, USDC, 1_000_000_000_000, 150_000_000_000, 25);
+        PriceOracle::new(&TokensConfig::default()).snapshot(&dex, [&pool])
+    }
+
+    fn opportunity() -> ArbitrageOpportunity {
+        let now = chrono::Utc::now();
+        ArbitrageOpportunity {
+            id: "test".to_string(),
+            token_mint: USDC.to_string(),
+            legs: Vec::new(),
+            input_amount: 1_000_000_000,
+            max_trade_size: Decimal::from(1_000),
+            buy_price: Decimal::from(150),
+            sell_price: Decimal::from(155),
+            price_difference: Decimal::new(3, 2),
+            profit_percentage: Decimal::from(3),
+            estimated_profit_usd: Decimal::from(30),
+            gas_cost_estimate: Decimal::new(1, 2),
+            net_profit_usd: Decimal::new(2999, 2),
+            confidence_score: Decimal::new(9, 1),
+            risk_score: Decimal::new(1, 1),
+            status: OpportunityStatus::Detected,
+            slot: 1,
+            detected_at: now,
+            expires_at: now,
+        }
+    }
+
+    fn simulator() -> Simulator {
+        Simulator::new(Arc::new(RpcClient::new(String::new())), 10.0, 200_000)
+    }
+
+    fn balances(usdc_gained: u64) -> BalanceChange {
+        BalanceChange {
+            lamports_before: 1_000_000_000,
+            // 0.0001 SOL of fees: $0.015
+            lamports_after: 999_900_000,
+            tokens_before: 5_000_000_000,
+            tokens_after: 5_000_000_000 + usdc_gained,
+        }
+    }
+
+    #[test]
+    fn test_profitable_simulation_passes() {
+        let report = simulator().assess(
+            &opportunity(),
+            &prices(),
+            SimulationOutcome {
+                slot: 7,
+                error: None,
+                logs: vec!["Program log: ok".to_string()],
+                units_consumed: Some(120_000),
+                balances: Some(balances(25_000_000)),
+            },
+        );
+        assert!(report.passed());
+        let net = report.net_profit_usd.unwrap();
+        assert!(net > Decimal::from(24) && net < Decimal::from(25));
+        assert_eq!(report.logs.len(), 1);
+        assert_eq!(report.slot, 7);
+    }
+
+    #[test]
+    fn test_unprofitable_or_failed_simulations_are_dropped() {
+        let simulator = simulator();
+        let prices = prices();
+        let opportunity = opportunity();
+
+        let thin = simulator.assess(
+            &opportunity,
+            &prices,
+            SimulationOutcome {
+                slot: 7,
+                error: None,
+                logs: Vec::new(),
+                units_consumed: Some(120_000),
+                balances: Some(balances(5_000_000)),
+            },
+        );
+        assert!(thin.rejection.unwrap().contains("below"));
+
+        let heavy = simulator.assess(
+            &opportunity,
+            &prices,
+            SimulationOutcome {
+                slot: 7,
+                error: None,
+                logs: Vec::new(),
+                units_consumed: Some(250_000),
+                balances: Some(balances(25_000_000)),
+            },
+        );
+        assert!(heavy.rejection.unwrap().contains("compute units"));
+
+        let failed = simulator.assess(
+            &opportunity,
+            &prices,
+            SimulationOutcome {
+                slot: 7,
+                error: Some("InstructionError(4, Custom(30))".to_string()),
+                logs: vec!["Program log: slippage".to_string()],
+                units_consumed: Some(80_000),
+                balances: None,
+            },
+        );
+        assert!(!failed.passed());
+        assert!(failed.net_profit_usd.is_none());
+
+        let record = TradeResult::rejected(&opportunity, failed);
+        assert_eq!(record.opportunity_id, "test");
+        assert_eq!(record.simulation.unwrap().logs.len(), 1);
+    }
+
+    #[test]
+    fn test_balances_read_from_token_accounts() {
+        let mut token = Account {
+            data: vec![0; 165],
+            ..Account::default()
+        };
+        token.data[64..72].copy_from_slice(&7u64.to_le_bytes());
+        let payer = Account {
+            lamports: 10,
+            ..Account::default()
+        };
+        let change =
+            BalanceChange::from_accounts((&payer, Some(&token)), (&payer, Some(&token))).unwrap();
+        assert_eq!(change.tokens_after, 7);
+        assert_eq!(change.net_usd(&USDC, &prices()), Some(0.0));
+        assert!(
+            BalanceChange::from_accounts((&payer, Some(&payer)), (&payer, Some(&token))).is_err()
+        );
+
+        // An account created by the transaction held nothing before it
+        let change = BalanceChange::from_accounts((&payer, None), (&payer, Some(&token))).unwrap();
+        assert_eq!((change.tokens_before, change.tokens_after), (0, 7));
+    }
+
+    #[test]
+    fn test_balances_read_from_transaction_metadata() {
+        let payer = Pubkey::new_unique();
+        let balance = |index: u8, owner: &Pubkey, amount: &str| {
+            serde_json::json!({
+                "accountIndex": index,
+                "mint": USDC.to_string(),
+                "owner": owner.to_string(),
+                "uiTokenAmount": {
+                    "uiAmount": null,
+                    "decimals": 6,
+                    "amount": amount,
+                    "uiAmountString": "",
+                },
+            })
+        };
+        let meta: UiTransactionStatusMeta = serde_json::from_value(serde_json::json!({
+            "err": null,
+            "status": { "Ok": null },
+            "fee": 5_000,
+            "preBalances": [1_000_000_000, 2_039_280],
+            "postBalances": [999_900_000, 2_039_280],
+            // The pool's vault moves the other way
+            "preTokenBalances": [
+                balance(1, &payer, "5000000000"),
+                balance(2, &Pubkey::new_unique(), "9000000000"),
+            ],
+            "postTokenBalances": [
+                balance(1, &payer, "5025000000"),
+                balance(2, &Pubkey::new_unique(), "8975000000"),
+            ],
+        }))
+        .unwrap();
+
+        let change = BalanceChange::from_meta(&meta, &payer, &USDC).unwrap();
+        assert_eq!(change, balances(25_000_000));
+
+        // The payer holding none of the token, like a closed wSOL account,
+        // leaves only the lamports
+        let change = BalanceChange::from_meta(&meta, &payer, &NATIVE_MINT).unwrap();
+        assert_eq!((change.tokens_before, change.tokens_after), (0, 0));
+        assert_eq!(change.lamports_after, 999_900_000);
+    }
+}
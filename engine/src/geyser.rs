@@ -1,18 +1,595 @@
 //! Geyser client module for the arbitrage engine
+//!
+//! Wraps the tonic client generated from `proto/geyser.proto`. Every
+//! subscription runs in its own task that converts protobuf messages into
+//! engine types and, when the stream drops, reconnects with exponential
+//! backoff and re-issues the same subscription.
 
-use crate::error::Result;
+use crate::config::SolanaConfig;
+use crate::error::{ArbitrageError, Result};
+use crate::pool_cache::AccountUpdate;
+use crate::utils::Backoff;
+use futures::Stream;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use std::future::Future;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::metadata::{Ascii, MetadataValue};
+use tonic::transport::{Channel, Endpoint};
+use tonic::{Status, Streaming};
+use tracing::{debug, info, warn};
+
+/// Code generated from `proto/geyser.proto`
+#[allow(clippy::all, missing_docs)]
+pub mod proto {
+    tonic::include_proto!("geyser");
+}
+
+use proto::geyser_client::GeyserClient as ProtoGeyserClient;
+
+/// Metadata key carrying the Geyser auth token
+const TOKEN_HEADER: &str = "x-token";
+/// Updates buffered per subscription before the reader applies backpressure
+const UPDATE_BUFFER: usize = 1024;
+/// Default connect timeout
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+/// First reconnect delay
+const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+/// Longest reconnect delay
+const MAX_BACKOFF: Duration = Duration::from_secs(10);
+
+/// Stream of updates from a data source
+pub type UpdateStream<T> = Pin<Box<dyn Stream<Item = T> + Send>>;
+
+/// Progress of a slot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlotStatus {
+    /// Shreds for the slot were received
+    Received,
+    /// The slot was processed by the node
+    Processed,
+    /// The slot was rooted
+    Rooted,
+}
+
+/// Slot notification
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotUpdate {
+    /// Slot number
+    pub slot: u64,
+    /// Parent slot, if known
+    pub parent: Option<u64>,
+    /// Slot progress
+    pub status: SlotStatus,
+}
+
+/// Transaction notification
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionUpdate {
+    /// Transaction signature
+    pub signature: Signature,
+    /// Slot the transaction landed in
+    pub slot: u64,
+    /// Whether this is a vote transaction
+    pub is_vote: bool,
+    /// Error message if the transaction failed
+    pub err: Option<String>,
+    /// Fee paid in lamports
+    pub fee: u64,
+    /// Program log output
+    pub log_messages: Vec<String>,
+}
+
+fn parse_commitment(commitment: &str) -> Result<proto::CommitmentLevel> {
+    match commitment {
+        "processed" => Ok(proto::CommitmentLevel::Processed),
+        "confirmed" => Ok(proto::CommitmentLevel::Confirmed),
+        "finalized" => Ok(proto::CommitmentLevel::Finalized),
+        other => Err(ArbitrageError::config(format!(
+            "Invalid commitment level: {}",
+            other
+        ))),
+    }
+}
+
+fn convert_account(update: proto::AccountUpdate) -> Option<AccountUpdate> {
+    let (Ok(pubkey), Ok(owner)) = (
+        Pubkey::from_str(&update.pubkey),
+        Pubkey::from_str(&update.owner),
+    ) else {
+        warn!("Dropping Geyser account update with invalid keys");
+        return None;
+    };
+
+    Some(AccountUpdate {
+        pubkey,
+        owner,
+        data: update.data,
+        slot: update.slot,
+        write_version: update.write_version,
+    })
+}
+
+fn convert_slot(update: proto::SlotUpdate) -> Option<SlotUpdate> {
+    let status = match proto::SlotStatus::try_from(update.status).ok()? {
+        proto::SlotStatus::Received => SlotStatus::Received,
+        proto::SlotStatus::Processed => SlotStatus::Processed,
+        proto::SlotStatus::Rooted => SlotStatus::Rooted,
+    };
+
+    Some(SlotUpdate {
+        slot: update.slot,
+        parent: update.parent,
+        status,
+    })
+}
+
+fn convert_transaction(update: proto::TransactionUpdate) -> Option<TransactionUpdate> {
+    let Ok(signature) = Signature::from_str(&update.signature) else {
+        warn!("Dropping Geyser transaction update with invalid signature");
+        return None;
+    };
+    let meta = update.meta.unwrap_or_default();
+
+    Some(TransactionUpdate {
+        signature,
+        slot: update.slot,
+        is_vote: update.is_vote,
+        err: meta.err,
+        fee: meta.fee,
+        log_messages: meta.log_messages,
+    })
+}
 
 /// Geyser gRPC client for streaming Solana data
-/// Temporarily disabled due to proto compilation issues
-#[derive(Debug, Default)]
+#[derive(Debug, Clone)]
 pub struct GeyserClient {
-    // TODO: Add gRPC client connection when proto issues are resolved
+    endpoint: String,
+    client: ProtoGeyserClient<Channel>,
+    token: Option<MetadataValue<Ascii>>,
+    commitment: proto::CommitmentLevel,
+    initial_backoff: Duration,
+    max_backoff: Duration,
 }
 
 impl GeyserClient {
-    /// Create a new Geyser client
-    pub async fn new(_endpoint: &str) -> Result<Self> {
-        // TODO: Initialize gRPC connection
-        Ok(Self {})
+    /// Create a new Geyser client. The connection is established lazily by
+    /// the first subscription.
+    pub async fn new(endpoint: &str) -> Result<Self> {
+        Self::connect(endpoint, DEFAULT_TIMEOUT)
+    }
+
+    /// Create a client from the `[solana]` settings, or `None` when no
+    /// Geyser endpoint is configured
+    pub fn from_config(config: &SolanaConfig) -> Result<Option<Self>> {
+        if config.geyser_endpoint.trim().is_empty() {
+            return Ok(None);
+        }
+
+        let mut client = Self::connect(
+            &config.geyser_endpoint,
+            Duration::from_millis(config.geyser_timeout_ms),
+        )?
+        .with_commitment(&config.commitment)?;
+        if !config.geyser_token.is_empty() {
+            client = client.with_token(&config.geyser_token)?;
+        }
+        Ok(Some(client))
+    }
+
+    fn connect(endpoint: &str, timeout: Duration) -> Result<Self> {
+        // tonic only understands http(s); configs commonly use grpc(s)
+        let url = if let Some(rest) = endpoint.strip_prefix("grpc://") {
+            format!("http://{}", rest)
+        } else if let Some(rest) = endpoint.strip_prefix("grpcs://") {
+            format!("https://{}", rest)
+        } else {
+            endpoint.to_string()
+        };
+
+        let channel = Endpoint::from_shared(url)
+            .map_err(|e| ArbitrageError::config(format!("Invalid Geyser endpoint: {}", e)))?
+            .connect_timeout(timeout)
+            .connect_lazy();
+
+        Ok(Self {
+            endpoint: endpoint.to_string(),
+            client: ProtoGeyserClient::new(channel),
+            token: None,
+            commitment: proto::CommitmentLevel::Confirmed,
+            initial_backoff: INITIAL_BACKOFF,
+            max_backoff: MAX_BACKOFF,
+        })
+    }
+
+    /// Send `token` as `x-token` with every request
+    pub fn with_token(mut self, token: &str) -> Result<Self> {
+        let token = token
+            .parse()
+            .map_err(|_| ArbitrageError::config("Geyser token is not valid ASCII"))?;
+        self.token = Some(token);
+        Ok(self)
+    }
+
+    /// Subscribe at `commitment` ("processed", "confirmed" or "finalized")
+    pub fn with_commitment(mut self, commitment: &str) -> Result<Self> {
+        self.commitment = parse_commitment(commitment)?;
+        Ok(self)
+    }
+
+    /// Set the reconnect backoff range
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Endpoint the client talks to
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    fn request<T>(&self, message: T) -> tonic::Request<T> {
+        let mut request = tonic::Request::new(message);
+        if let Some(token) = &self.token {
+            request.metadata_mut().insert(TOKEN_HEADER, token.clone());
+        }
+        request
+    }
+
+    /// Run `call` until the receiver goes away, re-subscribing with backoff
+    /// whenever the stream fails or ends
+    fn subscribe<Req, Msg, Out, Call, Fut>(
+        &self,
+        what: &'static str,
+        message: Req,
+        call: Call,
+        convert: fn(Msg) -> Option<Out>,
+    ) -> UpdateStream<Out>
+    where
+        Req: Clone + Send + 'static,
+        Msg: Send + 'static,
+        Out: Send + 'static,
+        Call: Fn(ProtoGeyserClient<Channel>, tonic::Request<Req>) -> Fut + Send + 'static,
+        Fut: Future<Output = std::result::Result<tonic::Response<Streaming<Msg>>, Status>> + Send,
+    {
+        let (tx, rx) = mpsc::channel(UPDATE_BUFFER);
+        let client = self.clone();
+
+        tokio::spawn(async move {
+            let mut backoff = Backoff::new(client.initial_backoff, client.max_backoff);
+            loop {
+                let request = client.request(message.clone());
+                match call(client.client.clone(), request).await {
+                    Ok(response) => {
+                        info!("Geyser {} subscription established", what);
+                        let mut stream = response.into_inner();
+                        loop {
+                            match stream.message().await {
+                                Ok(Some(message)) => {
+                                    backoff.reset();
+                                    let Some(update) = convert(message) else {
+                                        continue;
+                                    };
+                                    if tx.send(update).await.is_err() {
+                                        debug!("Geyser {} subscriber dropped", what);
+                                        return;
+                                    }
+                                }
+                                Ok(None) => {
+                                    warn!("Geyser {} stream ended", what);
+                                    break;
+                                }
+                                Err(status) => {
+                                    warn!("Geyser {} stream failed: {}", what, status);
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                    Err(status) => warn!("Geyser {} subscription failed: {}", what, status),
+                }
+
+                if tx.is_closed() {
+                    return;
+                }
+                let delay = backoff.next_delay();
+                debug!("Resubscribing to Geyser {} in {:?}", what, delay);
+                tokio::time::sleep(delay).await;
+            }
+        });
+
+        Box::pin(ReceiverStream::new(rx))
+    }
+
+    /// Stream updates of specific accounts
+    pub fn subscribe_accounts(&self, accounts: &[Pubkey]) -> UpdateStream<AccountUpdate> {
+        let message = proto::SubscribeAccountUpdatesRequest {
+            accounts: accounts.iter().map(Pubkey::to_string).collect(),
+            commitment: Some(self.commitment as i32),
+            include_vote_accounts: Some(false),
+        };
+        self.subscribe(
+            "account",
+            message,
+            |mut client, request| async move { client.subscribe_account_updates(request).await },
+            convert_account,
+        )
+    }
+
+    /// Stream updates of every account owned by `programs`
+    pub fn subscribe_programs(&self, programs: &[Pubkey]) -> UpdateStream<AccountUpdate> {
+        let message = proto::SubscribeProgramUpdatesRequest {
+            programs: programs.iter().map(Pubkey::to_string).collect(),
+            commitment: Some(self.commitment as i32),
+            include_accounts: Some(true),
+        };
+        self.subscribe(
+            "program",
+            message,
+            |mut client, request| async move { client.subscribe_program_updates(request).await },
+            convert_account,
+        )
+    }
+
+    /// Stream slot progress
+    pub fn subscribe_slots(&self) -> UpdateStream<SlotUpdate> {
+        let message = proto::SubscribeSlotUpdatesRequest {
+            commitment: Some(self.commitment as i32),
+        };
+        self.subscribe(
+            "slot",
+            message,
+            |mut client, request| async move { client.subscribe_slot_updates(request).await },
+            convert_slot,
+        )
+    }
+
+    /// Stream non-vote transactions touching any of `accounts`, failed ones
+    /// included
+    pub fn subscribe_transactions(&self, accounts: &[Pubkey]) -> UpdateStream<TransactionUpdate> {
+        let message = proto::SubscribeTransactionUpdatesRequest {
+            commitment: Some(self.commitment as i32),
+            include_vote_transactions: Some(false),
+            include_failed_transactions: Some(true),
+            account_filters: accounts.iter().map(Pubkey::to_string).collect(),
+        };
+        self.subscribe(
+            "transaction",
+            message,
+            |mut client, request| async move {
+                client.subscribe_transaction_updates(request).await
+            },
+            convert_transaction,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{stream, StreamExt};
+    use proto::geyser_server::{Geyser, GeyserServer};
+    use std::net::SocketAddr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use tokio_stream::wrappers::TcpListenerStream;
+
+    type ServerStream<T> = Pin<Box<dyn Stream<Item = std::result::Result<T, Status>> + Send>>;
+
+    const PROGRAM: Pubkey = Pubkey::new_from_array([9; 32]);
+
+    /// Geyser that drops the first account subscription after two updates
+    #[derive(Debug, Default)]
+    struct TestGeyser {
+        account_calls: AtomicUsize,
+        tokens: Mutex<Vec<String>>,
+    }
+
+    fn account(slot: u64) -> proto::AccountUpdate {
+        proto::AccountUpdate {
+            pubkey: Pubkey::new_from_array([slot as u8; 32]).to_string(),
+            owner: PROGRAM.to_string(),
+            data: vec![slot as u8],
+            write_version: slot * 10,
+            slot,
+            ..Default::default()
+        }
+    }
+
+    /// Emit `items`, then keep the stream open
+    fn open_stream<T: Send + 'static>(
+        items: Vec<std::result::Result<T, Status>>,
+    ) -> ServerStream<T> {
+        Box::pin(stream::iter(items).chain(stream::pending()))
+    }
+
+    #[tonic::async_trait]
+    impl Geyser for TestGeyser {
+        type SubscribeAccountUpdatesStream = ServerStream<proto::AccountUpdate>;
+        type SubscribeProgramUpdatesStream = ServerStream<proto::AccountUpdate>;
+        type SubscribeTransactionUpdatesStream = ServerStream<proto::TransactionUpdate>;
+        type SubscribeSlotUpdatesStream = ServerStream<proto::SlotUpdate>;
+        type SubscribeBlockUpdatesStream = ServerStream<proto::BlockUpdate>;
+
+        async fn subscribe_account_updates(
+            &self,
+            request: tonic::Request<proto::SubscribeAccountUpdatesRequest>,
+        ) -> std::result::Result<tonic::Response<Self::SubscribeAccountUpdatesStream>, Status>
+        {
+            if let Some(token) = request.metadata().get(TOKEN_HEADER) {
+                self.tokens
+                    .lock()
+                    .unwrap()
+                    .push(token.to_str().unwrap().to_string());
+            }
+
+            let stream: ServerStream<proto::AccountUpdate> =
+                if self.account_calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                    // Delay the failure so the updates are flushed before it
+                    let failure = stream::once(async {
+                        tokio::time::sleep(Duration::from_millis(50)).await;
+                        Err(Status::unavailable("node restarting"))
+                    });
+                    Box::pin(stream::iter(vec![Ok(account(1)), Ok(account(2))]).chain(failure))
+                } else {
+                    open_stream(vec![Ok(account(3))])
+                };
+            Ok(tonic::Response::new(stream))
+        }
+
+        async fn subscribe_program_updates(
+            &self,
+            request: tonic::Request<proto::SubscribeProgramUpdatesRequest>,
+        ) -> std::result::Result<tonic::Response<Self::SubscribeProgramUpdatesStream>, Status>
+        {
+            assert_eq!(request.get_ref().programs, vec![PROGRAM.to_string()]);
+            Ok(tonic::Response::new(open_stream(vec![Ok(account(7))])))
+        }
+
+        async fn subscribe_transaction_updates(
+            &self,
+            _request: tonic::Request<proto::SubscribeTransactionUpdatesRequest>,
+        ) -> std::result::Result<tonic::Response<Self::SubscribeTransactionUpdatesStream>, Status>
+        {
+            let update = proto::TransactionUpdate {
+                signature: Signature::from([3; 64]).to_string(),
+                slot: 5,
+                meta: Some(proto::TransactionMeta {
+                    err: Some("InstructionError".to_string()),
+                    fee: 5000,
+                    log_messages: vec!["Program log: hi".to_string()],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            Ok(tonic::Response::new(open_stream(vec![Ok(update)])))
+        }
+
+        async fn subscribe_slot_updates(
+            &self,
+            request: tonic::Request<proto::SubscribeSlotUpdatesRequest>,
+        ) -> std::result::Result<tonic::Response<Self::SubscribeSlotUpdatesStream>, Status>
+        {
+            assert_eq!(
+                request.get_ref().commitment,
+                Some(proto::CommitmentLevel::Processed as i32)
+            );
+            let update = proto::SlotUpdate {
+                slot: 42,
+                parent: Some(41),
+                status: proto::SlotStatus::Rooted as i32,
+            };
+            Ok(tonic::Response::new(open_stream(vec![Ok(update)])))
+        }
+
+        async fn subscribe_block_updates(
+            &self,
+            _request: tonic::Request<proto::SubscribeBlockUpdatesRequest>,
+        ) -> std::result::Result<tonic::Response<Self::SubscribeBlockUpdatesStream>, Status>
+        {
+            Err(Status::unimplemented("blocks"))
+        }
+
+        async fn ping(
+            &self,
+            request: tonic::Request<proto::PingRequest>,
+        ) -> std::result::Result<tonic::Response<proto::PongResponse>, Status> {
+            Ok(tonic::Response::new(proto::PongResponse {
+                id: request.get_ref().id,
+            }))
+        }
+    }
+
+    async fn spawn_server(service: Arc<TestGeyser>) -> SocketAddr {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(GeyserServer::from_arc(service))
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+        addr
+    }
+
+    async fn next<T>(stream: &mut UpdateStream<T>) -> T {
+        tokio::time::timeout(Duration::from_secs(5), stream.next())
+            .await
+            .expect("timed out waiting for update")
+            .expect("stream ended")
+    }
+
+    fn test_config(addr: SocketAddr) -> SolanaConfig {
+        SolanaConfig {
+            rpc_url: String::new(),
+            ws_url: String::new(),
+            commitment: "processed".to_string(),
+            geyser_endpoint: format!("grpc://{}", addr),
+            geyser_token: "secret".to_string(),
+            geyser_timeout_ms: 1000,
+            ..crate::config::Config::default().solana
+        }
+    }
+
+    #[test]
+    fn test_from_config_without_endpoint() {
+        let mut config = test_config("127.0.0.1:1".parse().unwrap());
+        config.geyser_endpoint = String::new();
+        assert!(GeyserClient::from_config(&config).unwrap().is_none());
+        assert!(parse_commitment("eventual").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_account_stream_resubscribes() {
+        let service = Arc::new(TestGeyser::default());
+        let addr = spawn_server(service.clone()).await;
+        let client = GeyserClient::from_config(&test_config(addr))
+            .unwrap()
+            .unwrap()
+            .with_backoff(Duration::from_millis(10), Duration::from_millis(50));
+
+        let mut updates = client.subscribe_accounts(&[Pubkey::new_unique()]);
+        let mut slots = Vec::new();
+        for _ in 0..3 {
+            let update = next(&mut updates).await;
+            assert_eq!(update.owner, PROGRAM);
+            assert_eq!(update.write_version, update.slot * 10);
+            slots.push(update.slot);
+        }
+
+        assert_eq!(slots, vec![1, 2, 3]);
+        assert_eq!(service.account_calls.load(Ordering::SeqCst), 2);
+        assert_eq!(*service.tokens.lock().unwrap(), vec!["secret", "secret"]);
+    }
+
+    #[tokio::test]
+    async fn test_program_slot_and_transaction_streams() {
+        let addr = spawn_server(Arc::new(TestGeyser::default())).await;
+        let client = GeyserClient::from_config(&test_config(addr))
+            .unwrap()
+            .unwrap();
+
+        let mut programs = client.subscribe_programs(&[PROGRAM]);
+        assert_eq!(next(&mut programs).await.slot, 7);
+
+        let mut slots = client.subscribe_slots();
+        assert_eq!(
+            next(&mut slots).await,
+            SlotUpdate {
+                slot: 42,
+                parent: Some(41),
+                status: SlotStatus::Rooted,
+            }
+        );
+
+        let mut transactions = client.subscribe_transactions(&[]);
+        let update = next(&mut transactions).await;
+        assert_eq!(update.signature, Signature::from([3; 64]));
+        assert_eq!(update.err.as_deref(), Some("InstructionError"));
+        assert_eq!(update.fee, 5000);
     }
 }
@@ -5,15 +5,24 @@
 
 pub mod config;
 pub mod database;
+pub mod datasource;
 pub mod dex;
 pub mod engine;
 pub mod error;
+pub mod execution;
 pub mod geyser;
 pub mod metrics;
 pub mod models;
+pub mod pool_cache;
+pub mod pricing;
+pub mod risk;
+pub mod scheduler;
 pub mod server;
 pub mod strategy;
+#[cfg(test)]
+mod test_support;
 pub mod utils;
+pub mod websocket;
 
 pub use config::Config;
 pub use engine::ArbitrageEngine;
@@ -3,30 +3,125 @@
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::transaction::TransactionError;
 
 /// Arbitrage opportunity model
+///
+/// Mirrors the `arbitrage_opportunities` table. A route is a list of legs
+/// starting and ending in `token_mint`; the buy side of the table is its first
+/// leg and the sell side its last.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ArbitrageOpportunity {
     /// Unique identifier for the opportunity
     pub id: String,
-    /// First token in the arbitrage pair
-    pub token_a: String,
-    /// Second token in the arbitrage pair
-    pub token_b: String,
-    /// First DEX in the arbitrage route
-    pub dex_a: String,
-    /// Second DEX in the arbitrage route
-    pub dex_b: String,
+    /// Token the route starts and ends in, and its profit is made in
+    pub token_mint: String,
+    /// Swaps of the route, in trade order
+    pub legs: Vec<OpportunityLeg>,
+    /// Input of the first leg, in base units of `token_mint`
+    pub input_amount: u64,
+    /// Largest input allowed by the position limit, in `token_mint`
+    pub max_trade_size: Decimal,
+    /// `token_mint` paid per unit bought on the first leg
+    pub buy_price: Decimal,
+    /// `token_mint` received per unit sold on the last leg
+    pub sell_price: Decimal,
+    /// Return of the route as a fraction of its input
+    pub price_difference: Decimal,
     /// Profit percentage for this opportunity
     pub profit_percentage: Decimal,
-    /// Absolute profit amount in base currency
-    pub profit_amount: Decimal,
-    /// Timestamp when the opportunity was created
-    pub created_at: DateTime<Utc>,
+    /// Profit before transaction fees, in USD
+    pub estimated_profit_usd: Decimal,
+    /// Estimated transaction fees, in USD
+    pub gas_cost_estimate: Decimal,
+    /// Profit after transaction fees, in USD
+    pub net_profit_usd: Decimal,
+    /// Confidence that the route fills as quoted, from 0 to 1
+    pub confidence_score: Decimal,
+    /// Execution risk, from 0 to 1
+    pub risk_score: Decimal,
+    /// Lifecycle status
+    pub status: OpportunityStatus,
+    /// Latest slot of the pool states the route was quoted from
+    pub slot: u64,
+    /// Timestamp when the opportunity was detected
+    pub detected_at: DateTime<Utc>,
     /// Timestamp when the opportunity expires
     pub expires_at: DateTime<Utc>,
 }
 
+impl ArbitrageOpportunity {
+    /// DEX of the first leg
+    pub fn buy_dex(&self) -> Option<&str> {
+        self.legs.first().map(|leg| leg.dex.as_str())
+    }
+
+    /// DEX of the last leg
+    pub fn sell_dex(&self) -> Option<&str> {
+        self.legs.last().map(|leg| leg.dex.as_str())
+    }
+
+    /// Pool of the first leg
+    pub fn buy_pool(&self) -> Option<&str> {
+        self.legs.first().map(|leg| leg.pool.as_str())
+    }
+
+    /// Pool of the last leg
+    pub fn sell_pool(&self) -> Option<&str> {
+        self.legs.last().map(|leg| leg.pool.as_str())
+    }
+
+    /// Expected output of the last leg, in base units of `token_mint`
+    pub fn output_amount(&self) -> u64 {
+        self.legs.last().map_or(0, |leg| leg.expected_out)
+    }
+
+    /// Check whether the opportunity has expired at `now`
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        now >= self.expires_at
+    }
+}
+
+/// One swap of an opportunity's route
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpportunityLeg {
+    /// Pool address
+    pub pool: String,
+    /// DEX the pool belongs to
+    pub dex: String,
+    /// Mint of the token going into the pool
+    pub input_mint: String,
+    /// Mint of the token coming out of the pool
+    pub output_mint: String,
+    /// Input amount, in base units
+    pub amount_in: u64,
+    /// Expected output amount, in base units
+    pub expected_out: u64,
+    /// Fee charged by the pool, in base units of the input token
+    pub fee_amount: u64,
+    /// Price impact versus the pool's spot price, in basis points
+    pub price_impact_bps: u32,
+}
+
+/// Opportunity lifecycle status, as in the `opportunity_status` enum
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OpportunityStatus {
+    /// Found by a scan
+    Detected,
+    /// Being checked before execution
+    Analyzing,
+    /// Transaction submitted
+    Executing,
+    /// Executed successfully
+    Completed,
+    /// Expired before execution
+    Expired,
+    /// Execution failed
+    Failed,
+}
+
 /// Trade execution result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TradeResult {
@@ -44,10 +139,127 @@ pub struct TradeResult {
     pub gas_cost: Decimal,
     /// Net profit after deducting gas costs
     pub net_profit: Option<Decimal>,
+    /// Pre-flight simulation of the transaction, if one ran
+    #[serde(default)]
+    pub simulation: Option<SimulationReport>,
+    /// Signature of the submitted transaction
+    #[serde(default)]
+    pub signature: Option<String>,
+    /// Slot the transaction landed in
+    #[serde(default)]
+    pub slot: Option<u64>,
+    /// Why the trade failed, if it did
+    #[serde(default)]
+    pub failure: Option<TradeFailure>,
+}
+
+impl TradeResult {
+    /// Record of a trade dropped before submission because its simulation
+    /// failed or wasn't profitable enough
+    pub fn rejected(opportunity: &ArbitrageOpportunity, simulation: SimulationReport) -> Self {
+        Self {
+            id: crate::utils::generate_id(),
+            opportunity_id: opportunity.id.clone(),
+            status: TradeStatus::Cancelled,
+            executed_at: Utc::now(),
+            actual_profit: None,
+            gas_cost: Decimal::ZERO,
+            net_profit: None,
+            simulation: Some(simulation),
+            signature: None,
+            slot: None,
+            failure: None,
+        }
+    }
+
+    /// Record of a trade about to be sent, paying `gas_cost`
+    pub fn pending(opportunity: &ArbitrageOpportunity, gas_cost: Decimal) -> Self {
+        Self {
+            id: crate::utils::generate_id(),
+            opportunity_id: opportunity.id.clone(),
+            status: TradeStatus::Pending,
+            executed_at: Utc::now(),
+            actual_profit: None,
+            gas_cost,
+            net_profit: None,
+            simulation: None,
+            signature: None,
+            slot: None,
+            failure: None,
+        }
+    }
+}
+
+/// Why a submitted trade failed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TradeFailure {
+    /// The blockhash expired before the transaction landed, so it was dropped
+    Expired {
+        /// Last block height the transaction could have landed at
+        last_valid_block_height: u64,
+    },
+    /// The transaction landed and failed
+    Reverted {
+        /// Index of the failing instruction, if an instruction failed
+        instruction: Option<u8>,
+        /// Program-specific error code, if the program returned one
+        custom_code: Option<u32>,
+        /// Readable description of the error
+        message: String,
+    },
+}
+
+impl From<&TransactionError> for TradeFailure {
+    fn from(error: &TransactionError) -> Self {
+        match error {
+            TransactionError::InstructionError(index, InstructionError::Custom(code)) => {
+                Self::Reverted {
+                    instruction: Some(*index),
+                    custom_code: Some(*code),
+                    message: format!("instruction {} failed with custom error {:#x}", index, code),
+                }
+            }
+            TransactionError::InstructionError(index, error) => Self::Reverted {
+                instruction: Some(*index),
+                custom_code: None,
+                message: format!("instruction {} failed: {}", index, error),
+            },
+            error => Self::Reverted {
+                instruction: None,
+                custom_code: None,
+                message: error.to_string(),
+            },
+        }
+    }
+}
+
+/// Outcome of simulating a trade's transaction before sending it
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SimulationReport {
+    /// Slot the simulation ran against
+    pub slot: u64,
+    /// Transaction error, if the simulation failed
+    pub error: Option<String>,
+    /// Program logs
+    pub logs: Vec<String>,
+    /// Compute units consumed
+    pub units_consumed: Option<u64>,
+    /// Profit after fees implied by the simulated balances, in USD
+    pub net_profit_usd: Option<Decimal>,
+    /// Why the trade was dropped, if it was
+    pub rejection: Option<String>,
+}
+
+impl SimulationReport {
+    /// Check whether the trade may be sent
+    pub fn passed(&self) -> bool {
+        self.rejection.is_none()
+    }
 }
 
 /// Trade execution status
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TradeStatus {
     /// Trade is waiting to be executed
     Pending,
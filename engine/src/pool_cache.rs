@@ -0,0 +1,571 @@
+//! In-memory pool state cache
+//!
+//! Holds the decoded state of every tracked pool keyed by pool address and
+//! keeps it current from account notifications, so opportunity scans read
+//! local memory instead of RPC. Accounts a pool's quotes depend on (vaults,
+//! tick arrays, bin arrays) are indexed back to their pool, and their raw data
+//! is kept so it can be re-applied whenever the pool account itself changes.
+
+use crate::config::OpportunitiesConfig;
+use crate::dex::{DexManager, PoolState};
+use crate::error::Result;
+use dashmap::DashMap;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+/// Maximum accounts per `getMultipleAccounts` request
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// Account notification from any data source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountUpdate {
+    /// Account address
+    pub pubkey: Pubkey,
+    /// Program owning the account
+    pub owner: Pubkey,
+    /// Raw account data
+    pub data: Vec<u8>,
+    /// Slot the update was observed at
+    pub slot: u64,
+    /// Validator write version, or 0 if the source doesn't provide one
+    pub write_version: u64,
+}
+
+/// A cached pool and the version of the pool account it was decoded from
+#[derive(Debug, Clone)]
+pub struct CachedPool {
+    /// Decoded pool state
+    pub state: Arc<PoolState>,
+    /// Slot of the pool account update
+    pub slot: u64,
+    /// Write version of the pool account update
+    pub write_version: u64,
+    /// When the pool or one of its dependent accounts last changed
+    pub updated_at: Instant,
+    /// Whether the entry is older than the cache's maximum age
+    pub stale: bool,
+}
+
+#[derive(Debug, Clone)]
+struct CachedAccount {
+    data: Vec<u8>,
+    slot: u64,
+    write_version: u64,
+}
+
+/// Updates older than what we already hold are dropped; equal versions are
+/// re-applied since sources without write versions repeat the same slot
+fn is_current(slot: u64, write_version: u64, held_slot: u64, held_write_version: u64) -> bool {
+    (slot, write_version) >= (held_slot, held_write_version)
+}
+
+/// Pool state store shared by data sources and strategies
+#[derive(Debug)]
+pub struct PoolCache {
+    dex: Arc<DexManager>,
+    max_age: Duration,
+    pools: DashMap<Pubkey, CachedPool>,
+    /// Dependent account -> pool it belongs to
+    dependents: DashMap<Pubkey, Pubkey>,
+    /// Raw data of dependent accounts
+    accounts: DashMap<Pubkey, CachedAccount>,
+}
+
+impl PoolCache {
+    /// Create an empty cache decoding pools with `dex`
+    pub fn new(dex: Arc<DexManager>, max_age: Duration) -> Self {
+        Self {
+            dex,
+            max_age,
+            pools: DashMap::new(),
+            dependents: DashMap::new(),
+            accounts: DashMap::new(),
+        }
+    }
+
+    /// Create an empty cache using `max_opportunity_age_ms` as the maximum age
+    pub fn from_config(dex: Arc<DexManager>, config: &OpportunitiesConfig) -> Self {
+        Self::new(dex, Duration::from_millis(config.max_opportunity_age_ms))
+    }
+
+    /// Age after which entries are flagged stale
+    pub fn max_age(&self) -> Duration {
+        self.max_age
+    }
+
+    fn flagged(&self, pool: &CachedPool) -> CachedPool {
+        let mut pool = pool.clone();
+        pool.stale = pool.updated_at.elapsed() > self.max_age;
+        pool
+    }
+
+    /// Get a pool by address
+    pub fn get(&self, address: &Pubkey) -> Option<CachedPool> {
+        self.pools.get(address).map(|entry| self.flagged(&entry))
+    }
+
+    /// Every cached pool, stale ones included
+    pub fn pools(&self) -> Vec<CachedPool> {
+        self.pools
+            .iter()
+            .map(|entry| self.flagged(entry.value()))
+            .collect()
+    }
+
+    /// Every pool that is not stale
+    pub fn fresh_pools(&self) -> Vec<CachedPool> {
+        self.pools()
+            .into_iter()
+            .filter(|pool| !pool.stale)
+            .collect()
+    }
+
+    /// Fresh pools trading `mint_a` against `mint_b`
+    pub fn pools_for_pair(&self, mint_a: &Pubkey, mint_b: &Pubkey) -> Vec<CachedPool> {
+        self.fresh_pools()
+            .into_iter()
+            .filter(|pool| pool.state.contains_mint(mint_a) && pool.state.contains_mint(mint_b))
+            .collect()
+    }
+
+    /// Number of cached pools
+    pub fn len(&self) -> usize {
+        self.pools.len()
+    }
+
+    /// Check whether the cache holds no pools
+    pub fn is_empty(&self) -> bool {
+        self.pools.is_empty()
+    }
+
+    /// Number of pools currently flagged stale
+    pub fn stale_count(&self) -> usize {
+        self.pools().iter().filter(|pool| pool.stale).count()
+    }
+
+    /// Every account the cache wants updates for: pools and their dependents
+    pub fn tracked_accounts(&self) -> Vec<Pubkey> {
+        self.pools
+            .iter()
+            .map(|entry| *entry.key())
+            .chain(self.dependents.iter().map(|entry| *entry.key()))
+            .collect()
+    }
+
+    /// Dependent accounts whose data has not been received yet
+    pub fn missing_dependents(&self) -> Vec<Pubkey> {
+        self.dependents
+            .iter()
+            .map(|entry| *entry.key())
+            .filter(|address| !self.accounts.contains_key(address))
+            .collect()
+    }
+
+    /// Insert or replace a decoded pool
+    pub fn insert(&self, state: PoolState, slot: u64, write_version: u64) -> Result<()> {
+        let adapter = self.dex.adapter(state.dex)?;
+        let address = state.address;
+        let mut state = state;
+
+        let dependents: HashSet<Pubkey> = adapter.dependent_accounts(&state).into_iter().collect();
+        for dependent in &dependents {
+            let Some(account) = self.accounts.get(dependent).map(|entry| entry.data.clone()) else {
+                continue;
+            };
+            if let Err(e) = adapter.apply_dependent_account(&mut state, dependent, &account) {
+                debug!(
+                    "Dropping dependent {} of pool {}: {}",
+                    dependent, address, e
+                );
+            }
+        }
+
+        // Forget dependents that dropped out, e.g. tick arrays the price left
+        let previous = self
+            .pools
+            .get(&address)
+            .map(|entry| adapter.dependent_accounts(&entry.state))
+            .unwrap_or_default();
+        for old in previous.iter().filter(|old| !dependents.contains(old)) {
+            self.dependents.remove(old);
+            self.accounts.remove(old);
+        }
+        for dependent in dependents {
+            self.dependents.insert(dependent, address);
+        }
+
+        self.pools.insert(
+            address,
+            CachedPool {
+                state: Arc::new(state),
+                slot,
+                write_version,
+                updated_at: Instant::now(),
+                stale: false,
+            },
+        );
+        Ok(())
+    }
+
+    /// Remove a pool and its dependent accounts
+    pub fn remove(&self, address: &Pubkey) -> Option<CachedPool> {
+        let (_, pool) = self.pools.remove(address)?;
+        self.dependents.retain(|dependent, owner| {
+            let keep = owner != address;
+            if !keep {
+                self.accounts.remove(dependent);
+            }
+            keep
+        });
+        Some(pool)
+    }
+
+    /// Apply an account notification, returning the pool it changed, if any.
+    ///
+    /// Pool accounts of a registered DEX are decoded and cached even if they
+    /// weren't tracked before; dependent accounts update their pool in place.
+    pub fn apply_account_update(&self, update: &AccountUpdate) -> Result<Option<Pubkey>> {
+        if let Some(pool) = self
+            .dependents
+            .get(&update.pubkey)
+            .map(|entry| *entry.value())
+        {
+            return self.apply_dependent_update(pool, update);
+        }
+
+        if let Some(held) = self.pools.get(&update.pubkey) {
+            if !is_current(
+                update.slot,
+                update.write_version,
+                held.slot,
+                held.write_version,
+            ) {
+                return Ok(None);
+            }
+        }
+
+        let Some(decoded) = self
+            .dex
+            .decode_pool(&update.owner, update.pubkey, &update.data)
+        else {
+            return Ok(None);
+        };
+        self.insert(decoded?, update.slot, update.write_version)?;
+        Ok(Some(update.pubkey))
+    }
+
+    fn apply_dependent_update(
+        &self,
+        pool: Pubkey,
+        update: &AccountUpdate,
+    ) -> Result<Option<Pubkey>> {
+        if let Some(held) = self.accounts.get(&update.pubkey) {
+            if !is_current(
+                update.slot,
+                update.write_version,
+                held.slot,
+                held.write_version,
+            ) {
+                return Ok(None);
+            }
+        }
+        self.accounts.insert(
+            update.pubkey,
+            CachedAccount {
+                data: update.data.clone(),
+                slot: update.slot,
+                write_version: update.write_version,
+            },
+        );
+
+        let Some(mut entry) = self.pools.get_mut(&pool) else {
+            return Ok(None);
+        };
+        let adapter = self.dex.adapter(entry.state.dex)?;
+        adapter.apply_dependent_account(
+            Arc::make_mut(&mut entry.state),
+            &update.pubkey,
+            &update.data,
+        )?;
+        entry.updated_at = Instant::now();
+        Ok(Some(pool))
+    }
+
+    /// Discover every pool of `dex` over RPC and load their dependent
+    /// accounts, returning the number of pools cached
+    pub async fn seed_from_rpc(&self, rpc: &RpcClient, dex: &str) -> Result<usize> {
+        let pools = self.dex.discover_pools(rpc, dex).await?;
+        let count = pools.len();
+        for pool in pools {
+            self.insert(pool, 0, 0)?;
+        }
+        self.load_missing_dependents(rpc).await?;
+        Ok(count)
+    }
+
+    /// Fetch dependent accounts that haven't been received yet, returning the
+    /// number loaded
+    pub async fn load_missing_dependents(&self, rpc: &RpcClient) -> Result<usize> {
+        let missing = self.missing_dependents();
+        let mut loaded = 0;
+
+        for chunk in missing.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let accounts = rpc.get_multiple_accounts(chunk).await?;
+            for (address, account) in chunk.iter().zip(accounts) {
+                let Some(account) = account else {
+                    continue;
+                };
+                let update = AccountUpdate {
+                    pubkey: *address,
+                    owner: account.owner,
+                    data: account.data,
+                    slot: 0,
+                    write_version: 0,
+                };
+                match self.apply_account_update(&update) {
+                    Ok(_) => loaded += 1,
+                    Err(e) => warn!("Failed to apply dependent account {}: {}", address, e),
+                }
+            }
+        }
+
+        debug!(
+            "Loaded {} of {} missing dependent accounts",
+            loaded,
+            missing.len()
+        );
+        Ok(loaded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dex::{DexAdapter, PoolData, Quote, QuoteRequest, SwapParams};
+    use crate::error::ArbitrageError;
+    use solana_sdk::instruction::Instruction;
+
+    const PROGRAM: Pubkey = Pubkey::new_from_array([7; 32]);
+    const MINT_A: Pubkey = Pubkey::new_from_array([1; 32]);
+    const MINT_B: Pubkey = Pubkey::new_from_array([2; 32]);
+
+    /// Pools are 40 bytes: a 32-byte dependent account address and a u64
+    /// rate. The dependent account's first byte is kept as the pool's
+    /// "liquidity" in `PoolData::Raw`.
+    #[derive(Debug)]
+    struct VaultAdapter;
+
+    impl DexAdapter for VaultAdapter {
+        fn id(&self) -> &'static str {
+            "vault"
+        }
+
+        fn program_id(&self) -> Pubkey {
+            PROGRAM
+        }
+
+        fn pool_account_len(&self) -> usize {
+            40
+        }
+
+        fn decode_pool(&self, address: Pubkey, data: &[u8]) -> Result<PoolState> {
+            Ok(PoolState {
+                address,
+                dex: "vault",
+                mint_a: MINT_A,
+                mint_b: MINT_B,
+                data: PoolData::Raw(data.to_vec()),
+            })
+        }
+
+        fn dependent_accounts(&self, pool: &PoolState) -> Vec<Pubkey> {
+            match &pool.data {
+                PoolData::Raw(data) => vec![Pubkey::try_from(&data[..32]).unwrap()],
+                _ => Vec::new(),
+            }
+        }
+
+        fn apply_dependent_account(
+            &self,
+            pool: &mut PoolState,
+            _address: &Pubkey,
+            data: &[u8],
+        ) -> Result<()> {
+            if let PoolData::Raw(raw) = &mut pool.data {
+                raw.truncate(40);
+                raw.push(data[0]);
+            }
+            Ok(())
+        }
+
+        fn quote(&self, _pool: &PoolState, _request: &QuoteRequest) -> Result<Quote> {
+            Err(ArbitrageError::dex_integration(
+                "VaultAdapter does not quote",
+            ))
+        }
+
+        fn swap_instruction(&self, _pool: &PoolState, _params: &SwapParams) -> Result<Instruction> {
+            Err(ArbitrageError::dex_integration(
+                "VaultAdapter does not swap",
+            ))
+        }
+    }
+
+    fn cache(max_age: Duration) -> PoolCache {
+        let mut dex = DexManager::new();
+        dex.register(Arc::new(VaultAdapter));
+        PoolCache::new(Arc::new(dex), max_age)
+    }
+
+    fn pool_update(pool: Pubkey, vault: Pubkey, rate: u64, slot: u64) -> AccountUpdate {
+        let mut data = vault.to_bytes().to_vec();
+        data.extend_from_slice(&rate.to_le_bytes());
+        AccountUpdate {
+            pubkey: pool,
+            owner: PROGRAM,
+            data,
+            slot,
+            write_version: 0,
+        }
+    }
+
+    fn vault_update(vault: Pubkey, liquidity: u8, slot: u64, write_version: u64) -> AccountUpdate {
+        AccountUpdate {
+            pubkey: vault,
+            owner: Pubkey::new_unique(),
+            data: vec![liquidity],
+            slot,
+            write_version,
+        }
+    }
+
+    fn raw(pool: &CachedPool) -> &[u8] {
+        match &pool.state.data {
+            PoolData::Raw(data) => data,
+            _ => panic!("expected raw pool data"),
+        }
+    }
+
+    #[test]
+    fn test_pool_and_dependent_updates() {
+        let cache = cache(Duration::from_secs(60));
+        let pool = Pubkey::new_unique();
+        let vault = Pubkey::new_unique();
+
+        assert_eq!(
+            cache
+                .apply_account_update(&pool_update(pool, vault, 1, 10))
+                .unwrap(),
+            Some(pool)
+        );
+        assert_eq!(cache.missing_dependents(), vec![vault]);
+
+        assert_eq!(
+            cache
+                .apply_account_update(&vault_update(vault, 5, 11, 1))
+                .unwrap(),
+            Some(pool)
+        );
+        assert_eq!(raw(&cache.get(&pool).unwrap())[40], 5);
+        assert!(cache.missing_dependents().is_empty());
+
+        // A newer pool account keeps the cached vault data
+        cache
+            .apply_account_update(&pool_update(pool, vault, 2, 12))
+            .unwrap();
+        let cached = cache.get(&pool).unwrap();
+        assert_eq!(cached.slot, 12);
+        assert_eq!(raw(&cached)[32], 2);
+        assert_eq!(raw(&cached)[40], 5);
+
+        // Unrelated accounts are ignored
+        let other = vault_update(Pubkey::new_unique(), 1, 12, 0);
+        assert_eq!(cache.apply_account_update(&other).unwrap(), None);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.tracked_accounts().len(), 2);
+    }
+
+    #[test]
+    fn test_out_of_order_updates_are_dropped() {
+        let cache = cache(Duration::from_secs(60));
+        let pool = Pubkey::new_unique();
+        let vault = Pubkey::new_unique();
+
+        cache
+            .apply_account_update(&pool_update(pool, vault, 2, 20))
+            .unwrap();
+        assert_eq!(
+            cache
+                .apply_account_update(&pool_update(pool, vault, 1, 19))
+                .unwrap(),
+            None
+        );
+        assert_eq!(raw(&cache.get(&pool).unwrap())[32], 2);
+
+        cache
+            .apply_account_update(&vault_update(vault, 9, 20, 5))
+            .unwrap();
+        assert_eq!(
+            cache
+                .apply_account_update(&vault_update(vault, 3, 20, 4))
+                .unwrap(),
+            None
+        );
+        assert_eq!(raw(&cache.get(&pool).unwrap())[40], 9);
+    }
+
+    #[test]
+    fn test_dependents_follow_the_pool() {
+        let cache = cache(Duration::from_secs(60));
+        let pool = Pubkey::new_unique();
+        let old_vault = Pubkey::new_unique();
+        let new_vault = Pubkey::new_unique();
+
+        cache
+            .apply_account_update(&pool_update(pool, old_vault, 1, 1))
+            .unwrap();
+        cache
+            .apply_account_update(&vault_update(old_vault, 4, 1, 0))
+            .unwrap();
+        cache
+            .apply_account_update(&pool_update(pool, new_vault, 1, 2))
+            .unwrap();
+
+        assert_eq!(cache.missing_dependents(), vec![new_vault]);
+        assert_eq!(
+            cache
+                .apply_account_update(&vault_update(old_vault, 8, 3, 0))
+                .unwrap(),
+            None
+        );
+
+        cache.remove(&pool).unwrap();
+        assert!(cache.is_empty());
+        assert!(cache.tracked_accounts().is_empty());
+    }
+
+    #[test]
+    fn test_stale_entries_are_flagged() {
+        let cache = cache(Duration::from_millis(1));
+        let pool = Pubkey::new_unique();
+        cache
+            .apply_account_update(&pool_update(pool, Pubkey::new_unique(), 1, 1))
+            .unwrap();
+
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(cache.get(&pool).unwrap().stale);
+        assert_eq!(cache.stale_count(), 1);
+        assert!(cache.fresh_pools().is_empty());
+        assert!(cache.pools_for_pair(&MINT_A, &MINT_B).is_empty());
+
+        let cache = PoolCache::new(cache.dex.clone(), Duration::from_secs(60));
+        cache
+            .apply_account_update(&pool_update(pool, Pubkey::new_unique(), 1, 1))
+            .unwrap();
+        assert_eq!(cache.pools_for_pair(&MINT_B, &MINT_A).len(), 1);
+    }
+}
@@ -0,0 +1,232 @@
+//! USD valuation of token amounts
+//!
+//! Prices are derived from cached pools rather than an external feed: USD
+//! stablecoins are pegged at 1, every token pooled against a priced token
+//! takes its spot price from the deepest such pool, and this repeats for a
+//! few hops so e.g. RAY is priced through RAY/SOL and SOL/USDC.
+
+use crate::config::TokensConfig;
+use crate::dex::{DexManager, PoolState, QuoteRequest};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::str::FromStr;
+use tracing::warn;
+
+/// Symbols of tokens valued at exactly one dollar
+pub const USD_STABLECOINS: &[&str] = &["USDC", "USDT"];
+
+/// Hops from a stablecoin through which prices are propagated
+const PRICE_HOPS: usize = 3;
+
+/// Fraction of one whole token quoted to sample a spot price
+const SPOT_SAMPLE_DIVISOR: u64 = 1_000;
+
+/// USD prices of the tokens reachable from a stablecoin at one point in time
+#[derive(Debug, Clone, Default)]
+pub struct PriceSnapshot {
+    prices: HashMap<Pubkey, f64>,
+    decimals: HashMap<Pubkey, u8>,
+    default_decimals: u8,
+}
+
+impl PriceSnapshot {
+    /// USD price of one whole token
+    pub fn price(&self, mint: &Pubkey) -> Option<f64> {
+        self.prices.get(mint).copied()
+    }
+
+    /// Decimals of `mint`, falling back to the configured default
+    pub fn decimals(&self, mint: &Pubkey) -> u8 {
+        self.decimals
+            .get(mint)
+            .copied()
+            .unwrap_or(self.default_decimals)
+    }
+
+    /// Convert base units of `mint` to whole tokens
+    pub fn ui_amount(&self, mint: &Pubkey, amount: u64) -> f64 {
+        amount as f64 / 10f64.powi(self.decimals(mint) as i32)
+    }
+
+    /// USD value of `amount` base units of `mint`
+    pub fn usd_value(&self, mint: &Pubkey, amount: u64) -> Option<f64> {
+        Some(self.ui_amount(mint, amount) * self.price(mint)?)
+    }
+
+    /// Base units of `mint` worth `usd` dollars
+    pub fn amount_for_usd(&self, mint: &Pubkey, usd: f64) -> Option<u64> {
+        let price = self.price(mint).filter(|price| *price > 0.0)?;
+        let amount = usd / price * 10f64.powi(self.decimals(mint) as i32);
+        (amount >= 1.0).then_some(amount.min(u64::MAX as f64) as u64)
+    }
+
+    /// USD value of a pool's reserves. A side whose token has no price is
+    /// assumed to be worth as much as the other side.
+    pub fn pool_liquidity_usd(&self, pool: &PoolState, reserves: (u64, u64)) -> Option<f64> {
+        let a = self.usd_value(&pool.mint_a, reserves.0);
+        let b = self.usd_value(&pool.mint_b, reserves.1);
+        match (a, b) {
+            (Some(a), Some(b)) => Some(a + b),
+            (Some(one), None) | (None, Some(one)) => Some(one * 2.0),
+            (None, None) => None,
+        }
+    }
+
+    /// Every priced token with its USD price
+    pub fn iter(&self) -> impl Iterator<Item = (&Pubkey, f64)> + '_ {
+        self.prices.iter().map(|(mint, price)| (mint, *price))
+    }
+
+    /// Number of priced tokens
+    pub fn len(&self) -> usize {
+        self.prices.len()
+    }
+
+    /// Check whether no token is priced
+    pub fn is_empty(&self) -> bool {
+        self.prices.is_empty()
+    }
+}
+
+/// Prices tokens from pool state
+#[derive(Debug, Clone)]
+pub struct PriceOracle {
+    stablecoins: Vec<Pubkey>,
+    decimals: HashMap<Pubkey, u8>,
+    default_decimals: u8,
+}
+
+impl PriceOracle {
+    /// Create an oracle from the known tokens
+    pub fn new(tokens: &TokensConfig) -> Self {
+        let mut stablecoins = Vec::new();
+        let mut decimals = HashMap::new();
+        for token in tokens.tokens.values() {
+            let Ok(mint) = Pubkey::from_str(&token.mint) else {
+                warn!(
+                    "Ignoring token {} with invalid mint {}",
+                    token.symbol, token.mint
+                );
+                continue;
+            };
+            decimals.insert(mint, token.decimals);
+            if USD_STABLECOINS.contains(&token.symbol.as_str()) {
+                stablecoins.push(mint);
+            }
+        }
+
+        Self {
+            stablecoins,
+            decimals,
+            default_decimals: tokens.default_decimals,
+        }
+    }
+
+    /// Price every token reachable from a stablecoin through `pools`
+    pub fn snapshot<'a>(
+        &self,
+        dex: &DexManager,
+        pools: impl IntoIterator<Item = &'a PoolState>,
+    ) -> PriceSnapshot {
+        let pools: Vec<&PoolState> = pools.into_iter().collect();
+        let mut snapshot = PriceSnapshot {
+            prices: self.stablecoins.iter().map(|mint| (*mint, 1.0)).collect(),
+            decimals: self.decimals.clone(),
+            default_decimals: self.default_decimals,
+        };
+
+        for _ in 0..PRICE_HOPS {
+            // Deepest pool pairing an unpriced token with a priced one:
+            // (depth in USD of the priced side, price)
+            let mut found: HashMap<Pubkey, (f64, f64)> = HashMap::new();
+            for pool in &pools {
+                for (known, unknown) in [(pool.mint_a, pool.mint_b), (pool.mint_b, pool.mint_a)] {
+                    if snapshot.prices.contains_key(&unknown) {
+                        continue;
+                    }
+                    let Some(price) = spot_price(dex, &snapshot, pool, &unknown, &known) else {
+                        continue;
+                    };
+                    let depth = dex
+                        .reserves(pool)
+                        .and_then(|(a, b)| {
+                            let reserve = if known == pool.mint_a { a } else { b };
+                            snapshot.usd_value(&known, reserve)
+                        })
+                        .unwrap_or(0.0);
+                    let best = found.entry(unknown).or_insert((depth, price));
+                    if depth > best.0 {
+                        *best = (depth, price);
+                    }
+                }
+            }
+
+            if found.is_empty() {
+                break;
+            }
+            snapshot
+                .prices
+                .extend(found.into_iter().map(|(mint, (_, price))| (mint, price)));
+        }
+
+        snapshot
+    }
+}
+
+/// USD price of `unknown` implied by selling a small amount of it for `known`
+fn spot_price(
+    dex: &DexManager,
+    snapshot: &PriceSnapshot,
+    pool: &PoolState,
+    unknown: &Pubkey,
+    known: &Pubkey,
+) -> Option<f64> {
+    let known_price = snapshot.price(known)?;
+    let amount = (10u64.pow(snapshot.decimals(unknown) as u32) / SPOT_SAMPLE_DIVISOR).max(1);
+    let quote = dex
+        .quote(pool, &QuoteRequest::exact_in(*unknown, amount))
+        .ok()?;
+    if quote.out_amount == 0 {
+        return None;
+    }
+    let rate = snapshot.ui_amount(known, quote.out_amount)
+        / snapshot.ui_amount(unknown, quote.in_amount - quote.fee_amount);
+    Some(rate * known_price)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dex::RaydiumAmmAdapter;
+    use crate::test_support::test_pool;
+    use std::sync::Arc;
+
+    const SOL: Pubkey = Pubkey::from_str_const("So11111111111111111111111111111111111111112");
+    const USDC: Pubkey = Pubkey::from_str_const("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+
+    #[test]
+    fn test_prices_propagate_from_stablecoins() {
+        let mut dex = DexManager::new();
+        dex.register(Arc::new(RaydiumAmmAdapter::new()));
+        let ray = Pubkey::new_unique();
+
+        // 1_000 SOL / 150_000 USDC and 10_000 RAY / 10 SOL, fee free
+        let pools = [
+            test_pool(SOL, USDC, 1_000_000_000_000, 150_000_000_000, 0),
+            test_pool(ray, SOL, 10_000_000_000_000, 10_000_000_000, 0),
+        ];
+        let snapshot = PriceOracle::new(&TokensConfig::default()).snapshot(&dex, &pools);
+
+        assert_eq!(snapshot.price(&USDC), Some(1.0));
+        assert!((snapshot.price(&SOL).unwrap() - 150.0).abs() < 0.01);
+        // RAY has no configured decimals and falls back to 9
+        assert!((snapshot.price(&ray).unwrap() - 0.15).abs() < 0.001);
+        assert!((snapshot.usd_value(&SOL, 2_000_000_000).unwrap() - 300.0).abs() < 0.1);
+        assert_eq!(snapshot.amount_for_usd(&USDC, 5.0), Some(5_000_000));
+
+        let liquidity = snapshot
+            .pool_liquidity_usd(&pools[0], dex.reserves(&pools[0]).unwrap())
+            .unwrap();
+        assert!((liquidity - 300_000.0).abs() < 100.0);
+    }
+}
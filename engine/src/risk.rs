@@ -0,0 +1,527 @@
+//! Pre-trade risk checks
+//!
+//! [`RiskManager`] gates every opportunity before it is executed against the
+//! `[risk]` limits: realized loss within the current UTC day, drawdown from
+//! peak equity, total open exposure, and open exposure per token and per DEX.
+//! Exposure is counted from the moment a trade is admitted until it is
+//! closed, so concurrent trades can't jointly breach a limit each of them
+//! respects on its own. Drawdown is measured from the equity the manager is
+//! given, which the engine takes from the value of the wallet once it can
+//! read and price it; until then there is no drawdown to measure and nothing
+//! is admitted. Repeated losses are handled separately by the
+//! [`circuit_breaker`], repeatedly failing routes are blacklisted by the
+//! [`reputation`] store, and trade sizes are chosen by the
+//! [`position_sizing`] module.
+
+pub mod circuit_breaker;
+pub mod position_sizing;
+pub mod reputation;
+
+use crate::config::{Config, RiskConfig};
+use crate::models::ArbitrageOpportunity;
+use crate::pricing::PriceSnapshot;
+use chrono::{DateTime, NaiveDate, Utc};
+use parking_lot::RwLock;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{BTreeSet, HashMap};
+use std::str::FromStr;
+use thiserror::Error;
+use tracing::{info, warn};
+
+pub use circuit_breaker::{
+    BreakerEvent, BreakerScope, BreakerState, BreakerStatus, CircuitBreaker, TradeOutcome,
+};
+pub use position_sizing::{PositionSize, PositionSizer, SizingInput};
+pub use reputation::{BlacklistEntry, Hop, RouteKey, RouteReputation};
+
+/// Why the risk manager refused a trade
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum RiskRejection {
+    /// Today's realized losses reached the daily limit
+    #[error("daily loss of ${loss_usd:.2} reached the ${limit_usd:.2} limit")]
+    DailyLoss {
+        /// Realized loss today, in USD
+        loss_usd: f64,
+        /// Configured limit, in USD
+        limit_usd: f64,
+    },
+    /// There is no equity to measure drawdown against, such as before the
+    /// wallet has been valued
+    #[error("no equity to trade against")]
+    NoEquity,
+    /// Equity fell too far below its peak
+    #[error("drawdown of {drawdown_percent:.2}% reached the {limit_percent:.2}% limit")]
+    Drawdown {
+        /// Decline from peak equity, in percent
+        drawdown_percent: f64,
+        /// Configured limit, in percent
+        limit_percent: f64,
+    },
+    /// The trade would take open exposure over the portfolio limit
+    #[error("exposure of ${exposure_usd:.2} would exceed the ${limit_usd:.2} portfolio limit")]
+    PortfolioValue {
+        /// Open exposure including the trade, in USD
+        exposure_usd: f64,
+        /// Configured limit, in USD
+        limit_usd: f64,
+    },
+    /// The trade would concentrate too much exposure in one token
+    #[error("exposure of ${exposure_usd:.2} to {mint} would exceed the ${limit_usd:.2} limit")]
+    TokenConcentration {
+        /// Token the exposure is in
+        mint: String,
+        /// Open exposure to the token including the trade, in USD
+        exposure_usd: f64,
+        /// Limit derived from the configured percentage, in USD
+        limit_usd: f64,
+    },
+    /// The trade would concentrate too much exposure in one DEX
+    #[error("exposure of ${exposure_usd:.2} to {dex} would exceed the ${limit_usd:.2} limit")]
+    DexConcentration {
+        /// DEX the exposure is on
+        dex: String,
+        /// Open exposure to the DEX including the trade, in USD
+        exposure_usd: f64,
+        /// Limit derived from the configured percentage, in USD
+        limit_usd: f64,
+    },
+    /// The trade's size can't be valued
+    #[error("no price for {mint}")]
+    Unpriced {
+        /// Token without a price
+        mint: String,
+    },
+}
+
+/// Exposure of one admitted trade
+#[derive(Debug, Clone)]
+struct Position {
+    size_usd: f64,
+    mints: BTreeSet<String>,
+    dexes: BTreeSet<String>,
+}
+
+impl Position {
+    fn of(opportunity: &ArbitrageOpportunity, size_usd: f64) -> Self {
+        let mut mints = BTreeSet::from([opportunity.token_mint.clone()]);
+        let mut dexes = BTreeSet::new();
+        for leg in &opportunity.legs {
+            mints.insert(leg.input_mint.clone());
+            mints.insert(leg.output_mint.clone());
+            dexes.insert(leg.dex.clone());
+        }
+        Self {
+            size_usd,
+            mints,
+            dexes,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct RiskState {
+    day: NaiveDate,
+    daily_pnl_usd: f64,
+    equity_usd: f64,
+    peak_equity_usd: f64,
+    open: HashMap<String, Position>,
+}
+
+/// Enforces the configured risk limits
+#[derive(Debug)]
+pub struct RiskManager {
+    config: RiskConfig,
+    state: RwLock<RiskState>,
+}
+
+/// Value of the input of `opportunity`, in USD
+pub fn trade_size_usd(opportunity: &ArbitrageOpportunity, prices: &PriceSnapshot) -> Option<f64> {
+    let mint = Pubkey::from_str(&opportunity.token_mint).ok()?;
+    prices.usd_value(&mint, opportunity.input_amount)
+}
+
+impl RiskManager {
+    /// Create a risk manager starting from `equity_usd`
+    pub fn new(config: RiskConfig, equity_usd: f64) -> Self {
+        let equity = equity_usd.max(0.0);
+        Self {
+            state: RwLock::new(RiskState {
+                day: Utc::now().date_naive(),
+                daily_pnl_usd: 0.0,
+                equity_usd: equity,
+                peak_equity_usd: equity,
+                open: HashMap::new(),
+            }),
+            config,
+        }
+    }
+
+    /// Create a risk manager with the configured limits, starting from
+    /// `equity_usd`
+    pub fn from_config(config: &Config, equity_usd: f64) -> Self {
+        Self::new(config.risk.clone(), equity_usd)
+    }
+
+    /// Risk limits
+    pub fn config(&self) -> &RiskConfig {
+        &self.config
+    }
+
+    /// Realized profit and loss of the current UTC day, in USD
+    pub fn daily_pnl_usd(&self) -> f64 {
+        let mut state = self.state.write();
+        Self::roll_day(&mut state, Utc::now());
+        state.daily_pnl_usd
+    }
+
+    /// Current equity, in USD
+    pub fn equity_usd(&self) -> f64 {
+        self.state.read().equity_usd
+    }
+
+    /// Rebase equity on `equity_usd`, such as the wallet's value once it is
+    /// known. Drawdown is measured from here on.
+    pub fn set_equity(&self, equity_usd: f64) {
+        let mut state = self.state.write();
+        state.equity_usd = equity_usd.max(0.0);
+        state.peak_equity_usd = state.equity_usd;
+        info!("Risk equity set to ${:.2}", state.equity_usd);
+    }
+
+    /// Decline of equity from its peak, in percent
+    pub fn drawdown_percent(&self) -> f64 {
+        let state = self.state.read();
+        Self::drawdown(&state)
+    }
+
+    /// Open exposure of admitted trades, in USD
+    pub fn exposure_usd(&self) -> f64 {
+        self.state
+            .read()
+            .open
+            .values()
+            .map(|position| position.size_usd)
+            .sum()
+    }
+
+    /// Check `opportunity` against every limit without admitting it
+    pub fn check(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        prices: &PriceSnapshot,
+    ) -> Result<(), RiskRejection> {
+        let position = self.position(opportunity, prices)?;
+        let mut state = self.state.write();
+        Self::roll_day(&mut state, Utc::now());
+        self.evaluate(&state, &position)
+    }
+
+    /// Check `opportunity` and, if every limit allows it, count its exposure
+    /// until [`RiskManager::close`] is called with its id
+    pub fn admit(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        prices: &PriceSnapshot,
+    ) -> Result<(), RiskRejection> {
+        let position = self.position(opportunity, prices)?;
+        let mut state = self.state.write();
+        Self::roll_day(&mut state, Utc::now());
+        if let Err(rejection) = self.evaluate(&state, &position) {
+            warn!("Rejected opportunity {}: {}", opportunity.id, rejection);
+            return Err(rejection);
+        }
+        state.open.insert(opportunity.id.clone(), position);
+        Ok(())
+    }
+
+    /// Release the exposure of the admitted opportunity `opportunity_id` and
+    /// record the profit or loss it realized
+    pub fn close(&self, opportunity_id: &str, pnl_usd: f64) {
+        let mut state = self.state.write();
+        Self::roll_day(&mut state, Utc::now());
+        state.open.remove(opportunity_id);
+        state.daily_pnl_usd += pnl_usd;
+        state.equity_usd += pnl_usd;
+        state.peak_equity_usd = state.peak_equity_usd.max(state.equity_usd);
+        if pnl_usd < 0.0 {
+            info!(
+                "Trade {} lost ${:.2}; ${:.2} today, {:.2}% drawdown",
+                opportunity_id,
+                -pnl_usd,
+                state.daily_pnl_usd,
+                Self::drawdown(&state)
+            );
+        }
+    }
+
+    fn position(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        prices: &PriceSnapshot,
+    ) -> Result<Position, RiskRejection> {
+        let size_usd =
+            trade_size_usd(opportunity, prices).ok_or_else(|| RiskRejection::Unpriced {
+                mint: opportunity.token_mint.clone(),
+            })?;
+        Ok(Position::of(opportunity, size_usd))
+    }
+
+    fn roll_day(state: &mut RiskState, now: DateTime<Utc>) {
+        let today = now.date_naive();
+        if state.day != today {
+            state.day = today;
+            state.daily_pnl_usd = 0.0;
+        }
+    }
+
+    fn drawdown(state: &RiskState) -> f64 {
+        if state.peak_equity_usd <= 0.0 {
+            return 0.0;
+        }
+        ((state.peak_equity_usd - state.equity_usd) / state.peak_equity_usd * 100.0).max(0.0)
+    }
+
+    fn evaluate(&self, state: &RiskState, position: &Position) -> Result<(), RiskRejection> {
+        let config = &self.config;
+
+        if state.peak_equity_usd <= 0.0 {
+            return Err(RiskRejection::NoEquity);
+        }
+
+        if -state.daily_pnl_usd >= config.max_daily_loss_usd {
+            return Err(RiskRejection::DailyLoss {
+                loss_usd: -state.daily_pnl_usd,
+                limit_usd: config.max_daily_loss_usd,
+            });
+        }
+
+        let drawdown = Self::drawdown(state);
+        if drawdown >= config.max_drawdown_percent {
+            return Err(RiskRejection::Drawdown {
+                drawdown_percent: drawdown,
+                limit_percent: config.max_drawdown_percent,
+            });
+        }
+
+        let open = || state.open.values();
+        let exposure = open().map(|open| open.size_usd).sum::<f64>() + position.size_usd;
+        if exposure > config.max_portfolio_value_usd {
+            return Err(RiskRejection::PortfolioValue {
+                exposure_usd: exposure,
+                limit_usd: config.max_portfolio_value_usd,
+            });
+        }
+
+        let limit_usd = (config.max_portfolio_value_usd * config.max_token_concentration_percent
+            / 100.0)
+            .min(config.max_token_exposure_usd.unwrap_or(f64::INFINITY));
+        for mint in &position.mints {
+            let exposure = open()
+                .filter(|open| open.mints.contains(mint))
+                .map(|open| open.size_usd)
+                .sum::<f64>()
+                + position.size_usd;
+            if exposure > limit_usd {
+                return Err(RiskRejection::TokenConcentration {
+                    mint: mint.clone(),
+                    exposure_usd: exposure,
+                    limit_usd,
+                });
+            }
+        }
+
+        let limit_usd = (config.max_portfolio_value_usd * config.max_dex_concentration_percent
+            / 100.0)
+            .min(config.max_dex_exposure_usd.unwrap_or(f64::INFINITY));
+        for dex in &position.dexes {
+            let exposure = open()
+                .filter(|open| open.dexes.contains(dex))
+                .map(|open| open.size_usd)
+                .sum::<f64>()
+                + position.size_usd;
+            if exposure > limit_usd {
+                return Err(RiskRejection::DexConcentration {
+                    dex: dex.clone(),
+                    exposure_usd: exposure,
+                    limit_usd,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TokensConfig;
+    use crate::dex::{DexManager, METEORA, NATIVE_MINT, ORCA, RAYDIUM};
+    use crate::models::{OpportunityLeg, OpportunityStatus};
+    use crate::pricing::PriceOracle;
+    use rust_decimal::Decimal;
+
+    const USDC: Pubkey = Pubkey::from_str_const("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+
+    /// Only stablecoins are priced
+    fn prices() -> PriceSnapshot {
+        PriceOracle::new(&TokensConfig::default()).snapshot(&DexManager::new(), [])
+    }
+
+    /// Two-leg USDC route through SOL on `dexes`, trading `usd` dollars
+    fn opportunity(id: &str, usd: u64, dexes: [&str; 2]) -> ArbitrageOpportunity {
+        let now = Utc::now();
+        let leg = |dex: &str, input: Pubkey, output: Pubkey| OpportunityLeg {
+            pool: Pubkey::new_unique().to_string(),
+            dex: dex.to_string(),
+            input_mint: input.to_string(),
+            output_mint: output.to_string(),
+            amount_in: 0,
+            expected_out: 0,
+            fee_amount: 0,
+            price_impact_bps: 0,
+        };
+        ArbitrageOpportunity {
+            id: id.to_string(),
+            token_mint: USDC.to_string(),
+            legs: vec![
+                leg(dexes[0], USDC, NATIVE_MINT),
+                leg(dexes[1], NATIVE_MINT, USDC),
+            ],
+            input_amount: usd * 1_000_000,
+            max_trade_size: Decimal::from(usd),
+            buy_price: Decimal::from(100),
+            sell_price: Decimal::from(101),
+            price_difference: Decimal::new(1, 2),
+            profit_percentage: Decimal::ONE,
+            estimated_profit_usd: Decimal::from(usd / 100),
+            gas_cost_estimate: Decimal::ZERO,
+            net_profit_usd: Decimal::from(usd / 100),
+            confidence_score: Decimal::new(9, 1),
+            risk_score: Decimal::new(1, 1),
+            status: OpportunityStatus::Detected,
+            slot: 1,
+            detected_at: now,
+            expires_at: now,
+        }
+    }
+
+    fn manager() -> RiskManager {
+        RiskManager::new(
+            RiskConfig {
+                max_daily_loss_usd: 500.0,
+                max_drawdown_percent: 10.0,
+                max_portfolio_value_usd: 10_000.0,
+                max_token_concentration_percent: 50.0,
+                max_dex_concentration_percent: 30.0,
+                ..RiskConfig::default()
+            },
+            10_000.0,
+        )
+    }
+
+    #[test]
+    fn test_concentration_and_portfolio_limits() {
+        let (risk, prices) = (manager(), prices());
+
+        // $2k through Raydium and Orca counts against both
+        risk.admit(&opportunity("a", 2_000, [RAYDIUM, ORCA]), &prices)
+            .unwrap();
+        assert_eq!(risk.exposure_usd(), 2_000.0);
+
+        // Another $1.5k on Raydium would put $3.5k on it, over 30% of $10k
+        assert_eq!(
+            risk.admit(&opportunity("b", 1_500, [RAYDIUM, METEORA]), &prices),
+            Err(RiskRejection::DexConcentration {
+                dex: RAYDIUM.to_string(),
+                exposure_usd: 3_500.0,
+                limit_usd: 3_000.0,
+            })
+        );
+        assert_eq!(risk.exposure_usd(), 2_000.0);
+
+        // Spread over other DEXes, token exposure hits 50% first
+        risk.admit(&opportunity("c", 2_500, [METEORA, "phoenix"]), &prices)
+            .unwrap();
+        assert!(matches!(
+            risk.check(&opportunity("d", 1_000, ["lifinity", "openbook"]), &prices),
+            Err(RiskRejection::TokenConcentration { exposure_usd, .. }) if exposure_usd == 5_500.0
+        ));
+
+        // Closing releases exposure
+        risk.close("a", 10.0);
+        assert_eq!(risk.exposure_usd(), 2_500.0);
+        risk.check(&opportunity("d", 1_000, ["lifinity", "openbook"]), &prices)
+            .unwrap();
+
+        // And nothing exceeds the portfolio
+        assert!(matches!(
+            risk.check(&opportunity("e", 10_100, ["lifinity", "openbook"]), &prices),
+            Err(RiskRejection::PortfolioValue { .. })
+        ));
+
+        // Exposure caps in USD apply on top of the concentration limits
+        let risk = RiskManager::new(
+            RiskConfig {
+                max_dex_exposure_usd: Some(1_500.0),
+                ..manager().config.clone()
+            },
+            10_000.0,
+        );
+        assert!(matches!(
+            risk.check(&opportunity("f", 2_000, [RAYDIUM, ORCA]), &prices),
+            Err(RiskRejection::DexConcentration { limit_usd, .. }) if limit_usd == 1_500.0
+        ));
+    }
+
+    #[test]
+    fn test_losses_stop_trading() {
+        let (risk, prices) = (manager(), prices());
+        let trade = opportunity("trade", 100, [RAYDIUM, ORCA]);
+
+        risk.close("a", -300.0);
+        risk.close("b", -250.0);
+        assert_eq!(risk.daily_pnl_usd(), -550.0);
+        assert_eq!(
+            risk.check(&trade, &prices),
+            Err(RiskRejection::DailyLoss {
+                loss_usd: 550.0,
+                limit_usd: 500.0,
+            })
+        );
+
+        // The daily limit resets at midnight UTC
+        risk.state.write().day = Utc::now().date_naive().pred_opt().unwrap();
+        risk.check(&trade, &prices).unwrap();
+
+        // Losses carried across days still count towards drawdown
+        risk.close("c", -450.0);
+        assert_eq!(risk.equity_usd(), 9_000.0);
+        assert!(matches!(
+            risk.check(&trade, &prices),
+            Err(RiskRejection::Drawdown { drawdown_percent, .. }) if drawdown_percent == 10.0
+        ));
+
+        // Rebasing on the wallet's value restarts drawdown from there
+        risk.set_equity(9_000.0);
+        assert_eq!(risk.drawdown_percent(), 0.0);
+        risk.check(&trade, &prices).unwrap();
+
+        // Nothing is admitted before there is equity to lose
+        let unfunded = RiskManager::new(RiskConfig::default(), 0.0);
+        assert_eq!(
+            unfunded.check(&trade, &prices),
+            Err(RiskRejection::NoEquity)
+        );
+        unfunded.set_equity(1_000.0);
+        unfunded.check(&trade, &prices).unwrap();
+
+        // Unpriced trades are refused
+        let mut unpriced = trade.clone();
+        unpriced.token_mint = Pubkey::new_unique().to_string();
+        assert!(matches!(
+            risk.check(&unpriced, &prices),
+            Err(RiskRejection::Unpriced { .. })
+        ));
+    }
+}
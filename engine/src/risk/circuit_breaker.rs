@@ -0,0 +1,539 @@
+//! Circuit breakers
+//!
+//! A breaker trips after `max_consecutive_losses` losing or failed trades in
+//! a row, or when more than `max_failure_rate` of the last
+//! `failure_rate_window` trades failed on chain. Breakers are kept globally,
+//! per DEX and per token pair, so a misbehaving pool only halts the routes
+//! through it. A tripped breaker stays open for `circuit_breaker_cooldown_ms`,
+//! then half-opens and lets a single probe trade through: a profitable probe
+//! closes it again, anything else reopens it. Submitting a trade claims the
+//! probe, and a trade that ends up not being sent must release it again.
+//! Every state change is broadcast as a [`BreakerEvent`].
+
+use crate::config::{Config, RiskConfig};
+use crate::models::{ArbitrageOpportunity, TradeResult, TradeStatus};
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::fmt;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+/// Breaker events buffered per subscriber
+const EVENT_BUFFER: usize = 64;
+
+/// What a breaker covers
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerScope {
+    /// Every trade
+    Global,
+    /// Trades with a leg on the DEX
+    Dex(String),
+    /// Trades with a leg between the two mints, in sorted order
+    Pair(String, String),
+}
+
+impl BreakerScope {
+    /// Scope of trades between `a` and `b`, in either direction
+    pub fn pair(a: &str, b: &str) -> Self {
+        if a <= b {
+            Self::Pair(a.to_string(), b.to_string())
+        } else {
+            Self::Pair(b.to_string(), a.to_string())
+        }
+    }
+
+    /// Every scope `opportunity` falls under, global first
+    pub fn of(opportunity: &ArbitrageOpportunity) -> BTreeSet<Self> {
+        let mut scopes = BTreeSet::from([Self::Global]);
+        for leg in &opportunity.legs {
+            scopes.insert(Self::Dex(leg.dex.clone()));
+            scopes.insert(Self::pair(&leg.input_mint, &leg.output_mint));
+        }
+        scopes
+    }
+}
+
+impl fmt::Display for BreakerScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Global => write!(f, "global"),
+            Self::Dex(dex) => write!(f, "dex {}", dex),
+            Self::Pair(a, b) => write!(f, "pair {}/{}", a, b),
+        }
+    }
+}
+
+/// State of a breaker
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    /// Trades pass
+    #[default]
+    Closed,
+    /// Trades are refused until the cooldown passes
+    Open,
+    /// One probe trade may pass
+    HalfOpen,
+}
+
+/// How a trade ended, as far as the breakers are concerned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TradeOutcome {
+    /// Landed and made money
+    Profit,
+    /// Landed and lost money
+    Loss,
+    /// Failed on chain or expired
+    Failed,
+}
+
+impl TradeOutcome {
+    /// Outcome of a resolved trade; landed trades of unknown profit count as
+    /// profitable
+    pub fn of(trade: &TradeResult) -> Self {
+        match trade.status {
+            TradeStatus::Failed => Self::Failed,
+            _ if trade
+                .net_profit
+                .is_some_and(|profit| profit < Decimal::ZERO) =>
+            {
+                Self::Loss
+            }
+            _ => Self::Profit,
+        }
+    }
+}
+
+/// State change of a breaker
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BreakerEvent {
+    /// Breaker that changed
+    pub scope: BreakerScope,
+    /// New state
+    pub state: BreakerState,
+    /// Why it changed
+    pub reason: String,
+    /// When it changed
+    pub at: DateTime<Utc>,
+}
+
+/// Snapshot of one breaker
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BreakerStatus {
+    /// Breaker the snapshot is of
+    pub scope: BreakerScope,
+    /// Current state
+    pub state: BreakerState,
+    /// Losing or failed trades in a row
+    pub consecutive_losses: u32,
+    /// Share of failed trades in the recent window
+    pub failure_rate: f64,
+    /// Time until an open breaker half-opens, in milliseconds
+    pub reopens_in_ms: Option<u64>,
+}
+
+#[derive(Debug, Default)]
+struct Breaker {
+    state: BreakerState,
+    opened_at: Option<Instant>,
+    probing: bool,
+    consecutive_losses: u32,
+    /// Whether each recent trade failed, oldest first
+    recent: VecDeque<bool>,
+}
+
+impl Breaker {
+    fn failure_rate(&self) -> f64 {
+        if self.recent.is_empty() {
+            return 0.0;
+        }
+        self.recent.iter().filter(|failed| **failed).count() as f64 / self.recent.len() as f64
+    }
+
+    fn open(&mut self) {
+        self.state = BreakerState::Open;
+        self.opened_at = Some(Instant::now());
+        self.probing = false;
+    }
+}
+
+/// Halts trading globally, per DEX or per pair after repeated losses
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    enabled: bool,
+    max_consecutive_losses: u32,
+    max_failure_rate: f64,
+    window: usize,
+    cooldown: Duration,
+    breakers: RwLock<HashMap<BreakerScope, Breaker>>,
+    events: broadcast::Sender<BreakerEvent>,
+}
+
+impl CircuitBreaker {
+    /// Create breakers with the circuit breaker settings of `config`
+    pub fn new(config: &RiskConfig) -> Self {
+        Self {
+            enabled: config.enable_circuit_breaker,
+            max_consecutive_losses: config.max_consecutive_losses.max(1),
+            max_failure_rate: config.max_failure_rate,
+            window: config.failure_rate_window.max(1),
+            cooldown: Duration::from_millis(config.circuit_breaker_cooldown_ms),
+            breakers: RwLock::new(HashMap::new()),
+            events: broadcast::channel(EVENT_BUFFER).0,
+        }
+    }
+
+    /// Create breakers with the configured settings
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(&config.risk)
+    }
+
+    /// Subscribe to breaker state changes
+    pub fn subscribe(&self) -> broadcast::Receiver<BreakerEvent> {
+        self.events.subscribe()
+    }
+
+    fn emit(&self, scope: &BreakerScope, state: BreakerState, reason: String) {
+        match state {
+            BreakerState::Open => warn!("Circuit breaker {} opened: {}", scope, reason),
+            _ => info!("Circuit breaker {} is now {:?}: {}", scope, state, reason),
+        }
+        // Nobody listening is fine
+        let _ = self.events.send(BreakerEvent {
+            scope: scope.clone(),
+            state,
+            reason,
+            at: Utc::now(),
+        });
+    }
+
+    /// Half-open every open breaker whose cooldown has passed
+    fn cool_down(&self, breakers: &mut HashMap<BreakerScope, Breaker>) {
+        for (scope, breaker) in breakers.iter_mut() {
+            let cooled = breaker.state == BreakerState::Open
+                && breaker
+                    .opened_at
+                    .is_some_and(|opened| opened.elapsed() >= self.cooldown);
+            if cooled {
+                breaker.state = BreakerState::HalfOpen;
+                breaker.probing = false;
+                self.emit(scope, BreakerState::HalfOpen, "cooldown passed".to_string());
+            }
+        }
+    }
+
+    /// First breaker among `scopes` refusing trades
+    fn refusal(
+        breakers: &HashMap<BreakerScope, Breaker>,
+        scopes: &BTreeSet<BreakerScope>,
+    ) -> Result<(), BreakerScope> {
+        for scope in scopes {
+            let Some(breaker) = breakers.get(scope) else {
+                continue;
+            };
+            let refused = match breaker.state {
+                BreakerState::Closed => false,
+                BreakerState::Open => true,
+                BreakerState::HalfOpen => breaker.probing,
+            };
+            if refused {
+                return Err(scope.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// Check whether `opportunity` may be submitted, returning the first
+    /// breaker refusing it otherwise. Half-open breakers allow it unless
+    /// their probe is already claimed; nothing is claimed here.
+    pub fn allow(&self, opportunity: &ArbitrageOpportunity) -> Result<(), BreakerScope> {
+        if !self.enabled {
+            return Ok(());
+        }
+        let mut breakers = self.breakers.write();
+        self.cool_down(&mut breakers);
+        Self::refusal(&breakers, &BreakerScope::of(opportunity))
+    }
+
+    /// Like [`Self::allow`], but also claim the probe of every half-open
+    /// breaker `opportunity` falls under, refusing other trades there until
+    /// its outcome is [recorded](Self::record) or the probe is
+    /// [released](Self::release_probe)
+    pub fn claim_probe(&self, opportunity: &ArbitrageOpportunity) -> Result<(), BreakerScope> {
+        if !self.enabled {
+            return Ok(());
+        }
+        let mut breakers = self.breakers.write();
+        self.cool_down(&mut breakers);
+
+        let scopes = BreakerScope::of(opportunity);
+        Self::refusal(&breakers, &scopes)?;
+        for scope in &scopes {
+            if let Some(breaker) = breakers.get_mut(scope) {
+                if breaker.state == BreakerState::HalfOpen {
+                    breaker.probing = true;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Release the probes claimed for `opportunity` when its trade is not
+    /// submitted after all, so the next trade can probe instead
+    pub fn release_probe(&self, opportunity: &ArbitrageOpportunity) {
+        if !self.enabled {
+            return;
+        }
+        let mut breakers = self.breakers.write();
+        for scope in BreakerScope::of(opportunity) {
+            if let Some(breaker) = breakers.get_mut(&scope) {
+                if breaker.state == BreakerState::HalfOpen {
+                    breaker.probing = false;
+                }
+            }
+        }
+    }
+
+    /// Record how a trade of `opportunity` ended
+    pub fn record(&self, opportunity: &ArbitrageOpportunity, outcome: TradeOutcome) {
+        if !self.enabled {
+            return;
+        }
+        let mut breakers = self.breakers.write();
+        for scope in BreakerScope::of(opportunity) {
+            let breaker = breakers.entry(scope.clone()).or_default();
+            breaker.recent.push_back(outcome == TradeOutcome::Failed);
+            while breaker.recent.len() > self.window {
+                breaker.recent.pop_front();
+            }
+            if outcome == TradeOutcome::Profit {
+                breaker.consecutive_losses = 0;
+            } else {
+                breaker.consecutive_losses += 1;
+            }
+
+            match breaker.state {
+                BreakerState::HalfOpen if outcome == TradeOutcome::Profit => {
+                    *breaker = Breaker::default();
+                    self.emit(&scope, BreakerState::Closed, "probe trade succeeded".into());
+                }
+                BreakerState::HalfOpen => {
+                    breaker.open();
+                    self.emit(
+                        &scope,
+                        BreakerState::Open,
+                        format!("probe trade ended as {:?}", outcome),
+                    );
+                }
+                BreakerState::Closed => {
+                    let reason = if breaker.consecutive_losses >= self.max_consecutive_losses {
+                        format!("{} losing trades in a row", breaker.consecutive_losses)
+                    } else if breaker.recent.len() >= self.window
+                        && breaker.failure_rate() > self.max_failure_rate
+                    {
+                        format!(
+                            "{:.0}% of the last {} trades failed",
+                            breaker.failure_rate() * 100.0,
+                            breaker.recent.len()
+                        )
+                    } else {
+                        continue;
+                    };
+                    breaker.open();
+                    self.emit(&scope, BreakerState::Open, reason);
+                }
+                BreakerState::Open => {}
+            }
+        }
+    }
+
+    /// State of the global breaker
+    pub fn global_state(&self) -> BreakerState {
+        let mut breakers = self.breakers.write();
+        self.cool_down(&mut breakers);
+        breakers
+            .get(&BreakerScope::Global)
+            .map(|breaker| breaker.state)
+            .unwrap_or_default()
+    }
+
+    /// Snapshot of every breaker that has seen a trade, global first
+    pub fn statuses(&self) -> Vec<BreakerStatus> {
+        let mut breakers = self.breakers.write();
+        self.cool_down(&mut breakers);
+        let mut statuses: Vec<BreakerStatus> = breakers
+            .iter()
+            .map(|(scope, breaker)| BreakerStatus {
+                scope: scope.clone(),
+                state: breaker.state,
+                consecutive_losses: breaker.consecutive_losses,
+                failure_rate: breaker.failure_rate(),
+                reopens_in_ms: breaker
+                    .opened_at
+                    .filter(|_| breaker.state == BreakerState::Open)
+                    .map(|opened| {
+                        self.cooldown.saturating_sub(opened.elapsed()).as_millis() as u64
+                    }),
+            })
+            .collect();
+        statuses.sort_by(|a, b| a.scope.cmp(&b.scope));
+        statuses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dex::{METEORA, ORCA, RAYDIUM};
+    use crate::models::{OpportunityLeg, OpportunityStatus};
+
+    const SOL: &str = "So11111111111111111111111111111111111111112";
+    const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+    const BONK: &str = "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263";
+
+    fn opportunity(mint: &str, dexes: [&str; 2]) -> ArbitrageOpportunity {
+        let now = Utc::now();
+        let leg = |dex: &str, input: &str, output: &str| OpportunityLeg {
+            pool: format!("{}-{}-{}", dex, input, output),
+            dex: dex.to_string(),
+            input_mint: input.to_string(),
+            output_mint: output.to_string(),
+            amount_in: 0,
+            expected_out: 0,
+            fee_amount: 0,
+            price_impact_bps: 0,
+        };
+        ArbitrageOpportunity {
+            id: crate::utils::generate_id(),
+            token_mint: mint.to_string(),
+            legs: vec![leg(dexes[0], mint, USDC), leg(dexes[1], USDC, mint)],
+            input_amount: 1_000_000_000,
+            max_trade_size: Decimal::ONE,
+            buy_price: Decimal::ONE,
+            sell_price: Decimal::ONE,
+            price_difference: Decimal::ZERO,
+            profit_percentage: Decimal::ZERO,
+            estimated_profit_usd: Decimal::ZERO,
+            gas_cost_estimate: Decimal::ZERO,
+            net_profit_usd: Decimal::ZERO,
+            confidence_score: Decimal::ONE,
+            risk_score: Decimal::ZERO,
+            status: OpportunityStatus::Detected,
+            slot: 1,
+            detected_at: now,
+            expires_at: now,
+        }
+    }
+
+    fn breaker(cooldown_ms: u64) -> CircuitBreaker {
+        CircuitBreaker::new(&RiskConfig {
+            max_consecutive_losses: 3,
+            circuit_breaker_cooldown_ms: cooldown_ms,
+            max_failure_rate: 0.5,
+            failure_rate_window: 4,
+            ..RiskConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_consecutive_losses_trip_every_scope_of_the_route() {
+        let breaker = breaker(60_000);
+        let mut events = breaker.subscribe();
+        let sol = opportunity(SOL, [RAYDIUM, ORCA]);
+
+        breaker.record(&sol, TradeOutcome::Loss);
+        breaker.record(&sol, TradeOutcome::Profit);
+        breaker.record(&sol, TradeOutcome::Loss);
+        breaker.record(&sol, TradeOutcome::Failed);
+        assert!(breaker.allow(&sol).is_ok());
+        breaker.record(&sol, TradeOutcome::Loss);
+
+        assert_eq!(breaker.allow(&sol), Err(BreakerScope::Global));
+        assert_eq!(breaker.global_state(), BreakerState::Open);
+        let opened: Vec<BreakerScope> = std::iter::from_fn(|| events.try_recv().ok())
+            .inspect(|event| assert_eq!(event.state, BreakerState::Open))
+            .map(|event| event.scope)
+            .collect();
+        assert_eq!(opened.len(), 4);
+        assert!(opened.contains(&BreakerScope::pair(USDC, SOL)));
+
+        let statuses = breaker.statuses();
+        assert_eq!(statuses[0].scope, BreakerScope::Global);
+        assert_eq!(statuses[0].consecutive_losses, 3);
+        assert!(statuses[0].reopens_in_ms.unwrap() > 59_000);
+    }
+
+    #[test]
+    fn test_failure_rate_trips_only_the_failing_dex() {
+        let breaker = breaker(60_000);
+        let sol = opportunity(SOL, [RAYDIUM, ORCA]);
+        let bonk = opportunity(BONK, [RAYDIUM, METEORA]);
+        let orca_only = opportunity(BONK, [ORCA, ORCA]);
+
+        // Orca fails half the time, in a pattern that never makes three
+        // losses in a row anywhere else
+        for _ in 0..2 {
+            breaker.record(&sol, TradeOutcome::Failed);
+            breaker.record(&bonk, TradeOutcome::Profit);
+            breaker.record(&orca_only, TradeOutcome::Failed);
+            breaker.record(&bonk, TradeOutcome::Profit);
+        }
+        assert_eq!(
+            breaker.allow(&orca_only),
+            Err(BreakerScope::Dex(ORCA.to_string()))
+        );
+        assert!(breaker.allow(&bonk).is_ok());
+        assert_eq!(breaker.global_state(), BreakerState::Closed);
+    }
+
+    #[test]
+    fn test_half_open_probe() {
+        let breaker = breaker(20);
+        let sol = opportunity(SOL, [RAYDIUM, ORCA]);
+        for _ in 0..3 {
+            breaker.record(&sol, TradeOutcome::Loss);
+        }
+        assert!(breaker.allow(&sol).is_err());
+
+        // After the cooldown a single probe passes, and failing it reopens
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(breaker.global_state(), BreakerState::HalfOpen);
+        assert!(breaker.allow(&sol).is_ok());
+        assert!(breaker.claim_probe(&sol).is_ok());
+        assert_eq!(breaker.allow(&sol), Err(BreakerScope::Global));
+        assert!(breaker.claim_probe(&sol).is_err());
+        breaker.record(&sol, TradeOutcome::Failed);
+        assert_eq!(breaker.global_state(), BreakerState::Open);
+
+        // A probe that is never sent hands the turn to the next trade
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(breaker.claim_probe(&sol).is_ok());
+        breaker.release_probe(&sol);
+        assert_eq!(breaker.global_state(), BreakerState::HalfOpen);
+
+        // A profitable probe closes every breaker again
+        assert!(breaker.claim_probe(&sol).is_ok());
+        breaker.record(&sol, TradeOutcome::Profit);
+        assert!(breaker
+            .statuses()
+            .iter()
+            .all(|status| status.state == BreakerState::Closed));
+        assert!(breaker.claim_probe(&sol).is_ok());
+        assert!(breaker.claim_probe(&sol).is_ok());
+
+        // Disabled breakers never trip
+        let disabled = CircuitBreaker::new(&RiskConfig {
+            enable_circuit_breaker: false,
+            ..RiskConfig::default()
+        });
+        for _ in 0..10 {
+            disabled.record(&sol, TradeOutcome::Failed);
+        }
+        assert!(disabled.allow(&sol).is_ok());
+    }
+}
@@ -0,0 +1,353 @@
+//! Position sizing
+//!
+//! An arbitrage either fills at a profit or, since every route is bounded by
+//! its minimum output, loses at most the slippage tolerance. That makes each
+//! trade a binary bet, and the Kelly criterion gives the share of bankroll
+//! maximizing long-run growth: `p / l - q / w` for success probability `p`,
+//! gain `w` on success and loss `l` on failure, both per dollar staked. Full
+//! Kelly is too aggressive for estimated odds, so positions take
+//! `trading.kelly_fraction` of it, shrunk further when the route's tokens
+//! have been more volatile than `risk.volatility_threshold`, and never exceed
+//! the default position size stretched by `position_size_multiplier`,
+//! `max_position_size_usd`, or the size the route was quoted at.
+//!
+//! Volatility is measured over prices sampled on a fixed clock, at most once
+//! every `risk.risk_check_interval_ms` however often scans run, so the last
+//! `risk.volatility_window` samples span a fixed time: 30 seconds with the
+//! defaults.
+
+use crate::config::{Config, RiskConfig, TradingConfig};
+use crate::models::ArbitrageOpportunity;
+use crate::pricing::PriceSnapshot;
+use crate::risk::trade_size_usd;
+use parking_lot::{Mutex, RwLock};
+use rust_decimal::prelude::ToPrimitive;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, VecDeque};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// Odds and payoff of a trade, per dollar staked
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SizingInput {
+    /// Probability the trade fills as quoted
+    pub success_probability: f64,
+    /// Net gain on success
+    pub win_fraction: f64,
+    /// Loss on failure
+    pub loss_fraction: f64,
+}
+
+impl SizingInput {
+    /// Odds of `opportunity`: its confidence as success probability and its
+    /// net profit relative to its size as gain
+    pub fn of(
+        opportunity: &ArbitrageOpportunity,
+        prices: &PriceSnapshot,
+        loss_fraction: f64,
+    ) -> Option<Self> {
+        let size_usd = trade_size_usd(opportunity, prices).filter(|size| *size > 0.0)?;
+        Some(Self {
+            success_probability: opportunity.confidence_score.to_f64()?,
+            win_fraction: opportunity.net_profit_usd.to_f64()? / size_usd,
+            loss_fraction,
+        })
+    }
+
+    /// Share of bankroll the Kelly criterion stakes, between 0 and 1
+    pub fn kelly(&self) -> f64 {
+        let p = self.success_probability.clamp(0.0, 1.0);
+        if self.win_fraction <= 0.0 || p == 0.0 {
+            return 0.0;
+        }
+        if self.loss_fraction <= 0.0 {
+            return 1.0;
+        }
+        (p / self.loss_fraction - (1.0 - p) / self.win_fraction).clamp(0.0, 1.0)
+    }
+}
+
+/// Size chosen for a trade
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PositionSize {
+    /// Size, in USD
+    pub size_usd: f64,
+    /// Size, in base units of the opportunity's input token
+    pub amount: u64,
+    /// Full Kelly share of bankroll
+    pub kelly: f64,
+    /// Highest recent volatility among the route's tokens
+    pub volatility: f64,
+    /// Factor the Kelly size was scaled by for volatility
+    pub volatility_scale: f64,
+}
+
+/// Sizes trades by fractional Kelly, adjusted for volatility
+#[derive(Debug)]
+pub struct PositionSizer {
+    kelly_fraction: f64,
+    loss_fraction: f64,
+    max_size_usd: f64,
+    volatility_threshold: f64,
+    window: usize,
+    sample_interval: Duration,
+    last_sample: Mutex<Option<Instant>>,
+    /// Recent USD prices per token, oldest first
+    history: RwLock<HashMap<Pubkey, VecDeque<f64>>>,
+}
+
+impl PositionSizer {
+    /// Create a sizer with the position limits of `trading` and the
+    /// volatility settings of `risk`
+    pub fn new(trading: &TradingConfig, risk: &RiskConfig) -> Self {
+        let stretched =
+            trading.default_position_size_usd * trading.position_size_multiplier.max(1.0);
+        Self {
+            kelly_fraction: trading.kelly_fraction.clamp(0.0, 1.0),
+            loss_fraction: trading.max_slippage_bps as f64 / 10_000.0,
+            max_size_usd: stretched.min(trading.max_position_size_usd),
+            volatility_threshold: risk.volatility_threshold,
+            window: risk.volatility_window.max(2),
+            sample_interval: Duration::from_millis(risk.risk_check_interval_ms),
+            last_sample: Mutex::new(None),
+            history: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Create a sizer with the configured limits
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(&config.trading, &config.risk)
+    }
+
+    /// Largest position, in USD
+    pub fn max_size_usd(&self) -> f64 {
+        self.max_size_usd
+    }
+
+    /// Record a price sample of `mint`
+    pub fn observe_price(&self, mint: Pubkey, price: f64) {
+        if !(price > 0.0 && price.is_finite()) {
+            return;
+        }
+        let mut history = self.history.write();
+        let samples = history.entry(mint).or_default();
+        samples.push_back(price);
+        while samples.len() > self.window {
+            samples.pop_front();
+        }
+    }
+
+    /// Record the price of every token in `prices`, unless the last sample
+    /// was taken less than the sampling interval ago
+    pub fn observe(&self, prices: &PriceSnapshot) {
+        {
+            let mut last_sample = self.last_sample.lock();
+            if last_sample.is_some_and(|sampled| sampled.elapsed() < self.sample_interval) {
+                return;
+            }
+            *last_sample = Some(Instant::now());
+        }
+        for (mint, price) in prices.iter() {
+            self.observe_price(*mint, price);
+        }
+    }
+
+    /// Standard deviation of the log returns between recent price samples of
+    /// `mint`, if it has at least two
+    pub fn volatility(&self, mint: &Pubkey) -> Option<f64> {
+        let history = self.history.read();
+        let samples = history.get(mint).filter(|samples| samples.len() >= 2)?;
+        let returns: Vec<f64> = samples
+            .iter()
+            .zip(samples.iter().skip(1))
+            .map(|(a, b)| (b / a).ln())
+            .collect();
+        let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+        let variance =
+            returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / returns.len() as f64;
+        Some(variance.sqrt())
+    }
+
+    /// Highest volatility among the tokens `opportunity` trades, zero if none
+    /// has a history
+    pub fn route_volatility(&self, opportunity: &ArbitrageOpportunity) -> f64 {
+        opportunity
+            .legs
+            .iter()
+            .flat_map(|leg| [&leg.input_mint, &leg.output_mint])
+            .chain([&opportunity.token_mint])
+            .filter_map(|mint| Pubkey::from_str(mint).ok())
+            .filter_map(|mint| self.volatility(&mint))
+            .fold(0.0, f64::max)
+    }
+
+    /// Size `opportunity` for a bankroll of `bankroll_usd`. `None` if the
+    /// trade has no edge or can't be priced.
+    pub fn size(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        prices: &PriceSnapshot,
+        bankroll_usd: f64,
+    ) -> Option<PositionSize> {
+        let quoted_usd = trade_size_usd(opportunity, prices)?;
+        let kelly = SizingInput::of(opportunity, prices, self.loss_fraction)?.kelly();
+        if kelly == 0.0 {
+            return None;
+        }
+        let volatility = self.route_volatility(opportunity);
+        let volatility_scale = if volatility > self.volatility_threshold {
+            self.volatility_threshold / volatility
+        } else {
+            1.0
+        };
+
+        let size_usd = (bankroll_usd.max(0.0) * self.kelly_fraction * kelly * volatility_scale)
+            .min(self.max_size_usd)
+            .min(quoted_usd);
+        let mint = Pubkey::from_str(&opportunity.token_mint).ok()?;
+        let amount = prices.amount_for_usd(&mint, size_usd)?;
+        Some(PositionSize {
+            size_usd,
+            amount,
+            kelly,
+            volatility,
+            volatility_scale,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TokensConfig;
+    use crate::dex::{DexManager, NATIVE_MINT, ORCA, RAYDIUM};
+    use crate::models::{OpportunityLeg, OpportunityStatus};
+    use crate::pricing::PriceOracle;
+    use chrono::Utc;
+    use rust_decimal::Decimal;
+
+    const USDC: Pubkey = Pubkey::from_str_const("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+
+    fn prices() -> PriceSnapshot {
+        PriceOracle::new(&TokensConfig::default()).snapshot(&DexManager::new(), [])
+    }
+
+    /// USDC route through SOL quoted at `usd` dollars
+    fn opportunity(usd: u64, net_profit_usd: Decimal, confidence: Decimal) -> ArbitrageOpportunity {
+        let now = Utc::now();
+        let leg = |dex: &str, input: Pubkey, output: Pubkey| OpportunityLeg {
+            pool: Pubkey::new_unique().to_string(),
+            dex: dex.to_string(),
+            input_mint: input.to_string(),
+            output_mint: output.to_string(),
+            amount_in: 0,
+            expected_out: 0,
+            fee_amount: 0,
+            price_impact_bps: 0,
+        };
+        ArbitrageOpportunity {
+            id: "sized".to_string(),
+            token_mint: USDC.to_string(),
+            legs: vec![
+                leg(RAYDIUM, USDC, NATIVE_MINT),
+                leg(ORCA, NATIVE_MINT, USDC),
+            ],
+            input_amount: usd * 1_000_000,
+            max_trade_size: Decimal::from(usd),
+            buy_price: Decimal::from(100),
+            sell_price: Decimal::from(101),
+            price_difference: Decimal::new(1, 2),
+            profit_percentage: Decimal::ONE,
+            estimated_profit_usd: net_profit_usd,
+            gas_cost_estimate: Decimal::ZERO,
+            net_profit_usd,
+            confidence_score: confidence,
+            risk_score: Decimal::new(1, 1),
+            status: OpportunityStatus::Detected,
+            slot: 1,
+            detected_at: now,
+            expires_at: now,
+        }
+    }
+
+    fn sizer() -> PositionSizer {
+        PositionSizer::new(
+            &TradingConfig {
+                default_position_size_usd: 1_000.0,
+                position_size_multiplier: 1.5,
+                max_position_size_usd: 50_000.0,
+                max_slippage_bps: 100,
+                kelly_fraction: 0.5,
+                ..TradingConfig::default()
+            },
+            &RiskConfig {
+                volatility_threshold: 0.05,
+                volatility_window: 5,
+                ..RiskConfig::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_kelly() {
+        let input = |p, w, l| SizingInput {
+            success_probability: p,
+            win_fraction: w,
+            loss_fraction: l,
+        };
+        // 0.8 / 0.02 - 0.2 / 0.01 = 20: certain enough to stake everything
+        assert_eq!(input(0.8, 0.01, 0.02).kelly(), 1.0);
+        // 0.6 / 0.25 - 0.4 / 0.2 = 0.4
+        assert!((input(0.6, 0.2, 0.25).kelly() - 0.4).abs() < 1e-9);
+        // No edge, no bet
+        assert_eq!(input(0.5, 0.01, 0.02).kelly(), 0.0);
+        assert_eq!(input(0.9, -0.01, 0.02).kelly(), 0.0);
+    }
+
+    #[test]
+    fn test_sizes_are_bounded_and_volatility_adjusted() {
+        let (sizer, prices) = (sizer(), prices());
+        assert_eq!(sizer.max_size_usd(), 1_500.0);
+
+        // 0.7 / 0.01 - 0.3 / 0.005 = 10 is capped at full Kelly, and half
+        // of a $2k bankroll is still under every limit
+        let trade = opportunity(5_000, Decimal::from(25), Decimal::new(7, 1));
+        let size = sizer.size(&trade, &prices, 2_000.0).unwrap();
+        assert_eq!(size.kelly, 1.0);
+        assert_eq!(size.size_usd, 1_000.0);
+        assert_eq!(size.amount, 1_000_000_000);
+
+        // A larger bankroll hits the stretched default size
+        let size = sizer.size(&trade, &prices, 100_000.0).unwrap();
+        assert_eq!(size.size_usd, 1_500.0);
+
+        // And nothing is sized past the quoted size
+        let small = opportunity(800, Decimal::from(4), Decimal::new(7, 1));
+        let size = sizer.size(&small, &prices, 100_000.0).unwrap();
+        assert_eq!(size.size_usd, 800.0);
+
+        // SOL swinging 10% per sample about halves the position
+        for price in [100.0, 110.0, 100.0, 110.0, 100.0] {
+            sizer.observe_price(NATIVE_MINT, price);
+        }
+        let volatility = sizer.volatility(&NATIVE_MINT).unwrap();
+        assert!((volatility - 1.1f64.ln()).abs() < 1e-9);
+        let size = sizer.size(&trade, &prices, 2_000.0).unwrap();
+        assert!((size.volatility_scale - 0.05 / volatility).abs() < 1e-9);
+        assert!((size.size_usd - 1_000.0 * 0.05 / volatility).abs() < 1e-6);
+
+        // Stable prices don't scale anything, and snapshots taken within
+        // the sampling interval of the last one aren't recorded
+        sizer.observe(&prices);
+        sizer.observe(&prices);
+        assert_eq!(sizer.volatility(&USDC), None);
+        for _ in 0..5 {
+            sizer.observe_price(NATIVE_MINT, 100.0);
+        }
+        assert_eq!(sizer.route_volatility(&trade), 0.0);
+
+        // Trades without an edge aren't sized
+        let coin_flip = opportunity(5_000, Decimal::from(25), Decimal::new(5, 1));
+        assert_eq!(sizer.size(&coin_flip, &prices, 2_000.0), None);
+    }
+}
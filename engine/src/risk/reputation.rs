@@ -0,0 +1,450 @@
+//! Route reputation
+//!
+//! A failed trade counts against every hop it took, a pool traded in one
+//! direction, and against its route as a whole. A hop or route failing
+//! `max_failures` times within `failure_window_ms` is blacklisted for
+//! `blacklist_ms`, doubled for every previous blacklisting up to
+//! `max_blacklist_ms`. Blacklistings expire on their own, a successful trade
+//! forgives a route's failures, and a hop or route that stays clean for
+//! `max_blacklist_ms` starts over from a single `blacklist_ms`. Strategies
+//! skip routes through anything blacklisted, and operators can list and
+//! clear entries over the HTTP API.
+
+use crate::config::{Config, RouteBlacklistConfig};
+use crate::dex::PoolState;
+use crate::models::{ArbitrageOpportunity, OpportunityLeg};
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// A pool traded in one direction
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Hop {
+    /// Pool address
+    pub pool: String,
+    /// Mint going into the pool
+    pub input_mint: String,
+    /// Mint coming out of the pool
+    pub output_mint: String,
+}
+
+impl Hop {
+    /// Hop taken by `leg`
+    pub fn of(leg: &OpportunityLeg) -> Self {
+        Self {
+            pool: leg.pool.clone(),
+            input_mint: leg.input_mint.clone(),
+            output_mint: leg.output_mint.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Hop {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({} -> {})",
+            self.pool, self.input_mint, self.output_mint
+        )
+    }
+}
+
+/// What a reputation is kept for
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RouteKey {
+    /// One hop, whatever route it is part of
+    Hop(Hop),
+    /// A full route, hop by hop
+    Route {
+        /// Hops in order
+        hops: Vec<Hop>,
+    },
+}
+
+impl RouteKey {
+    /// Every hop of `hops` followed by the route they form
+    pub fn all(hops: Vec<Hop>) -> Vec<Self> {
+        let mut keys: Vec<Self> = hops.iter().cloned().map(Self::Hop).collect();
+        keys.push(Self::Route { hops });
+        keys
+    }
+
+    /// Every key `opportunity` is judged by
+    pub fn of(opportunity: &ArbitrageOpportunity) -> Vec<Self> {
+        Self::all(opportunity.legs.iter().map(Hop::of).collect())
+    }
+
+    /// Stable identifier of the key, as used by the HTTP API
+    pub fn id(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.to_string().as_bytes());
+        hex::encode(&hasher.finalize()[..8])
+    }
+}
+
+impl fmt::Display for RouteKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Hop(hop) => write!(f, "hop {}", hop),
+            Self::Route { hops } => {
+                write!(f, "route")?;
+                for (i, hop) in hops.iter().enumerate() {
+                    write!(f, "{}{}", if i == 0 { " " } else { ", " }, hop)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A blacklisted hop or route, as reported to the API
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlacklistEntry {
+    /// Identifier to clear the entry by
+    pub id: String,
+    /// What is blacklisted
+    pub key: RouteKey,
+    /// Number of times it has been blacklisted in a row
+    pub strikes: u32,
+    /// When the blacklisting expires
+    pub blacklisted_until: DateTime<Utc>,
+}
+
+#[derive(Debug, Default)]
+struct Reputation {
+    /// Failures since the last blacklisting, oldest first
+    failures: VecDeque<Instant>,
+    last_failure: Option<Instant>,
+    strikes: u32,
+    blacklisted_until: Option<(Instant, DateTime<Utc>)>,
+}
+
+impl Reputation {
+    fn is_blacklisted(&self, now: Instant) -> bool {
+        self.blacklisted_until.is_some_and(|(until, _)| until > now)
+    }
+}
+
+/// Tracks failing hops and routes and blacklists them
+#[derive(Debug)]
+pub struct RouteReputation {
+    config: RouteBlacklistConfig,
+    entries: RwLock<HashMap<RouteKey, Reputation>>,
+}
+
+impl RouteReputation {
+    /// Create an empty store
+    pub fn new(config: RouteBlacklistConfig) -> Self {
+        Self {
+            config,
+            entries: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Create an empty store with the configured thresholds
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(config.route_blacklist.clone())
+    }
+
+    /// Blacklisting thresholds
+    pub fn config(&self) -> &RouteBlacklistConfig {
+        &self.config
+    }
+
+    fn blacklist_duration(&self, strikes: u32) -> Duration {
+        let doublings = strikes.saturating_sub(1).min(32);
+        let ms = self
+            .config
+            .blacklist_ms
+            .saturating_mul(1 << doublings)
+            .min(self.config.max_blacklist_ms.max(self.config.blacklist_ms));
+        Duration::from_millis(ms)
+    }
+
+    /// Record a failed trade of `opportunity`
+    pub fn record_failure(&self, opportunity: &ArbitrageOpportunity) {
+        if !self.config.enabled {
+            return;
+        }
+        let now = Instant::now();
+        let window = Duration::from_millis(self.config.failure_window_ms);
+        let forgiven = Duration::from_millis(self.config.max_blacklist_ms);
+        let mut entries = self.entries.write();
+
+        for key in RouteKey::of(opportunity) {
+            let reputation = entries.entry(key.clone()).or_default();
+            if reputation
+                .last_failure
+                .is_some_and(|last| now.duration_since(last) >= forgiven)
+                && !reputation.is_blacklisted(now)
+            {
+                reputation.strikes = 0;
+            }
+            reputation.last_failure = Some(now);
+            reputation.failures.push_back(now);
+            while reputation
+                .failures
+                .front()
+                .is_some_and(|failed| now.duration_since(*failed) > window)
+            {
+                reputation.failures.pop_front();
+            }
+
+            if reputation.failures.len() >= self.config.max_failures.max(1) as usize
+                && !reputation.is_blacklisted(now)
+            {
+                reputation.strikes += 1;
+                reputation.failures.clear();
+                let duration = self.blacklist_duration(reputation.strikes);
+                let until =
+                    Utc::now() + chrono::Duration::milliseconds(duration.as_millis() as i64);
+                reputation.blacklisted_until = Some((now + duration, until));
+                warn!(
+                    "Blacklisted {} for {}s after repeated failures",
+                    key,
+                    duration.as_secs()
+                );
+            }
+        }
+    }
+
+    /// Record a successful trade of `opportunity`, forgiving the failures of
+    /// its route and hops
+    pub fn record_success(&self, opportunity: &ArbitrageOpportunity) {
+        let now = Instant::now();
+        let mut entries = self.entries.write();
+        for key in RouteKey::of(opportunity) {
+            if let Some(reputation) = entries.get_mut(&key) {
+                reputation.failures.clear();
+                if !reputation.is_blacklisted(now) {
+                    entries.remove(&key);
+                }
+            }
+        }
+    }
+
+    /// Whether `key` is currently blacklisted
+    pub fn is_blacklisted(&self, key: &RouteKey) -> bool {
+        let now = Instant::now();
+        self.entries
+            .read()
+            .get(key)
+            .is_some_and(|reputation| reputation.is_blacklisted(now))
+    }
+
+    fn allows_hops(&self, hops: Vec<Hop>) -> bool {
+        if !self.config.enabled {
+            return true;
+        }
+        let now = Instant::now();
+        let entries = self.entries.read();
+        if !entries
+            .values()
+            .any(|reputation| reputation.is_blacklisted(now))
+        {
+            return true;
+        }
+        RouteKey::all(hops).iter().all(|key| {
+            !entries
+                .get(key)
+                .is_some_and(|reputation| reputation.is_blacklisted(now))
+        })
+    }
+
+    /// Whether neither `opportunity`'s route nor any of its hops is
+    /// blacklisted
+    pub fn allows(&self, opportunity: &ArbitrageOpportunity) -> bool {
+        self.allows_hops(opportunity.legs.iter().map(Hop::of).collect())
+    }
+
+    /// Like [`Self::allows`], for the route trading `start` through `route`
+    pub fn allows_route(&self, route: &[&PoolState], start: &Pubkey) -> bool {
+        let mut mint = *start;
+        let hops = route
+            .iter()
+            .map(|pool| {
+                let output = if pool.mint_a == mint {
+                    pool.mint_b
+                } else {
+                    pool.mint_a
+                };
+                let hop = Hop {
+                    pool: pool.address.to_string(),
+                    input_mint: mint.to_string(),
+                    output_mint: output.to_string(),
+                };
+                mint = output;
+                hop
+            })
+            .collect();
+        self.allows_hops(hops)
+    }
+
+    /// Every active blacklisting, soonest to expire first
+    pub fn blacklist(&self) -> Vec<BlacklistEntry> {
+        let now = Instant::now();
+        let mut blacklist: Vec<BlacklistEntry> = self
+            .entries
+            .read()
+            .iter()
+            .filter(|(_, reputation)| reputation.is_blacklisted(now))
+            .filter_map(|(key, reputation)| {
+                Some(BlacklistEntry {
+                    id: key.id(),
+                    key: key.clone(),
+                    strikes: reputation.strikes,
+                    blacklisted_until: reputation.blacklisted_until?.1,
+                })
+            })
+            .collect();
+        blacklist.sort_by(|a, b| (a.blacklisted_until, &a.key).cmp(&(b.blacklisted_until, &b.key)));
+        blacklist
+    }
+
+    /// Lift the blacklisting with identifier `id` and forget its history,
+    /// returning whether one was found
+    pub fn clear(&self, id: &str) -> bool {
+        let mut entries = self.entries.write();
+        let Some(key) = entries.keys().find(|key| key.id() == id).cloned() else {
+            return false;
+        };
+        entries.remove(&key);
+        info!("Cleared {} from the route blacklist", key);
+        true
+    }
+
+    /// Lift every blacklisting and forget all failures, returning the number
+    /// of blacklistings lifted
+    pub fn clear_all(&self) -> usize {
+        let now = Instant::now();
+        let mut entries = self.entries.write();
+        let lifted = entries
+            .values()
+            .filter(|reputation| reputation.is_blacklisted(now))
+            .count();
+        entries.clear();
+        info!("Cleared the route blacklist ({} entries)", lifted);
+        lifted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::OpportunityStatus;
+    use rust_decimal::Decimal;
+
+    fn opportunity(pools: &[(&str, &str, &str)]) -> ArbitrageOpportunity {
+        let now = Utc::now();
+        ArbitrageOpportunity {
+            id: crate::utils::generate_id(),
+            token_mint: pools[0].1.to_string(),
+            legs: pools
+                .iter()
+                .map(|(pool, input, output)| OpportunityLeg {
+                    pool: pool.to_string(),
+                    dex: "raydium".to_string(),
+                    input_mint: input.to_string(),
+                    output_mint: output.to_string(),
+                    amount_in: 0,
+                    expected_out: 0,
+                    fee_amount: 0,
+                    price_impact_bps: 0,
+                })
+                .collect(),
+            input_amount: 1_000_000,
+            max_trade_size: Decimal::ONE,
+            buy_price: Decimal::ONE,
+            sell_price: Decimal::ONE,
+            price_difference: Decimal::ZERO,
+            profit_percentage: Decimal::ZERO,
+            estimated_profit_usd: Decimal::ZERO,
+            gas_cost_estimate: Decimal::ZERO,
+            net_profit_usd: Decimal::ZERO,
+            confidence_score: Decimal::ONE,
+            risk_score: Decimal::ZERO,
+            status: OpportunityStatus::Detected,
+            slot: 1,
+            detected_at: now,
+            expires_at: now,
+        }
+    }
+
+    fn reputation(blacklist_ms: u64) -> RouteReputation {
+        RouteReputation::new(RouteBlacklistConfig {
+            max_failures: 2,
+            blacklist_ms,
+            max_blacklist_ms: blacklist_ms * 3,
+            ..RouteBlacklistConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_failures_blacklist_hops_in_one_direction() {
+        let reputation = reputation(60_000);
+        let route = opportunity(&[("ab", "A", "B"), ("ba", "B", "A")]);
+        let through_ab = opportunity(&[("ab", "A", "B"), ("bc", "B", "C")]);
+        let reversed = opportunity(&[("ba", "A", "B"), ("ab", "B", "A")]);
+
+        reputation.record_failure(&route);
+        assert!(reputation.allows(&route));
+        reputation.record_failure(&route);
+        assert!(!reputation.allows(&route));
+
+        // Other routes through a failing hop are skipped too, but the same
+        // pools traded the other way aren't
+        assert!(!reputation.allows(&through_ab));
+        assert!(reputation.allows(&reversed));
+
+        let blacklist = reputation.blacklist();
+        assert_eq!(blacklist.len(), 3);
+        assert!(blacklist.iter().all(|entry| entry.strikes == 1));
+        assert!(blacklist
+            .iter()
+            .any(|entry| matches!(&entry.key, RouteKey::Route { hops } if hops.len() == 2)));
+
+        // Operators can lift entries one by one or all at once
+        let hop = RouteKey::Hop(Hop::of(&route.legs[0]));
+        assert!(reputation.clear(&hop.id()));
+        assert!(!reputation.clear(&hop.id()));
+        assert!(reputation.allows(&through_ab));
+        assert!(!reputation.allows(&route));
+        assert_eq!(reputation.clear_all(), 2);
+        assert!(reputation.allows(&route));
+    }
+
+    #[test]
+    fn test_blacklistings_expire_and_grow_on_repeat() {
+        let reputation = reputation(50);
+        let route = opportunity(&[("ab", "A", "B"), ("ba", "B", "A")]);
+        let key = RouteKey::of(&route).pop().unwrap();
+
+        reputation.record_failure(&route);
+        reputation.record_failure(&route);
+        assert!(reputation.is_blacklisted(&key));
+        std::thread::sleep(Duration::from_millis(70));
+        assert!(!reputation.is_blacklisted(&key));
+
+        // The second blacklisting lasts twice as long
+        reputation.record_failure(&route);
+        reputation.record_failure(&route);
+        assert_eq!(reputation.blacklist()[0].strikes, 2);
+        std::thread::sleep(Duration::from_millis(70));
+        assert!(reputation.is_blacklisted(&key));
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(!reputation.is_blacklisted(&key));
+
+        // A success wipes the slate
+        reputation.record_failure(&route);
+        reputation.record_success(&route);
+        reputation.record_failure(&route);
+        assert!(reputation.allows(&route));
+    }
+}
@@ -0,0 +1,291 @@
+//! Event-driven opportunity scanning
+//!
+//! The scheduler applies account updates to the pool cache as they arrive.
+//! Every batch of updates, i.e. whatever is already queued when the first one
+//! is picked up, capped at `MAX_UPDATE_BATCH`, is followed by an incremental
+//! scan that only re-evaluates routes through the pools the batch changed.
+//! A full scan still runs every `scan_interval_ms` to catch anything that
+//! changes without an account update, such as pools going stale or
+//! time-dependent fees. Scans are CPU-bound and run on the blocking pool, so
+//! they never hold up the runtime while updates queue up behind them.
+
+use crate::config::OpportunitiesConfig;
+use crate::error::ArbitrageError;
+use crate::geyser::UpdateStream;
+use crate::models::ArbitrageOpportunity;
+use crate::pool_cache::{AccountUpdate, PoolCache};
+use crate::strategy::StrategyManager;
+use futures::{FutureExt, StreamExt};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::time::MissedTickBehavior;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{debug, warn};
+
+/// Buffered scan reports before the scheduler waits for the consumer
+const REPORT_BUFFER: usize = 64;
+
+/// Most updates applied before an incremental scan, so a burst of updates
+/// cannot hold off scanning and full scans indefinitely
+const MAX_UPDATE_BATCH: usize = 1024;
+
+/// What triggered a scan
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanKind {
+    /// Routes through pools changed by an update batch
+    Incremental,
+    /// Every route, on the fallback interval
+    Full,
+}
+
+/// Outcome of one scan
+#[derive(Debug, Clone)]
+pub struct ScanReport {
+    /// What triggered the scan
+    pub kind: ScanKind,
+    /// Pools changed by the update batch; zero for full scans
+    pub changed_pools: usize,
+    /// Opportunities found, most profitable first
+    pub opportunities: Vec<ArbitrageOpportunity>,
+    /// Time spent scanning
+    pub elapsed: Duration,
+}
+
+/// Drives strategies from pool updates
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    strategy: Arc<StrategyManager>,
+    pool_cache: Arc<PoolCache>,
+    scan_interval: Duration,
+}
+
+impl Scheduler {
+    /// Create a scheduler running full scans every `scan_interval`
+    pub fn new(
+        strategy: Arc<StrategyManager>,
+        pool_cache: Arc<PoolCache>,
+        scan_interval: Duration,
+    ) -> Self {
+        Self {
+            strategy,
+            pool_cache,
+            scan_interval,
+        }
+    }
+
+    /// Create a scheduler using `scan_interval_ms` as the full scan interval
+    pub fn from_config(
+        strategy: Arc<StrategyManager>,
+        pool_cache: Arc<PoolCache>,
+        config: &OpportunitiesConfig,
+    ) -> Self {
+        Self::new(
+            strategy,
+            pool_cache,
+            Duration::from_millis(config.scan_interval_ms.max(1)),
+        )
+    }
+
+    /// Consume `updates` in a background task and stream a report for every
+    /// scan. The task stops when `updates` ends or the report stream is
+    /// dropped.
+    pub fn spawn(self, updates: UpdateStream<AccountUpdate>) -> UpdateStream<ScanReport> {
+        let (tx, rx) = mpsc::channel(REPORT_BUFFER);
+        tokio::spawn(self.run(updates, tx));
+        Box::pin(ReceiverStream::new(rx))
+    }
+
+    async fn run(self, mut updates: UpdateStream<AccountUpdate>, tx: mpsc::Sender<ScanReport>) {
+        let mut interval = tokio::time::interval(self.scan_interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            let report = tokio::select! {
+                update = updates.next() => {
+                    let Some(update) = update else {
+                        debug!("Update stream ended, stopping scheduler");
+                        return;
+                    };
+                    let mut changed = HashSet::new();
+                    self.apply(&update, &mut changed);
+                    for _ in 1..MAX_UPDATE_BATCH {
+                        let Some(Some(update)) = updates.next().now_or_never() else {
+                            break;
+                        };
+                        self.apply(&update, &mut changed);
+                    }
+                    if changed.is_empty() {
+                        continue;
+                    }
+                    self.notify(&changed);
+                    self.scan(Some(changed)).await
+                }
+                _ = interval.tick() => self.scan(None).await,
+                _ = tx.closed() => return,
+            };
+
+            if tx.send(report).await.is_err() {
+                return;
+            }
+        }
+    }
+
+    fn apply(&self, update: &AccountUpdate, changed: &mut HashSet<Pubkey>) {
+        match self.pool_cache.apply_account_update(update) {
+            Ok(Some(pool)) => {
+                changed.insert(pool);
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Failed to apply update of {}: {}", update.pubkey, e),
+        }
+    }
+
+    fn notify(&self, changed: &HashSet<Pubkey>) {
+        for pool in changed {
+            if let Some(cached) = self.pool_cache.get(pool) {
+                self.strategy.on_pool_update(&cached.state);
+            }
+        }
+    }
+
+    /// Scan routes through the `changed` pools, or every route if `None`, on
+    /// the blocking pool
+    async fn scan(&self, changed: Option<HashSet<Pubkey>>) -> ScanReport {
+        let kind = match changed {
+            Some(_) => ScanKind::Incremental,
+            None => ScanKind::Full,
+        };
+        let changed_pools = changed.as_ref().map_or(0, HashSet::len);
+        let strategy = self.strategy.clone();
+
+        let started = Instant::now();
+        let opportunities = tokio::task::spawn_blocking(move || strategy.scan(changed.as_ref()))
+            .await
+            .unwrap_or_else(|e| Err(ArbitrageError::internal(format!("Scan panicked: {}", e))))
+            .unwrap_or_else(|e| {
+                warn!("{:?} scan failed: {}", kind, e);
+                Vec::new()
+            });
+        ScanReport {
+            kind,
+            changed_pools,
+            opportunities,
+            elapsed: started.elapsed(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::dex::{PoolData, ORCA, RAYDIUM, TOKEN_PROGRAM_ID};
+    use crate::test_support::{cross_dex_cache, usdc_pool};
+
+    const SOL: Pubkey = Pubkey::from_str_const("So11111111111111111111111111111111111111112");
+
+    struct Setup {
+        cache: Arc<PoolCache>,
+        scheduler: Scheduler,
+    }
+
+    fn setup(scan_interval: Duration) -> Setup {
+        let (dex, cache) = cross_dex_cache();
+        let strategy = Arc::new(StrategyManager::new(&Config::default(), dex, cache.clone()));
+        Setup {
+            scheduler: Scheduler::new(strategy, cache.clone(), scan_interval),
+            cache,
+        }
+    }
+
+    /// Insert a 1_000-token pool against USDC at `usdc_per_token`, returning
+    /// its pc vault
+    fn add_pool(cache: &PoolCache, dex: &'static str, mint: Pubkey, usdc_per_token: u64) -> Pubkey {
+        let pool = usdc_pool(dex, mint, usdc_per_token);
+        let PoolData::Raydium(state) = &pool.data else {
+            unreachable!()
+        };
+        let pc_vault = state.pc_vault;
+        cache.insert(pool, 1, 0).unwrap();
+        pc_vault
+    }
+
+    fn vault_update(vault: Pubkey, amount: u64, slot: u64) -> AccountUpdate {
+        let mut data = vec![0u8; 165];
+        data[64..72].copy_from_slice(&amount.to_le_bytes());
+        AccountUpdate {
+            pubkey: vault,
+            owner: TOKEN_PROGRAM_ID,
+            data,
+            slot,
+            write_version: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_updates_trigger_incremental_scans() {
+        let Setup { cache, scheduler } = setup(Duration::from_secs(3600));
+        let vault = add_pool(&cache, RAYDIUM, SOL, 150);
+        add_pool(&cache, ORCA, SOL, 150);
+
+        let (tx, rx) = mpsc::channel(16);
+        let mut reports = scheduler.spawn(Box::pin(ReceiverStream::new(rx)));
+
+        // The interval's first tick fires immediately
+        let first = reports.next().await.unwrap();
+        assert_eq!(first.kind, ScanKind::Full);
+        assert!(first.opportunities.is_empty());
+
+        // SOL drops to 145 on Raydium; both updates land in one batch
+        tx.send(vault_update(vault, 146_000_000_000, 2))
+            .await
+            .unwrap();
+        tx.send(vault_update(vault, 145_000_000_000, 3))
+            .await
+            .unwrap();
+        let report = reports.next().await.unwrap();
+        assert_eq!(report.kind, ScanKind::Incremental);
+        assert_eq!(report.changed_pools, 1);
+        assert!(!report.opportunities.is_empty());
+
+        // Updates that change nothing don't trigger a scan, and the stream
+        // ends with the updates
+        tx.send(vault_update(Pubkey::new_unique(), 1, 4))
+            .await
+            .unwrap();
+        drop(tx);
+        assert!(reports.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_incremental_scan_skips_untouched_routes() {
+        let Setup { cache, scheduler } = setup(Duration::from_secs(3600));
+        add_pool(&cache, RAYDIUM, SOL, 150);
+        add_pool(&cache, ORCA, SOL, 155);
+        let other = add_pool(&cache, RAYDIUM, Pubkey::new_unique(), 2);
+
+        let (tx, rx) = mpsc::channel(16);
+        let mut reports = scheduler.spawn(Box::pin(ReceiverStream::new(rx)));
+        assert!(!reports.next().await.unwrap().opportunities.is_empty());
+
+        tx.send(vault_update(other, 2_100_000_000, 2))
+            .await
+            .unwrap();
+        let report = reports.next().await.unwrap();
+        assert_eq!(report.kind, ScanKind::Incremental);
+        assert!(report.opportunities.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_full_scans_run_on_interval() {
+        let Setup { scheduler, .. } = setup(Duration::from_millis(10));
+        let (_tx, rx) = mpsc::channel::<AccountUpdate>(1);
+        let mut reports = scheduler.spawn(Box::pin(ReceiverStream::new(rx)));
+        for _ in 0..3 {
+            assert_eq!(reports.next().await.unwrap().kind, ScanKind::Full);
+        }
+    }
+}
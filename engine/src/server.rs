@@ -1,13 +1,19 @@
 //! HTTP server module
 
 use crate::error::Result;
-use axum::{routing::get, Router};
+use crate::risk::{BlacklistEntry, RouteReputation};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::{routing::delete, routing::get, Json, Router};
+use serde_json::{json, Value};
 use std::net::SocketAddr;
+use std::sync::Arc;
 
 /// HTTP server for the arbitrage engine
 #[derive(Debug)]
 pub struct Server {
     addr: SocketAddr,
+    reputation: Option<Arc<RouteReputation>>,
 }
 
 impl Server {
@@ -17,15 +23,39 @@ impl Server {
             crate::error::ArbitrageError::config(format!("Invalid server address: {}", e))
         })?;
 
-        Ok(Self { addr })
+        Ok(Self {
+            addr,
+            reputation: None,
+        })
     }
 
-    /// Start the server
-    pub async fn start(&self) -> Result<()> {
-        let app = Router::new()
+    /// Serve the route blacklist of `reputation` under `/blacklist`
+    pub fn with_route_reputation(mut self, reputation: Arc<RouteReputation>) -> Self {
+        self.reputation = Some(reputation);
+        self
+    }
+
+    /// Routes served
+    pub fn router(&self) -> Router {
+        let mut app = Router::new()
             .route("/health", get(health_check))
             .route("/status", get(status));
 
+        if let Some(reputation) = &self.reputation {
+            app = app.merge(
+                Router::new()
+                    .route("/blacklist", get(list_blacklist).delete(clear_blacklist))
+                    .route("/blacklist/{id}", delete(clear_blacklist_entry))
+                    .with_state(reputation.clone()),
+            );
+        }
+        app
+    }
+
+    /// Start the server
+    pub async fn start(&self) -> Result<()> {
+        let app = self.router();
+
         let listener = tokio::net::TcpListener::bind(&self.addr)
             .await
             .map_err(|e| {
@@ -49,3 +79,125 @@ async fn health_check() -> &'static str {
 async fn status() -> &'static str {
     "Running"
 }
+
+/// Blacklisted hops and routes
+async fn list_blacklist(
+    State(reputation): State<Arc<RouteReputation>>,
+) -> Json<Vec<BlacklistEntry>> {
+    Json(reputation.blacklist())
+}
+
+/// Lift every blacklisting
+async fn clear_blacklist(State(reputation): State<Arc<RouteReputation>>) -> Json<Value> {
+    Json(json!({ "cleared": reputation.clear_all() }))
+}
+
+/// Lift one blacklisting by id
+async fn clear_blacklist_entry(
+    State(reputation): State<Arc<RouteReputation>>,
+    Path(id): Path<String>,
+) -> StatusCode {
+    if reputation.clear(&id) {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RouteBlacklistConfig;
+    use crate::models::{ArbitrageOpportunity, OpportunityLeg, OpportunityStatus};
+    use crate::risk::{Hop, RouteKey};
+    use axum::body::Body;
+    use axum::http::{Method, Request};
+    use rust_decimal::Decimal;
+    use tower::ServiceExt;
+
+    fn failing_opportunity() -> ArbitrageOpportunity {
+        let now = chrono::Utc::now();
+        let leg = |pool: &str, input: &str, output: &str| OpportunityLeg {
+            pool: pool.to_string(),
+            dex: "raydium".to_string(),
+            input_mint: input.to_string(),
+            output_mint: output.to_string(),
+            amount_in: 0,
+            expected_out: 0,
+            fee_amount: 0,
+            price_impact_bps: 0,
+        };
+        ArbitrageOpportunity {
+            id: "failing".to_string(),
+            token_mint: "A".to_string(),
+            legs: vec![leg("ab", "A", "B"), leg("ba", "B", "A")],
+            input_amount: 1,
+            max_trade_size: Decimal::ONE,
+            buy_price: Decimal::ONE,
+            sell_price: Decimal::ONE,
+            price_difference: Decimal::ZERO,
+            profit_percentage: Decimal::ZERO,
+            estimated_profit_usd: Decimal::ZERO,
+            gas_cost_estimate: Decimal::ZERO,
+            net_profit_usd: Decimal::ZERO,
+            confidence_score: Decimal::ONE,
+            risk_score: Decimal::ZERO,
+            status: OpportunityStatus::Failed,
+            slot: 1,
+            detected_at: now,
+            expires_at: now,
+        }
+    }
+
+    async fn call(app: &Router, method: Method, uri: &str) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    #[tokio::test]
+    async fn test_blacklist_api() {
+        let reputation = Arc::new(RouteReputation::new(RouteBlacklistConfig {
+            max_failures: 1,
+            ..RouteBlacklistConfig::default()
+        }));
+        let app = Server::new("127.0.0.1", 0)
+            .unwrap()
+            .with_route_reputation(reputation.clone())
+            .router();
+        let opportunity = failing_opportunity();
+        reputation.record_failure(&opportunity);
+
+        let (status, listed) = call(&app, Method::GET, "/blacklist").await;
+        assert_eq!(status, StatusCode::OK);
+        let listed: Vec<BlacklistEntry> = serde_json::from_value(listed).unwrap();
+        assert_eq!(listed.len(), 3);
+        let hop = RouteKey::Hop(Hop::of(&opportunity.legs[0]));
+        let entry = listed.iter().find(|entry| entry.key == hop).unwrap();
+        assert_eq!(entry.id, hop.id());
+
+        let uri = format!("/blacklist/{}", entry.id);
+        assert_eq!(
+            call(&app, Method::DELETE, &uri).await.0,
+            StatusCode::NO_CONTENT
+        );
+        assert_eq!(
+            call(&app, Method::DELETE, &uri).await.0,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(reputation.blacklist().len(), 2);
+
+        let (status, cleared) = call(&app, Method::DELETE, "/blacklist").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(cleared, json!({ "cleared": 2 }));
+        assert!(reputation.allows(&opportunity));
+    }
+}
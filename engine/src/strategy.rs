@@ -1,29 +1,940 @@
 //! Trading strategy module
+//!
+//! Opportunities are detected against the local pool cache: every scan takes
+//! a snapshot of fresh pools, prices tokens from them, and hands both to each
+//! enabled [`Strategy`] through a [`ScanContext`], which quotes candidate
+//! routes through the DEX adapters exactly as the pools would fill them and
+//! sizes them for maximum profit with [`sizing`]. Once the bankroll is known,
+//! routes are scaled down further to the position the [`PositionSizer`]
+//! stakes on them, which samples the scans' prices on a fixed clock to track
+//! volatility.
+//!
+//! [`StrategyManager`] hosts the strategies by name. The built-in
+//! [`CrossDexStrategy`] and [`TriangularStrategy`] are registered by default;
+//! downstream crates can register their own and every strategy can be
+//! enabled, disabled and reconfigured at runtime.
 
-use crate::error::Result;
-use crate::models::ArbitrageOpportunity;
+pub mod cross_dex;
+pub mod cycles;
+pub mod sizing;
+pub mod triangular;
+
+pub use cross_dex::{CrossDexParams, CrossDexStrategy, CROSS_DEX};
+pub use triangular::{TriangularParams, TriangularStrategy, TRIANGULAR};
+
+use crate::config::{Config, OpportunitiesConfig, TokensConfig};
+use crate::dex::{DexManager, PoolState, NATIVE_MINT};
+use crate::error::{ArbitrageError, Result};
+use crate::execution::BASE_FEE_LAMPORTS;
+use crate::models::{ArbitrageOpportunity, OpportunityLeg, OpportunityStatus};
+use crate::pool_cache::PoolCache;
+use crate::pricing::{PriceOracle, PriceSnapshot};
+use crate::risk::{PositionSizer, RouteReputation};
+use crate::utils::generate_id;
+use chrono::Utc;
+use parking_lot::RwLock;
+use rust_decimal::Decimal;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+
+use sizing::SizedRoute;
+
+/// Token white/blacklists resolved to mints
+#[derive(Debug, Clone, Default)]
+pub struct TokenFilter {
+    whitelist: HashSet<Pubkey>,
+    blacklist: HashSet<Pubkey>,
+}
+
+impl TokenFilter {
+    /// Resolve the configured lists. Entries are mint addresses or symbols of
+    /// tokens in the `[tokens]` section.
+    pub fn new(config: &OpportunitiesConfig, tokens: &TokensConfig) -> Self {
+        Self {
+            whitelist: resolve_tokens(&config.whitelisted_tokens, tokens),
+            blacklist: resolve_tokens(&config.blacklisted_tokens, tokens),
+        }
+    }
+
+    /// Check whether `mint` may be traded
+    pub fn allows(&self, mint: &Pubkey) -> bool {
+        !self.blacklist.contains(mint)
+            && (self.whitelist.is_empty() || self.whitelist.contains(mint))
+    }
+
+    /// Check whether both mints of `pool` may be traded
+    pub fn allows_pool(&self, pool: &PoolState) -> bool {
+        self.allows(&pool.mint_a) && self.allows(&pool.mint_b)
+    }
+}
+
+fn resolve_tokens(entries: &[String], tokens: &TokensConfig) -> HashSet<Pubkey> {
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let mint = tokens
+                .by_symbol(entry)
+                .map(|token| token.mint.as_str())
+                .unwrap_or(entry);
+            match Pubkey::from_str(mint) {
+                Ok(mint) => Some(mint),
+                Err(_) => {
+                    warn!("Ignoring unknown token in filter list: {}", entry);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+fn to_decimal(value: f64) -> Decimal {
+    Decimal::from_f64_retain(value)
+        .unwrap_or_default()
+        .round_dp(6)
+}
+
+/// Clamp a score to `0..=1` with two decimal places, as stored
+fn to_score(value: f64) -> Decimal {
+    to_decimal(value.clamp(0.0, 1.0)).round_dp(2)
+}
+
+/// Deserialize strategy parameters, reporting failures as config errors
+pub fn parse_params<T: DeserializeOwned>(strategy: &str, config: Value) -> Result<T> {
+    serde_json::from_value(config)
+        .map_err(|e| ArbitrageError::config(format!("Invalid {} strategy config: {}", strategy, e)))
+}
+
+/// Profit and price impact limits a route must meet
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteLimits {
+    /// Minimum expected profit in USD
+    pub min_profit_usd: f64,
+    /// Minimum expected profit as a percentage of the input
+    pub min_profit_percent: f64,
+    /// Maximum summed price impact of the legs, in basis points
+    pub max_price_impact_bps: u32,
+}
+
+impl RouteLimits {
+    /// JSON schema of an object holding the limits plus `properties`
+    pub fn schema(properties: Value) -> Value {
+        let mut schema = json!({
+            "type": "object",
+            "properties": {
+                "min_profit_usd": { "type": "number", "minimum": 0 },
+                "min_profit_percent": { "type": "number", "minimum": 0 },
+                "max_price_impact_bps": { "type": "integer", "minimum": 0 }
+            },
+            "required": ["min_profit_usd", "min_profit_percent", "max_price_impact_bps"]
+        });
+        if let (Some(all), Value::Object(extra)) =
+            (schema["properties"].as_object_mut(), properties)
+        {
+            all.extend(extra);
+        }
+        schema
+    }
+}
+
+impl From<&OpportunitiesConfig> for RouteLimits {
+    fn from(config: &OpportunitiesConfig) -> Self {
+        Self {
+            min_profit_usd: config.min_profit_usd,
+            min_profit_percent: config.min_profit_percent,
+            max_price_impact_bps: config.max_price_impact_bps,
+        }
+    }
+}
+
+/// Everything a strategy sees during one scan
+#[derive(Debug, Clone, Copy)]
+pub struct ScanContext<'a> {
+    /// DEX adapters for quoting
+    pub dex: &'a DexManager,
+    /// Fresh pools at the time of the scan
+    pub pools: &'a [Arc<PoolState>],
+    /// Token prices derived from `pools`
+    pub prices: &'a PriceSnapshot,
+    /// Token white/blacklists
+    pub filter: &'a TokenFilter,
+    /// Blacklisted hops and routes
+    pub reputation: &'a RouteReputation,
+    /// Opportunity detection settings
+    pub config: &'a OpportunitiesConfig,
+    /// Largest input of a single trade, in USD
+    pub max_position_size_usd: f64,
+    /// Sizes positions by fractional Kelly
+    pub sizer: &'a PositionSizer,
+    /// Bankroll positions are sized against, in USD, or `None` to trade
+    /// routes at their most profitable size
+    pub bankroll_usd: Option<f64>,
+    /// Pools changed since the last scan, or `None` for a full scan
+    pub changed: Option<&'a HashSet<Pubkey>>,
+    /// Latest slot of the snapshot's pool states
+    pub slot: u64,
+    /// Estimated fees of one arbitrage transaction, in USD
+    pub gas_cost_usd: f64,
+}
+
+impl<'a> ScanContext<'a> {
+    /// Check whether a route through `pools` needs re-evaluating: always in a
+    /// full scan, otherwise only if one of its pools changed
+    pub fn touches(&self, pools: &[&PoolState]) -> bool {
+        self.changed
+            .is_none_or(|changed| pools.iter().any(|pool| changed.contains(&pool.address)))
+    }
+
+    /// Pools whose tokens pass the filter and whose liquidity meets the minimum
+    pub fn eligible_pools(&self) -> Vec<&'a PoolState> {
+        let prices = self.prices;
+        self.pools
+            .iter()
+            .map(Arc::as_ref)
+            .filter(|pool| self.filter.allows_pool(pool))
+            .filter(|pool| {
+                self.dex
+                    .reserves(pool)
+                    .and_then(|reserves| prices.pool_liquidity_usd(pool, reserves))
+                    .is_some_and(|liquidity| liquidity >= self.config.min_liquidity_usd)
+            })
+            .collect()
+    }
+
+    /// Size `route` for maximum profit, capped at the position staked on it
+    /// if the bankroll is known, and build the opportunity if it meets
+    /// `limits` at that size. Routes untouched by an incremental scan or
+    /// through a blacklisted hop are skipped.
+    pub fn evaluate_route(
+        &self,
+        route: &[&PoolState],
+        start: &Pubkey,
+        limits: &RouteLimits,
+    ) -> Option<ArbitrageOpportunity> {
+        if !self.touches(route) || !self.reputation.allows_route(route, start) {
+            return None;
+        }
+        let max_input = self
+            .prices
+            .amount_for_usd(start, self.max_position_size_usd)?;
+        let sized = sizing::optimal_size(self.dex, route, start, max_input)?;
+        let opportunity = self.opportunity(route, start, sized, max_input, limits)?;
+
+        let Some(bankroll_usd) = self.bankroll_usd else {
+            return Some(opportunity);
+        };
+        let position = self.sizer.size(&opportunity, self.prices, bankroll_usd)?;
+        if position.amount >= opportunity.input_amount {
+            return Some(opportunity);
+        }
+        let resized = sizing::quote_route(self.dex, route, start, position.amount)?;
+        self.opportunity(route, start, resized, max_input, limits)
+    }
+
+    /// Build the opportunity of `route` traded as `sized`, if it meets
+    /// `limits`
+    fn opportunity(
+        &self,
+        route: &[&PoolState],
+        start: &Pubkey,
+        sized: SizedRoute,
+        max_input: u64,
+        limits: &RouteLimits,
+    ) -> Option<ArbitrageOpportunity> {
+        if sized.price_impact_bps > limits.max_price_impact_bps {
+            return None;
+        }
+
+        let amount = sized.input_amount;
+        let profit = sized.profit()?;
+        let profit_usd = self.prices.usd_value(start, profit)?;
+        let net_profit_usd = profit_usd - self.gas_cost_usd;
+        let profit_percent = profit as f64 / amount as f64 * 100.0;
+        if net_profit_usd < limits.min_profit_usd || profit_percent < limits.min_profit_percent {
+            return None;
+        }
+
+        let (first, last) = (sized.legs.first()?, sized.legs.last()?);
+        let buy_price = self.prices.ui_amount(start, first.in_amount)
+            / self.prices.ui_amount(&first.output_mint, first.out_amount);
+        let sell_price = self.prices.ui_amount(start, last.out_amount)
+            / self.prices.ui_amount(&last.input_mint, last.in_amount);
+        let detected_at = Utc::now();
+        Some(ArbitrageOpportunity {
+            id: generate_id(),
+            token_mint: start.to_string(),
+            input_amount: amount,
+            max_trade_size: to_decimal(self.prices.ui_amount(start, max_input)),
+            buy_price: to_decimal(buy_price),
+            sell_price: to_decimal(sell_price),
+            price_difference: to_decimal(profit as f64 / amount as f64),
+            profit_percentage: to_decimal(profit_percent),
+            estimated_profit_usd: to_decimal(profit_usd),
+            gas_cost_estimate: to_decimal(self.gas_cost_usd),
+            net_profit_usd: to_decimal(net_profit_usd),
+            confidence_score: to_score(self.confidence(&sized, limits)),
+            risk_score: to_score(self.risk(route, start, amount)),
+            status: OpportunityStatus::Detected,
+            slot: self.slot,
+            detected_at,
+            expires_at: detected_at
+                + chrono::Duration::milliseconds(self.config.max_opportunity_age_ms as i64),
+            legs: route
+                .iter()
+                .zip(sized.legs)
+                .map(|(pool, quote)| OpportunityLeg {
+                    pool: pool.address.to_string(),
+                    dex: pool.dex.to_string(),
+                    input_mint: quote.input_mint.to_string(),
+                    output_mint: quote.output_mint.to_string(),
+                    amount_in: quote.in_amount,
+                    expected_out: quote.out_amount,
+                    fee_amount: quote.fee_amount,
+                    price_impact_bps: quote.price_impact_bps,
+                })
+                .collect(),
+        })
+    }
+
+    /// Confidence that a route fills as quoted: price impact uses up the
+    /// impact budget and every leg past the second adds another pool that
+    /// can move before the transaction lands
+    fn confidence(&self, sized: &SizedRoute, limits: &RouteLimits) -> f64 {
+        let impact = sized.price_impact_bps as f64 / limits.max_price_impact_bps.max(1) as f64;
+        let extra_legs = sized.legs.len().saturating_sub(2) as i32;
+        (1.0 - impact / 2.0) * 0.9f64.powi(extra_legs)
+    }
+
+    /// Risk of a route: the share of its shallowest pool's liquidity the
+    /// trade moves through
+    fn risk(&self, route: &[&PoolState], start: &Pubkey, amount: u64) -> f64 {
+        let Some(trade_usd) = self.prices.usd_value(start, amount) else {
+            return 1.0;
+        };
+        route
+            .iter()
+            .map(|pool| {
+                self.dex
+                    .reserves(pool)
+                    .and_then(|reserves| self.prices.pool_liquidity_usd(pool, reserves))
+                    .map_or(1.0, |liquidity| trade_usd / liquidity)
+            })
+            .fold(0.0, f64::max)
+    }
+}
+
+/// An opportunity detection strategy
+pub trait Strategy: Send + Sync + std::fmt::Debug {
+    /// Unique name the strategy is managed under
+    fn name(&self) -> &str;
+
+    /// JSON schema of the parameters accepted by [`Strategy::configure`]
+    fn config_schema(&self) -> Value;
+
+    /// Current parameters
+    fn config(&self) -> Value;
+
+    /// Replace the parameters
+    fn configure(&mut self, config: Value) -> Result<()>;
+
+    /// Called whenever a cached pool changes
+    fn on_pool_update(&mut self, _pool: &PoolState) {}
+
+    /// Find opportunities in the scan snapshot
+    fn scan(&self, ctx: &ScanContext<'_>) -> Result<Vec<ArbitrageOpportunity>>;
+}
+
+/// Registered strategy as reported to the API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategyInfo {
+    /// Strategy name
+    pub name: String,
+    /// Whether the strategy runs in scans
+    pub enabled: bool,
+    /// Current parameters
+    pub config: Value,
+    /// JSON schema of the parameters
+    pub schema: Value,
+}
+
+#[derive(Debug)]
+struct Registered {
+    strategy: Box<dyn Strategy>,
+    enabled: bool,
+}
 
 /// Strategy manager
 #[derive(Debug)]
 pub struct StrategyManager {
-    // TODO: Add strategy implementations
+    config: OpportunitiesConfig,
+    dex: Arc<DexManager>,
+    pool_cache: Arc<PoolCache>,
+    oracle: PriceOracle,
+    filter: TokenFilter,
+    reputation: Arc<RouteReputation>,
+    sizer: Arc<PositionSizer>,
+    bankroll_usd: RwLock<Option<f64>>,
+    max_position_size_usd: f64,
+    fee_lamports: u64,
+    strategies: RwLock<Vec<Registered>>,
 }
 
 impl StrategyManager {
-    /// Create a new strategy manager
-    pub fn new() -> Self {
-        Self {}
+    /// Create a strategy manager scanning the pools in `pool_cache`, with the
+    /// built-in strategies enabled as configured
+    pub fn new(config: &Config, dex: Arc<DexManager>, pool_cache: Arc<PoolCache>) -> Self {
+        let opportunities = &config.opportunities;
+        let manager = Self {
+            config: opportunities.clone(),
+            dex,
+            pool_cache,
+            oracle: PriceOracle::new(&config.tokens),
+            filter: TokenFilter::new(opportunities, &config.tokens),
+            reputation: Arc::new(RouteReputation::from_config(config)),
+            sizer: Arc::new(PositionSizer::from_config(config)),
+            bankroll_usd: RwLock::new(None),
+            max_position_size_usd: config.trading.max_position_size_usd,
+            fee_lamports: BASE_FEE_LAMPORTS + config.solana.priority_fee_lamports,
+            strategies: RwLock::new(Vec::new()),
+        };
+
+        manager.strategies.write().extend([
+            Registered {
+                strategy: Box::new(CrossDexStrategy::new(opportunities)),
+                enabled: opportunities.enable_cross_dex_arbitrage,
+            },
+            Registered {
+                strategy: Box::new(TriangularStrategy::new(opportunities)),
+                enabled: opportunities.enable_triangular_arbitrage,
+            },
+        ]);
+        manager
+    }
+
+    /// Register a strategy, failing if one with the same name exists
+    pub fn register(&self, strategy: Box<dyn Strategy>, enabled: bool) -> Result<()> {
+        let mut strategies = self.strategies.write();
+        if strategies
+            .iter()
+            .any(|registered| registered.strategy.name() == strategy.name())
+        {
+            return Err(ArbitrageError::config(format!(
+                "Strategy {} is already registered",
+                strategy.name()
+            )));
+        }
+
+        info!("Registering strategy {}", strategy.name());
+        strategies.push(Registered { strategy, enabled });
+        Ok(())
+    }
+
+    /// Remove the strategy registered under `name`
+    pub fn unregister(&self, name: &str) -> Option<Box<dyn Strategy>> {
+        let mut strategies = self.strategies.write();
+        let index = strategies
+            .iter()
+            .position(|registered| registered.strategy.name() == name)?;
+        Some(strategies.remove(index).strategy)
+    }
+
+    fn with_strategy<T>(&self, name: &str, f: impl FnOnce(&mut Registered) -> T) -> Result<T> {
+        let mut strategies = self.strategies.write();
+        strategies
+            .iter_mut()
+            .find(|registered| registered.strategy.name() == name)
+            .map(f)
+            .ok_or_else(|| ArbitrageError::config(format!("Unknown strategy: {}", name)))
+    }
+
+    /// Include or exclude a strategy from scans
+    pub fn set_enabled(&self, name: &str, enabled: bool) -> Result<()> {
+        self.with_strategy(name, |registered| registered.enabled = enabled)?;
+        info!(
+            "Strategy {} {}",
+            name,
+            if enabled { "enabled" } else { "disabled" }
+        );
+        Ok(())
+    }
+
+    /// Include a strategy in scans
+    pub fn enable(&self, name: &str) -> Result<()> {
+        self.set_enabled(name, true)
+    }
+
+    /// Exclude a strategy from scans
+    pub fn disable(&self, name: &str) -> Result<()> {
+        self.set_enabled(name, false)
     }
 
-    /// Find arbitrage opportunities
+    /// Replace the parameters of a strategy
+    pub fn configure(&self, name: &str, config: Value) -> Result<()> {
+        self.with_strategy(name, |registered| registered.strategy.configure(config))??;
+        info!("Strategy {} reconfigured", name);
+        Ok(())
+    }
+
+    /// Every registered strategy with its state and parameters
+    pub fn strategies(&self) -> Vec<StrategyInfo> {
+        self.strategies
+            .read()
+            .iter()
+            .map(|registered| StrategyInfo {
+                name: registered.strategy.name().to_string(),
+                enabled: registered.enabled,
+                config: registered.strategy.config(),
+                schema: registered.strategy.config_schema(),
+            })
+            .collect()
+    }
+
+    /// Notify enabled strategies that a cached pool changed
+    pub fn on_pool_update(&self, pool: &PoolState) {
+        for registered in self.strategies.write().iter_mut() {
+            if registered.enabled {
+                registered.strategy.on_pool_update(pool);
+            }
+        }
+    }
+
+    /// Reputation of the routes scanned, consulted before any opportunity is
+    /// emitted
+    pub fn reputation(&self) -> &Arc<RouteReputation> {
+        &self.reputation
+    }
+
+    /// Position sizer scaling routes to the bankroll
+    pub fn sizer(&self) -> &Arc<PositionSizer> {
+        &self.sizer
+    }
+
+    /// Bankroll positions are sized against, if known
+    pub fn bankroll_usd(&self) -> Option<f64> {
+        *self.bankroll_usd.read()
+    }
+
+    /// Size positions against a bankroll of `bankroll_usd` from the next
+    /// scan on
+    pub fn set_bankroll(&self, bankroll_usd: f64) {
+        *self.bankroll_usd.write() = Some(bankroll_usd.max(0.0));
+    }
+
+    /// Token prices derived from the fresh pools in the cache
+    pub fn prices(&self) -> PriceSnapshot {
+        let pools: Vec<Arc<PoolState>> = self
+            .pool_cache
+            .fresh_pools()
+            .into_iter()
+            .map(|pool| pool.state)
+            .collect();
+        self.oracle
+            .snapshot(&self.dex, pools.iter().map(Arc::as_ref))
+    }
+
+    /// Find arbitrage opportunities with every enabled strategy, most
+    /// profitable first
     pub async fn find_opportunities(&self) -> Result<Vec<ArbitrageOpportunity>> {
-        // TODO: Implement opportunity detection
-        Ok(vec![])
+        self.scan(None)
+    }
+
+    /// Like [`Self::find_opportunities`], but only evaluate routes through
+    /// at least one of the `changed` pools
+    pub async fn find_opportunities_touching(
+        &self,
+        changed: &HashSet<Pubkey>,
+    ) -> Result<Vec<ArbitrageOpportunity>> {
+        self.scan(Some(changed))
+    }
+
+    /// Scan routes through at least one of the `changed` pools, or every
+    /// route if `None`, with every enabled strategy. This is CPU-bound work
+    /// that blocks until the scan completes; async callers driving it
+    /// continuously should run it on the blocking pool.
+    pub fn scan(&self, changed: Option<&HashSet<Pubkey>>) -> Result<Vec<ArbitrageOpportunity>> {
+        let cached = self.pool_cache.fresh_pools();
+        let slot = cached.iter().map(|pool| pool.slot).max().unwrap_or(0);
+        let pools: Vec<Arc<PoolState>> = cached.into_iter().map(|pool| pool.state).collect();
+        let prices = self
+            .oracle
+            .snapshot(&self.dex, pools.iter().map(Arc::as_ref));
+        self.sizer.observe(&prices);
+        let ctx = ScanContext {
+            dex: &self.dex,
+            pools: &pools,
+            prices: &prices,
+            filter: &self.filter,
+            reputation: &self.reputation,
+            config: &self.config,
+            max_position_size_usd: self.max_position_size_usd,
+            sizer: &self.sizer,
+            bankroll_usd: self.bankroll_usd(),
+            changed,
+            slot,
+            gas_cost_usd: prices
+                .usd_value(&NATIVE_MINT, self.fee_lamports)
+                .unwrap_or(0.0),
+        };
+
+        let mut opportunities = Vec::new();
+        for registered in self.strategies.read().iter() {
+            if !registered.enabled {
+                continue;
+            }
+            match registered.strategy.scan(&ctx) {
+                Ok(found) => opportunities.extend(
+                    found
+                        .into_iter()
+                        .filter(|opportunity| self.reputation.allows(opportunity)),
+                ),
+                Err(e) => warn!("Strategy {} failed: {}", registered.strategy.name(), e),
+            }
+        }
+
+        opportunities.sort_by_key(|opportunity| std::cmp::Reverse(opportunity.net_profit_usd));
+        debug!(
+            "Found {} opportunities across {} pools",
+            opportunities.len(),
+            pools.len()
+        );
+        Ok(opportunities)
     }
 }
 
-impl Default for StrategyManager {
-    fn default() -> Self {
-        Self::new()
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dex::{ORCA, RAYDIUM};
+    use crate::test_support::{cross_dex_cache, test_pool, usdc_pool, USDC};
+
+    const SOL: Pubkey = Pubkey::from_str_const("So11111111111111111111111111111111111111112");
+
+    fn manager(config: Config, pools: Vec<PoolState>) -> StrategyManager {
+        let (dex, cache) = cross_dex_cache();
+        for pool in pools {
+            cache.insert(pool, 1, 0).unwrap();
+        }
+        StrategyManager::new(&config, dex, cache)
+    }
+
+    #[tokio::test]
+    async fn test_detects_cross_dex_spread() {
+        let manager = manager(
+            Config::default(),
+            vec![usdc_pool(RAYDIUM, SOL, 150), usdc_pool(ORCA, SOL, 155)],
+        );
+        let opportunities = manager.find_opportunities().await.unwrap();
+
+        // SOL is cheaper on Raydium: the cycle pays starting from either token
+        assert_eq!(opportunities.len(), 2);
+        for opportunity in &opportunities {
+            let (other, buy_dex, sell_dex) = if opportunity.token_mint == USDC.to_string() {
+                (SOL, RAYDIUM, ORCA)
+            } else {
+                (USDC, ORCA, RAYDIUM)
+            };
+            let [buy, sell] = opportunity.legs.as_slice() else {
+                panic!("expected two legs");
+            };
+            assert_eq!(buy.output_mint, other.to_string());
+            assert_eq!(sell.input_mint, buy.output_mint);
+            assert_eq!(sell.amount_in, buy.expected_out);
+            assert_eq!(buy.amount_in, opportunity.input_amount);
+            assert_eq!(opportunity.buy_dex(), Some(buy_dex));
+            assert_eq!(opportunity.sell_dex(), Some(sell_dex));
+            assert!(opportunity.sell_price > opportunity.buy_price);
+            assert!(opportunity.output_amount() > opportunity.input_amount);
+            assert!(opportunity.profit_percentage > Decimal::ONE);
+            assert!(opportunity.net_profit_usd > Decimal::TEN);
+            assert!(opportunity.net_profit_usd < opportunity.estimated_profit_usd);
+            assert!(opportunity.confidence_score > Decimal::ZERO);
+            assert!(opportunity.confidence_score <= Decimal::ONE);
+            // The trade is small next to either pool
+            assert!(opportunity.risk_score < Decimal::new(5, 2));
+            assert_eq!(opportunity.status, OpportunityStatus::Detected);
+            assert_eq!(opportunity.slot, 1);
+            assert!(opportunity.expires_at > opportunity.detected_at);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_trade_size_is_capped() {
+        let mut config = Config::default();
+        config.trading.max_position_size_usd = 100.0;
+        config.opportunities.min_profit_usd = 0.0;
+        let opportunities = manager(
+            config,
+            vec![usdc_pool(RAYDIUM, SOL, 150), usdc_pool(ORCA, SOL, 155)],
+        )
+        .find_opportunities()
+        .await
+        .unwrap();
+
+        let usdc = opportunities
+            .iter()
+            .find(|opportunity| opportunity.token_mint == USDC.to_string())
+            .unwrap();
+        assert_eq!(usdc.input_amount, 100_000_000);
+        assert_eq!(usdc.max_trade_size, Decimal::ONE_HUNDRED);
+        assert!(usdc.net_profit_usd < Decimal::TEN);
+
+        let uncapped = manager(
+            Config::default(),
+            vec![usdc_pool(RAYDIUM, SOL, 150), usdc_pool(ORCA, SOL, 155)],
+        )
+        .find_opportunities()
+        .await
+        .unwrap();
+        assert!(uncapped[0].input_amount > 100_000_000);
+        assert!(uncapped[0].net_profit_usd > usdc.net_profit_usd);
+    }
+
+    #[tokio::test]
+    async fn test_positions_follow_the_bankroll() {
+        let mut config = Config::default();
+        config.opportunities.min_profit_usd = 0.0;
+        let manager = manager(
+            config,
+            vec![usdc_pool(RAYDIUM, SOL, 150), usdc_pool(ORCA, SOL, 155)],
+        );
+        let usdc_input = |opportunities: Vec<ArbitrageOpportunity>| {
+            opportunities
+                .into_iter()
+                .find(|opportunity| opportunity.token_mint == USDC.to_string())
+                .map(|opportunity| opportunity.input_amount)
+        };
+        let optimal = usdc_input(manager.find_opportunities().await.unwrap()).unwrap();
+
+        // A quarter Kelly of a $1k bankroll stakes at most $250
+        manager.set_bankroll(1_000.0);
+        let staked = usdc_input(manager.find_opportunities().await.unwrap()).unwrap();
+        assert!(staked <= 250_000_000);
+        assert!(staked < optimal);
+
+        // Nothing is staked without a bankroll
+        manager.set_bankroll(0.0);
+        assert_eq!(
+            usdc_input(manager.find_opportunities().await.unwrap()),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_filters_apply() {
+        let pools = || vec![usdc_pool(RAYDIUM, SOL, 150), usdc_pool(ORCA, SOL, 155)];
+
+        let mut config = Config::default();
+        config.opportunities.blacklisted_tokens = vec!["SOL".to_string()];
+        assert!(manager(config, pools())
+            .find_opportunities()
+            .await
+            .unwrap()
+            .is_empty());
+
+        let mut config = Config::default();
+        config.opportunities.whitelisted_tokens = vec![USDC.to_string()];
+        assert!(manager(config, pools())
+            .find_opportunities()
+            .await
+            .unwrap()
+            .is_empty());
+
+        let mut config = Config::default();
+        config.opportunities.min_profit_usd = 1_000_000.0;
+        assert!(manager(config, pools())
+            .find_opportunities()
+            .await
+            .unwrap()
+            .is_empty());
+
+        let mut config = Config::default();
+        config.opportunities.min_liquidity_usd = 1_000_000.0;
+        assert!(manager(config, pools())
+            .find_opportunities()
+            .await
+            .unwrap()
+            .is_empty());
+
+        let mut config = Config::default();
+        config.opportunities.enable_cross_dex_arbitrage = false;
+        assert!(manager(config, pools())
+            .find_opportunities()
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_blacklisted_routes_are_skipped() {
+        let manager = manager(
+            Config::default(),
+            vec![usdc_pool(RAYDIUM, SOL, 150), usdc_pool(ORCA, SOL, 155)],
+        );
+        let opportunities = manager.find_opportunities().await.unwrap();
+        assert_eq!(opportunities.len(), 2);
+
+        // Both cycles trade the same hops, so failing one blacklists both
+        for _ in 0..manager.reputation().config().max_failures {
+            manager.reputation().record_failure(&opportunities[0]);
+        }
+        assert!(manager.find_opportunities().await.unwrap().is_empty());
+
+        manager.reputation().clear_all();
+        assert_eq!(manager.find_opportunities().await.unwrap().len(), 2);
+    }
+
+    fn triangle() -> Vec<PoolState> {
+        // X trades at 0.1 SOL but 16 USDC while SOL is at 150 USDC
+        let x = Pubkey::new_from_array([42; 32]);
+        vec![
+            test_pool(SOL, USDC, 1_000_000_000_000, 150_000_000_000, 25),
+            test_pool(SOL, x, 1_000_000_000_000, 10_000_000_000_000, 25),
+            test_pool(x, USDC, 10_000_000_000_000, 160_000_000_000, 25),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_detects_triangular_cycle() {
+        let pools = triangle();
+        let addresses: HashSet<String> = pools.iter().map(|p| p.address.to_string()).collect();
+        let opportunities = manager(Config::default(), pools)
+            .find_opportunities()
+            .await
+            .unwrap();
+
+        assert_eq!(opportunities.len(), 1);
+        let opportunity = &opportunities[0];
+        assert_eq!(opportunity.legs.len(), 3);
+        assert_eq!(
+            opportunity
+                .legs
+                .iter()
+                .map(|leg| leg.pool.clone())
+                .collect::<HashSet<_>>(),
+            addresses
+        );
+        assert_eq!(opportunity.buy_dex(), Some(RAYDIUM));
+        assert_eq!(
+            opportunity.legs.last().unwrap().output_mint,
+            opportunity.token_mint
+        );
+        assert!(opportunity.profit_percentage > Decimal::ONE);
+        assert!(opportunity.net_profit_usd > Decimal::from(20));
+
+        // Incremental scans only find the cycle through a changed pool
+        let pools = triangle();
+        let changed = HashSet::from([pools[1].address]);
+        let incremental = manager(Config::default(), pools);
+        let opportunities = incremental
+            .find_opportunities_touching(&changed)
+            .await
+            .unwrap();
+        assert_eq!(opportunities.len(), 1);
+        assert!(incremental
+            .find_opportunities_touching(&HashSet::from([Pubkey::new_unique()]))
+            .await
+            .unwrap()
+            .is_empty());
+
+        let mut config = Config::default();
+        config.opportunities.enable_triangular_arbitrage = false;
+        assert!(manager(config, triangle())
+            .find_opportunities()
+            .await
+            .unwrap()
+            .is_empty());
+
+        let mut config = Config::default();
+        config.opportunities.max_scan_depth = 2;
+        assert!(manager(config, triangle())
+            .find_opportunities()
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    /// Reports one fixed opportunity and counts pool updates
+    #[derive(Debug, Default)]
+    struct Fixed {
+        updates: usize,
+    }
+
+    impl Strategy for Fixed {
+        fn name(&self) -> &str {
+            "fixed"
+        }
+
+        fn config_schema(&self) -> Value {
+            json!({ "type": "object" })
+        }
+
+        fn config(&self) -> Value {
+            json!({ "updates": self.updates })
+        }
+
+        fn configure(&mut self, _config: Value) -> Result<()> {
+            Ok(())
+        }
+
+        fn on_pool_update(&mut self, _pool: &PoolState) {
+            self.updates += 1;
+        }
+
+        fn scan(&self, ctx: &ScanContext<'_>) -> Result<Vec<ArbitrageOpportunity>> {
+            let pools: Vec<&PoolState> = ctx.eligible_pools();
+            let limits = RouteLimits {
+                min_profit_usd: 0.0,
+                min_profit_percent: 0.0,
+                max_price_impact_bps: u32::MAX,
+            };
+            Ok(ctx
+                .evaluate_route(&[pools[0], pools[1]], &SOL, &limits)
+                .into_iter()
+                .chain(ctx.evaluate_route(&[pools[1], pools[0]], &SOL, &limits))
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_strategies_and_runtime_control() {
+        let manager = manager(
+            Config::default(),
+            vec![usdc_pool(RAYDIUM, SOL, 150), usdc_pool(ORCA, SOL, 155)],
+        );
+        manager.register(Box::new(Fixed::default()), true).unwrap();
+        assert!(manager.register(Box::new(Fixed::default()), true).is_err());
+
+        let names: Vec<String> = manager.strategies().into_iter().map(|s| s.name).collect();
+        assert_eq!(names, vec![CROSS_DEX, TRIANGULAR, "fixed"]);
+
+        manager.disable(CROSS_DEX).unwrap();
+        let found = manager.find_opportunities().await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].token_mint, SOL.to_string());
+
+        manager.on_pool_update(&usdc_pool(RAYDIUM, SOL, 150));
+        let fixed = manager.strategies().pop().unwrap();
+        assert_eq!(fixed.config["updates"], 1);
+
+        manager.disable("fixed").unwrap();
+        manager.enable(CROSS_DEX).unwrap();
+        assert_eq!(manager.find_opportunities().await.unwrap().len(), 2);
+
+        let mut params = manager.strategies()[0].config.clone();
+        params["min_profit_usd"] = json!(1_000_000.0);
+        manager.configure(CROSS_DEX, params).unwrap();
+        assert!(manager.find_opportunities().await.unwrap().is_empty());
+
+        assert!(manager
+            .configure(CROSS_DEX, json!({ "dexes": "raydium" }))
+            .is_err());
+        assert!(manager.enable("missing").is_err());
+        assert!(manager.unregister("fixed").is_some());
+        assert_eq!(manager.strategies().len(), 2);
     }
 }
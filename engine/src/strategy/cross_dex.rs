@@ -0,0 +1,137 @@
+//! Two-leg cross-DEX arbitrage
+//!
+//! For every token pair quoted on two or more DEXes, buys on one DEX and sells
+//! on another, starting from either token of the pair. Incremental scans only
+//! look at the pairs of changed pools.
+
+use super::{parse_params, RouteLimits, ScanContext, Strategy};
+use crate::config::OpportunitiesConfig;
+use crate::dex::PoolState;
+use crate::error::Result;
+use crate::models::ArbitrageOpportunity;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, HashSet};
+
+/// Name of the cross-DEX strategy
+pub const CROSS_DEX: &str = "cross_dex";
+
+/// Cross-DEX strategy parameters
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrossDexParams {
+    /// Profit and price impact limits
+    #[serde(flatten)]
+    pub limits: RouteLimits,
+    /// DEXes routes may use; empty for all
+    #[serde(default)]
+    pub dexes: Vec<String>,
+}
+
+/// Two-leg cross-DEX strategy
+#[derive(Debug, Clone)]
+pub struct CrossDexStrategy {
+    params: CrossDexParams,
+}
+
+impl CrossDexStrategy {
+    /// Create the strategy with limits from the opportunities settings
+    pub fn new(config: &OpportunitiesConfig) -> Self {
+        Self {
+            params: CrossDexParams {
+                limits: RouteLimits::from(config),
+                dexes: Vec::new(),
+            },
+        }
+    }
+
+    /// Current parameters
+    pub fn params(&self) -> &CrossDexParams {
+        &self.params
+    }
+
+    fn uses_dex(&self, dex: &str) -> bool {
+        self.params.dexes.is_empty() || self.params.dexes.iter().any(|d| d == dex)
+    }
+}
+
+impl Strategy for CrossDexStrategy {
+    fn name(&self) -> &str {
+        CROSS_DEX
+    }
+
+    fn config_schema(&self) -> Value {
+        RouteLimits::schema(json!({
+            "dexes": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "DEXes routes may use; empty for all"
+            }
+        }))
+    }
+
+    fn config(&self) -> Value {
+        json!(self.params)
+    }
+
+    fn configure(&mut self, config: Value) -> Result<()> {
+        self.params = parse_params(CROSS_DEX, config)?;
+        Ok(())
+    }
+
+    fn scan(&self, ctx: &ScanContext<'_>) -> Result<Vec<ArbitrageOpportunity>> {
+        let pools: Vec<&PoolState> = ctx
+            .eligible_pools()
+            .into_iter()
+            .filter(|pool| self.uses_dex(pool.dex))
+            .collect();
+        let changed_pairs: Option<HashSet<(Pubkey, Pubkey)>> = ctx.changed.map(|changed| {
+            pools
+                .iter()
+                .filter(|pool| changed.contains(&pool.address))
+                .map(|pool| pair_key(pool))
+                .collect()
+        });
+
+        let mut pairs: HashMap<(Pubkey, Pubkey), Vec<&PoolState>> = HashMap::new();
+        for pool in pools {
+            let key = pair_key(pool);
+            if changed_pairs
+                .as_ref()
+                .is_none_or(|changed| changed.contains(&key))
+            {
+                pairs.entry(key).or_default().push(pool);
+            }
+        }
+
+        let mut opportunities = Vec::new();
+        for ((mint_a, mint_b), pools) in pairs {
+            let dexes: HashSet<&str> = pools.iter().map(|pool| pool.dex).collect();
+            if dexes.len() < 2 || !ctx.touches(&pools) {
+                continue;
+            }
+
+            for buy in &pools {
+                for sell in pools.iter().filter(|sell| sell.dex != buy.dex) {
+                    for start in [mint_a, mint_b] {
+                        opportunities.extend(ctx.evaluate_route(
+                            &[buy, sell],
+                            &start,
+                            &self.params.limits,
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(opportunities)
+    }
+}
+
+/// Mints of a pool in a fixed order, so both pools of a pair share a key
+fn pair_key(pool: &PoolState) -> (Pubkey, Pubkey) {
+    if pool.mint_a < pool.mint_b {
+        (pool.mint_a, pool.mint_b)
+    } else {
+        (pool.mint_b, pool.mint_a)
+    }
+}
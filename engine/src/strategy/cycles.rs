@@ -0,0 +1,300 @@
+//! Negative-cycle search over the token graph
+//!
+//! Every pool contributes one edge per direction weighted by `-ln(rate)`,
+//! where the rate is what a probe-sized swap returns after fees. Trading
+//! around a cycle multiplies the input by the product of its rates, so a
+//! profitable route is a cycle whose weights sum below zero. Cycles are found
+//! with a hop-limited Bellman-Ford from every token, which also bounds route
+//! length; incremental scans only start it from the tokens of changed pools.
+
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, HashSet};
+
+/// Weights above this are treated as zero so rounding noise is not reported
+const EPSILON: f64 = 1e-12;
+
+/// Directed swap through a pool
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Edge {
+    /// Index of the input token
+    pub from: usize,
+    /// Index of the output token
+    pub to: usize,
+    /// Caller-defined pool index
+    pub pool: usize,
+    /// `-ln(rate)`
+    pub weight: f64,
+}
+
+/// Cycle of swaps returning to its first token
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cycle {
+    /// Input token of each hop; the first is where the cycle starts and ends
+    pub tokens: Vec<Pubkey>,
+    /// Pool index of each hop
+    pub pools: Vec<usize>,
+    /// Sum of the hop weights; negative for profitable cycles
+    pub weight: f64,
+}
+
+impl Cycle {
+    /// Number of hops
+    pub fn len(&self) -> usize {
+        self.pools.len()
+    }
+
+    /// Check whether the cycle has no hops
+    pub fn is_empty(&self) -> bool {
+        self.pools.is_empty()
+    }
+
+    /// Product of the hop rates
+    pub fn rate(&self) -> f64 {
+        (-self.weight).exp()
+    }
+}
+
+/// Directed multigraph of tokens and the pools between them
+#[derive(Debug, Clone, Default)]
+pub struct TokenGraph {
+    tokens: Vec<Pubkey>,
+    index: HashMap<Pubkey, usize>,
+    edges: Vec<Edge>,
+}
+
+impl TokenGraph {
+    /// Create an empty graph
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn node(&mut self, mint: Pubkey) -> usize {
+        *self.index.entry(mint).or_insert_with(|| {
+            self.tokens.push(mint);
+            self.tokens.len() - 1
+        })
+    }
+
+    /// Add a swap from `from` to `to` through `pool` returning `rate` output
+    /// units per input unit. Non-positive rates are ignored.
+    pub fn add_edge(&mut self, from: Pubkey, to: Pubkey, pool: usize, rate: f64) {
+        if !(rate > 0.0 && rate.is_finite()) {
+            return;
+        }
+        let from = self.node(from);
+        let to = self.node(to);
+        self.edges.push(Edge {
+            from,
+            to,
+            pool,
+            weight: -rate.ln(),
+        });
+    }
+
+    /// Number of tokens
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// Check whether the graph has no tokens
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Number of edges
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Simple negative cycles of `min_hops..=max_hops` hops, one per set of
+    /// rotations, most negative first
+    pub fn negative_cycles(&self, min_hops: usize, max_hops: usize) -> Vec<Cycle> {
+        self.collect_cycles(0..self.tokens.len(), min_hops, max_hops)
+    }
+
+    /// Like [`TokenGraph::negative_cycles`], but only cycles passing through
+    /// one of `tokens`
+    pub fn negative_cycles_through(
+        &self,
+        tokens: &HashSet<Pubkey>,
+        min_hops: usize,
+        max_hops: usize,
+    ) -> Vec<Cycle> {
+        let sources = tokens
+            .iter()
+            .filter_map(|mint| self.index.get(mint).copied());
+        self.collect_cycles(sources, min_hops, max_hops)
+    }
+
+    fn collect_cycles(
+        &self,
+        sources: impl IntoIterator<Item = usize>,
+        min_hops: usize,
+        max_hops: usize,
+    ) -> Vec<Cycle> {
+        let mut seen = HashSet::new();
+        let mut cycles = Vec::new();
+        for source in sources {
+            for cycle in self.cycles_through(source, min_hops, max_hops) {
+                if seen.insert(canonical(&cycle.pools)) {
+                    cycles.push(cycle);
+                }
+            }
+        }
+        cycles.sort_by(|a, b| a.weight.total_cmp(&b.weight));
+        cycles
+    }
+
+    /// Hop-limited Bellman-Ford from `source`: `levels[k][v]` is the lightest
+    /// `k`-hop walk from `source` to `v` that does not revisit `source`, and
+    /// `closing[k]` the lightest `k`-hop walk back to it
+    fn cycles_through(&self, source: usize, min_hops: usize, max_hops: usize) -> Vec<Cycle> {
+        let n = self.tokens.len();
+        let mut levels: Vec<Vec<Option<(f64, usize)>>> = vec![vec![None; n]];
+        let mut closing: Vec<Option<(f64, usize)>> = vec![None];
+        levels[0][source] = Some((0.0, usize::MAX));
+
+        for hops in 1..=max_hops {
+            let previous = &levels[hops - 1];
+            let mut current = vec![None; n];
+            let mut close: Option<(f64, usize)> = None;
+            for (e, edge) in self.edges.iter().enumerate() {
+                let Some((distance, _)) = previous[edge.from] else {
+                    continue;
+                };
+                let distance = distance + edge.weight;
+                let slot = if edge.to == source {
+                    &mut close
+                } else {
+                    &mut current[edge.to]
+                };
+                if slot.is_none_or(|(best, _)| distance < best) {
+                    *slot = Some((distance, e));
+                }
+            }
+            levels.push(current);
+            closing.push(close);
+        }
+
+        let mut cycles = Vec::new();
+        for (hops, close) in closing.iter().enumerate().skip(min_hops.max(1)) {
+            let Some((weight, last)) = *close else {
+                continue;
+            };
+            if weight >= -EPSILON {
+                continue;
+            }
+            if let Some(cycle) = self.trace(&levels, hops, last, weight) {
+                cycles.push(cycle);
+            }
+        }
+        cycles
+    }
+
+    /// Walk predecessors back from the closing edge, rejecting walks that
+    /// repeat a token
+    fn trace(
+        &self,
+        levels: &[Vec<Option<(f64, usize)>>],
+        hops: usize,
+        last: usize,
+        weight: f64,
+    ) -> Option<Cycle> {
+        let mut edges = vec![self.edges[last]];
+        let mut node = self.edges[last].from;
+        for level in (1..hops).rev() {
+            let (_, e) = levels[level][node]?;
+            edges.push(self.edges[e]);
+            node = self.edges[e].from;
+        }
+        edges.reverse();
+
+        let mut visited = HashSet::new();
+        if !edges.iter().all(|edge| visited.insert(edge.from)) {
+            return None;
+        }
+
+        Some(Cycle {
+            tokens: edges.iter().map(|edge| self.tokens[edge.from]).collect(),
+            pools: edges.iter().map(|edge| edge.pool).collect(),
+            weight,
+        })
+    }
+}
+
+/// Rotation of `pools` starting at its smallest element, so every rotation
+/// of a cycle maps to the same key
+fn canonical(pools: &[usize]) -> Vec<usize> {
+    let start = pools
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, pool)| **pool)
+        .map(|(i, _)| i)
+        .unwrap_or(0);
+    pools[start..]
+        .iter()
+        .chain(&pools[..start])
+        .copied()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finds_triangle_within_hop_limit() {
+        let (a, b, c) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let mut graph = TokenGraph::new();
+        // a -> b -> c -> a returns 1.02x; the reverse loses
+        graph.add_edge(a, b, 0, 2.0);
+        graph.add_edge(b, a, 0, 0.499);
+        graph.add_edge(b, c, 1, 3.0);
+        graph.add_edge(c, b, 1, 0.333);
+        graph.add_edge(c, a, 2, 0.17);
+        graph.add_edge(a, c, 2, 5.8);
+
+        let cycles = graph.negative_cycles(3, 3);
+        assert_eq!(cycles.len(), 1);
+        let cycle = &cycles[0];
+        assert_eq!(cycle.len(), 3);
+        assert_eq!(canonical(&cycle.pools), vec![0, 1, 2]);
+        assert!((cycle.rate() - 1.02).abs() < 1e-9);
+
+        assert!(graph.negative_cycles(3, 2).is_empty());
+
+        let through = graph.negative_cycles_through(&HashSet::from([c]), 3, 3);
+        assert_eq!(through.len(), 1);
+        assert_eq!(canonical(&through[0].pools), vec![0, 1, 2]);
+        assert!(graph
+            .negative_cycles_through(&HashSet::from([Pubkey::new_unique()]), 3, 3)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_prefers_the_better_parallel_pool() {
+        let (a, b, c, d) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let mut graph = TokenGraph::new();
+        graph.add_edge(a, b, 0, 1.0);
+        graph.add_edge(b, c, 1, 1.0);
+        graph.add_edge(c, d, 2, 1.0);
+        graph.add_edge(d, a, 3, 1.01);
+        graph.add_edge(d, a, 4, 1.03);
+        graph.add_edge(a, a, 5, 0.0);
+
+        let cycles = graph.negative_cycles(3, 4);
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(cycles[0].len(), 4);
+        assert_eq!(canonical(&cycles[0].pools), vec![0, 1, 2, 4]);
+        assert_eq!(graph.edge_count(), 5);
+    }
+}
@@ -0,0 +1,214 @@
+//! Trade size optimization
+//!
+//! A route's output is concave in its input, so profit `out(x) - x` has a
+//! single maximum. Routes made only of constant-product legs fold into one
+//! virtual pool whose optimum has a closed form; any other route is sized by
+//! golden-section search over exact integer quotes.
+
+use crate::dex::{DexManager, PoolData, PoolState, Quote, QuoteRequest};
+use solana_sdk::pubkey::Pubkey;
+
+/// `1 / golden ratio`
+const INV_PHI: f64 = 0.618_033_988_749_895;
+
+/// A route quoted at a specific input size
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SizedRoute {
+    /// Input amount of the first leg
+    pub input_amount: u64,
+    /// Quote of each leg
+    pub legs: Vec<Quote>,
+    /// Sum of the legs' price impact, in basis points
+    pub price_impact_bps: u32,
+}
+
+impl SizedRoute {
+    /// Output of the last leg
+    pub fn output_amount(&self) -> u64 {
+        self.legs.last().map_or(0, |quote| quote.out_amount)
+    }
+
+    /// Output minus input, if positive
+    pub fn profit(&self) -> Option<u64> {
+        self.output_amount()
+            .checked_sub(self.input_amount)
+            .filter(|profit| *profit > 0)
+    }
+}
+
+/// Quote `amount` of `start` through every pool of `route` in turn. Fails if
+/// any leg fails or the route does not end in `start`.
+pub fn quote_route(
+    dex: &DexManager,
+    route: &[&PoolState],
+    start: &Pubkey,
+    amount: u64,
+) -> Option<SizedRoute> {
+    let mut mint = *start;
+    let mut out_amount = amount;
+    let mut price_impact_bps = 0;
+    let mut legs = Vec::with_capacity(route.len());
+    for pool in route {
+        let quote = dex
+            .quote(pool, &QuoteRequest::exact_in(mint, out_amount))
+            .ok()?;
+        mint = quote.output_mint;
+        out_amount = quote.out_amount;
+        price_impact_bps += quote.price_impact_bps;
+        legs.push(quote);
+    }
+
+    (mint == *start).then_some(SizedRoute {
+        input_amount: amount,
+        legs,
+        price_impact_bps,
+    })
+}
+
+/// Input in `1..=max_input` maximizing the route's profit, with the route
+/// quoted at that size. `None` if the route loses money at every size.
+pub fn optimal_size(
+    dex: &DexManager,
+    route: &[&PoolState],
+    start: &Pubkey,
+    max_input: u64,
+) -> Option<SizedRoute> {
+    if max_input == 0 || route.is_empty() {
+        return None;
+    }
+
+    let input = match constant_product_optimum(route, start) {
+        Some(optimum) => (optimum.round().min(max_input as f64) as u64).max(1),
+        None => golden_section(1, max_input, |amount| {
+            quote_route(dex, route, start, amount)
+                .map(|sized| sized.output_amount() as i128 - amount as i128)
+                .unwrap_or(i128::MIN)
+        }),
+    };
+
+    let sized = quote_route(dex, route, start, input)?;
+    sized.profit().is_some().then_some(sized)
+}
+
+/// Closed-form optimum when every leg is a constant-product pool
+///
+/// A leg with reserves `(r_in, r_out)` and fee factor `g` returns
+/// `g * r_out * x / (r_in + g * x)`. Chaining a virtual pool `(a, b, g)` with
+/// a leg `(r_in, r_out, g2)` gives another virtual pool
+/// `(a * r_in / (r_in + g2 * b), g2 * b * r_out / (r_in + g2 * b), g)`, and
+/// the profit of a virtual pool peaks at `(sqrt(g * a * b) - a) / g`.
+pub fn constant_product_optimum(route: &[&PoolState], start: &Pubkey) -> Option<f64> {
+    let mut mint = *start;
+    let mut virtual_pool: Option<(f64, f64, f64)> = None;
+    for pool in route {
+        let (reserve_in, reserve_out, fee_factor) = constant_product_leg(pool, &mint)?;
+        mint = pool.other_mint(&mint)?;
+        virtual_pool = Some(match virtual_pool {
+            None => (reserve_in, reserve_out, fee_factor),
+            Some((a, b, g)) => {
+                let denominator = reserve_in + fee_factor * b;
+                (
+                    a * reserve_in / denominator,
+                    fee_factor * b * reserve_out / denominator,
+                    g,
+                )
+            }
+        });
+    }
+
+    let (a, b, g) = virtual_pool?;
+    Some(((g * a * b).sqrt() - a) / g)
+}
+
+/// Reserves and fee factor of a constant-product pool swapped from `input_mint`
+fn constant_product_leg(pool: &PoolState, input_mint: &Pubkey) -> Option<(f64, f64, f64)> {
+    let PoolData::Raydium(state) = &pool.data else {
+        return None;
+    };
+    if state.swap_fee_denominator == 0 {
+        return None;
+    }
+    let fee_factor = 1.0 - state.swap_fee_numerator as f64 / state.swap_fee_denominator as f64;
+    let (coin, pc) = (state.coin_reserve() as f64, state.pc_reserve() as f64);
+    if pool.is_a_to_b(input_mint).ok()? {
+        Some((coin, pc, fee_factor))
+    } else {
+        Some((pc, coin, fee_factor))
+    }
+}
+
+/// Argmax of a unimodal function over the integers `lo..=hi`
+fn golden_section(mut lo: u64, mut hi: u64, f: impl Fn(u64) -> i128) -> u64 {
+    while hi - lo > 2 {
+        let step = ((hi - lo) as f64 * (1.0 - INV_PHI)) as u64;
+        let (left, right) = (lo + step.max(1), hi - step.max(1));
+        if f(left) < f(right) {
+            lo = left;
+        } else {
+            hi = right;
+        }
+    }
+    (lo..=hi).max_by_key(|x| f(*x)).unwrap_or(lo)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dex::RaydiumAmmAdapter;
+    use crate::test_support::test_pool;
+    use std::sync::Arc;
+
+    fn dex() -> DexManager {
+        let mut dex = DexManager::new();
+        dex.register(Arc::new(RaydiumAmmAdapter::new()));
+        dex
+    }
+
+    /// x sells for 155 y in the second pool and costs 150 y in the first
+    fn spread() -> (Pubkey, [PoolState; 2]) {
+        let (x, y) = (Pubkey::new_unique(), Pubkey::new_unique());
+        (
+            x,
+            [
+                test_pool(x, y, 1_000_000_000, 150_000_000_000, 25),
+                test_pool(y, x, 155_000_000_000, 1_000_000_000, 25),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_closed_form_matches_search() {
+        let dex = dex();
+        let (x, pools) = spread();
+        let route = [&pools[1], &pools[0]];
+
+        let optimum = constant_product_optimum(&route, &x).unwrap();
+        let searched = golden_section(1, 1_000_000_000, |amount| {
+            quote_route(&dex, &route, &x, amount)
+                .map(|sized| sized.output_amount() as i128 - amount as i128)
+                .unwrap_or(i128::MIN)
+        });
+        // Ceil-rounded fees make the curve slightly jagged near the top
+        assert!((optimum - searched as f64).abs() / optimum < 1e-2);
+
+        let best = optimal_size(&dex, &route, &x, u64::MAX).unwrap();
+        let profit = best.profit().unwrap();
+        for other in [best.input_amount * 9 / 10, best.input_amount * 11 / 10] {
+            let sized = quote_route(&dex, &route, &x, other).unwrap();
+            assert!(sized.profit().unwrap_or(0) <= profit);
+        }
+        assert_eq!(best.legs.len(), 2);
+    }
+
+    #[test]
+    fn test_size_is_capped_and_losing_routes_rejected() {
+        let dex = dex();
+        let (x, pools) = spread();
+
+        let capped = optimal_size(&dex, &[&pools[1], &pools[0]], &x, 1_000_000).unwrap();
+        assert_eq!(capped.input_amount, 1_000_000);
+
+        assert!(optimal_size(&dex, &[&pools[0], &pools[1]], &x, u64::MAX).is_none());
+        assert!(optimal_size(&dex, &[&pools[1], &pools[0]], &x, 0).is_none());
+    }
+}
@@ -0,0 +1,168 @@
+//! Triangular and longer cycles
+//!
+//! Builds the token graph from every eligible pool, with rates sampled at a
+//! probe size, and re-quotes each negative cycle found in it with exact
+//! integer amounts before reporting it. Incremental scans only quote the
+//! pools close enough to a changed pool to share a cycle with it, and only
+//! search cycles through the changed pools' tokens.
+
+use super::cycles::TokenGraph;
+use super::{parse_params, RouteLimits, ScanContext, Strategy};
+use crate::config::OpportunitiesConfig;
+use crate::dex::{PoolState, QuoteRequest};
+use crate::error::Result;
+use crate::models::ArbitrageOpportunity;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashSet;
+
+/// Name of the triangular strategy
+pub const TRIANGULAR: &str = "triangular";
+
+/// Shortest cycle reported; two-leg cycles are cross-DEX routes
+const MIN_HOPS: usize = 3;
+
+/// Input size, in USD, at which pool rates are sampled for the token graph
+const PROBE_SIZE_USD: f64 = 1_000.0;
+
+/// Triangular strategy parameters
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TriangularParams {
+    /// Profit and price impact limits
+    #[serde(flatten)]
+    pub limits: RouteLimits,
+    /// Longest cycle searched, in hops
+    pub max_hops: usize,
+}
+
+/// Negative-cycle strategy
+#[derive(Debug, Clone)]
+pub struct TriangularStrategy {
+    params: TriangularParams,
+}
+
+impl TriangularStrategy {
+    /// Create the strategy with limits from the opportunities settings and
+    /// `max_scan_depth` as the longest cycle
+    pub fn new(config: &OpportunitiesConfig) -> Self {
+        Self {
+            params: TriangularParams {
+                limits: RouteLimits::from(config),
+                max_hops: config.max_scan_depth,
+            },
+        }
+    }
+
+    /// Current parameters
+    pub fn params(&self) -> &TriangularParams {
+        &self.params
+    }
+}
+
+impl Strategy for TriangularStrategy {
+    fn name(&self) -> &str {
+        TRIANGULAR
+    }
+
+    fn config_schema(&self) -> Value {
+        RouteLimits::schema(json!({
+            "max_hops": {
+                "type": "integer",
+                "minimum": MIN_HOPS,
+                "description": "Longest cycle searched, in hops"
+            }
+        }))
+    }
+
+    fn config(&self) -> Value {
+        json!(self.params)
+    }
+
+    fn configure(&mut self, config: Value) -> Result<()> {
+        self.params = parse_params(TRIANGULAR, config)?;
+        Ok(())
+    }
+
+    fn scan(&self, ctx: &ScanContext<'_>) -> Result<Vec<ArbitrageOpportunity>> {
+        let mut pools: Vec<&PoolState> = ctx.eligible_pools();
+        let changed_tokens = ctx.changed.map(|changed| {
+            let tokens = changed_tokens(&pools, changed);
+            pools = neighbourhood(&pools, &tokens, self.params.max_hops.saturating_sub(1) / 2);
+            tokens
+        });
+
+        let mut graph = TokenGraph::new();
+        for (index, pool) in pools.iter().enumerate() {
+            for (input, output) in [(pool.mint_a, pool.mint_b), (pool.mint_b, pool.mint_a)] {
+                let Some(amount) = ctx.prices.amount_for_usd(&input, PROBE_SIZE_USD) else {
+                    continue;
+                };
+                if let Ok(quote) = ctx.dex.quote(pool, &QuoteRequest::exact_in(input, amount)) {
+                    graph.add_edge(
+                        input,
+                        output,
+                        index,
+                        quote.out_amount as f64 / amount as f64,
+                    );
+                }
+            }
+        }
+
+        let cycles = match &changed_tokens {
+            Some(tokens) => graph.negative_cycles_through(tokens, MIN_HOPS, self.params.max_hops),
+            None => graph.negative_cycles(MIN_HOPS, self.params.max_hops),
+        };
+        Ok(cycles
+            .into_iter()
+            .filter_map(|cycle| {
+                let route: Vec<&PoolState> = cycle.pools.iter().map(|i| pools[*i]).collect();
+                ctx.evaluate_route(&route, &cycle.tokens[0], &self.params.limits)
+            })
+            .collect())
+    }
+}
+
+/// Mints of the changed pools among `pools`
+fn changed_tokens(pools: &[&PoolState], changed: &HashSet<Pubkey>) -> HashSet<Pubkey> {
+    pools
+        .iter()
+        .filter(|pool| changed.contains(&pool.address))
+        .flat_map(|pool| [pool.mint_a, pool.mint_b])
+        .collect()
+}
+
+/// Pools whose tokens are both within `radius` hops of `tokens`. A cycle of
+/// `n` hops through a pool between two of `tokens` never strays further than
+/// `(n - 1) / 2` hops from them.
+fn neighbourhood<'a>(
+    pools: &[&'a PoolState],
+    tokens: &HashSet<Pubkey>,
+    radius: usize,
+) -> Vec<&'a PoolState> {
+    let mut reached = tokens.clone();
+    for _ in 0..radius {
+        let next: Vec<Pubkey> = pools
+            .iter()
+            .filter_map(|pool| {
+                match (
+                    reached.contains(&pool.mint_a),
+                    reached.contains(&pool.mint_b),
+                ) {
+                    (true, false) => Some(pool.mint_b),
+                    (false, true) => Some(pool.mint_a),
+                    _ => None,
+                }
+            })
+            .collect();
+        if next.is_empty() {
+            break;
+        }
+        reached.extend(next);
+    }
+    pools
+        .iter()
+        .filter(|pool| reached.contains(&pool.mint_a) && reached.contains(&pool.mint_b))
+        .copied()
+        .collect()
+}
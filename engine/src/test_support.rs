@@ -0,0 +1,124 @@
+//! Pool and adapter fixtures shared by unit tests across modules
+
+use crate::dex::raydium::{RaydiumAmmAdapter, AMM_INFO_LEN};
+use crate::dex::{
+    DexAdapter, DexManager, PoolData, PoolState, Quote, QuoteRequest, RaydiumAmmState, SwapParams,
+    ORCA, RAYDIUM,
+};
+use crate::error::Result;
+use crate::pool_cache::PoolCache;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// `AmmStatus::SwapOnly`
+const RAYDIUM_STATUS_SWAP_ONLY: u64 = 6;
+
+/// USDC mint, the quote token of [`usdc_pool`]
+pub(crate) const USDC: Pubkey =
+    Pubkey::from_str_const("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+
+/// Swappable Raydium pool with the given reserves and fee
+pub(crate) fn test_pool(
+    mint_a: Pubkey,
+    mint_b: Pubkey,
+    reserve_a: u64,
+    reserve_b: u64,
+    fee_bps: u64,
+) -> PoolState {
+    PoolState {
+        address: Pubkey::new_unique(),
+        dex: RAYDIUM,
+        mint_a,
+        mint_b,
+        data: PoolData::Raydium(RaydiumAmmState {
+            status: RAYDIUM_STATUS_SWAP_ONLY,
+            coin_decimals: 0,
+            pc_decimals: 0,
+            swap_fee_numerator: fee_bps,
+            swap_fee_denominator: 10_000,
+            need_take_pnl_coin: 0,
+            need_take_pnl_pc: 0,
+            coin_vault: Pubkey::new_unique(),
+            pc_vault: Pubkey::new_unique(),
+            open_orders: Pubkey::default(),
+            coin_vault_amount: reserve_a,
+            pc_vault_amount: reserve_b,
+            open_orders_coin_total: 0,
+            open_orders_pc_total: 0,
+        }),
+    }
+}
+
+/// 1_000 tokens of `mint` against USDC at `usdc_per_token` on `dex`, with a
+/// 0.25% fee
+pub(crate) fn usdc_pool(dex: &'static str, mint: Pubkey, usdc_per_token: u64) -> PoolState {
+    let mut pool = test_pool(
+        mint,
+        USDC,
+        1_000_000_000_000,
+        usdc_per_token * 1_000_000_000,
+        25,
+    );
+    pool.dex = dex;
+    pool
+}
+
+/// Raydium and Orca, played by the Raydium adapter, over an empty pool cache
+/// that keeps entries fresh for a minute
+pub(crate) fn cross_dex_cache() -> (Arc<DexManager>, Arc<PoolCache>) {
+    let mut dex = DexManager::new();
+    dex.register(Arc::new(RaydiumAmmAdapter::new()));
+    dex.register(Arc::new(RenamedAdapter(ORCA)));
+    let dex = Arc::new(dex);
+    let cache = Arc::new(PoolCache::new(dex.clone(), Duration::from_secs(60)));
+    (dex, cache)
+}
+
+/// Raydium adapter registered under another DEX id, for cross-DEX tests
+#[derive(Debug)]
+pub(crate) struct RenamedAdapter(pub &'static str);
+
+impl DexAdapter for RenamedAdapter {
+    fn id(&self) -> &'static str {
+        self.0
+    }
+
+    fn program_id(&self) -> Pubkey {
+        Pubkey::new_from_array([9; 32])
+    }
+
+    fn pool_account_len(&self) -> usize {
+        AMM_INFO_LEN
+    }
+
+    fn decode_pool(&self, address: Pubkey, data: &[u8]) -> Result<PoolState> {
+        RaydiumAmmAdapter.decode_pool(address, data)
+    }
+
+    fn dependent_accounts(&self, pool: &PoolState) -> Vec<Pubkey> {
+        RaydiumAmmAdapter.dependent_accounts(pool)
+    }
+
+    fn apply_dependent_account(
+        &self,
+        pool: &mut PoolState,
+        address: &Pubkey,
+        data: &[u8],
+    ) -> Result<()> {
+        RaydiumAmmAdapter.apply_dependent_account(pool, address, data)
+    }
+
+    fn reserves(&self, pool: &PoolState) -> Option<(u64, u64)> {
+        RaydiumAmmAdapter.reserves(pool)
+    }
+
+    fn quote(&self, pool: &PoolState, request: &QuoteRequest) -> Result<Quote> {
+        RaydiumAmmAdapter.quote(pool, request)
+    }
+
+    fn swap_instruction(&self, pool: &PoolState, params: &SwapParams) -> Result<Instruction> {
+        RaydiumAmmAdapter.swap_instruction(pool, params)
+    }
+}
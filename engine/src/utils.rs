@@ -3,6 +3,7 @@
 use crate::error::Result;
 use rust_decimal::Decimal;
 use std::str::FromStr;
+use std::time::Duration;
 
 /// Convert string to Decimal safely
 pub fn parse_decimal(s: &str) -> Result<Decimal> {
@@ -36,6 +37,37 @@ pub fn current_timestamp() -> u64 {
         .as_secs()
 }
 
+/// Exponential backoff for reconnect loops
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    current: Duration,
+}
+
+impl Backoff {
+    /// Create a backoff starting at `initial` and doubling up to `max`
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            current: initial,
+        }
+    }
+
+    /// Delay to wait before the next attempt
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.current;
+        self.current = (self.current * 2).min(self.max);
+        delay
+    }
+
+    /// Start over from the initial delay after a successful attempt
+    pub fn reset(&mut self) {
+        self.current = self.initial;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -61,4 +93,15 @@ mod tests {
         assert_ne!(id1, id2);
         assert_eq!(id1.len(), 36); // UUID v4 length
     }
+
+    #[test]
+    fn test_backoff() {
+        let mut backoff = Backoff::new(Duration::from_millis(100), Duration::from_millis(350));
+        assert_eq!(backoff.next_delay(), Duration::from_millis(100));
+        assert_eq!(backoff.next_delay(), Duration::from_millis(200));
+        assert_eq!(backoff.next_delay(), Duration::from_millis(350));
+        assert_eq!(backoff.next_delay(), Duration::from_millis(350));
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), Duration::from_millis(100));
+    }
 }
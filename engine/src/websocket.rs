@@ -0,0 +1,409 @@
+//! Solana RPC websocket data source
+//!
+//! Fallback for environments without a Geyser endpoint: streams account,
+//! program and slot notifications over the standard PubSub websocket and
+//! yields the same update types as the Geyser client. Each subscription owns
+//! one connection and re-subscribes after reconnecting with backoff.
+
+use crate::config::SolanaConfig;
+use crate::geyser::{SlotStatus, SlotUpdate, UpdateStream};
+use crate::pool_cache::AccountUpdate;
+use crate::utils::Backoff;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, info, warn};
+
+/// Updates buffered per subscription before the reader applies backpressure
+const UPDATE_BUFFER: usize = 1024;
+/// First reconnect delay
+const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+/// Longest reconnect delay
+const MAX_BACKOFF: Duration = Duration::from_secs(10);
+
+/// A subscription request and the account it is for, if any. Account
+/// notifications don't carry the address, so it is remembered here.
+#[derive(Debug, Clone)]
+struct Subscription {
+    method: &'static str,
+    params: Value,
+    pubkey: Option<Pubkey>,
+}
+
+/// Converts a notification's `params.result` into an update
+type Convert<T> = fn(&Value, Option<Pubkey>) -> Option<T>;
+
+fn notification_slot(result: &Value) -> u64 {
+    result["context"]["slot"].as_u64().unwrap_or_default()
+}
+
+fn decode_account(pubkey: Pubkey, account: &Value, slot: u64) -> Option<AccountUpdate> {
+    let owner = Pubkey::from_str(account["owner"].as_str()?).ok()?;
+    let data = STANDARD.decode(account["data"][0].as_str()?).ok()?;
+
+    Some(AccountUpdate {
+        pubkey,
+        owner,
+        data,
+        slot,
+        write_version: 0,
+    })
+}
+
+fn convert_account(result: &Value, pubkey: Option<Pubkey>) -> Option<AccountUpdate> {
+    decode_account(pubkey?, &result["value"], notification_slot(result))
+}
+
+fn convert_program_account(result: &Value, _pubkey: Option<Pubkey>) -> Option<AccountUpdate> {
+    let value = &result["value"];
+    let pubkey = Pubkey::from_str(value["pubkey"].as_str()?).ok()?;
+    decode_account(pubkey, &value["account"], notification_slot(result))
+}
+
+fn convert_slot(result: &Value, _pubkey: Option<Pubkey>) -> Option<SlotUpdate> {
+    Some(SlotUpdate {
+        slot: result["slot"].as_u64()?,
+        parent: result["parent"].as_u64(),
+        status: SlotStatus::Processed,
+    })
+}
+
+/// Solana PubSub websocket client
+#[derive(Debug, Clone)]
+pub struct WsClient {
+    url: String,
+    commitment: String,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl WsClient {
+    /// Create a client for a PubSub websocket URL. Connections are opened
+    /// per subscription.
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            commitment: "confirmed".to_string(),
+            initial_backoff: INITIAL_BACKOFF,
+            max_backoff: MAX_BACKOFF,
+        }
+    }
+
+    /// Create a client from the `[solana]` settings
+    pub fn from_config(config: &SolanaConfig) -> Self {
+        Self::new(&config.ws_url).with_commitment(&config.commitment)
+    }
+
+    /// Subscribe at `commitment`
+    pub fn with_commitment(mut self, commitment: &str) -> Self {
+        self.commitment = commitment.to_string();
+        self
+    }
+
+    /// Set the reconnect backoff range
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Websocket URL the client connects to
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn account_config(&self) -> Value {
+        json!({ "encoding": "base64", "commitment": self.commitment })
+    }
+
+    /// Stream updates of specific accounts
+    pub fn subscribe_accounts(&self, accounts: &[Pubkey]) -> UpdateStream<AccountUpdate> {
+        let subscriptions = accounts
+            .iter()
+            .map(|pubkey| Subscription {
+                method: "accountSubscribe",
+                params: json!([pubkey.to_string(), self.account_config()]),
+                pubkey: Some(*pubkey),
+            })
+            .collect();
+        self.subscribe("account", subscriptions, convert_account)
+    }
+
+    /// Stream updates of every account owned by `programs`
+    pub fn subscribe_programs(&self, programs: &[Pubkey]) -> UpdateStream<AccountUpdate> {
+        let subscriptions = programs
+            .iter()
+            .map(|program| Subscription {
+                method: "programSubscribe",
+                params: json!([program.to_string(), self.account_config()]),
+                pubkey: None,
+            })
+            .collect();
+        self.subscribe("program", subscriptions, convert_program_account)
+    }
+
+    /// Stream slots as the node processes them
+    pub fn subscribe_slots(&self) -> UpdateStream<SlotUpdate> {
+        let subscription = Subscription {
+            method: "slotSubscribe",
+            params: json!([]),
+            pubkey: None,
+        };
+        self.subscribe("slot", vec![subscription], convert_slot)
+    }
+
+    /// Keep `subscriptions` alive on one connection until the receiver goes
+    /// away, reconnecting with backoff whenever the socket drops
+    fn subscribe<T: Send + 'static>(
+        &self,
+        what: &'static str,
+        subscriptions: Vec<Subscription>,
+        convert: Convert<T>,
+    ) -> UpdateStream<T> {
+        let (tx, rx) = mpsc::channel(UPDATE_BUFFER);
+        let client = self.clone();
+
+        tokio::spawn(async move {
+            let mut backoff = Backoff::new(client.initial_backoff, client.max_backoff);
+            loop {
+                tokio::select! {
+                    _ = client.run(what, &subscriptions, convert, &tx, &mut backoff) => {}
+                    _ = tx.closed() => {}
+                }
+
+                if tx.is_closed() {
+                    debug!("Websocket {} subscriber dropped", what);
+                    return;
+                }
+                let delay = backoff.next_delay();
+                debug!("Resubscribing to websocket {} in {:?}", what, delay);
+                tokio::time::sleep(delay).await;
+            }
+        });
+
+        Box::pin(ReceiverStream::new(rx))
+    }
+
+    /// Run one connection until it drops
+    async fn run<T>(
+        &self,
+        what: &'static str,
+        subscriptions: &[Subscription],
+        convert: Convert<T>,
+        tx: &mpsc::Sender<T>,
+        backoff: &mut Backoff,
+    ) {
+        let (socket, _) = match tokio_tungstenite::connect_async(self.url.as_str()).await {
+            Ok(connection) => connection,
+            Err(e) => {
+                warn!(
+                    "Websocket {} connection to {} failed: {}",
+                    what, self.url, e
+                );
+                return;
+            }
+        };
+        let (mut sink, mut stream) = socket.split();
+
+        for (index, subscription) in subscriptions.iter().enumerate() {
+            let request = json!({
+                "jsonrpc": "2.0",
+                "id": index + 1,
+                "method": subscription.method,
+                "params": subscription.params,
+            });
+            if let Err(e) = sink.send(Message::text(request.to_string())).await {
+                warn!("Websocket {} subscribe failed: {}", what, e);
+                return;
+            }
+        }
+
+        // Subscription id -> account it was made for
+        let mut active: HashMap<u64, Option<Pubkey>> = HashMap::new();
+
+        while let Some(message) = stream.next().await {
+            let text = match message {
+                Ok(Message::Text(text)) => text,
+                Ok(Message::Close(_)) => break,
+                Ok(_) => continue,
+                Err(e) => {
+                    warn!("Websocket {} stream failed: {}", what, e);
+                    break;
+                }
+            };
+            let Ok(value) = serde_json::from_str::<Value>(text.as_str()) else {
+                warn!("Dropping malformed websocket {} message", what);
+                continue;
+            };
+
+            // Subscription confirmations answer our request ids
+            if let Some(id) = value["id"].as_u64() {
+                let subscription = (id as usize)
+                    .checked_sub(1)
+                    .and_then(|index| subscriptions.get(index));
+                match (value["result"].as_u64(), subscription) {
+                    (Some(subscription_id), Some(subscription)) => {
+                        active.insert(subscription_id, subscription.pubkey);
+                        if active.len() == subscriptions.len() {
+                            info!("Websocket {} subscriptions established", what);
+                            backoff.reset();
+                        }
+                    }
+                    _ => warn!("Websocket {} subscribe rejected: {}", what, value["error"]),
+                }
+                continue;
+            }
+
+            let params = &value["params"];
+            let Some(subscription_id) = params["subscription"].as_u64() else {
+                continue;
+            };
+            let Some(pubkey) = active.get(&subscription_id) else {
+                continue;
+            };
+            let Some(update) = convert(&params["result"], *pubkey) else {
+                continue;
+            };
+            if tx.send(update).await.is_err() {
+                return;
+            }
+        }
+
+        warn!("Websocket {} connection to {} closed", what, self.url);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::SocketAddr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    const OWNER: Pubkey = Pubkey::new_from_array([4; 32]);
+
+    /// PubSub server that answers every subscription with id 100 + n and
+    /// sends one notification per subscription, using the connection number
+    /// as the slot. With `drop_first`, the first connection is closed after
+    /// its first notification.
+    async fn spawn_server(drop_first: bool) -> (SocketAddr, Arc<AtomicUsize>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let counter = connections.clone();
+
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let connection = counter.fetch_add(1, Ordering::SeqCst) as u64 + 1;
+                tokio::spawn(async move {
+                    let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+                    while let Some(Ok(Message::Text(text))) = socket.next().await {
+                        let request: Value = serde_json::from_str(text.as_str()).unwrap();
+                        let id = request["id"].as_u64().unwrap();
+                        let method = request["method"].as_str().unwrap();
+                        let subscription = 100 + id;
+                        let reply = json!({ "jsonrpc": "2.0", "id": id, "result": subscription });
+                        socket.send(Message::text(reply.to_string())).await.unwrap();
+
+                        let account = json!({
+                            "data": [STANDARD.encode([connection as u8, 2, 3]), "base64"],
+                            "owner": OWNER.to_string(),
+                            "lamports": 1,
+                            "executable": false,
+                            "rentEpoch": 0,
+                        });
+                        let result = match method {
+                            "accountSubscribe" => json!({
+                                "context": { "slot": connection },
+                                "value": account,
+                            }),
+                            "programSubscribe" => json!({
+                                "context": { "slot": connection },
+                                "value": {
+                                    "pubkey": Pubkey::new_from_array([5; 32]).to_string(),
+                                    "account": account,
+                                },
+                            }),
+                            _ => json!({ "slot": connection, "parent": connection - 1, "root": 0 }),
+                        };
+                        let notification = json!({
+                            "jsonrpc": "2.0",
+                            "method": method.replace("Subscribe", "Notification"),
+                            "params": { "result": result, "subscription": subscription },
+                        });
+                        socket
+                            .send(Message::text(notification.to_string()))
+                            .await
+                            .unwrap();
+
+                        if drop_first && connection == 1 {
+                            socket.close(None).await.unwrap();
+                            return;
+                        }
+                    }
+                });
+            }
+        });
+        (addr, connections)
+    }
+
+    fn client(addr: SocketAddr) -> WsClient {
+        WsClient::new(&format!("ws://{}", addr))
+            .with_backoff(Duration::from_millis(10), Duration::from_millis(50))
+    }
+
+    async fn next<T>(stream: &mut UpdateStream<T>) -> T {
+        tokio::time::timeout(Duration::from_secs(5), stream.next())
+            .await
+            .expect("timed out waiting for update")
+            .expect("stream ended")
+    }
+
+    #[tokio::test]
+    async fn test_account_notifications_carry_the_subscribed_key() {
+        let (addr, _) = spawn_server(false).await;
+        let accounts = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let mut updates = client(addr).subscribe_accounts(&accounts);
+
+        let mut received = [next(&mut updates).await, next(&mut updates).await];
+        received.sort_by_key(|update| accounts.iter().position(|a| *a == update.pubkey));
+        assert_eq!(received[0].pubkey, accounts[0]);
+        assert_eq!(received[1].pubkey, accounts[1]);
+        assert_eq!(received[0].owner, OWNER);
+        assert_eq!(received[0].data, vec![1, 2, 3]);
+        assert_eq!(received[0].slot, 1);
+    }
+
+    #[tokio::test]
+    async fn test_program_and_slot_notifications() {
+        let (addr, _) = spawn_server(false).await;
+        let client = client(addr);
+
+        let mut programs = client.subscribe_programs(&[OWNER]);
+        let update = next(&mut programs).await;
+        assert_eq!(update.pubkey, Pubkey::new_from_array([5; 32]));
+        assert_eq!(update.owner, OWNER);
+
+        let mut slots = client.subscribe_slots();
+        let update = next(&mut slots).await;
+        assert_eq!(update.status, SlotStatus::Processed);
+        assert_eq!(update.parent, Some(update.slot - 1));
+    }
+
+    #[tokio::test]
+    async fn test_resubscribes_after_disconnect() {
+        let (addr, connections) = spawn_server(true).await;
+        let mut slots = client(addr).subscribe_slots();
+
+        assert_eq!(next(&mut slots).await.slot, 1);
+        assert_eq!(next(&mut slots).await.slot, 2);
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+    }
+}